@@ -0,0 +1,232 @@
+use std::{fs, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, has_instance_stdin, is_instance_running, write_instance_stdin},
+    models::{OpEntry, PlayerProfile, WhitelistEntry},
+    players,
+    properties::ServerProperties,
+};
+
+const DEFAULT_OP_LEVEL: u8 = 4;
+
+/// How a player list change gets applied to an instance
+enum ListTarget {
+    /// nuko started the server, so commands go over stdin and the server updates the file
+    Console,
+    /// The server is stopped and the JSON file can be edited directly
+    File(std::path::PathBuf),
+}
+
+fn list_target(app_handle: &tauri::AppHandle, id: &str) -> Result<ListTarget, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    if has_instance_stdin(id) {
+        return Ok(ListTarget::Console);
+    }
+
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    // A server nuko isn't attached to would overwrite our edits when it saves
+    if is_instance_running(&instance_dir) {
+        return Err(format!(
+            "'{}' is running outside nuko; stop it before editing player lists",
+            config.name
+        ));
+    }
+    Ok(ListTarget::File(instance_dir))
+}
+
+fn require_username(player: &str) -> Result<(), String> {
+    if players::is_valid_username(player) {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid Minecraft username", player))
+    }
+}
+
+pub fn read_json_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+pub fn write_json_list<T: Serialize>(path: &Path, entries: &[T]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Resolve a player the way the instance will identify them: by Mojang UUID in online mode,
+/// or by the derived offline UUID otherwise
+pub async fn resolve_for_instance(
+    app_handle: &tauri::AppHandle,
+    instance_dir: &Path,
+    player: &str,
+) -> Result<PlayerProfile, String> {
+    let online_mode = ServerProperties::load(instance_dir)?
+        .get("online-mode")
+        .map(|value| value.trim() != "false")
+        .unwrap_or(true);
+
+    if online_mode {
+        players::resolve_player(app_handle, player).await
+    } else {
+        require_username(player)?;
+        Ok(PlayerProfile {
+            uuid: players::offline_uuid(player),
+            name: player.to_string(),
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn get_whitelist(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<WhitelistEntry>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    read_json_list(&instance_dir.join("whitelist.json"))
+}
+
+#[tauri::command]
+pub async fn add_to_whitelist(
+    app_handle: tauri::AppHandle,
+    id: String,
+    player: String,
+) -> Result<(), String> {
+    let player = player.trim().to_string();
+    match list_target(&app_handle, &id)? {
+        ListTarget::Console => {
+            require_username(&player)?;
+            write_instance_stdin(&id, &format!("whitelist add {}", player))
+        }
+        ListTarget::File(instance_dir) => {
+            let profile = resolve_for_instance(&app_handle, &instance_dir, &player).await?;
+            let path = instance_dir.join("whitelist.json");
+            let mut entries: Vec<WhitelistEntry> = read_json_list(&path)?;
+            if entries.iter().any(|entry| entry.uuid == profile.uuid) {
+                return Ok(());
+            }
+            entries.push(WhitelistEntry {
+                uuid: profile.uuid,
+                name: profile.name,
+            });
+            write_json_list(&path, &entries)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_from_whitelist(
+    app_handle: tauri::AppHandle,
+    id: String,
+    player: String,
+) -> Result<(), String> {
+    let player = player.trim().to_string();
+    match list_target(&app_handle, &id)? {
+        ListTarget::Console => {
+            require_username(&player)?;
+            write_instance_stdin(&id, &format!("whitelist remove {}", player))
+        }
+        ListTarget::File(instance_dir) => {
+            let path = instance_dir.join("whitelist.json");
+            let mut entries: Vec<WhitelistEntry> = read_json_list(&path)?;
+            entries.retain(|entry| !entry.name.eq_ignore_ascii_case(&player));
+            write_json_list(&path, &entries)
+        }
+    }
+}
+
+/// The level `op` grants on this server (`op-permission-level`)
+fn default_op_level(instance_dir: &Path) -> Result<u8, String> {
+    Ok(ServerProperties::load(instance_dir)?
+        .get("op-permission-level")
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_OP_LEVEL))
+}
+
+#[tauri::command]
+pub async fn get_ops(app_handle: tauri::AppHandle, id: String) -> Result<Vec<OpEntry>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    read_json_list(&instance_dir.join("ops.json"))
+}
+
+/// Make a player an operator. `level` (1-4) defaults to the server's `op-permission-level`;
+/// a different level can only be written while the server is stopped, since `op` over the
+/// console always uses the default
+#[tauri::command]
+pub async fn add_op(
+    app_handle: tauri::AppHandle,
+    id: String,
+    player: String,
+    level: Option<u8>,
+) -> Result<(), String> {
+    let player = player.trim().to_string();
+    if let Some(level) = level {
+        if !(1..=4).contains(&level) {
+            return Err("Operator level must be between 1 and 4".into());
+        }
+    }
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let default_level = default_op_level(&instance_dir)?;
+
+    match list_target(&app_handle, &id)? {
+        ListTarget::Console => {
+            require_username(&player)?;
+            if level.is_some_and(|level| level != default_level) {
+                return Err(format!(
+                    "While the server is running operators get level {}; stop it to set a different level",
+                    default_level
+                ));
+            }
+            write_instance_stdin(&id, &format!("op {}", player))
+        }
+        ListTarget::File(instance_dir) => {
+            let profile = resolve_for_instance(&app_handle, &instance_dir, &player).await?;
+            let path = instance_dir.join("ops.json");
+            let mut entries: Vec<OpEntry> = read_json_list(&path)?;
+            let level = level.unwrap_or(default_level);
+
+            match entries.iter_mut().find(|entry| entry.uuid == profile.uuid) {
+                Some(entry) => entry.level = level,
+                None => entries.push(OpEntry {
+                    uuid: profile.uuid,
+                    name: profile.name,
+                    level,
+                    bypasses_player_limit: false,
+                }),
+            }
+            write_json_list(&path, &entries)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_op(
+    app_handle: tauri::AppHandle,
+    id: String,
+    player: String,
+) -> Result<(), String> {
+    let player = player.trim().to_string();
+    match list_target(&app_handle, &id)? {
+        ListTarget::Console => {
+            require_username(&player)?;
+            write_instance_stdin(&id, &format!("deop {}", player))
+        }
+        ListTarget::File(instance_dir) => {
+            let path = instance_dir.join("ops.json");
+            let mut entries: Vec<OpEntry> = read_json_list(&path)?;
+            entries.retain(|entry| !entry.name.eq_ignore_ascii_case(&player));
+            write_json_list(&path, &entries)
+        }
+    }
+}
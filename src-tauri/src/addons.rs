@@ -0,0 +1,307 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    filesystem,
+    instance::get_instance_by_id,
+    models::{AddonInfo, AddonLockfile, LockedAddon},
+};
+
+const DISABLED_SUFFIX: &str = ".disabled";
+
+/// Metadata declared inside an addon jar
+#[derive(Default)]
+struct Manifest {
+    id: Option<String>,
+    name: Option<String>,
+    version: Option<String>,
+    authors: Vec<String>,
+    description: Option<String>,
+}
+
+/// Jar files in `dir`, including ones disabled by renaming them to `.jar.disabled`
+pub fn addon_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            name.ends_with(".jar") || name.ends_with(".jar.disabled")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn read_entry<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Strip matching YAML quotes from a scalar
+fn yaml_scalar(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Read the handful of top-level keys nuko cares about from a plugin.yml. Only flat scalars
+/// and string lists (inline or block) are understood, which covers what Bukkit plugins use
+/// for these fields
+fn parse_plugin_yml(content: &str) -> Manifest {
+    let mut manifest = Manifest::default();
+    let mut in_authors = false;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with(' ') || line.starts_with('\t') || line.starts_with('-') {
+            if in_authors {
+                if let Some(author) = line.trim().strip_prefix('-') {
+                    manifest.authors.push(yaml_scalar(author));
+                }
+            }
+            continue;
+        }
+        in_authors = false;
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "name" => manifest.name = non_empty(&yaml_scalar(value)),
+            "version" => manifest.version = non_empty(&yaml_scalar(value)),
+            "description" => manifest.description = non_empty(&yaml_scalar(value)),
+            "author" => manifest.authors.extend(non_empty(&yaml_scalar(value))),
+            "authors" => {
+                if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                    manifest
+                        .authors
+                        .extend(list.split(',').filter_map(|a| non_empty(&yaml_scalar(a))));
+                } else if value.is_empty() {
+                    in_authors = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    manifest.id = manifest.name.clone();
+    manifest.authors.dedup();
+    manifest
+}
+
+fn parse_fabric_mod_json(content: &str) -> Option<Manifest> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let text = |key: &str| json.get(key).and_then(|v| v.as_str()).and_then(non_empty);
+
+    let authors = json
+        .get("authors")
+        .and_then(|v| v.as_array())
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| {
+                    author
+                        .as_str()
+                        .or_else(|| author.get("name").and_then(|n| n.as_str()))
+                        .and_then(non_empty)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Manifest {
+        id: text("id"),
+        name: text("name"),
+        version: text("version"),
+        authors,
+        description: text("description"),
+    })
+}
+
+fn parse_mods_toml(content: &str) -> Option<Manifest> {
+    let value: toml::Value = toml::from_str(content).ok()?;
+    let entry = value.get("mods")?.as_array()?.first()?;
+    let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).and_then(non_empty);
+
+    Some(Manifest {
+        id: text("modId"),
+        name: text("displayName"),
+        version: text("version"),
+        authors: text("authors")
+            .map(|authors| authors.split(',').filter_map(non_empty).collect::<Vec<_>>())
+            .unwrap_or_default(),
+        description: text("description"),
+    })
+}
+
+/// The `Implementation-Version` from the jar manifest, which Forge substitutes for
+/// `${file.jarVersion}` at runtime
+fn implementation_version<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Option<String> {
+    read_entry(archive, "META-INF/MANIFEST.MF")?
+        .lines()
+        .find_map(|line| line.strip_prefix("Implementation-Version:"))
+        .and_then(non_empty)
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    for name in ["paper-plugin.yml", "plugin.yml"] {
+        if let Some(content) = read_entry(&mut archive, name) {
+            return Ok(parse_plugin_yml(&content));
+        }
+    }
+    if let Some(manifest) =
+        read_entry(&mut archive, "fabric.mod.json").and_then(|c| parse_fabric_mod_json(&c))
+    {
+        return Ok(manifest);
+    }
+    for name in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(mut manifest) = read_entry(&mut archive, name).and_then(|c| parse_mods_toml(&c))
+        {
+            if manifest
+                .version
+                .as_deref()
+                .is_some_and(|version| version.starts_with("${"))
+            {
+                manifest.version = implementation_version(&mut archive);
+            }
+            return Ok(manifest);
+        }
+    }
+
+    Ok(Manifest::default())
+}
+
+/// Build the listing entry for a single jar, falling back to the file name when the jar
+/// has no readable metadata
+pub fn addon_info(path: &Path, kind: &str) -> AddonInfo {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let enabled = !file_name.ends_with(DISABLED_SUFFIX);
+    let stem = file_name
+        .trim_end_matches(DISABLED_SUFFIX)
+        .trim_end_matches(".jar")
+        .to_string();
+
+    let manifest = read_manifest(path).unwrap_or_else(|e| {
+        println!("{}", e);
+        Manifest::default()
+    });
+
+    AddonInfo {
+        kind: kind.to_string(),
+        id: manifest.id,
+        name: manifest.name.unwrap_or(stem),
+        version: manifest.version,
+        authors: manifest.authors,
+        description: manifest.description,
+        enabled,
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        file_name,
+    }
+}
+
+/// Plugins and mods installed in an instance, with the metadata declared in each jar
+#[tauri::command]
+pub async fn list_addons(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<AddonInfo>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        [("plugins", "plugin"), ("mods", "mod")]
+            .iter()
+            .flat_map(|(folder, kind)| {
+                addon_files(&instance_dir.join(folder))
+                    .into_iter()
+                    .map(move |path| addon_info(&path, kind))
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Failed to list addons: {}", e))
+}
+
+const LOCKFILE: &str = "addons.lock";
+
+/// Read the instance's `addons.lock`, which is empty until something is installed from a
+/// platform
+pub fn load_lockfile(instance_dir: &Path) -> Result<AddonLockfile, String> {
+    let path = instance_dir.join(LOCKFILE);
+    if !path.exists() {
+        return Ok(AddonLockfile::default());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", LOCKFILE, e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", LOCKFILE, e))
+}
+
+pub fn save_lockfile(instance_dir: &Path, lockfile: &AddonLockfile) -> Result<(), String> {
+    let content = toml::to_string_pretty(lockfile)
+        .map_err(|e| format!("Failed to serialize {}: {}", LOCKFILE, e))?;
+    fs::write(instance_dir.join(LOCKFILE), content)
+        .map_err(|e| format!("Failed to write {}: {}", LOCKFILE, e))
+}
+
+/// Record an installed addon, replacing any earlier entry for the same project. When the
+/// new version has a different file name the old jar is removed so both don't load
+pub fn record_install(instance_dir: &Path, addon: LockedAddon) -> Result<(), String> {
+    let mut lockfile = load_lockfile(instance_dir)?;
+
+    lockfile.addons.retain(|existing| {
+        let same = existing.source == addon.source && existing.project_id == addon.project_id;
+        if same && existing.file_name != addon.file_name {
+            let old = instance_dir
+                .join(&existing.folder)
+                .join(&existing.file_name);
+            let _ = fs::remove_file(&old);
+            let _ = fs::remove_file(
+                old.with_file_name(format!("{}{}", existing.file_name, DISABLED_SUFFIX)),
+            );
+        }
+        !same
+    });
+    lockfile.addons.push(addon);
+
+    save_lockfile(instance_dir, &lockfile)
+}
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, get_playit_tunnels, is_instance_running},
+    models::{ConnectionAddress, PlayitTunnelMetadata},
+    portmap, ports, protocol,
+};
+
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+const PUBLIC_IP_URL: &str = "https://api.ipify.org";
+/// Pings the address from the internet, so the verdict isn't fooled by NAT hairpinning
+const STATUS_CHECK_URL: &str = "https://api.mcsrvstat.us/3/";
+const DEFAULT_PORT: u16 = 25565;
+
+#[derive(Deserialize)]
+struct StatusCheck {
+    online: bool,
+}
+
+fn client() -> Result<Client, String> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// The address format players type in: bracketed IPv6 and no port when it's the default
+fn format_address(host: &str, port: u16) -> String {
+    let host = if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    };
+    if port == DEFAULT_PORT {
+        host
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// The tunnel players should use, preferring a public one over the tailnet-only address
+fn public_tunnel(tunnels: &[PlayitTunnelMetadata]) -> Option<(String, u16)> {
+    tunnels
+        .iter()
+        .filter(|tunnel| {
+            tunnel
+                .protocol
+                .as_deref()
+                .is_none_or(|protocol| protocol == "TCP" || protocol == "BOTH")
+        })
+        .filter_map(|tunnel| {
+            Some((
+                tunnel.id.as_deref() == Some("tailnet"),
+                tunnel.public_hostname.clone()?,
+                tunnel.public_port?,
+            ))
+        })
+        .min_by_key(|(tailnet_only, _, _)| *tailnet_only)
+        .map(|(_, host, port)| (host, port))
+}
+
+async fn public_ip() -> Result<String, String> {
+    client()?
+        .get(PUBLIC_IP_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to detect public IP: {}", e))?
+        .text()
+        .await
+        .map(|ip| ip.trim().to_string())
+        .map_err(|e| format!("Failed to detect public IP: {}", e))
+}
+
+async fn external_check(address: &str) -> Result<bool, String> {
+    let check: StatusCheck = client()?
+        .get(format!("{}{}", STATUS_CHECK_URL, address))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Status check failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse status check: {}", e))?;
+    Ok(check.online)
+}
+
+/// Where the instance can be reached from the internet
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    /// `tunnel`, `port_forward` or `direct`
+    pub source: &'static str,
+    pub public_ip: Option<String>,
+}
+
+/// The instance's active tunnel, else its router mapping, else the public IP and server port
+pub async fn public_endpoint(app_handle: &tauri::AppHandle, id: &str) -> Result<Endpoint, String> {
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let server_port = ports::server_port(&instance_dir);
+
+    let tunnels = get_playit_tunnels(app_handle.clone(), id.to_string()).await?;
+    let forwarded = portmap::status(id).filter(|status| status.active);
+    let (host, port, source, public_ip) = match (public_tunnel(&tunnels), forwarded) {
+        (Some((host, port)), _) => (host, port, "tunnel", None),
+        (None, Some(status)) => {
+            let ip = match status.external_ip {
+                Some(ip) => ip,
+                None => public_ip().await?,
+            };
+            let port = status.external_port.unwrap_or(server_port);
+            (ip.clone(), port, "port_forward", Some(ip))
+        }
+        (None, None) => {
+            let ip = public_ip().await?;
+            (ip.clone(), server_port, "direct", Some(ip))
+        }
+    };
+    Ok(Endpoint {
+        host,
+        port,
+        source,
+        public_ip,
+    })
+}
+
+/// Work out the address players should use for the instance (its tunnel, router mapping or
+/// public IP and port) and test whether a Server List Ping gets through to it
+#[tauri::command]
+pub async fn get_connection_address(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<ConnectionAddress, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let Endpoint {
+        host,
+        port,
+        source,
+        public_ip,
+    } = public_endpoint(&app_handle, &id).await?;
+    let address = format_address(&host, port);
+
+    let mut result = ConnectionAddress {
+        address: address.clone(),
+        host: host.clone(),
+        port,
+        source: source.to_string(),
+        public_ip,
+        reachable: false,
+        checked_from: None,
+        detail: None,
+    };
+    if !is_instance_running(&instance_dir) {
+        result.detail = Some("The server is not running".into());
+        return Ok(result);
+    }
+
+    match external_check(&address).await {
+        Ok(online) => {
+            result.reachable = online;
+            result.checked_from = Some("external".into());
+            if !online {
+                result.detail = Some(match source {
+                    "tunnel" => "The tunnel is up but isn't reaching the server".into(),
+                    _ => format!(
+                        "Nothing answered on port {} from the internet. Forward it on your router or use a tunnel",
+                        port
+                    ),
+                });
+            }
+        }
+        // Fall back to pinging from here, which only proves the address routes back to us
+        Err(check_error) => {
+            let ping = tauri::async_runtime::spawn_blocking(move || protocol::ping(&host, port))
+                .await
+                .map_err(|e| format!("Failed to ping server: {}", e))?;
+            result.checked_from = Some("local".into());
+            match ping {
+                Ok(_) => result.reachable = true,
+                Err(e) => result.detail = Some(format!("{}; {}", check_error, e)),
+            }
+        }
+    }
+    Ok(result)
+}
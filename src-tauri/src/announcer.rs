@@ -0,0 +1,109 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tauri::Manager;
+
+use crate::{
+    filesystem,
+    index::InstanceIndex,
+    instance::{
+        get_instance_by_id, has_instance_stdin, is_instance_online, update_instance_config,
+        write_instance_stdin,
+    },
+    models::{Announcement, AnnouncementFormat},
+};
+
+const TICK: Duration = Duration::from_secs(15);
+
+/// The console command that broadcasts an announcement to every player
+fn command(announcement: &Announcement) -> String {
+    match announcement.format {
+        AnnouncementFormat::Say => format!("say {}", announcement.message),
+        AnnouncementFormat::Tellraw => format!("tellraw @a {}", announcement.message),
+    }
+}
+
+fn validate(announcement: &Announcement) -> Result<(), String> {
+    if announcement.message.trim().is_empty() || announcement.message.contains(['\r', '\n']) {
+        return Err("Announcements must be a single non-empty line".into());
+    }
+    if announcement.interval_minutes == 0 {
+        return Err("Announcements must repeat at most once a minute".into());
+    }
+    if announcement.format == AnnouncementFormat::Tellraw {
+        serde_json::from_str::<serde_json::Value>(&announcement.message).map_err(|e| {
+            format!(
+                "'{}' is not a valid JSON text component: {}",
+                announcement.message, e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Broadcast each instance's enabled announcements on their interval while the server is
+/// online. The first broadcast comes one interval after the server finished starting
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // When each (instance, announcement) was last sent, or when its server came online
+        let mut last_sent: HashMap<(String, String), Instant> = HashMap::new();
+        loop {
+            tokio::time::sleep(TICK).await;
+
+            let instances = match filesystem::get_instances_dir(&app_handle)
+                .and_then(|instances_dir| app_handle.state::<InstanceIndex>().all(&instances_dir))
+            {
+                Ok(instances) => instances,
+                Err(e) => {
+                    println!("Failed to load instances for announcements: {}", e);
+                    continue;
+                }
+            };
+
+            let mut active = Vec::new();
+            for entry in instances {
+                let id = entry.config.id;
+                if !is_instance_online(&id) || !has_instance_stdin(&id) {
+                    continue;
+                }
+                for announcement in entry.config.announcements.iter().filter(|a| a.enabled) {
+                    let key = (id.clone(), announcement.id.clone());
+                    active.push(key.clone());
+                    let sent = last_sent.entry(key).or_insert_with(Instant::now);
+                    if sent.elapsed() < Duration::from_secs(announcement.interval_minutes * 60) {
+                        continue;
+                    }
+                    *sent = Instant::now();
+                    if let Err(e) = write_instance_stdin(&id, &command(announcement)) {
+                        println!("Failed to send announcement to {}: {}", id, e);
+                    }
+                }
+            }
+            // Forget servers that went offline so their timers restart when they're back
+            last_sent.retain(|key, _| active.contains(key));
+        }
+    });
+}
+
+/// Replace the instance's announcements. Ones without an id get a new one
+#[tauri::command]
+pub async fn set_announcements(
+    app_handle: tauri::AppHandle,
+    id: String,
+    announcements: Vec<Announcement>,
+) -> Result<Vec<Announcement>, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let mut announcements = announcements;
+    for announcement in &mut announcements {
+        announcement.message = announcement.message.trim().to_string();
+        validate(announcement)?;
+        if announcement.id.is_empty() {
+            announcement.id = uuid::Uuid::new_v4().to_string();
+        }
+    }
+    config.announcements = announcements.clone();
+    update_instance_config(&app_handle, &config)?;
+    Ok(announcements)
+}
@@ -0,0 +1,228 @@
+use crate::{models::MotdSegment, motd::COLORS};
+
+const ESC: char = '\u{1b}';
+const SECTION: char = '§';
+
+/// Legacy color names for the 16 standard ANSI colors, indexed by SGR 30-37 then 90-97
+const ANSI_COLORS: [&str; 16] = [
+    "black",
+    "dark_red",
+    "dark_green",
+    "gold",
+    "dark_blue",
+    "dark_purple",
+    "dark_aqua",
+    "gray",
+    "dark_gray",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "light_purple",
+    "aqua",
+    "white",
+];
+
+fn named(name: &str) -> (Option<String>, Option<String>) {
+    let hex = COLORS
+        .iter()
+        .find(|(_, color, _)| *color == name)
+        .map(|(_, _, hex)| hex.to_string());
+    (Some(name.to_string()), hex)
+}
+
+/// A truecolor value, named when it is exactly one of the legacy colors
+fn rgb(r: u8, g: u8, b: u8) -> (Option<String>, Option<String>) {
+    let hex = format!("#{:02X}{:02X}{:02X}", r, g, b);
+    let name = COLORS
+        .iter()
+        .find(|(_, _, known)| *known == hex)
+        .map(|(_, name, _)| name.to_string());
+    (name, Some(hex))
+}
+
+/// Color from the xterm 256-color palette
+fn palette(index: u8) -> (Option<String>, Option<String>) {
+    match index {
+        0..=15 => named(ANSI_COLORS[index as usize]),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            rgb(level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// Apply the parameters of one SGR (`ESC [ ... m`) sequence
+fn apply_sgr(params: &str, style: &mut MotdSegment) {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u16>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        let color = match code {
+            0 => {
+                *style = MotdSegment::default();
+                continue;
+            }
+            1 => {
+                style.bold = true;
+                continue;
+            }
+            3 => {
+                style.italic = true;
+                continue;
+            }
+            4 => {
+                style.underlined = true;
+                continue;
+            }
+            5 | 6 => {
+                style.obfuscated = true;
+                continue;
+            }
+            9 => {
+                style.strikethrough = true;
+                continue;
+            }
+            22 => {
+                style.bold = false;
+                continue;
+            }
+            23 => {
+                style.italic = false;
+                continue;
+            }
+            24 => {
+                style.underlined = false;
+                continue;
+            }
+            25 => {
+                style.obfuscated = false;
+                continue;
+            }
+            29 => {
+                style.strikethrough = false;
+                continue;
+            }
+            30..=37 => named(ANSI_COLORS[(code - 30) as usize]),
+            90..=97 => named(ANSI_COLORS[(code - 90 + 8) as usize]),
+            39 => (None, None),
+            38 => match codes.next() {
+                Some(5) => palette(codes.next().unwrap_or(0) as u8),
+                Some(2) => {
+                    let mut channel = || codes.next().unwrap_or(0).min(255) as u8;
+                    let (r, g, b) = (channel(), channel(), channel());
+                    rgb(r, g, b)
+                }
+                _ => continue,
+            },
+            // Background colors aren't rendered, but their arguments must be skipped
+            48 => {
+                match codes.next() {
+                    Some(5) => {
+                        codes.next();
+                    }
+                    Some(2) => {
+                        codes.nth(2);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        (style.color, style.hex) = color;
+    }
+}
+
+/// Split a console line into styled segments, understanding both ANSI escape sequences and
+/// legacy `§` codes. Other escape sequences (cursor movement etc.) are dropped
+pub fn parse(line: &str) -> Vec<MotdSegment> {
+    let mut segments = Vec::new();
+    let mut current = MotdSegment::default();
+    let mut chars = line.chars().peekable();
+
+    let mut restyle = |current: &mut MotdSegment, next: MotdSegment| {
+        let text = std::mem::take(&mut current.text);
+        if !text.is_empty() {
+            segments.push(MotdSegment {
+                text,
+                ..current.clone()
+            });
+        }
+        *current = next;
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => {
+                if chars.peek() != Some(&'[') {
+                    chars.next();
+                    continue;
+                }
+                chars.next();
+                let mut params = String::new();
+                let mut terminator = None;
+                for c in chars.by_ref() {
+                    if ('\u{40}'..='\u{7e}').contains(&c) {
+                        terminator = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if terminator == Some('m') {
+                    let mut next = MotdSegment {
+                        text: String::new(),
+                        ..current.clone()
+                    };
+                    apply_sgr(&params, &mut next);
+                    restyle(&mut current, next);
+                }
+            }
+            SECTION => {
+                let Some(code) = chars.next().map(|code| code.to_ascii_lowercase()) else {
+                    break;
+                };
+                let mut next = MotdSegment {
+                    text: String::new(),
+                    ..current.clone()
+                };
+                if let Some((_, name, hex)) = COLORS.iter().find(|(c, _, _)| *c == code) {
+                    next = MotdSegment {
+                        color: Some(name.to_string()),
+                        hex: Some(hex.to_string()),
+                        ..MotdSegment::default()
+                    };
+                } else {
+                    match code {
+                        'k' => next.obfuscated = true,
+                        'l' => next.bold = true,
+                        'm' => next.strikethrough = true,
+                        'n' => next.underlined = true,
+                        'o' => next.italic = true,
+                        'r' => next = MotdSegment::default(),
+                        _ => continue,
+                    }
+                }
+                restyle(&mut current, next);
+            }
+            c => current.text.push(c),
+        }
+    }
+    restyle(&mut current, MotdSegment::default());
+
+    segments
+}
+
+/// The text of parsed segments without any styling
+pub fn plain(segments: &[MotdSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
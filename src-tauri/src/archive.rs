@@ -0,0 +1,294 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use flate2::read::GzDecoder;
+use tar::EntryType;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Every file under `dir` for which `skip` (given the path relative to `root`) is false,
+/// depth first
+pub fn collect_files(
+    root: &Path,
+    dir: &Path,
+    skip: &impl Fn(&Path) -> bool,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    for entry in
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+    {
+        let path = entry
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .path();
+        if skip(path.strip_prefix(root).unwrap_or(&path)) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(root, &path, skip, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Zip `src` into `dest` with `src`'s folder name as the top-level entry. `on_progress` is
+/// called with (bytes written, total bytes) after each file
+pub fn zip_dir(src: &Path, dest: &Path, on_progress: impl FnMut(u64, u64)) -> Result<(), String> {
+    let root = src
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid directory {}", src.display()))?;
+    zip_dir_filtered(src, dest, Some(&root), |_| false, on_progress)
+}
+
+/// Zip the contents of `src` into `dest`, optionally nested under a `root` folder, leaving out
+/// anything `skip` returns true for
+pub fn zip_dir_filtered(
+    src: &Path,
+    dest: &Path,
+    root: Option<&str>,
+    skip: impl Fn(&Path) -> bool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(src, src, &skip, &mut files)?;
+    let total: u64 = files
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let file = fs::File::create(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(total > u32::MAX as u64);
+
+    let mut written = 0;
+    for path in files {
+        let relative = path.strip_prefix(src).unwrap_or(&path);
+        let name = match root {
+            Some(root) => Path::new(root).join(relative),
+            None => relative.to_path_buf(),
+        }
+        .to_string_lossy()
+        .replace('\\', "/");
+
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add {}: {}", path.display(), e))?;
+        let mut input = fs::File::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        written += io::copy(&mut input, &mut zip)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        on_progress(written, total);
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish {}: {}", dest.display(), e))?
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+}
+
+/// Zip several files and folders into `dest`. Each one is stored under its own name, so
+/// `plugins/Essentials` becomes `Essentials/...` in the archive
+pub fn zip_paths(
+    paths: &[PathBuf],
+    dest: &Path,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let base = path.parent().unwrap_or(path);
+        if path.is_dir() {
+            let mut files = Vec::new();
+            collect_files(path, path, &|_| false, &mut files)?;
+            entries.extend(files.into_iter().map(|file| (base.to_path_buf(), file)));
+        } else {
+            entries.push((base.to_path_buf(), path.clone()));
+        }
+    }
+    let total: u64 = entries
+        .iter()
+        .filter_map(|(_, path)| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    let file = fs::File::create(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(total > u32::MAX as u64);
+
+    let mut written = 0;
+    for (base, path) in entries {
+        let name = path
+            .strip_prefix(&base)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add {}: {}", path.display(), e))?;
+        let mut input = fs::File::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        written += io::copy(&mut input, &mut zip)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        on_progress(written, total);
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish {}: {}", dest.display(), e))?
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+}
+
+/// Extract `src` into `dest`. Entries that would land outside `dest` are rejected
+pub fn unzip_to(src: &Path, dest: &Path) -> Result<(), String> {
+    unzip_with_progress(src, dest, |_| Ok(()), |_, _| {})
+}
+
+/// Like `unzip_to`, calling `on_progress` with (bytes extracted, total uncompressed bytes)
+/// after each entry. `check_path` can reject an entry's destination before it's written
+pub fn unzip_with_progress(
+    src: &Path,
+    dest: &Path,
+    mut check_path: impl FnMut(&Path) -> Result<(), String>,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let file =
+        fs::File::open(src).map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    let total: u64 = (0..archive.len())
+        .filter_map(|i| archive.by_index_raw(i).ok().map(|entry| entry.size()))
+        .sum();
+
+    let mut written = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+        let relative = entry
+            .enclosed_name()
+            .filter(|relative| {
+                relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            })
+            .ok_or_else(|| format!("Unsafe path '{}' in {}", entry.name(), src.display()))?;
+        let path = dest.join(relative);
+        check_path(&path)?;
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut output = fs::File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        written += io::copy(&mut entry, &mut output)
+            .map_err(|e| format!("Failed to extract {}: {}", path.display(), e))?;
+        on_progress(written, total);
+    }
+
+    Ok(())
+}
+
+/// Counts bytes read so tar.gz progress can be reported against the compressed size
+struct CountingReader<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Extract a `.tar.gz` into `dest`, calling `on_progress` with (compressed bytes read,
+/// archive size). Only regular files and folders are extracted; links are skipped and paths
+/// that would escape `dest` or fail `check_path` are rejected
+pub fn untar_gz_with_progress(
+    src: &Path,
+    dest: &Path,
+    mut check_path: impl FnMut(&Path) -> Result<(), String>,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let file =
+        fs::File::open(src).map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let read = Arc::new(AtomicU64::new(0));
+    let mut archive = tar::Archive::new(GzDecoder::new(CountingReader {
+        inner: io::BufReader::new(file),
+        read: read.clone(),
+    }));
+
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?
+    {
+        let mut entry = entry.map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+        let relative = entry
+            .path()
+            .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?
+            .into_owned();
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "Unsafe path '{}' in {}",
+                relative.display(),
+                src.display()
+            ));
+        }
+        let path = dest.join(&relative);
+        check_path(&path)?;
+
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                fs::create_dir_all(&path)
+                    .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            }
+            EntryType::Regular | EntryType::Continuous => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+                }
+                let mut output = fs::File::create(&path)
+                    .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                io::copy(&mut entry, &mut output)
+                    .map_err(|e| format!("Failed to extract {}: {}", path.display(), e))?;
+                // Keep executable bits, e.g. for the binaries of a downloaded Java runtime
+                #[cfg(unix)]
+                if let Ok(mode) = entry.header().mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    let _ = fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o755));
+                }
+            }
+            _ => continue,
+        }
+        on_progress(read.load(Ordering::Relaxed), total);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::{fs, future::Future, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::{filesystem, models::AuditEntry};
+
+const DEFAULT_LIMIT: usize = 500;
+
+tokio::task_local! {
+    /// Who is acting in the current task, when it isn't the user
+    static ACTOR: &'static str;
+}
+
+/// Run `future` on behalf of `actor` (e.g. "scheduler"), so the actions it takes are
+/// attributed to it in the audit log instead of the user
+pub async fn as_actor<F: Future>(actor: &'static str, future: F) -> F::Output {
+    ACTOR.scope(actor, future).await
+}
+
+fn current_actor() -> &'static str {
+    ACTOR.try_with(|actor| *actor).unwrap_or("user")
+}
+
+/// Audit logs live outside the instance directory so they outlast the instance and aren't
+/// touched by restoring a backup
+fn audit_path(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("audit");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create audit dir: {}", e))?;
+    Ok(dir.join(format!("{}.jsonl", id)))
+}
+
+/// Record a state-changing action on an instance. Failing to write the audit log never
+/// fails the action itself
+pub fn record(app_handle: &tauri::AppHandle, id: &str, action: &str, params: Value) {
+    let entry = AuditEntry {
+        time: Utc::now().to_rfc3339(),
+        actor: current_actor().to_string(),
+        action: action.to_string(),
+        params,
+    };
+    let result =
+        audit_path(app_handle, id).and_then(|path| filesystem::append_json_line(&path, &entry));
+    if let Err(e) = result {
+        println!("Failed to record {} in the audit log: {}", action, e);
+    }
+}
+
+/// Top-level config fields that differ between two versions of nuko.toml
+pub fn changed_fields(old: &Value, new: &Value) -> Vec<String> {
+    // Bookkeeping nuko updates on its own isn't worth auditing
+    fn strip(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| {
+                        !matches!(
+                            key.as_str(),
+                            "last_played" | "play_time_minutes" | "last_run" | "last_error"
+                        )
+                    })
+                    .map(|(key, value)| (key.clone(), strip(value)))
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(strip).collect()),
+            other => other.clone(),
+        }
+    }
+
+    let (Value::Object(old), Value::Object(new)) = (strip(old), strip(new)) else {
+        return Vec::new();
+    };
+    let mut fields: Vec<String> = new
+        .iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .chain(old.keys().filter(|key| !new.contains_key(*key)).cloned())
+        .collect();
+    fields.sort();
+    fields
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid time '{}': {}", value, e))
+}
+
+/// Actions taken on an instance between `since` and `until` (RFC 3339, both optional),
+/// newest first and at most `limit` of them
+#[tauri::command]
+pub async fn get_audit_log(
+    app_handle: tauri::AppHandle,
+    id: String,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    let since = since.as_deref().map(parse_time).transpose()?;
+    let until = until.as_deref().map(parse_time).transpose()?;
+
+    let content = match fs::read_to_string(audit_path(&app_handle, &id)?) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read audit log: {}", e)),
+    };
+
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|entry| {
+            let Ok(time) = parse_time(&entry.time) else {
+                return false;
+            };
+            since.is_none_or(|since| time >= since) && until.is_none_or(|until| time <= until)
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT))
+        .collect())
+}
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    audit,
+    instance::{self, get_instance_by_id, update_instance_config},
+    models::{AutomationAction, AutomationRule, AutomationTrigger},
+    secrets,
+};
+
+/// What happened in the console, as far as automations are concerned
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    PlayerJoined,
+    PlayerLeft,
+    ServerEmpty,
+    ServerReady,
+    ServerStopping,
+    Chat,
+    LogLine,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerEvent {
+    pub kind: TriggerKind,
+    pub player: Option<String>,
+    pub message: Option<String>,
+    pub line: String,
+}
+
+/// An instance's enabled rules with their regexes compiled once
+struct CompiledRule {
+    rule: AutomationRule,
+    pattern: Option<Regex>,
+}
+
+fn get_rule_cache() -> &'static Mutex<HashMap<String, Arc<Vec<CompiledRule>>>> {
+    static RULES: OnceLock<Mutex<HashMap<String, Arc<Vec<CompiledRule>>>>> = OnceLock::new();
+    RULES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Bumped to call off delayed actions, which only run if the generation they were scheduled
+/// in is still current
+fn get_generations() -> &'static Mutex<HashMap<String, u64>> {
+    static GENERATIONS: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    GENERATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn generation(id: &str) -> u64 {
+    *get_generations().lock().unwrap().get(id).unwrap_or(&0)
+}
+
+fn cancel_pending(id: &str) {
+    *get_generations()
+        .lock()
+        .unwrap()
+        .entry(id.to_string())
+        .or_default() += 1;
+}
+
+fn compile(rules: Vec<AutomationRule>) -> Vec<CompiledRule> {
+    rules
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| {
+            let pattern = match &rule.trigger {
+                AutomationTrigger::Chat { pattern: Some(p) }
+                | AutomationTrigger::LogMatch { pattern: p } => match Regex::new(p) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        println!("Skipping automation '{}': {}", rule.name, e);
+                        return None;
+                    }
+                },
+                _ => None,
+            };
+            Some(CompiledRule { rule, pattern })
+        })
+        .collect()
+}
+
+fn rules_for(app_handle: &tauri::AppHandle, id: &str) -> Arc<Vec<CompiledRule>> {
+    if let Some(rules) = get_rule_cache().lock().unwrap().get(id) {
+        return rules.clone();
+    }
+    let rules = Arc::new(compile(
+        get_instance_by_id(app_handle, id)
+            .map(|config| config.automations)
+            .unwrap_or_default(),
+    ));
+    get_rule_cache()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), rules.clone());
+    rules
+}
+
+/// Drop the compiled rules and any pending actions, e.g. once the server has exited. Rules
+/// are reloaded from nuko.toml on the next event
+pub fn clear(id: &str) {
+    get_rule_cache().lock().unwrap().remove(id);
+    cancel_pending(id);
+}
+
+fn matches(compiled: &CompiledRule, event: &TriggerEvent) -> bool {
+    match (&compiled.rule.trigger, &event.kind) {
+        (AutomationTrigger::PlayerJoined, TriggerKind::PlayerJoined)
+        | (AutomationTrigger::PlayerLeft, TriggerKind::PlayerLeft)
+        | (AutomationTrigger::ServerEmpty, TriggerKind::ServerEmpty)
+        | (AutomationTrigger::ServerReady, TriggerKind::ServerReady)
+        | (AutomationTrigger::ServerStopping, TriggerKind::ServerStopping) => true,
+        (AutomationTrigger::Chat { .. }, TriggerKind::Chat) => {
+            match (&compiled.pattern, &event.message) {
+                (Some(pattern), Some(message)) => pattern.is_match(message),
+                (None, _) => true,
+                _ => false,
+            }
+        }
+        (AutomationTrigger::LogMatch { .. }, TriggerKind::LogLine) => compiled
+            .pattern
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_match(&event.line)),
+        _ => false,
+    }
+}
+
+fn fill_placeholders(template: &str, event: &TriggerEvent) -> String {
+    template
+        .replace("{player}", event.player.as_deref().unwrap_or(""))
+        .replace("{message}", event.message.as_deref().unwrap_or(""))
+        .replace("{line}", &event.line)
+}
+
+async fn execute(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    rule: &AutomationRule,
+    event: &TriggerEvent,
+) -> Result<(), String> {
+    match &rule.action {
+        AutomationAction::Command { command } => {
+            // Console input is line based, so a placeholder can't smuggle in a second command
+            let command = fill_placeholders(command, event).replace(['\r', '\n'], " ");
+            instance::write_instance_stdin(id, &command)?;
+            audit::record(
+                app_handle,
+                id,
+                "command",
+                serde_json::json!({ "command": command, "rule": rule.name }),
+            );
+            Ok(())
+        }
+        AutomationAction::Webhook { url } => {
+            // The URL embeds the webhook's token, so it's kept out of errors and logs
+            let url = &secrets::load_credential(app_handle, url)?;
+            let response = reqwest::Client::new()
+                .post(url)
+                .timeout(Duration::from_secs(10))
+                .json(&serde_json::json!({
+                    "instance_id": id,
+                    "rule": rule.name,
+                    "event": event,
+                }))
+                .send()
+                .await
+                .map_err(|e| format!("Webhook of '{}' failed: {}", rule.name, e.without_url()))?;
+            if !response.status().is_success() {
+                return Err(format!(
+                    "Webhook of '{}' returned HTTP {}",
+                    rule.name,
+                    response.status()
+                ));
+            }
+            Ok(())
+        }
+        AutomationAction::Stop => instance::stop_instance(app_handle.clone(), id.to_string()).await,
+        AutomationAction::Restart => {
+            instance::restart_instance(app_handle.clone(), id.to_string()).await
+        }
+        AutomationAction::CancelPending => {
+            cancel_pending(id);
+            Ok(())
+        }
+    }
+}
+
+/// Run every enabled rule of the instance that `event` triggers. Delayed actions are dropped
+/// if a `cancel_pending` action (or the server exiting) happens before they are due
+pub fn dispatch(app_handle: &tauri::AppHandle, id: &str, event: TriggerEvent) {
+    let rules = rules_for(app_handle, id);
+    for compiled in rules.iter().filter(|compiled| matches(compiled, &event)) {
+        let app_handle = app_handle.clone();
+        let id = id.to_string();
+        let rule = compiled.rule.clone();
+        let event = event.clone();
+        let scheduled_in = generation(&id);
+
+        tauri::async_runtime::spawn(async move {
+            if rule.delay_secs > 0 {
+                tokio::time::sleep(Duration::from_secs(rule.delay_secs)).await;
+                if generation(&id) != scheduled_in {
+                    return;
+                }
+            }
+            let result = audit::as_actor("automation", execute(&app_handle, &id, &rule, &event));
+            if let Err(e) = result.await {
+                println!("Automation '{}' failed: {}", rule.name, e);
+            }
+        });
+    }
+}
+
+fn validate(trigger: &AutomationTrigger, action: &AutomationAction) -> Result<(), String> {
+    match trigger {
+        AutomationTrigger::Chat { pattern: Some(p) }
+        | AutomationTrigger::LogMatch { pattern: p } => {
+            Regex::new(p).map_err(|e| format!("Invalid pattern '{}': {}", p, e))?;
+        }
+        _ => {}
+    }
+    match action {
+        AutomationAction::Command { command } if command.trim().is_empty() => {
+            Err("Command cannot be empty".into())
+        }
+        AutomationAction::Webhook { url }
+            if !(url.starts_with("https://") || url.starts_with("http://")) =>
+        {
+            Err("Webhook URLs must start with http:// or https://".into())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_automations(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<AutomationRule>, String> {
+    Ok(get_instance_by_id(&app_handle, &id)?.automations)
+}
+
+#[tauri::command]
+pub async fn add_automation(
+    app_handle: tauri::AppHandle,
+    id: String,
+    name: String,
+    trigger: AutomationTrigger,
+    action: AutomationAction,
+    delay_secs: Option<u64>,
+) -> Result<AutomationRule, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    validate(&trigger, &action)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Automation name cannot be empty".into());
+    }
+
+    let rule_id = uuid::Uuid::new_v4().to_string();
+    // Webhook URLs embed their token, so they're kept in the credential store
+    let action = match action {
+        AutomationAction::Webhook { url } => AutomationAction::Webhook {
+            url: secrets::store_credential(
+                &app_handle,
+                &format!("webhook:{}:{}", id, rule_id),
+                url.trim(),
+            )?,
+        },
+        action => action,
+    };
+    let rule = AutomationRule {
+        id: rule_id,
+        name: name.to_string(),
+        trigger,
+        action,
+        delay_secs: delay_secs.unwrap_or(0),
+        enabled: true,
+    };
+    config.automations.push(rule.clone());
+    update_instance_config(&app_handle, &config)?;
+    get_rule_cache().lock().unwrap().remove(&id);
+
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn remove_automation(
+    app_handle: tauri::AppHandle,
+    id: String,
+    rule_id: String,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let index = config
+        .automations
+        .iter()
+        .position(|rule| rule.id == rule_id)
+        .ok_or_else(|| format!("Automation {} not found", rule_id))?;
+    if let AutomationAction::Webhook { url } = config.automations.remove(index).action {
+        secrets::delete_credential(&url);
+    }
+    update_instance_config(&app_handle, &config)?;
+    get_rule_cache().lock().unwrap().remove(&id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_automation_enabled(
+    app_handle: tauri::AppHandle,
+    id: String,
+    rule_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let rule = config
+        .automations
+        .iter_mut()
+        .find(|rule| rule.id == rule_id)
+        .ok_or_else(|| format!("Automation {} not found", rule_id))?;
+    rule.enabled = enabled;
+    update_instance_config(&app_handle, &config)?;
+    get_rule_cache().lock().unwrap().remove(&id);
+    Ok(())
+}
@@ -0,0 +1,735 @@
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+use tauri::{Emitter, Manager};
+
+use crate::{
+    archive::{unzip_to, zip_dir_filtered},
+    audit, config, filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, is_instance_running, update_instance_config},
+    models::{
+        BackupInfo, BackupProgress, BackupUploadProgress, BackupUsage, InstanceConfig, JarChange,
+        RemoteTarget, RetentionPolicy, SnapshotManifest, TimelineKind,
+    },
+    notifications::{self, NotificationKind},
+    remote, snapshot, timeline,
+    world::with_saving_paused,
+};
+
+/// Hashes of incremental backup objects already mirrored to the remote target
+const UPLOADED_OBJECTS: &str = "remote-objects.txt";
+/// Emit upload progress at most once per this many bytes
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// Top-level entries that are regenerated, re-downloaded or only kept for debugging, so not
+/// worth backing up. nuko.toml is left out so restoring never rolls back nuko's own settings
+const EXCLUDED: [&str; 7] = [
+    "nuko.toml",
+    "logs",
+    "diagnostics",
+    "cache",
+    "libraries",
+    "versions",
+    ".fabric",
+];
+
+fn is_excluded(relative: &Path) -> bool {
+    relative
+        .components()
+        .next()
+        .map(|first| EXCLUDED.contains(&first.as_os_str().to_string_lossy().as_ref()))
+        .unwrap_or(false)
+}
+
+/// data_dir/backups/<instance id>, created on demand
+pub fn get_backups_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let backups_dir = filesystem::get_data_dir(app_handle)?
+        .join("backups")
+        .join(id);
+    fs::create_dir_all(&backups_dir)
+        .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+    Ok(backups_dir)
+}
+
+fn metadata_path(backups_dir: &Path, backup_id: &str) -> PathBuf {
+    backups_dir.join(format!("{}.toml", backup_id))
+}
+
+fn archive_path(backups_dir: &Path, backup_id: &str) -> PathBuf {
+    backups_dir.join(format!("{}.zip", backup_id))
+}
+
+fn manifest_path(backups_dir: &Path, backup_id: &str) -> PathBuf {
+    backups_dir.join(format!("{}.manifest.json", backup_id))
+}
+
+/// Content-addressed files shared by every incremental backup of the instance
+fn objects_dir(backups_dir: &Path) -> PathBuf {
+    backups_dir.join("objects")
+}
+
+fn read_manifest(backups_dir: &Path, backup_id: &str) -> Result<SnapshotManifest, String> {
+    let content = fs::read_to_string(manifest_path(backups_dir, backup_id))
+        .map_err(|e| format!("Failed to read manifest for backup {}: {}", backup_id, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse manifest for backup {}: {}", backup_id, e))
+}
+
+/// Drop stored objects that no remaining incremental backup uses
+fn prune_objects(backups_dir: &Path) -> Result<(), String> {
+    let manifests: Vec<SnapshotManifest> = read_backups(backups_dir)?
+        .iter()
+        .filter(|backup| backup.incremental)
+        .map(|backup| read_manifest(backups_dir, &backup.id))
+        .collect::<Result<_, _>>()?;
+    snapshot::collect_garbage(&objects_dir(backups_dir), &manifests)?;
+    Ok(())
+}
+
+/// Resolve a backup id to its archive, rejecting ids that aren't backups of this instance
+fn find_backup(backups_dir: &Path, backup_id: &str) -> Result<BackupInfo, String> {
+    if backup_id.is_empty() || backup_id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid backup id '{}'", backup_id));
+    }
+    let content = fs::read_to_string(metadata_path(backups_dir, backup_id))
+        .map_err(|_| format!("Backup {} not found", backup_id))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse backup metadata: {}", e))
+}
+
+/// Timestamped id, with a counter if two backups land in the same second
+fn new_backup_id(backups_dir: &Path) -> String {
+    let base = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut backup_id = base.clone();
+    let mut n = 1;
+    while metadata_path(backups_dir, &backup_id).exists() {
+        n += 1;
+        backup_id = format!("{}-{}", base, n);
+    }
+    backup_id
+}
+
+/// Back up the instance directory into backups/<instance>/, as a zip or, with
+/// `incremental_backups`, as a manifest over the shared object store. Running servers are
+/// flushed and have autosave paused while files are read. Progress is emitted as
+/// `backup-progress`, and failures raise a notification
+pub async fn create_backup_internal(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    note: String,
+    kind: &str,
+    jar_change: Option<JarChange>,
+) -> Result<BackupInfo, String> {
+    let result = run_backup(app_handle, id, note, kind, jar_change).await;
+    if let Ok(info) = &result {
+        audit::record(
+            app_handle,
+            id,
+            "backup",
+            serde_json::json!({ "backup_id": info.id, "kind": kind, "note": info.note }),
+        );
+        timeline::record(
+            app_handle,
+            id,
+            TimelineKind::Backup,
+            Some(kind.to_string()),
+            None,
+            None,
+        );
+    }
+    if let Err(e) = &result {
+        let name = get_instance_by_id(app_handle, id)
+            .map(|config| config.name)
+            .unwrap_or_else(|_| id.to_string());
+        notifications::notify(
+            app_handle,
+            NotificationKind::BackupFailed,
+            &format!("Backup of {} failed", name),
+            e,
+        );
+    }
+    result
+}
+
+async fn run_backup(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    note: String,
+    kind: &str,
+    jar_change: Option<JarChange>,
+) -> Result<BackupInfo, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let backups_dir = get_backups_dir(app_handle, id)?;
+    let backup_id = new_backup_id(&backups_dir);
+
+    let on_progress = {
+        let app_handle = app_handle.clone();
+        let id = id.to_string();
+        let backup_id = backup_id.clone();
+        move |bytes_done, bytes_total| {
+            let _ = app_handle.emit(
+                "backup-progress",
+                BackupProgress {
+                    id: id.clone(),
+                    backup_id: backup_id.clone(),
+                    bytes_done,
+                    bytes_total,
+                },
+            );
+        }
+    };
+
+    let size_bytes = if config.incremental_backups {
+        let previous = read_backups(&backups_dir)?
+            .iter()
+            .find(|backup| backup.incremental)
+            .and_then(|backup| read_manifest(&backups_dir, &backup.id).ok());
+        let objects = objects_dir(&backups_dir);
+
+        let (manifest, stored) = with_saving_paused(id, move || {
+            snapshot::snapshot(
+                &instance_dir,
+                &objects,
+                previous.as_ref(),
+                is_excluded,
+                on_progress,
+            )
+        })
+        .await?;
+
+        let json = serde_json::to_string(&manifest)
+            .map_err(|e| format!("Failed to serialize backup manifest: {}", e))?;
+        fs::write(manifest_path(&backups_dir, &backup_id), json)
+            .map_err(|e| format!("Failed to write backup manifest: {}", e))?;
+        stored
+    } else {
+        let archive = archive_path(&backups_dir, &backup_id);
+        let dest = archive.clone();
+        with_saving_paused(id, move || {
+            zip_dir_filtered(&instance_dir, &dest, None, is_excluded, on_progress)
+        })
+        .await
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&archive);
+        })?;
+        fs::metadata(&archive).map(|m| m.len()).unwrap_or(0)
+    };
+
+    let info = BackupInfo {
+        id: backup_id.clone(),
+        instance_id: id.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        note,
+        kind: kind.to_string(),
+        size_bytes,
+        incremental: config.incremental_backups,
+        jar_change,
+    };
+    let toml_string = toml::to_string_pretty(&info)
+        .map_err(|e| format!("Failed to serialize backup metadata: {}", e))?;
+    fs::write(metadata_path(&backups_dir, &backup_id), toml_string)
+        .map_err(|e| format!("Failed to write backup metadata: {}", e))?;
+
+    if kind == "scheduled" {
+        enforce_retention(app_handle, id)?;
+    }
+
+    if let Some(target) = config::get_config(app_handle.clone())?.backup_remote {
+        let app_handle = app_handle.clone();
+        let id = id.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            // Failures are reported through the upload progress event
+            let _ = mirror_backup(&app_handle, &target, &id, &backup_id);
+        });
+    }
+
+    Ok(info)
+}
+
+/// Files making up a backup as (local path, remote path), data first so the metadata only
+/// appears remotely once everything it refers to is there
+fn backup_upload_files(
+    backups_dir: &Path,
+    id: &str,
+    info: &BackupInfo,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut files = Vec::new();
+
+    if info.incremental {
+        let uploaded = fs::read_to_string(backups_dir.join(UPLOADED_OBJECTS)).unwrap_or_default();
+        let uploaded: HashSet<&str> = uploaded.lines().collect();
+        let mut queued = HashSet::new();
+        for entry in read_manifest(backups_dir, &info.id)?.files {
+            if uploaded.contains(entry.hash.as_str()) || !queued.insert(entry.hash.clone()) {
+                continue;
+            }
+            files.push((
+                objects_dir(backups_dir)
+                    .join(&entry.hash[..2])
+                    .join(&entry.hash),
+                format!("{}/objects/{}/{}", id, &entry.hash[..2], entry.hash),
+            ));
+        }
+        files.push((
+            manifest_path(backups_dir, &info.id),
+            format!("{}/{}.manifest.json", id, info.id),
+        ));
+    } else {
+        files.push((
+            archive_path(backups_dir, &info.id),
+            format!("{}/{}.zip", id, info.id),
+        ));
+    }
+
+    files.push((
+        metadata_path(backups_dir, &info.id),
+        format!("{}/{}.toml", id, info.id),
+    ));
+    Ok(files)
+}
+
+/// Upload every file of a backup, returning the bytes sent
+fn upload_backup_files(
+    app_handle: &tauri::AppHandle,
+    target: &RemoteTarget,
+    id: &str,
+    backup_id: &str,
+) -> Result<u64, String> {
+    let backups_dir = get_backups_dir(app_handle, id)?;
+    let info = find_backup(&backups_dir, backup_id)?;
+    let files = backup_upload_files(&backups_dir, id, &info)?;
+    let total: u64 = files
+        .iter()
+        .filter_map(|(path, _)| fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+
+    let mut done = 0;
+    for (local, remote_path) in files {
+        let progress = {
+            let app_handle = app_handle.clone();
+            let id = id.to_string();
+            let backup_id = backup_id.to_string();
+            let mut last = 0;
+            move |sent: u64| {
+                if sent.saturating_sub(last) < PROGRESS_STEP {
+                    return;
+                }
+                last = sent;
+                let _ = app_handle.emit(
+                    "backup-upload-progress",
+                    BackupUploadProgress {
+                        id: id.clone(),
+                        backup_id: backup_id.clone(),
+                        bytes_done: done + sent,
+                        bytes_total: total,
+                        finished: false,
+                        error: None,
+                    },
+                );
+            }
+        };
+        remote::upload_file(app_handle, target, &local, &remote_path, progress)?;
+        done += fs::metadata(&local).map(|m| m.len()).unwrap_or(0);
+
+        if remote_path.contains("/objects/") {
+            let hash = remote_path.rsplit('/').next().unwrap_or_default();
+            let mut record = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(backups_dir.join(UPLOADED_OBJECTS))
+                .map_err(|e| format!("Failed to record uploaded object: {}", e))?;
+            writeln!(record, "{}", hash)
+                .map_err(|e| format!("Failed to record uploaded object: {}", e))?;
+        }
+    }
+    Ok(total)
+}
+
+/// Copy a backup to the remote target, emitting `backup-upload-progress`. Objects already
+/// mirrored for earlier incremental backups are skipped
+fn mirror_backup(
+    app_handle: &tauri::AppHandle,
+    target: &RemoteTarget,
+    id: &str,
+    backup_id: &str,
+) -> Result<(), String> {
+    let emit = |bytes_done: u64, bytes_total: u64, finished: bool, error: Option<String>| {
+        let _ = app_handle.emit(
+            "backup-upload-progress",
+            BackupUploadProgress {
+                id: id.to_string(),
+                backup_id: backup_id.to_string(),
+                bytes_done,
+                bytes_total,
+                finished,
+                error,
+            },
+        );
+    };
+
+    match upload_backup_files(app_handle, target, id, backup_id) {
+        Ok(total) => {
+            emit(total, total, true, None);
+            Ok(())
+        }
+        Err(e) => {
+            emit(0, 0, true, Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+/// Mirror a backup to the configured remote target, e.g. to retry a failed upload
+#[tauri::command]
+pub async fn upload_backup(
+    app_handle: tauri::AppHandle,
+    id: String,
+    backup_id: String,
+) -> Result<(), String> {
+    let target = config::get_config(app_handle.clone())?
+        .backup_remote
+        .ok_or("No remote backup target is configured")?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        mirror_backup(&app_handle, &target, &id, &backup_id)
+    })
+    .await
+    .map_err(|e| format!("Failed to upload backup: {}", e))?
+}
+
+#[tauri::command]
+pub async fn create_backup(
+    app_handle: tauri::AppHandle,
+    id: String,
+    note: Option<String>,
+) -> Result<BackupInfo, String> {
+    create_backup_internal(&app_handle, &id, note.unwrap_or_default(), "manual", None).await
+}
+
+/// Every backup in the directory, newest first
+fn read_backups(backups_dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let mut backups: Vec<BackupInfo> = fs::read_dir(backups_dir)
+        .map_err(|e| format!("Failed to read backups directory: {}", e))?
+        .flatten()
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("toml"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| toml::from_str::<BackupInfo>(&content).ok())
+        .collect();
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<BackupInfo>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    read_backups(&get_backups_dir(&app_handle, &id)?)
+}
+
+fn remove_backup_files(backups_dir: &Path, backup_id: &str) -> Result<(), String> {
+    let archive = archive_path(backups_dir, backup_id);
+    if archive.exists() {
+        fs::remove_file(&archive).map_err(|e| format!("Failed to delete backup: {}", e))?;
+    }
+    let manifest = manifest_path(backups_dir, backup_id);
+    if manifest.exists() {
+        fs::remove_file(&manifest)
+            .map_err(|e| format!("Failed to delete backup manifest: {}", e))?;
+    }
+    fs::remove_file(metadata_path(backups_dir, backup_id))
+        .map_err(|e| format!("Failed to delete backup metadata: {}", e))
+}
+
+/// Ids of the scheduled backups `policy` no longer keeps. Manual backups and the safety
+/// backups taken before updates and restores are never pruned. `backups` must be newest first
+fn backups_to_prune(backups: &[BackupInfo], policy: &RetentionPolicy) -> Vec<String> {
+    if policy.is_unset() {
+        return Vec::new();
+    }
+
+    let now = Utc::now();
+    let scheduled: Vec<(&BackupInfo, DateTime<Utc>)> = backups
+        .iter()
+        .filter(|backup| backup.kind == "scheduled")
+        .filter_map(|backup| {
+            let created = DateTime::parse_from_rfc3339(&backup.created_at).ok()?;
+            Some((backup, created.with_timezone(&Utc)))
+        })
+        .collect();
+
+    let mut keep: HashSet<&str> = HashSet::new();
+    let keep_last = policy.keep_last.unwrap_or(0) as usize;
+    keep.extend(scheduled.iter().take(keep_last).map(|(b, _)| b.id.as_str()));
+
+    if let Some(days) = policy.keep_daily {
+        let cutoff = now - Duration::days(days as i64);
+        let mut seen = HashSet::new();
+        for (backup, created) in &scheduled {
+            if *created > cutoff && seen.insert(created.date_naive()) {
+                keep.insert(&backup.id);
+            }
+        }
+    }
+
+    if let Some(weeks) = policy.keep_weekly {
+        let cutoff = now - Duration::weeks(weeks as i64);
+        let mut seen = HashSet::new();
+        for (backup, created) in &scheduled {
+            let week = created.iso_week();
+            if *created > cutoff && seen.insert((week.year(), week.week())) {
+                keep.insert(&backup.id);
+            }
+        }
+    }
+
+    scheduled
+        .iter()
+        .filter(|(backup, _)| !keep.contains(backup.id.as_str()))
+        .map(|(backup, _)| backup.id.clone())
+        .collect()
+}
+
+/// Delete scheduled backups that fall outside the instance's retention policy. Returns the
+/// ids that were removed
+pub fn enforce_retention(app_handle: &tauri::AppHandle, id: &str) -> Result<Vec<String>, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let backups_dir = get_backups_dir(app_handle, id)?;
+    let pruned = backups_to_prune(&read_backups(&backups_dir)?, &config.backup_retention);
+
+    for backup_id in &pruned {
+        remove_backup_files(&backups_dir, backup_id)?;
+    }
+    if !pruned.is_empty() {
+        prune_objects(&backups_dir)?;
+    }
+    Ok(pruned)
+}
+
+#[tauri::command]
+pub async fn set_incremental_backups(
+    app_handle: tauri::AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.incremental_backups = enabled;
+    update_instance_config(&app_handle, &config)
+}
+
+#[tauri::command]
+pub async fn set_backup_retention(
+    app_handle: tauri::AppHandle,
+    id: String,
+    retention: RetentionPolicy,
+) -> Result<Vec<String>, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.backup_retention = retention;
+    update_instance_config(&app_handle, &config)?;
+    enforce_retention(&app_handle, &id)
+}
+
+/// Disk used by backups, per instance
+#[tauri::command]
+pub async fn get_backup_usage(app_handle: tauri::AppHandle) -> Result<Vec<BackupUsage>, String> {
+    let data_dir = filesystem::get_data_dir(&app_handle)?;
+    let instances_dir = filesystem::get_instances_dir(&app_handle)?;
+
+    let mut usage: Vec<BackupUsage> = app_handle
+        .state::<InstanceIndex>()
+        .all(&instances_dir)?
+        .into_iter()
+        .map(|entry| {
+            let backups_dir = data_dir.join("backups").join(&entry.config.id);
+            BackupUsage {
+                count: read_backups(&backups_dir).map(|b| b.len()).unwrap_or(0),
+                total_bytes: filesystem::dir_size(&backups_dir),
+                instance_id: entry.config.id,
+                name: entry.config.name,
+            }
+        })
+        .collect();
+
+    usage.sort_by_key(|u| Reverse(u.total_bytes));
+    Ok(usage)
+}
+
+#[tauri::command]
+pub async fn delete_backup(
+    app_handle: tauri::AppHandle,
+    id: String,
+    backup_id: String,
+) -> Result<(), String> {
+    let backups_dir = get_backups_dir(&app_handle, &id)?;
+    let info = find_backup(&backups_dir, &backup_id)?;
+    remove_backup_files(&backups_dir, &backup_id)?;
+    if info.incremental {
+        prune_objects(&backups_dir)?;
+    }
+    audit::record(
+        &app_handle,
+        &id,
+        "delete_backup",
+        serde_json::json!({ "backup_id": backup_id }),
+    );
+    Ok(())
+}
+
+/// Replace the instance's files with a backup. The server must be stopped, and a
+/// "pre-restore" backup of the current state is taken first so the restore can be undone
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: tauri::AppHandle,
+    id: String,
+    backup_id: String,
+) -> Result<BackupInfo, String> {
+    let (_, safety) = restore_backup_internal(&app_handle, &id, &backup_id).await?;
+    audit::record(
+        &app_handle,
+        &id,
+        "restore",
+        serde_json::json!({ "backup_id": backup_id, "safety_backup_id": safety.id }),
+    );
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(safety)
+}
+
+/// Restore a backup, returning it along with the safety backup taken beforehand
+async fn restore_backup_internal(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    backup_id: &str,
+) -> Result<(BackupInfo, BackupInfo), String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before restoring a backup", config.name));
+    }
+
+    let backups_dir = get_backups_dir(app_handle, id)?;
+    let info = find_backup(&backups_dir, backup_id)?;
+    // Load everything the restore needs up front so a missing file fails before anything
+    // in the instance is touched
+    let manifest = if info.incremental {
+        Some(read_manifest(&backups_dir, backup_id)?)
+    } else {
+        None
+    };
+    let archive = archive_path(&backups_dir, backup_id);
+    if manifest.is_none() && !archive.exists() {
+        return Err(format!("Backup archive {} is missing", backup_id));
+    }
+
+    let safety = create_backup_internal(
+        app_handle,
+        id,
+        format!("Before restoring {}", backup_id),
+        "pre-restore",
+        None,
+    )
+    .await?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        for entry in fs::read_dir(&instance_dir)
+            .map_err(|e| format!("Failed to read instance directory: {}", e))?
+            .flatten()
+        {
+            if is_excluded(Path::new(&entry.file_name())) {
+                continue;
+            }
+            let path = entry.path();
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+
+        match manifest {
+            Some(manifest) => {
+                snapshot::restore(&manifest, &objects_dir(&backups_dir), &instance_dir)
+            }
+            None => unzip_to(&archive, &instance_dir),
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to restore backup: {}", e))??;
+
+    timeline::record(
+        app_handle,
+        id,
+        TimelineKind::Restored,
+        Some(info.created_at.clone()),
+        None,
+        None,
+    );
+    Ok((info, safety))
+}
+
+/// Take a backup before server.jar is replaced, recording the software it ran until now
+pub async fn backup_before_jar_change(
+    app_handle: &tauri::AppHandle,
+    config: &InstanceConfig,
+    reason: &str,
+) -> Result<BackupInfo, String> {
+    let jar_change = JarChange {
+        reason: reason.to_string(),
+        software: config.software.clone(),
+        version: config.version.clone(),
+        loader: config.loader.clone(),
+        build: config.build.clone(),
+        custom_jar_path: config.custom_jar_path.clone(),
+    };
+    create_backup_internal(
+        app_handle,
+        &config.id,
+        format!("Before {}", reason),
+        "pre-update",
+        Some(jar_change),
+    )
+    .await
+}
+
+/// Undo a server.jar change by restoring the backup taken before it and switching the
+/// instance back to the software and version it ran then
+#[tauri::command]
+pub async fn rollback_jar_change(
+    app_handle: tauri::AppHandle,
+    id: String,
+    backup_id: String,
+) -> Result<BackupInfo, String> {
+    let backups_dir = get_backups_dir(&app_handle, &id)?;
+    let jar_change = find_backup(&backups_dir, &backup_id)?
+        .jar_change
+        .ok_or_else(|| format!("Backup {} was not taken before a jar change", backup_id))?;
+
+    let (_, safety) = restore_backup_internal(&app_handle, &id, &backup_id).await?;
+    audit::record(
+        &app_handle,
+        &id,
+        "rollback_jar",
+        serde_json::json!({ "backup_id": backup_id, "safety_backup_id": safety.id }),
+    );
+
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.software = jar_change.software;
+    config.version = jar_change.version;
+    config.loader = jar_change.loader;
+    config.build = jar_change.build;
+    config.custom_jar_path = jar_change.custom_jar_path;
+    update_instance_config(&app_handle, &config)?;
+
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(safety)
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use tauri::{async_runtime, AppHandle, Emitter, Manager};
+use tokio::time::sleep;
+
+use crate::{
+    audit, filesystem,
+    index::InstanceIndex,
+    instance::{
+        get_instance_by_id, is_instance_online, is_instance_running, restart_instance,
+        start_instance, stop_instance, update_instance_config,
+    },
+    models::{BulkActionResult, InstanceConfig, StartupProgress},
+};
+
+const ONLINE_TIMEOUT_SECS: u64 = 300;
+
+/// Run `action` for every id concurrently and report each outcome separately, so one failing
+/// instance doesn't hide the result of the others
+async fn run_bulk<F, Fut>(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+    action: F,
+) -> Vec<BulkActionResult>
+where
+    F: Fn(AppHandle, String) -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let handles: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let handle = async_runtime::spawn(action(app_handle.clone(), id.clone()));
+            (id, handle)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for (id, handle) in handles {
+        let outcome = match handle.await {
+            Ok(result) => result,
+            Err(e) => Err(format!("Task failed: {}", e)),
+        };
+        results.push(BulkActionResult {
+            id,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+
+    results
+}
+
+#[tauri::command]
+pub async fn start_instances(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<BulkActionResult>, String> {
+    Ok(run_bulk(app_handle, ids, start_instance).await)
+}
+
+#[tauri::command]
+pub async fn stop_instances(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<BulkActionResult>, String> {
+    Ok(run_bulk(app_handle, ids, stop_instance).await)
+}
+
+#[tauri::command]
+pub async fn restart_instances(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<BulkActionResult>, String> {
+    Ok(run_bulk(app_handle, ids, restart_instance).await)
+}
+
+#[tauri::command]
+pub async fn set_instance_startup(
+    app_handle: AppHandle,
+    id: String,
+    depends_on: Vec<String>,
+    start_before: Vec<String>,
+    delay_seconds: u64,
+) -> Result<(), String> {
+    let previous = get_instance_by_id(&app_handle, &id)?;
+    let mut config = previous.clone();
+
+    for other in depends_on.iter().chain(&start_before) {
+        if *other == id {
+            return Err("An instance cannot depend on itself".into());
+        }
+        get_instance_by_id(&app_handle, other)?;
+    }
+
+    config.startup.depends_on = depends_on;
+    config.startup.start_before = start_before;
+    config.startup.delay_seconds = delay_seconds;
+
+    // Reject the change if it would make the dependency graph impossible to start
+    update_instance_config(&app_handle, &config)?;
+    let mut affected = vec![id];
+    affected.extend(config.startup.start_before.iter().cloned());
+    if let Err(e) = startup_order(&app_handle, &affected) {
+        update_instance_config(&app_handle, &previous)?;
+        return Err(e);
+    }
+
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(())
+}
+
+/// Instances that must be online before `config` starts: its own `depends_on` plus every
+/// instance that asked to start before it
+fn dependencies(config: &InstanceConfig, instances: &[InstanceConfig]) -> Vec<String> {
+    let mut dependencies = config.startup.depends_on.clone();
+    for other in instances {
+        if other.startup.start_before.contains(&config.id) && !dependencies.contains(&other.id) {
+            dependencies.push(other.id.clone());
+        }
+    }
+    dependencies
+}
+
+/// Order the given instances (and everything they depend on) so dependencies come first.
+/// Each instance is returned with its resolved dependencies
+fn startup_order(
+    app_handle: &AppHandle,
+    ids: &[String],
+) -> Result<Vec<(InstanceConfig, Vec<String>)>, String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    let instances: Vec<InstanceConfig> = app_handle
+        .state::<InstanceIndex>()
+        .all(&instances_dir)?
+        .into_iter()
+        .map(|entry| entry.config)
+        .collect();
+
+    let mut order = Vec::new();
+    let mut visiting = HashSet::new();
+    let mut visited = HashSet::new();
+
+    for id in ids {
+        visit(
+            app_handle,
+            &instances,
+            id,
+            &mut visiting,
+            &mut visited,
+            &mut order,
+        )?;
+    }
+
+    Ok(order)
+}
+
+fn visit(
+    app_handle: &AppHandle,
+    instances: &[InstanceConfig],
+    id: &str,
+    visiting: &mut HashSet<String>,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<(InstanceConfig, Vec<String>)>,
+) -> Result<(), String> {
+    if visited.contains(id) {
+        return Ok(());
+    }
+
+    let config = get_instance_by_id(app_handle, id)?;
+    if !visiting.insert(id.to_string()) {
+        return Err(format!(
+            "Startup dependencies of '{}' form a cycle",
+            config.name
+        ));
+    }
+
+    let dependencies = dependencies(&config, instances);
+    for dependency in &dependencies {
+        visit(app_handle, instances, dependency, visiting, visited, order)?;
+    }
+
+    visiting.remove(id);
+    visited.insert(id.to_string());
+    order.push((config, dependencies));
+    Ok(())
+}
+
+fn emit_progress(
+    app_handle: &AppHandle,
+    event: &str,
+    config: &InstanceConfig,
+    stage: &str,
+    error: Option<String>,
+) {
+    let _ = app_handle.emit(
+        event,
+        StartupProgress {
+            id: config.id.clone(),
+            name: config.name.clone(),
+            stage: stage.to_string(),
+            error,
+        },
+    );
+}
+
+async fn wait_until_online(config: &InstanceConfig, instance_dir: &Path) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_secs(ONLINE_TIMEOUT_SECS);
+
+    while Instant::now() < deadline {
+        if is_instance_online(&config.id) {
+            return Ok(());
+        }
+        if !is_instance_running(instance_dir) {
+            return Err(format!(
+                "Instance '{}' exited before coming online",
+                config.name
+            ));
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    Err(format!(
+        "Instance '{}' did not come online within {} seconds",
+        config.name, ONLINE_TIMEOUT_SECS
+    ))
+}
+
+async fn start_in_order(
+    app_handle: &AppHandle,
+    event: &str,
+    config: &InstanceConfig,
+    dependencies: &[String],
+    failed: &HashSet<String>,
+) -> Result<(), String> {
+    if let Some(dependency) = dependencies
+        .iter()
+        .find(|dependency| failed.contains(*dependency))
+    {
+        let name = get_instance_by_id(app_handle, dependency)
+            .map(|dependency| dependency.name)
+            .unwrap_or_else(|_| dependency.clone());
+        return Err(format!("Dependency '{}' failed to start", name));
+    }
+
+    let instance_dir = filesystem::get_instance_dir(app_handle, &config.id)?;
+
+    // Servers started outside of this run can't be observed coming online, so trust them
+    if is_instance_running(&instance_dir) {
+        return Ok(());
+    }
+
+    if config.startup.delay_seconds > 0 {
+        emit_progress(app_handle, event, config, "waiting", None);
+        sleep(Duration::from_secs(config.startup.delay_seconds)).await;
+    }
+
+    emit_progress(app_handle, event, config, "starting", None);
+    start_instance(app_handle.clone(), config.id.clone()).await?;
+    wait_until_online(config, &instance_dir).await
+}
+
+/// Start a set of instances respecting their `depends_on`/`start_before` ordering and delays,
+/// waiting for each instance to come online before starting the ones that depend on it
+#[tauri::command]
+pub async fn start_network(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<BulkActionResult>, String> {
+    start_ordered(&app_handle, "network-start-progress", &ids).await
+}
+
+/// Start every instance flagged with `autostart`, one after another, reporting progress through
+/// `autostart-progress` events
+pub async fn autostart_instances(app_handle: AppHandle) -> Result<Vec<BulkActionResult>, String> {
+    let instances_dir = filesystem::get_instances_dir(&app_handle)?;
+    let ids: Vec<String> = app_handle
+        .state::<InstanceIndex>()
+        .all(&instances_dir)?
+        .into_iter()
+        .filter(|entry| entry.config.startup.autostart)
+        .map(|entry| entry.config.id)
+        .collect();
+
+    audit::as_actor(
+        "autostart",
+        start_ordered(&app_handle, "autostart-progress", &ids),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn set_instance_autostart(
+    app_handle: AppHandle,
+    id: String,
+    autostart: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.startup.autostart = autostart;
+    update_instance_config(&app_handle, &config)?;
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(())
+}
+
+async fn start_ordered(
+    app_handle: &AppHandle,
+    event: &str,
+    ids: &[String],
+) -> Result<Vec<BulkActionResult>, String> {
+    let order = startup_order(app_handle, ids)?;
+
+    let mut failed = HashSet::new();
+    let mut results = Vec::with_capacity(order.len());
+
+    for (config, dependencies) in order {
+        let outcome = start_in_order(app_handle, event, &config, &dependencies, &failed).await;
+        match &outcome {
+            Ok(()) => emit_progress(app_handle, event, &config, "online", None),
+            Err(e) => {
+                failed.insert(config.id.clone());
+                emit_progress(app_handle, event, &config, "failed", Some(e.clone()));
+            }
+        }
+
+        results.push(BulkActionResult {
+            id: config.id,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+
+    Ok(results)
+}
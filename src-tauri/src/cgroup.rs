@@ -0,0 +1,110 @@
+use std::process::Command;
+
+use crate::{
+    instance::{get_instance_by_id, update_instance_config},
+    jvmflags::parse_memory_mb,
+    models::JavaConfig,
+};
+
+fn has_limits(java: &JavaConfig) -> bool {
+    java.memory_limit.is_some() || java.cpu_limit.is_some()
+}
+
+/// Whether transient systemd scopes can be created for this user. Needs a running user
+/// manager, which headless sessions may not have
+#[cfg(target_os = "linux")]
+fn scopes_available() -> bool {
+    use std::process::Stdio;
+
+    Command::new("systemd-run")
+        .args(["--user", "--scope", "--quiet", "--", "true"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// The command that launches `java_path`. With a memory or CPU limit set it runs in its own
+/// cgroup, as a transient systemd scope; `systemd-run --scope` execs the command itself, so
+/// the PID, working directory and pipes are the server's own. Returns a warning when the
+/// limits can't be applied and the server will run without them
+pub fn java_command(java_path: &str, java: &JavaConfig) -> (Command, Option<String>) {
+    if !has_limits(java) {
+        return (Command::new(java_path), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if !scopes_available() {
+            return (
+                Command::new(java_path),
+                Some("systemd-run isn't available, starting without resource limits".into()),
+            );
+        }
+
+        let mut cmd = Command::new("systemd-run");
+        cmd.args(["--user", "--scope", "--quiet", "--collect"]);
+        if let Some(limit) = &java.memory_limit {
+            cmd.arg("-p").arg(format!("MemoryMax={}", limit.trim()));
+        }
+        if let Some(cores) = java.cpu_limit {
+            cmd.arg("-p")
+                .arg(format!("CPUQuota={}%", (cores * 100.0).round() as u64));
+        }
+        cmd.arg("--").arg(java_path);
+        (cmd, None)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        (
+            Command::new(java_path),
+            Some("Resource limits are only supported on Linux".into()),
+        )
+    }
+}
+
+/// Set or clear an instance's memory and CPU caps. They apply from the next start
+#[tauri::command]
+pub async fn set_resource_limits(
+    app_handle: tauri::AppHandle,
+    id: String,
+    memory_limit: Option<String>,
+    cpu_limit: Option<f64>,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let memory_limit = memory_limit
+        .map(|limit| limit.trim().to_string())
+        .filter(|limit| !limit.is_empty());
+
+    if let Some(limit) = &memory_limit {
+        let limit_mb = parse_memory_mb(limit)
+            .filter(|&mb| mb > 0)
+            .ok_or_else(|| format!("Invalid memory limit '{}'", limit))?;
+        // The JVM needs room for metaspace, threads and buffers on top of the heap
+        if let Some(heap_mb) = parse_memory_mb(&config.java.max_memory) {
+            if limit_mb <= heap_mb {
+                return Err(format!(
+                    "The memory limit must be larger than the max heap ({})",
+                    config.java.max_memory
+                ));
+            }
+        }
+    }
+    if let Some(cores) = cpu_limit {
+        let available = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        if !(cores > 0.0 && cores <= available as f64) {
+            return Err(format!(
+                "The CPU limit must be between 0 and {} cores",
+                available
+            ));
+        }
+    }
+
+    config.java.memory_limit = memory_limit;
+    config.java.cpu_limit = cpu_limit;
+    update_instance_config(&app_handle, &config)
+}
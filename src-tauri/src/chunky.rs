@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use tauri::Emitter;
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, write_instance_stdin},
+    models::ChunkyProgress,
+    modrinth,
+};
+
+const CHUNKY_PROJECT: &str = "chunky";
+
+/// Latest progress reported by Chunky for each instance
+fn get_progress_map() -> &'static Mutex<HashMap<String, ChunkyProgress>> {
+    static PROGRESS: OnceLock<Mutex<HashMap<String, ChunkyProgress>>> = OnceLock::new();
+    PROGRESS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The text between `start` and the next `end` after it
+fn between<'a>(line: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let rest = &line[line.find(start)? + start.len()..];
+    Some(rest.find(end).map(|i| &rest[..i]).unwrap_or(rest))
+}
+
+/// Parse Chunky's progress lines, e.g.
+/// `[Chunky] Task running for world. Processed: 1024 chunks (2.56%), ETA: 0:05:12, Rate: 98.3 cps, Current: 3, -7`
+/// `[Chunky] Task finished for world. Processed: 40000 chunks (100.00%), Total time: 0:06:45`
+fn parse_progress(line: &str) -> Option<ChunkyProgress> {
+    let (marker, finished) = if line.contains("Task running for ") {
+        ("Task running for ", false)
+    } else if line.contains("Task finished for ") {
+        ("Task finished for ", true)
+    } else {
+        return None;
+    };
+
+    Some(ChunkyProgress {
+        world: between(line, marker, ". Processed:")?.to_string(),
+        chunks: between(line, "Processed: ", " chunks")?
+            .trim()
+            .parse()
+            .ok()?,
+        percent: between(line, "(", "%)")?.trim().parse().ok()?,
+        eta: between(line, "ETA: ", ",").map(|eta| eta.trim().to_string()),
+        rate: between(line, "Rate: ", " cps").and_then(|rate| rate.trim().parse().ok()),
+        finished,
+    })
+}
+
+/// Turn Chunky output in the server console into `chunky-progress-{id}` events
+pub fn handle_log_line(app_handle: &tauri::AppHandle, id: &str, line: &str) {
+    if !line.contains("[Chunky]") {
+        return;
+    }
+    let Some(progress) = parse_progress(line) else {
+        return;
+    };
+
+    get_progress_map()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), progress.clone());
+    let _ = app_handle.emit(&format!("chunky-progress-{}", id), progress);
+}
+
+/// Install the Chunky plugin or mod that matches the instance's software and Minecraft
+/// version. Returns the downloaded file names
+#[tauri::command]
+pub async fn install_chunky(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<String>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let (loaders, folder) = modrinth::loader_for(config.effective_software()).ok_or_else(|| {
+        format!(
+            "Chunky needs a plugin or mod loader, which {} doesn't have",
+            config.effective_software()
+        )
+    })?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    modrinth::install(
+        &instance_dir,
+        folder,
+        CHUNKY_PROJECT,
+        None,
+        loaders,
+        config.effective_version(),
+    )
+    .await
+}
+
+/// Start pre-generating chunks within `radius` blocks of the center (0, 0 unless given)
+#[tauri::command]
+pub async fn start_pregeneration(
+    app_handle: tauri::AppHandle,
+    id: String,
+    world: String,
+    radius: u32,
+    center_x: Option<i64>,
+    center_z: Option<i64>,
+) -> Result<(), String> {
+    get_instance_by_id(&app_handle, &id)?;
+    if radius == 0 {
+        return Err("Radius must be greater than 0".into());
+    }
+    if world.trim().is_empty() || world.contains(char::is_whitespace) {
+        return Err(format!("Invalid world name '{}'", world));
+    }
+
+    get_progress_map().lock().unwrap().remove(&id);
+
+    write_instance_stdin(&id, &format!("chunky world {}", world))?;
+    write_instance_stdin(
+        &id,
+        &format!(
+            "chunky center {} {}",
+            center_x.unwrap_or(0),
+            center_z.unwrap_or(0)
+        ),
+    )?;
+    write_instance_stdin(&id, &format!("chunky radius {}", radius))?;
+    write_instance_stdin(&id, "chunky start")
+}
+
+#[tauri::command]
+pub async fn pause_pregeneration(id: String) -> Result<(), String> {
+    write_instance_stdin(&id, "chunky pause")
+}
+
+#[tauri::command]
+pub async fn continue_pregeneration(id: String) -> Result<(), String> {
+    write_instance_stdin(&id, "chunky continue")
+}
+
+/// Cancel every running Chunky task. Chunky asks for confirmation, so that is sent as well
+#[tauri::command]
+pub async fn cancel_pregeneration(id: String) -> Result<(), String> {
+    write_instance_stdin(&id, "chunky cancel")?;
+    write_instance_stdin(&id, "chunky confirm")?;
+    get_progress_map().lock().unwrap().remove(&id);
+    Ok(())
+}
+
+/// The most recent progress Chunky reported for the instance, if a task has run
+#[tauri::command]
+pub async fn get_pregeneration_progress(id: String) -> Result<Option<ChunkyProgress>, String> {
+    Ok(get_progress_map().lock().unwrap().get(&id).cloned())
+}
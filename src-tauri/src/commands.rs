@@ -0,0 +1,232 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use crate::{
+    consolelog, filesystem,
+    instance::{self, get_instance_by_id},
+    properties::ServerProperties,
+    rcon,
+};
+
+const HISTORY_FILE: &str = "history.txt";
+const KNOWN_COMMANDS_FILE: &str = "commands.json";
+const MAX_HISTORY: usize = 500;
+/// How long `help` output is collected when it goes through stdin
+const HELP_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Commands every vanilla server has, used until `help` has been captured from the server
+const VANILLA_COMMANDS: &[&str] = &[
+    "advancement",
+    "attribute",
+    "ban",
+    "ban-ip",
+    "banlist",
+    "bossbar",
+    "clear",
+    "clone",
+    "damage",
+    "data",
+    "datapack",
+    "debug",
+    "defaultgamemode",
+    "deop",
+    "difficulty",
+    "effect",
+    "enchant",
+    "execute",
+    "experience",
+    "fill",
+    "fillbiome",
+    "forceload",
+    "function",
+    "gamemode",
+    "gamerule",
+    "give",
+    "help",
+    "item",
+    "jfr",
+    "kick",
+    "kill",
+    "list",
+    "locate",
+    "loot",
+    "me",
+    "msg",
+    "op",
+    "pardon",
+    "pardon-ip",
+    "particle",
+    "perf",
+    "place",
+    "playsound",
+    "publish",
+    "random",
+    "recipe",
+    "reload",
+    "ride",
+    "save-all",
+    "save-off",
+    "save-on",
+    "say",
+    "schedule",
+    "scoreboard",
+    "seed",
+    "setblock",
+    "setidletimeout",
+    "setworldspawn",
+    "spawnpoint",
+    "spectate",
+    "spreadplayers",
+    "stop",
+    "stopsound",
+    "summon",
+    "tag",
+    "team",
+    "teammsg",
+    "teleport",
+    "tell",
+    "tellraw",
+    "tick",
+    "time",
+    "title",
+    "tp",
+    "transfer",
+    "trigger",
+    "w",
+    "weather",
+    "whitelist",
+    "worldborder",
+    "xp",
+];
+/// Extra commands Paper-family servers add on top of vanilla
+const PAPER_COMMANDS: &[&str] = &[
+    "mspt", "paper", "plugins", "pl", "restart", "spark", "timings", "tps", "version", "ver",
+];
+
+fn console_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?
+        .join("console")
+        .join(id);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create console dir: {}", e))?;
+    Ok(dir)
+}
+
+fn read_history(app_handle: &tauri::AppHandle, id: &str) -> Result<Vec<String>, String> {
+    let path = console_dir(app_handle, id)?.join(HISTORY_FILE);
+    Ok(fs::read_to_string(path)
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+/// Remember a command sent to the console, skipping immediate repeats
+pub fn record(app_handle: &tauri::AppHandle, id: &str, command: &str) -> Result<(), String> {
+    let command = command.trim();
+    if command.is_empty() || command.contains('\n') {
+        return Ok(());
+    }
+
+    let mut history = read_history(app_handle, id)?;
+    if history.last().map(String::as_str) == Some(command) {
+        return Ok(());
+    }
+    history.push(command.to_string());
+    if history.len() > MAX_HISTORY {
+        history.drain(..history.len() - MAX_HISTORY);
+    }
+
+    let path = console_dir(app_handle, id)?.join(HISTORY_FILE);
+    fs::write(path, history.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write command history: {}", e))
+}
+
+/// Commands previously sent to this instance, oldest first
+#[tauri::command]
+pub async fn get_command_history(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<String>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    read_history(&app_handle, &id)
+}
+
+/// Command names from `help` output, whose lines look like "/give <targets> <item>"
+fn parse_help(lines: &[String]) -> Vec<String> {
+    let mut commands: Vec<String> = lines
+        .iter()
+        .flat_map(|line| line.split('\n'))
+        .filter_map(|line| {
+            let name = consolelog::log_message(line.trim()).strip_prefix('/')?;
+            let name = name.split_whitespace().next()?;
+            Some(name.trim_end_matches(':').to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .collect();
+    commands.sort();
+    commands.dedup();
+    commands
+}
+
+/// Ask the running server for its command list, over RCON if possible and otherwise by
+/// reading the console after sending `help` to stdin
+async fn query_help(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    instance_dir: &std::path::Path,
+) -> Result<Vec<String>, String> {
+    let rcon_enabled = ServerProperties::load(instance_dir)
+        .map(|properties| properties.get("enable-rcon").map(str::trim) == Some("true"))
+        .unwrap_or(false);
+    if rcon_enabled {
+        if let Ok(output) = rcon::execute(app_handle, id, "help".to_string()).await {
+            // RCON replies run the lines together, each starting with '/'
+            let lines: Vec<String> = output.split('/').map(|part| format!("/{}", part)).collect();
+            return Ok(parse_help(&lines));
+        }
+    }
+
+    if !instance::has_instance_stdin(id) {
+        return Err("Instance isn't running".into());
+    }
+    let since = instance::log_line_count(id);
+    instance::write_instance_stdin(id, "help")?;
+
+    tokio::time::sleep(HELP_TIMEOUT).await;
+    Ok(parse_help(&instance::logs_since(id, since)))
+}
+
+/// Command names for console autocomplete. While the server runs its `help` output is used
+/// (and cached, so plugin commands are still offered after it stops); otherwise a bundled
+/// list for the instance's software
+#[tauri::command]
+pub async fn get_known_commands(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<String>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let cache_path = console_dir(&app_handle, &id)?.join(KNOWN_COMMANDS_FILE);
+
+    if instance::is_instance_online(&id) {
+        if let Ok(commands) = query_help(&app_handle, &id, &instance_dir).await {
+            if !commands.is_empty() {
+                if let Ok(json) = serde_json::to_string(&commands) {
+                    let _ = fs::write(&cache_path, json);
+                }
+                return Ok(commands);
+            }
+        }
+    }
+
+    if let Some(cached) = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+    {
+        return Ok(cached);
+    }
+
+    let mut commands: Vec<String> = VANILLA_COMMANDS.iter().map(|c| c.to_string()).collect();
+    if matches!(config.effective_software(), "papermc" | "purpur") {
+        commands.extend(PAPER_COMMANDS.iter().map(|c| c.to_string()));
+    }
+    commands.sort();
+    Ok(commands)
+}
@@ -1,8 +1,15 @@
 use std::fs;
 use tauri::{AppHandle, Emitter};
 
+use crate::download;
 use crate::filesystem::get_data_dir;
-use crate::models::GlobalConfig;
+use crate::jvmflags::parse_memory_mb;
+use crate::migrate::{self, ConfigFile};
+use crate::models::{
+    default_max_log_lines, DownloadSettings, GlobalConfig, InstanceDefaults, NotificationSettings,
+    RemoteTarget,
+};
+use crate::secrets;
 
 #[tauri::command]
 pub fn get_config(app_handle: AppHandle) -> Result<GlobalConfig, String> {
@@ -11,7 +18,18 @@ pub fn get_config(app_handle: AppHandle) -> Result<GlobalConfig, String> {
 
     if !config_path.exists() {
         let default_config = GlobalConfig {
+            config_version: migrate::GLOBAL_CONFIG_VERSION,
             theme: "dark".to_string(),
+            backup_remote: None,
+            max_log_lines: default_max_log_lines(),
+            notifications: NotificationSettings::default(),
+            close_to_tray: false,
+            curseforge_api_key: None,
+            ngrok_authtoken: None,
+            cloudflare_api_token: None,
+            instances_dir: None,
+            instance_defaults: InstanceDefaults::default(),
+            downloads: DownloadSettings::default(),
         };
         let toml_string = toml::to_string_pretty(&default_config)
             .map_err(|e| format!("Failed to serialize default config: {}", e))?;
@@ -20,13 +38,7 @@ pub fn get_config(app_handle: AppHandle) -> Result<GlobalConfig, String> {
         return Ok(default_config);
     }
 
-    let config_str = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read config.toml: {}", e))?;
-
-    let config: GlobalConfig =
-        toml::from_str(&config_str).map_err(|e| format!("Failed to parse config.toml: {}", e))?;
-
-    Ok(config)
+    migrate::load(&config_path, ConfigFile::Global)
 }
 
 #[tauri::command]
@@ -35,14 +47,34 @@ pub fn set_theme(app_handle: AppHandle, theme: String) -> Result<(), String> {
     let config_path = data_dir.join("config.toml");
 
     let mut config = if config_path.exists() {
-        let config_str = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config.toml: {}", e))?;
-        toml::from_str(&config_str).unwrap_or_else(|_| GlobalConfig {
+        migrate::load(&config_path, ConfigFile::Global).unwrap_or_else(|_| GlobalConfig {
+            config_version: migrate::GLOBAL_CONFIG_VERSION,
             theme: theme.clone(),
+            backup_remote: None,
+            max_log_lines: default_max_log_lines(),
+            notifications: NotificationSettings::default(),
+            close_to_tray: false,
+            curseforge_api_key: None,
+            ngrok_authtoken: None,
+            cloudflare_api_token: None,
+            instances_dir: None,
+            instance_defaults: InstanceDefaults::default(),
+            downloads: DownloadSettings::default(),
         })
     } else {
         GlobalConfig {
+            config_version: migrate::GLOBAL_CONFIG_VERSION,
             theme: theme.clone(),
+            backup_remote: None,
+            max_log_lines: default_max_log_lines(),
+            notifications: NotificationSettings::default(),
+            close_to_tray: false,
+            curseforge_api_key: None,
+            ngrok_authtoken: None,
+            cloudflare_api_token: None,
+            instances_dir: None,
+            instance_defaults: InstanceDefaults::default(),
+            downloads: DownloadSettings::default(),
         }
     };
 
@@ -60,3 +92,214 @@ pub fn set_theme(app_handle: AppHandle, theme: String) -> Result<(), String> {
 
     Ok(())
 }
+
+/// Set or clear the remote target backups are mirrored to
+#[tauri::command]
+pub fn set_backup_remote(
+    app_handle: AppHandle,
+    target: Option<RemoteTarget>,
+) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    let mut target = target;
+    let old = config.backup_remote.as_ref().and_then(remote_secret);
+    let new = target.as_mut().and_then(remote_secret_mut);
+
+    // Re-saving the target unchanged sends the existing reference back
+    if let Some(old) = old.filter(|old| Some(*old) != new.as_deref()) {
+        secrets::delete_credential(old);
+    }
+    if let Some(secret) = new.filter(|secret| !secrets::is_stored_credential(secret)) {
+        *secret = secrets::store_credential(&app_handle, "backup-remote", secret)?;
+    }
+    config.backup_remote = target;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// The credential field of a remote target, if it has one
+fn remote_secret(target: &RemoteTarget) -> Option<&String> {
+    match target {
+        RemoteTarget::S3 {
+            secret_access_key, ..
+        } => Some(secret_access_key),
+        RemoteTarget::WebDav { password, .. } => password.as_ref(),
+        RemoteTarget::Sftp { .. } => None,
+    }
+}
+
+fn remote_secret_mut(target: &mut RemoteTarget) -> Option<&mut String> {
+    match target {
+        RemoteTarget::S3 {
+            secret_access_key, ..
+        } => Some(secret_access_key),
+        RemoteTarget::WebDav { password, .. } => password.as_mut(),
+        RemoteTarget::Sftp { .. } => None,
+    }
+}
+
+/// Set how many console lines are kept in memory per instance. Applies from the next start
+#[tauri::command]
+pub fn set_max_log_lines(app_handle: AppHandle, max_lines: usize) -> Result<(), String> {
+    if max_lines < 100 {
+        return Err("At least 100 console lines must be kept".into());
+    }
+
+    let mut config = get_config(app_handle.clone())?;
+    config.max_log_lines = max_lines;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+#[tauri::command]
+pub fn set_notification_settings(
+    app_handle: AppHandle,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    config.notifications = settings;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+#[tauri::command]
+pub fn set_close_to_tray(app_handle: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    config.close_to_tray = enabled;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// Set or clear the CurseForge API key used for mod search and downloads
+#[tauri::command]
+pub fn set_curseforge_api_key(app_handle: AppHandle, key: Option<String>) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    if let Some(old) = &config.curseforge_api_key {
+        secrets::delete_credential(old);
+    }
+    config.curseforge_api_key = key
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .map(|key| secrets::store_credential(&app_handle, "curseforge", &key))
+        .transpose()?;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// Set or clear the ngrok authtoken used for ngrok tunnels
+#[tauri::command]
+pub fn set_ngrok_authtoken(app_handle: AppHandle, token: Option<String>) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    if let Some(old) = &config.ngrok_authtoken {
+        secrets::delete_credential(old);
+    }
+    config.ngrok_authtoken = token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .map(|token| secrets::store_credential(&app_handle, "ngrok", &token))
+        .transpose()?;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// Set or clear the Cloudflare API token used to publish SRV records
+#[tauri::command]
+pub fn set_cloudflare_api_token(
+    app_handle: AppHandle,
+    token: Option<String>,
+) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    if let Some(old) = &config.cloudflare_api_token {
+        secrets::delete_credential(old);
+    }
+    config.cloudflare_api_token = token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .map(|token| secrets::store_credential(&app_handle, "cloudflare", &token))
+        .transpose()?;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// The settings new instances are created with
+#[tauri::command]
+pub fn get_instance_defaults(app_handle: AppHandle) -> Result<InstanceDefaults, String> {
+    Ok(get_config(app_handle)?.instance_defaults)
+}
+
+/// Change the settings new instances are created with. Existing instances keep their own
+#[tauri::command]
+pub fn set_instance_defaults(
+    app_handle: AppHandle,
+    mut defaults: InstanceDefaults,
+) -> Result<(), String> {
+    let min_mb = parse_memory_mb(&defaults.min_memory)
+        .ok_or(format!("Invalid memory amount '{}'", defaults.min_memory))?;
+    let max_mb = parse_memory_mb(&defaults.max_memory)
+        .ok_or(format!("Invalid memory amount '{}'", defaults.max_memory))?;
+    if min_mb > max_mb {
+        return Err("Minimum memory cannot be more than maximum memory".into());
+    }
+    if defaults.port_range_start == 0 || defaults.port_range_start > defaults.port_range_end {
+        return Err("The port range must start at 1 or above and not end before it starts".into());
+    }
+    defaults.java_path = defaults
+        .java_path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    defaults.jvm_args.retain(|arg| !arg.trim().is_empty());
+
+    let mut config = get_config(app_handle.clone())?;
+    config.instance_defaults = defaults;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+/// Set how many downloads run at once and the optional bandwidth cap they share. Applies to
+/// downloads started from now on
+#[tauri::command]
+pub fn set_download_settings(
+    app_handle: AppHandle,
+    settings: DownloadSettings,
+) -> Result<(), String> {
+    if settings.max_parallel == 0 {
+        return Err("At least one download must be allowed at a time".into());
+    }
+    if settings.bandwidth_limit_kib == Some(0) {
+        return Err("The bandwidth cap must be above 0 KiB/s".into());
+    }
+
+    let mut config = get_config(app_handle.clone())?;
+    config.downloads = settings;
+
+    let config_path = get_data_dir(&app_handle)?.join("config.toml");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&config_path, toml_string)
+        .map_err(|e| format!("Failed to write config.toml: {}", e))?;
+    download::apply_limits(&config.downloads);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    audit, filesystem,
+    instance::get_instance_by_id,
+    models::{ConfigFileContent, ConfigFileInfo},
+};
+
+/// Server-level config files, relative to the instance directory
+const SERVER_CONFIGS: &[&str] = &[
+    "server.properties",
+    "bukkit.yml",
+    "spigot.yml",
+    "paper.yml",
+    "purpur.yml",
+    "commands.yml",
+    "permissions.yml",
+    "help.yml",
+    "ops.json",
+    "whitelist.json",
+];
+/// Config files larger than this are probably data, not settings
+const MAX_CONFIG_SIZE: u64 = 2 * 1024 * 1024;
+
+fn format_of(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "yml" | "yaml" => Some("yaml"),
+        "toml" => Some("toml"),
+        "json" => Some("json"),
+        "properties" => Some("properties"),
+        _ => None,
+    }
+}
+
+/// Files directly inside `dir` with a format nuko can validate
+fn configs_in(instance_dir: &Path, dir: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(instance_dir.join(dir)) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| format!("{}/{}", dir, entry.file_name().to_string_lossy()))
+        .filter(|path| format_of(path).is_some())
+        .collect()
+}
+
+/// Relative paths of every editable config file that exists in the instance: the server's
+/// own files, everything under `config/` (Paper's split configs and mod configs) and each
+/// plugin's `config.yml`
+fn config_paths(instance_dir: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = SERVER_CONFIGS
+        .iter()
+        .filter(|path| instance_dir.join(path).is_file())
+        .map(|path| path.to_string())
+        .collect();
+
+    let mut config_dir = configs_in(instance_dir, "config");
+    config_dir.sort();
+    paths.extend(config_dir);
+
+    if let Ok(entries) = fs::read_dir(instance_dir.join("plugins")) {
+        let mut plugin_configs: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join("config.yml").is_file())
+            .map(|entry| format!("plugins/{}/config.yml", entry.file_name().to_string_lossy()))
+            .collect();
+        plugin_configs.sort();
+        paths.extend(plugin_configs);
+    }
+
+    paths
+}
+
+/// Resolve a path from the frontend, only accepting files `list_config_files` would return
+fn resolve(instance_dir: &Path, path: &str) -> Result<(PathBuf, &'static str), String> {
+    let path = path.replace('\\', "/");
+    if !config_paths(instance_dir).contains(&path) {
+        return Err(format!("{} isn't an editable config file", path));
+    }
+    let format = format_of(&path).ok_or_else(|| format!("Unknown config format for {}", path))?;
+    Ok((instance_dir.join(&path), format))
+}
+
+/// Check `content` parses as `format`, so a typo can't stop the server from starting
+fn validate(format: &str, content: &str) -> Result<(), String> {
+    match format {
+        "yaml" => serde_yaml::from_str::<serde_yaml::Value>(content)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid YAML: {}", e)),
+        "toml" => toml::from_str::<toml::Value>(content)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid TOML: {}", e)),
+        "json" => serde_json::from_str::<serde_json::Value>(content)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid JSON: {}", e)),
+        _ => Ok(()),
+    }
+}
+
+/// Config files that can be edited in the instance
+#[tauri::command]
+pub async fn list_config_files(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<ConfigFileInfo>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    Ok(config_paths(&instance_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(instance_dir.join(&path)).ok()?;
+            Some(ConfigFileInfo {
+                format: format_of(&path)?.to_string(),
+                size_bytes: metadata.len(),
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339()),
+                path,
+            })
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn read_config_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<ConfigFileContent, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let (file, format) = resolve(&instance_dir, &path)?;
+
+    let size = fs::metadata(&file)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .len();
+    if size > MAX_CONFIG_SIZE {
+        return Err(format!("{} is too large to edit", path));
+    }
+    let content =
+        fs::read_to_string(&file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    Ok(ConfigFileContent {
+        path,
+        format: format.to_string(),
+        content,
+    })
+}
+
+/// Validate and save a config file. The previous version is kept next to it as `<name>.bak`
+#[tauri::command]
+pub async fn write_config_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let (file, format) = resolve(&instance_dir, &path)?;
+    validate(format, &content)?;
+
+    let file_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    fs::copy(&file, file.with_file_name(format!("{}.bak", file_name)))
+        .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+
+    let partial = file.with_file_name(format!("{}.part", file_name));
+    fs::write(&partial, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    fs::rename(&partial, &file).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    audit::record(
+        &app_handle,
+        &id,
+        "write_file",
+        serde_json::json!({ "path": path }),
+    );
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use flate2::read::GzDecoder;
+use regex::RegexBuilder;
+
+use crate::{
+    filesystem,
+    instance::{self, get_instance_by_id},
+    models::{LogMatch, LogSearchOptions, ServerLogFile},
+};
+
+const CONSOLE_LOG: &str = "console.log";
+/// The console log is rotated once it grows past this size
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+/// Rotated files kept as console.1.log (newest) to console.N.log (oldest)
+const ROTATED_LOGS: usize = 5;
+const DEFAULT_HISTORY_LINES: usize = 1000;
+/// Server log files above this size aren't loaded into the UI
+const MAX_SERVER_LOG_READ: u64 = 32 * 1024 * 1024;
+const DEFAULT_SEARCH_LIMIT: usize = 500;
+const LEVELS: [&str; 5] = ["INFO", "WARN", "ERROR", "FATAL", "DEBUG"];
+
+struct OpenLog {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+fn get_open_logs() -> &'static Mutex<HashMap<String, OpenLog>> {
+    static LOGS: OnceLock<Mutex<HashMap<String, OpenLog>>> = OnceLock::new();
+    LOGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn console_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?
+        .join("console")
+        .join(id);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create console log dir: {}", e))?;
+    Ok(dir)
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("console.{}.log", index))
+}
+
+fn open_append(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open console log: {}", e))
+}
+
+/// Start persisting an instance's console, called whenever the server is started
+pub fn open(app_handle: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    let dir = console_dir(app_handle, id)?;
+    let file = open_append(&dir.join(CONSOLE_LOG))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    get_open_logs()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), OpenLog { dir, file, size });
+    Ok(())
+}
+
+pub fn close(id: &str) {
+    get_open_logs().lock().unwrap().remove(id);
+}
+
+/// Shift console.log to console.1.log, console.1.log to console.2.log and so on
+fn rotate(log: &mut OpenLog) -> Result<(), String> {
+    let _ = fs::remove_file(rotated_path(&log.dir, ROTATED_LOGS));
+    for index in (1..ROTATED_LOGS).rev() {
+        let _ = fs::rename(
+            rotated_path(&log.dir, index),
+            rotated_path(&log.dir, index + 1),
+        );
+    }
+    fs::rename(log.dir.join(CONSOLE_LOG), rotated_path(&log.dir, 1))
+        .map_err(|e| format!("Failed to rotate console log: {}", e))?;
+
+    log.file = open_append(&log.dir.join(CONSOLE_LOG))?;
+    log.size = 0;
+    Ok(())
+}
+
+/// Append a captured console line to the instance's console log
+pub fn append(id: &str, line: &str) {
+    let mut logs = get_open_logs().lock().unwrap();
+    let Some(log) = logs.get_mut(id) else {
+        return;
+    };
+
+    if log.size >= MAX_LOG_SIZE {
+        if let Err(e) = rotate(log) {
+            println!("{}", e);
+        }
+    }
+    if writeln!(log.file, "{}", line).is_ok() {
+        log.size += line.len() as u64 + 1;
+    }
+}
+
+/// The last `lines` lines persisted for an instance, reaching into rotated files when the
+/// current one is shorter than that
+#[tauri::command]
+pub async fn get_console_history(
+    app_handle: tauri::AppHandle,
+    id: String,
+    lines: Option<usize>,
+) -> Result<Vec<String>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let dir = console_dir(&app_handle, &id)?;
+    let wanted = lines.unwrap_or(DEFAULT_HISTORY_LINES);
+
+    let files = std::iter::once(dir.join(CONSOLE_LOG))
+        .chain((1..=ROTATED_LOGS).map(|i| rotated_path(&dir, i)));
+
+    let mut history = VecDeque::with_capacity(wanted);
+    for path in files {
+        if history.len() >= wanted {
+            break;
+        }
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+
+        let mut tail = VecDeque::with_capacity(wanted);
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if tail.len() == wanted - history.len() {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+        for line in tail.into_iter().rev() {
+            history.push_front(line);
+        }
+    }
+
+    Ok(history.into())
+}
+
+/// Log files the server wrote itself, i.e. logs/latest.log and the gzipped archives
+#[tauri::command]
+pub async fn list_server_logs(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<ServerLogFile>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let logs_dir = filesystem::get_instance_dir(&app_handle, &id)?.join("logs");
+    if !logs_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut files: Vec<ServerLogFile> = fs::read_dir(&logs_dir)
+        .map_err(|e| format!("Failed to read logs directory: {}", e))?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !(name.ends_with(".log") || name.ends_with(".log.gz")) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(ServerLogFile {
+                name,
+                size_bytes: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()),
+            })
+        })
+        .collect();
+
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(files)
+}
+
+/// Contents of one of the server's own log files, decompressing archived ones
+#[tauri::command]
+pub async fn read_server_log(
+    app_handle: tauri::AppHandle,
+    id: String,
+    name: String,
+) -> Result<String, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid log file name '{}'", name));
+    }
+
+    let path = filesystem::get_instance_dir(&app_handle, &id)?
+        .join("logs")
+        .join(&name);
+    let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+
+    let mut content = Vec::new();
+    let reader: Box<dyn Read> = if name.ends_with(".gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    reader
+        .take(MAX_SERVER_LOG_READ)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Log level of a console line, read from prefixes like "[12:00:00 WARN]:" (Paper) or
+/// "[12:00:00] [Server thread/WARN]:" (vanilla)
+pub fn log_level(line: &str) -> Option<&'static str> {
+    let prefix = &line[..line.find("]:")?];
+    let tag = prefix.rsplit(['[', ' ', '/']).next()?;
+    LEVELS.iter().find(|level| **level == tag).copied()
+}
+
+/// The message part of a console line, without the "[12:00:00 INFO]: " prefix
+pub fn log_message(line: &str) -> &str {
+    match line.split_once("]: ") {
+        Some((prefix, rest)) if prefix.starts_with('[') => rest,
+        _ => line,
+    }
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    let Ok(file) = File::open(path) else {
+        return vec![];
+    };
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    BufReader::new(reader)
+        .lines()
+        .map_while(Result::ok)
+        .collect()
+}
+
+/// Search the live console and, optionally, every log file nuko or the server wrote
+#[tauri::command]
+pub async fn search_instance_logs(
+    app_handle: tauri::AppHandle,
+    id: String,
+    query: String,
+    options: Option<LogSearchOptions>,
+) -> Result<Vec<LogMatch>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let options = options.unwrap_or_default();
+    let pattern = if options.regex {
+        query.clone()
+    } else {
+        regex::escape(&query)
+    };
+    let matcher = RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))?;
+    let level = options.level.as_deref().map(str::to_uppercase);
+    let limit = options.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let buffered = instance::logs_since(&id, 0);
+    let first_line = instance::log_line_count(&id) - buffered.len();
+    let mut sources = vec![("console".to_string(), first_line, buffered)];
+
+    if options.include_files {
+        let console_dir = console_dir(&app_handle, &id)?;
+        let logs_dir = filesystem::get_instance_dir(&app_handle, &id)?.join("logs");
+        let mut paths: Vec<PathBuf> = std::iter::once(console_dir.join(CONSOLE_LOG))
+            .chain((1..=ROTATED_LOGS).map(|i| rotated_path(&console_dir, i)))
+            .collect();
+        if let Ok(entries) = fs::read_dir(&logs_dir) {
+            let mut server_logs: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let name = path.to_string_lossy();
+                    name.ends_with(".log") || name.ends_with(".log.gz")
+                })
+                .collect();
+            server_logs.sort();
+            server_logs.reverse();
+            paths.extend(server_logs);
+        }
+
+        let search_paths = paths.clone();
+        let files = tauri::async_runtime::spawn_blocking(move || {
+            search_paths
+                .iter()
+                .map(|path| read_lines(path))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|e| format!("Failed to read log files: {}", e))?;
+
+        for (path, lines) in paths.iter().zip(files) {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            sources.push((name, 0, lines));
+        }
+    }
+
+    let mut matches = Vec::new();
+    for (source, first_line, lines) in sources {
+        for (index, line) in lines.into_iter().enumerate() {
+            if matches.len() >= limit {
+                return Ok(matches);
+            }
+            let line_level = log_level(&line);
+            if level.is_some() && line_level != level.as_deref() {
+                continue;
+            }
+            if matcher.is_match(&line) {
+                matches.push(LogMatch {
+                    source: source.clone(),
+                    line_number: first_line + index + 1,
+                    level: line_level.map(str::to_string),
+                    line,
+                });
+            }
+        }
+    }
+
+    Ok(matches)
+}
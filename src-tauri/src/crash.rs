@@ -0,0 +1,283 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::Utc;
+use tauri::Emitter;
+
+use crate::{
+    audit, filesystem,
+    instance::{get_instance_by_id, record_log_line, start_instance, update_instance_config},
+    models::{
+        AutoRestartConfig, CrashEvent, CrashLoop, CrashLoopEvent, CrashReport, CrashReportInfo,
+    },
+    notifications::{self, NotificationKind},
+};
+
+/// Attached reports are cut off at this size so the event stays small
+const MAX_ATTACHED_REPORT: usize = 256 * 1024;
+
+/// Crash reports written by Minecraft (crash-reports/*.txt) and fatal JVM error logs
+/// (hs_err_pid*.log in the server directory), newest first
+fn find_reports(instance_dir: &Path) -> Vec<(CrashReportInfo, PathBuf)> {
+    let mut candidates = Vec::new();
+    if let Ok(entries) = fs::read_dir(instance_dir.join("crash-reports")) {
+        candidates.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".txt"))
+                .map(|entry| ("crash-report", entry)),
+        );
+    }
+    if let Ok(entries) = fs::read_dir(instance_dir) {
+        candidates.extend(
+            entries
+                .flatten()
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.starts_with("hs_err_pid") && name.ends_with(".log")
+                })
+                .map(|entry| ("hs_err", entry)),
+        );
+    }
+
+    let mut reports: Vec<(CrashReportInfo, PathBuf, SystemTime)> = candidates
+        .into_iter()
+        .filter_map(|(kind, entry)| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((
+                CrashReportInfo {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    kind: kind.to_string(),
+                    size_bytes: metadata.len(),
+                    created_at: chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+                },
+                entry.path(),
+                modified,
+            ))
+        })
+        .collect();
+
+    reports.sort_by_key(|(_, _, modified)| Reverse(*modified));
+    reports
+        .into_iter()
+        .map(|(info, path, _)| (info, path))
+        .collect()
+}
+
+/// When each instance recently crashed, to tell a crash loop apart from a one-off crash
+fn get_crash_times() -> &'static Mutex<HashMap<String, Vec<Instant>>> {
+    static CRASH_TIMES: OnceLock<Mutex<HashMap<String, Vec<Instant>>>> = OnceLock::new();
+    CRASH_TIMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Forget an instance's crash history, called when it's started after a crash loop
+pub fn reset(id: &str) {
+    get_crash_times().lock().unwrap().remove(id);
+}
+
+fn read_report(path: &Path, info: CrashReportInfo) -> Result<CrashReport, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", info.name, e))?;
+    let truncated = bytes.len() > MAX_ATTACHED_REPORT;
+    let content = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_ATTACHED_REPORT)]);
+    Ok(CrashReport {
+        info,
+        content: content.into_owned(),
+        truncated,
+    })
+}
+
+/// Called once a server process has exited. An exit nobody asked for, or one that left a
+/// new crash report behind, emits `instance-crashed` with the newest report attached.
+/// Returns whether the exit was a crash
+pub fn handle_exit(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    instance_dir: &Path,
+    started_at: SystemTime,
+    exit_code: Option<i32>,
+    requested: bool,
+) -> bool {
+    let newest = find_reports(instance_dir)
+        .into_iter()
+        .next()
+        .filter(|(_, path)| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .map(|modified| modified >= started_at)
+                .unwrap_or(false)
+        });
+
+    let abnormal = !requested && exit_code != Some(0);
+    if !abnormal && newest.is_none() {
+        return false;
+    }
+
+    let name = get_instance_by_id(app_handle, id)
+        .map(|config| config.name)
+        .unwrap_or_else(|_| id.to_string());
+    notifications::notify(
+        app_handle,
+        NotificationKind::Crash,
+        &format!("{} crashed", name),
+        &match (&newest, exit_code) {
+            (Some((info, _)), _) => format!("See {} for details", info.name),
+            (None, Some(code)) => format!("The server exited with code {}", code),
+            (None, None) => "The server exited unexpectedly".to_string(),
+        },
+    );
+
+    let report = newest.and_then(|(info, path)| read_report(&path, info).ok());
+    let _ = app_handle.emit(
+        "instance-crashed",
+        CrashEvent {
+            instance_id: id.to_string(),
+            exit_code,
+            report: report.clone(),
+        },
+    );
+
+    if abnormal {
+        auto_restart(app_handle, id, report);
+    }
+    true
+}
+
+/// Restart a crashed server if the instance asks for it, unless it crashed too often
+/// recently. A crash loop is recorded on the instance and reported with the last crash
+/// report instead of restarting a server that can't stay up
+fn auto_restart(app_handle: &tauri::AppHandle, id: &str, report: Option<CrashReport>) {
+    let Ok(mut config) = get_instance_by_id(app_handle, id) else {
+        return;
+    };
+    let policy = config.auto_restart.clone();
+    if !policy.enabled {
+        return;
+    }
+
+    let window = Duration::from_secs(policy.window_minutes.max(1) * 60);
+    let crashes = {
+        let mut crash_times = get_crash_times().lock().unwrap();
+        let times = crash_times.entry(id.to_string()).or_default();
+        times.retain(|time| time.elapsed() < window);
+        times.push(Instant::now());
+        times.len() as u32
+    };
+
+    if crashes > policy.max_crashes {
+        reset(id);
+        config.crash_loop = Some(CrashLoop {
+            since: Utc::now().to_rfc3339(),
+            crashes,
+        });
+        if let Err(e) = update_instance_config(app_handle, &config) {
+            println!("Failed to mark {} as crash-looping: {}", id, e);
+        }
+        record_log_line(
+            app_handle,
+            id,
+            format!(
+                "[nuko] Crashed {} times in {} minutes, not restarting again",
+                crashes, policy.window_minutes
+            ),
+        );
+        notifications::notify(
+            app_handle,
+            NotificationKind::Crash,
+            &format!("{} is crash-looping", config.name),
+            &format!(
+                "It crashed {} times in {} minutes, so it won't be restarted automatically",
+                crashes, policy.window_minutes
+            ),
+        );
+        let _ = app_handle.emit(
+            "instance-crash-loop",
+            CrashLoopEvent {
+                instance_id: id.to_string(),
+                crashes,
+                window_minutes: policy.window_minutes,
+                report,
+            },
+        );
+        return;
+    }
+
+    record_log_line(
+        app_handle,
+        id,
+        format!("[nuko] Restarting in {} seconds", policy.delay_secs),
+    );
+    let app_handle = app_handle.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(policy.delay_secs)).await;
+        let restart = start_instance(app_handle.clone(), id.clone());
+        if let Err(e) = audit::as_actor("auto-restart", restart).await {
+            record_log_line(
+                &app_handle,
+                &id,
+                format!("[nuko] Automatic restart failed: {}", e),
+            );
+        }
+    });
+}
+
+/// Configure restarting the server after a crash
+#[tauri::command]
+pub async fn set_auto_restart(
+    app_handle: tauri::AppHandle,
+    id: String,
+    auto_restart: AutoRestartConfig,
+) -> Result<(), String> {
+    if auto_restart.max_crashes == 0 {
+        return Err("At least one crash must be allowed before giving up".into());
+    }
+    if auto_restart.window_minutes == 0 {
+        return Err("The crash window must be at least a minute".into());
+    }
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.auto_restart = auto_restart;
+    update_instance_config(&app_handle, &config)
+}
+
+#[tauri::command]
+pub async fn list_crash_reports(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<CrashReportInfo>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    Ok(find_reports(&instance_dir)
+        .into_iter()
+        .map(|(info, _)| info)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn read_crash_report(
+    app_handle: tauri::AppHandle,
+    id: String,
+    name: String,
+) -> Result<CrashReport, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let (info, path) = find_reports(&instance_dir)
+        .into_iter()
+        .find(|(info, _)| info.name == name)
+        .ok_or_else(|| format!("Crash report '{}' not found", name))?;
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+
+    Ok(CrashReport {
+        info,
+        content,
+        truncated: false,
+    })
+}
@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::Utc;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    addons, download, filesystem,
+    instance::{ensure_playit_secret, get_instance_by_id, is_instance_running},
+    models::{CrossplaySetup, LockedAddon},
+    modrinth,
+    playit::PlayitClient,
+    ports,
+};
+
+const API: &str = "https://download.geysermc.org/v2/projects";
+const DEFAULT_BEDROCK_PORT: u16 = 19132;
+const FABRIC_API_PROJECT: &str = "fabric-api";
+
+#[derive(Debug, Deserialize)]
+struct GeyserBuild {
+    version: String,
+    build: u64,
+    downloads: HashMap<String, GeyserDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeyserDownload {
+    name: String,
+    sha256: String,
+}
+
+/// Geyser's download platform, install folder and config folder for an instance's software.
+/// Geyser has no Forge build
+fn platform_for(software: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    match software {
+        "papermc" | "purpur" => Some(("spigot", "plugins", "plugins/Geyser-Spigot")),
+        "fabric" => Some(("fabric", "mods", "config/Geyser-Fabric")),
+        "neoforge" => Some(("neoforge", "mods", "config/Geyser-NeoForge")),
+        _ => None,
+    }
+}
+
+/// Download the latest build of a GeyserMC project for `platform` into `folder`, verified
+/// against the published SHA-256, and record it in `addons.lock`
+async fn install_latest(
+    instance_dir: &Path,
+    project: &str,
+    platform: &str,
+    folder: &str,
+) -> Result<String, String> {
+    let url = format!("{}/{}/versions/latest/builds/latest", API, project);
+    let build: GeyserBuild = reqwest::get(&url)
+        .await
+        .map_err(|e| format!("GET {} failed: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} build info: {}", project, e))?;
+    let download = build
+        .downloads
+        .get(platform)
+        .ok_or_else(|| format!("{} has no {} build", project, platform))?;
+
+    let url = format!(
+        "{}/{}/versions/{}/builds/{}/downloads/{}",
+        API, project, build.version, build.build, platform
+    );
+    println!("Downloading {} from {}...", download.name, url);
+    let bytes = download::fetch_bytes(reqwest::Client::new().get(&url)).await?;
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if !download.sha256.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "Hash mismatch for {}: expected {}, got {}",
+            download.name, download.sha256, actual
+        ));
+    }
+
+    let install_dir = instance_dir.join(folder);
+    fs::create_dir_all(&install_dir)
+        .map_err(|e| format!("Failed to create {}: {}", install_dir.display(), e))?;
+    let dest = install_dir.join(&download.name);
+    fs::write(&dest, &bytes).map_err(|e| format!("Writing {} failed: {}", dest.display(), e))?;
+
+    addons::record_install(
+        instance_dir,
+        LockedAddon {
+            source: "geysermc".into(),
+            project_id: project.to_string(),
+            version_id: build.build.to_string(),
+            version_number: format!("{}-b{}", build.version, build.build),
+            file_name: download.name.clone(),
+            folder: folder.to_string(),
+            sha1: Some(format!("{:x}", sha1::Sha1::digest(&bytes))),
+            installed_at: Utc::now().to_rfc3339(),
+        },
+    )?;
+
+    Ok(download.name.clone())
+}
+
+/// Point Geyser's Bedrock listener at `port`. Before the first start there is no config yet,
+/// so a minimal one is written and Geyser fills in the rest of its defaults
+fn write_bedrock_port(config_dir: &Path, port: u16) -> Result<(), String> {
+    fs::create_dir_all(config_dir)
+        .map_err(|e| format!("Failed to create {}: {}", config_dir.display(), e))?;
+    let path = config_dir.join("config.yml");
+
+    let Ok(content) = fs::read_to_string(&path) else {
+        let content = format!(
+            "bedrock:\n  port: {}\n  clone-remote-port: false\nremote:\n  auth-type: floodgate\n",
+            port
+        );
+        return fs::write(&path, content)
+            .map_err(|e| format!("Failed to write Geyser config: {}", e));
+    };
+
+    // Only the `port:` directly under `bedrock:` is ours, `remote:` has one as well
+    let mut in_bedrock = false;
+    let mut replaced = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if !line.starts_with(' ') && !line.trim().is_empty() && !line.starts_with('#') {
+                in_bedrock = line.trim_end() == "bedrock:";
+            } else if in_bedrock && !replaced {
+                let trimmed = line.trim_start();
+                if let Some(rest) = trimmed.strip_prefix("port:") {
+                    replaced = true;
+                    let indent = &line[..line.len() - trimmed.len()];
+                    let comment = rest.find('#').map(|i| &rest[i..]).unwrap_or("");
+                    return format!("{}port: {} {}", indent, port, comment)
+                        .trim_end()
+                        .to_string();
+                }
+            }
+            line.to_string()
+        })
+        .collect();
+    if !replaced {
+        return Err("Couldn't find the Bedrock port in Geyser's config.yml".into());
+    }
+
+    fs::write(&path, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write Geyser config: {}", e))
+}
+
+/// Install Geyser and Floodgate for the instance's platform so Bedrock players can join,
+/// listening on `bedrock_port` (or the first free port from 19132). With `create_tunnel`, a
+/// playit Bedrock tunnel is created for that port as well
+#[tauri::command]
+pub async fn setup_crossplay(
+    app_handle: tauri::AppHandle,
+    id: String,
+    bedrock_port: Option<u16>,
+    create_tunnel: bool,
+) -> Result<CrossplaySetup, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let (platform, folder, config_dir) = platform_for(&config.software).ok_or_else(|| {
+        format!(
+            "Geyser doesn't support {} instances, use Paper, Purpur, Fabric or NeoForge",
+            config.software
+        )
+    })?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!(
+            "Stop '{}' before setting up crossplay",
+            config.name
+        ));
+    }
+    if create_tunnel && !config.playit {
+        return Err("Enable playit for this instance to create a Bedrock tunnel".into());
+    }
+
+    let reserved = ports::reserved_ports(&app_handle, &id)?;
+    let bedrock_port = match bedrock_port {
+        Some(0) => return Err("Bedrock port cannot be 0".into()),
+        Some(port) => port,
+        None => ports::next_free_udp_port(DEFAULT_BEDROCK_PORT, &reserved)
+            .ok_or("No free UDP port for Bedrock")?,
+    };
+
+    let geyser_file = install_latest(&instance_dir, "geyser", platform, folder).await?;
+    let floodgate_file = install_latest(&instance_dir, "floodgate", platform, folder).await?;
+    // Geyser-Fabric needs the Fabric API mod
+    if platform == "fabric" {
+        modrinth::install(
+            &instance_dir,
+            folder,
+            FABRIC_API_PROJECT,
+            None,
+            &["fabric"],
+            &config.version,
+        )
+        .await?;
+    }
+
+    write_bedrock_port(&instance_dir.join(config_dir), bedrock_port)?;
+
+    let tunnel_id = if create_tunnel {
+        let secret = ensure_playit_secret(&app_handle, &mut config, &instance_dir).await?;
+        Some(
+            PlayitClient::new(secret)?
+                .create_tunnel(
+                    &format!("{} (Bedrock)", config.name),
+                    "minecraft-bedrock",
+                    "udp",
+                    bedrock_port,
+                )
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    Ok(CrossplaySetup {
+        geyser_file,
+        floodgate_file,
+        bedrock_port,
+        tunnel_id,
+    })
+}
@@ -0,0 +1,288 @@
+use std::{fs, path::Path};
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    addons,
+    config::get_config,
+    download, filesystem,
+    instance::get_instance_by_id,
+    models::{
+        AddonInstallOutcome, CurseForgeFile, CurseForgeMod, CurseForgeResponse,
+        CurseForgeSearchResults, LockedAddon,
+    },
+    secrets,
+};
+
+const API: &str = "https://api.curseforge.com/v1";
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+const MINECRAFT_GAME_ID: &str = "432";
+const MODS_CLASS_ID: &str = "6";
+const MAX_PAGE_SIZE: u32 = 50;
+const REQUIRED_DEPENDENCY: u32 = 3;
+const SHA1_ALGO: u32 = 1;
+
+/// CurseForge's `modLoaderType` for an instance's server software
+pub fn mod_loader_type(software: &str) -> Option<u32> {
+    match software {
+        "forge" => Some(1),
+        "fabric" => Some(4),
+        "neoforge" => Some(6),
+        _ => None,
+    }
+}
+
+/// The CurseForge API key from the credential store
+pub fn api_key(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let stored = get_config(app_handle.clone())?
+        .curseforge_api_key
+        .ok_or("Add a CurseForge API key in settings first")?;
+    secrets::load_credential(app_handle, &stored)
+}
+
+/// API key and loader type for an instance, or why CurseForge can't be used with it
+fn instance_context(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<(String, u32, String), String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let loader = mod_loader_type(config.effective_software()).ok_or_else(|| {
+        format!(
+            "CurseForge mods can't run on {} instances",
+            config.effective_software()
+        )
+    })?;
+    let key = api_key(app_handle)?;
+    Ok((key, loader, config.effective_version().to_string()))
+}
+
+async fn get_json<T: DeserializeOwned>(
+    key: &str,
+    path: &str,
+    query: &[(&str, String)],
+) -> Result<CurseForgeResponse<T>, String> {
+    let url = format!("{}{}", API, path);
+    let response = Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?
+        .get(&url)
+        .header("x-api-key", key)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| format!("GET {} failed: {}", url, e))?;
+    if response.status() == reqwest::StatusCode::FORBIDDEN {
+        return Err("CurseForge rejected the API key".into());
+    }
+    if !response.status().is_success() {
+        return Err(format!("{} -> HTTP {}", url, response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response from {}: {}", url, e))
+}
+
+/// Search CurseForge for mods that support the instance's loader and Minecraft version,
+/// most popular first
+#[tauri::command]
+pub async fn search_curseforge(
+    app_handle: tauri::AppHandle,
+    id: String,
+    query: String,
+    index: Option<u32>,
+    page_size: Option<u32>,
+) -> Result<CurseForgeSearchResults, String> {
+    let (key, loader, game_version) = instance_context(&app_handle, &id)?;
+
+    let response: CurseForgeResponse<Vec<CurseForgeMod>> = get_json(
+        &key,
+        "/mods/search",
+        &[
+            ("gameId", MINECRAFT_GAME_ID.into()),
+            ("classId", MODS_CLASS_ID.into()),
+            ("searchFilter", query),
+            ("gameVersion", game_version),
+            ("modLoaderType", loader.to_string()),
+            ("sortField", "2".into()),
+            ("sortOrder", "desc".into()),
+            ("index", index.unwrap_or(0).to_string()),
+            (
+                "pageSize",
+                page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE).to_string(),
+            ),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to search CurseForge: {}", e))?;
+
+    Ok(CurseForgeSearchResults {
+        mods: response.data,
+        pagination: response.pagination,
+    })
+}
+
+/// Files of a mod compatible with the instance, newest first
+#[tauri::command]
+pub async fn get_curseforge_files(
+    app_handle: tauri::AppHandle,
+    id: String,
+    mod_id: u64,
+) -> Result<Vec<CurseForgeFile>, String> {
+    let (key, loader, game_version) = instance_context(&app_handle, &id)?;
+    compatible_files(&key, mod_id, loader, &game_version).await
+}
+
+pub async fn compatible_files(
+    key: &str,
+    mod_id: u64,
+    loader: u32,
+    game_version: &str,
+) -> Result<Vec<CurseForgeFile>, String> {
+    let response: CurseForgeResponse<Vec<CurseForgeFile>> = get_json(
+        key,
+        &format!("/mods/{}/files", mod_id),
+        &[
+            ("gameVersion", game_version.to_string()),
+            ("modLoaderType", loader.to_string()),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch CurseForge files for {}: {}", mod_id, e))?;
+
+    let mut files = response.data;
+    files.sort_by(|a, b| b.file_date.cmp(&a.file_date));
+    Ok(files)
+}
+
+/// Download a file into `mods/`, checking it against CurseForge's SHA-1
+async fn download_file(
+    file: &CurseForgeFile,
+    url: &str,
+    mods_dir: &Path,
+) -> Result<String, String> {
+    println!("Downloading {} from {}...", file.file_name, url);
+    let bytes = download::fetch_bytes(reqwest::Client::new().get(url)).await?;
+
+    let actual = format!("{:x}", Sha1::digest(&bytes));
+    if let Some(expected) = file.hashes.iter().find(|hash| hash.algo == SHA1_ALGO) {
+        if !expected.value.eq_ignore_ascii_case(&actual) {
+            return Err(format!(
+                "Hash mismatch for {}: expected {}, got {}",
+                file.file_name, expected.value, actual
+            ));
+        }
+    }
+
+    let partial = mods_dir.join(format!("{}.part", file.file_name));
+    fs::write(&partial, &bytes)
+        .map_err(|e| format!("Writing {} failed: {}", partial.display(), e))?;
+    let dest = mods_dir.join(&file.file_name);
+    fs::rename(&partial, &dest).map_err(|e| format!("Writing {} failed: {}", dest.display(), e))?;
+    Ok(actual)
+}
+
+/// Install a CurseForge mod (the given file, or the newest compatible one) and its required
+/// dependencies into `mods/`. Authors can opt out of third-party downloads, in which case
+/// the file's page is returned so the user can fetch it in a browser. Returns one outcome
+/// per file
+#[tauri::command]
+pub async fn install_curseforge_mod(
+    app_handle: tauri::AppHandle,
+    id: String,
+    mod_id: u64,
+    file_id: Option<u64>,
+) -> Result<Vec<AddonInstallOutcome>, String> {
+    let (key, loader, game_version) = instance_context(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let mods_dir = instance_dir.join("mods");
+    fs::create_dir_all(&mods_dir)
+        .map_err(|e| format!("Failed to create {}: {}", mods_dir.display(), e))?;
+
+    let mut outcomes = Vec::new();
+    let mut queue = vec![(mod_id, file_id, true)];
+    let mut seen = Vec::new();
+
+    while let Some((mod_id, file_id, requested)) = queue.pop() {
+        if seen.contains(&mod_id) {
+            continue;
+        }
+        seen.push(mod_id);
+
+        if !requested
+            && addons::load_lockfile(&instance_dir)?
+                .addons
+                .iter()
+                .any(|addon| addon.source == "curseforge" && addon.project_id == mod_id.to_string())
+        {
+            continue;
+        }
+
+        let info: CurseForgeResponse<CurseForgeMod> =
+            get_json(&key, &format!("/mods/{}", mod_id), &[])
+                .await
+                .map_err(|e| format!("Failed to fetch CurseForge mod {}: {}", mod_id, e))?;
+        let info = info.data;
+
+        let file = match file_id {
+            Some(file_id) => {
+                get_json::<CurseForgeFile>(
+                    &key,
+                    &format!("/mods/{}/files/{}", mod_id, file_id),
+                    &[],
+                )
+                .await
+                .map_err(|e| format!("Failed to fetch CurseForge file {}: {}", file_id, e))?
+                .data
+            }
+            None => compatible_files(&key, mod_id, loader, &game_version)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("{} has no file for {}", info.name, game_version))?,
+        };
+
+        queue.extend(
+            file.dependencies
+                .iter()
+                .filter(|dependency| dependency.relation_type == REQUIRED_DEPENDENCY)
+                .map(|dependency| (dependency.mod_id, None, false)),
+        );
+
+        let Some(url) = file.download_url.clone() else {
+            outcomes.push(AddonInstallOutcome::ManualDownload {
+                url: format!("{}/files/{}", info.links.website_url, file.id),
+                reason: format!(
+                    "The author of {} doesn't allow downloads outside CurseForge",
+                    info.name
+                ),
+            });
+            continue;
+        };
+
+        let sha1 = download_file(&file, &url, &mods_dir).await?;
+        addons::record_install(
+            &instance_dir,
+            LockedAddon {
+                source: "curseforge".into(),
+                project_id: mod_id.to_string(),
+                version_id: file.id.to_string(),
+                version_number: file.display_name.clone(),
+                file_name: file.file_name.clone(),
+                folder: "mods".into(),
+                sha1: Some(sha1),
+                installed_at: Utc::now().to_rfc3339(),
+            },
+        )?;
+        outcomes.push(AddonInstallOutcome::Installed {
+            file_name: file.file_name,
+        });
+    }
+
+    Ok(outcomes)
+}
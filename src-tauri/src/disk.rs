@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::Emitter;
+
+use crate::{
+    filesystem::{self, dir_size},
+    instance::get_instance_by_id,
+    models::{DiskSpaceWarning, DiskUsage},
+};
+
+/// Cached usage is served as-is for this long before a background rescan
+const CACHE_TTL: Duration = Duration::from_secs(60);
+/// Warn once the data directory's volume has less than this much space left
+const LOW_DISK_THRESHOLD: u64 = 2 * 1024 * 1024 * 1024;
+
+struct CachedUsage {
+    usage: DiskUsage,
+    scanned_at: Instant,
+    refreshing: bool,
+}
+
+fn get_usage_map() -> &'static Mutex<HashMap<String, CachedUsage>> {
+    static USAGE: OnceLock<Mutex<HashMap<String, CachedUsage>>> = OnceLock::new();
+    USAGE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn scan(app_handle: &tauri::AppHandle, id: &str) -> Result<DiskUsage, String> {
+    let data_dir = filesystem::get_data_dir(app_handle)?;
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+
+    let mut worlds = 0;
+    let mut plugins = 0;
+    let mut mods = 0;
+    let mut logs = 0;
+    let mut instance_total = 0;
+
+    for entry in fs::read_dir(&instance_dir)
+        .map_err(|e| format!("Failed to read instance directory: {}", e))?
+        .flatten()
+    {
+        let path = entry.path();
+        let size = if path.is_dir() {
+            dir_size(&path)
+        } else {
+            entry.metadata().map(|m| m.len()).unwrap_or(0)
+        };
+        instance_total += size;
+
+        match entry.file_name().to_string_lossy().as_ref() {
+            "plugins" => plugins += size,
+            "mods" => mods += size,
+            "logs" | "crash-reports" => logs += size,
+            _ if path.join("level.dat").exists() => worlds += size,
+            _ => {}
+        }
+    }
+
+    let backups = dir_size(&data_dir.join("backups").join(id))
+        + dir_size(&data_dir.join("world-backups").join(id));
+
+    Ok(DiskUsage {
+        total_bytes: instance_total + backups,
+        worlds_bytes: worlds,
+        plugins_bytes: plugins,
+        mods_bytes: mods,
+        logs_bytes: logs,
+        backups_bytes: backups,
+        other_bytes: instance_total - worlds - plugins - mods - logs,
+    })
+}
+
+fn store(id: &str, usage: DiskUsage) {
+    get_usage_map().lock().unwrap().insert(
+        id.to_string(),
+        CachedUsage {
+            usage,
+            scanned_at: Instant::now(),
+            refreshing: false,
+        },
+    );
+}
+
+/// Total size of an instance plus a breakdown by worlds, plugins, mods, logs and backups.
+/// The first call scans the directory; later calls return the cached result and rescan in
+/// the background once it's stale
+#[tauri::command]
+pub async fn get_instance_disk_usage(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<DiskUsage, String> {
+    get_instance_by_id(&app_handle, &id)?;
+
+    let cached = {
+        let mut usage_map = get_usage_map().lock().unwrap();
+        match usage_map.get_mut(&id) {
+            Some(cached) => {
+                if cached.scanned_at.elapsed() >= CACHE_TTL && !cached.refreshing {
+                    cached.refreshing = true;
+                    let app_handle = app_handle.clone();
+                    let id = id.clone();
+                    tauri::async_runtime::spawn_blocking(move || match scan(&app_handle, &id) {
+                        Ok(usage) => store(&id, usage),
+                        Err(e) => {
+                            println!("Failed to scan disk usage of {}: {}", id, e);
+                            if let Some(cached) = get_usage_map().lock().unwrap().get_mut(&id) {
+                                cached.refreshing = false;
+                            }
+                        }
+                    });
+                }
+                Some(cached.usage.clone())
+            }
+            None => None,
+        }
+    };
+    if let Some(usage) = cached {
+        return Ok(usage);
+    }
+
+    let scan_handle = app_handle.clone();
+    let scan_id = id.clone();
+    let usage = tauri::async_runtime::spawn_blocking(move || scan(&scan_handle, &scan_id))
+        .await
+        .map_err(|e| format!("Disk usage scan failed: {}", e))??;
+    store(&id, usage.clone());
+    Ok(usage)
+}
+
+/// Free and total space of the volume holding `path`
+fn volume_space(path: &Path) -> Option<(u64, u64)> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| (disk.available_space(), disk.total_space()))
+}
+
+/// Emit `low-disk-space` when the data directory's volume drops below the threshold. Only
+/// fires again after space has recovered, so it doesn't repeat every check
+pub fn check_free_space(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let data_dir = filesystem::get_data_dir(app_handle)?;
+    let Some((available, total)) = volume_space(&data_dir) else {
+        return Ok(());
+    };
+
+    if available >= LOW_DISK_THRESHOLD {
+        WARNED.store(false, Ordering::Relaxed);
+        return Ok(());
+    }
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        let _ = app_handle.emit(
+            "low-disk-space",
+            DiskSpaceWarning {
+                path: data_dir.to_string_lossy().to_string(),
+                available_bytes: available,
+                total_bytes: total,
+            },
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::net::IpAddr;
+
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::{
+    address::{self, Endpoint},
+    config::get_config,
+    instance::{get_instance_by_id, update_instance_config},
+    models::SrvRecordResult,
+    secrets,
+};
+
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+const CLOUDFLARE_API: &str = "https://api.cloudflare.com/client/v4";
+
+#[derive(Deserialize)]
+struct CloudflareError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct CloudflareResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<CloudflareError>,
+    result: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct Zone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct DnsRecord {
+    id: String,
+}
+
+struct Cloudflare {
+    http: Client,
+    token: String,
+}
+
+impl Cloudflare {
+    fn new(token: String) -> Result<Self, String> {
+        let http = Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        Ok(Self { http, token })
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, String> {
+        let response: CloudflareResponse<T> = request
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| format!("Cloudflare request failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Cloudflare response: {}", e))?;
+        if !response.success {
+            let errors: Vec<String> = response
+                .errors
+                .iter()
+                .map(|error| format!("{} ({})", error.message, error.code))
+                .collect();
+            return Err(format!(
+                "Cloudflare rejected the request: {}",
+                errors.join(", ")
+            ));
+        }
+        response
+            .result
+            .ok_or_else(|| "Cloudflare returned an empty response".into())
+    }
+
+    /// The zone `domain` belongs to, found by trying each parent domain in turn
+    async fn find_zone(&self, domain: &str) -> Result<Zone, String> {
+        let labels: Vec<&str> = domain.split('.').collect();
+        for start in 0..labels.len().saturating_sub(1) {
+            let name = labels[start..].join(".");
+            let zones: Vec<Zone> = self
+                .send(
+                    self.http
+                        .get(format!("{}/zones", CLOUDFLARE_API))
+                        .query(&[("name", name.as_str())]),
+                )
+                .await?;
+            if let Some(zone) = zones.into_iter().find(|zone| zone.name == name) {
+                return Ok(zone);
+            }
+        }
+        Err(format!(
+            "No zone for {} in this Cloudflare account, or the token can't see it",
+            domain
+        ))
+    }
+
+    /// Update the record of this type and name if there is one, otherwise create it
+    async fn upsert(&self, zone: &Zone, record: serde_json::Value) -> Result<(), String> {
+        let records_url = format!("{}/zones/{}/dns_records", CLOUDFLARE_API, zone.id);
+        let existing: Vec<DnsRecord> = self
+            .send(self.http.get(&records_url).query(&[
+                ("type", record["type"].as_str().unwrap_or_default()),
+                ("name", record["name"].as_str().unwrap_or_default()),
+            ]))
+            .await?;
+
+        let request = match existing.first() {
+            Some(existing) => self
+                .http
+                .put(format!("{}/{}", records_url, existing.id))
+                .json(&record),
+            None => self.http.post(&records_url).json(&record),
+        };
+        self.send::<DnsRecord>(request).await.map(|_| ())
+    }
+}
+
+/// Point `_minecraft._tcp.<domain>` at the instance's tunnel or public address through the
+/// Cloudflare API, so players can join with just the domain. SRV targets must be hostnames,
+/// so a bare public IP gets an unproxied A/AAAA record on the domain itself
+#[tauri::command]
+pub async fn publish_srv_record(
+    app_handle: tauri::AppHandle,
+    id: String,
+    domain: String,
+) -> Result<SrvRecordResult, String> {
+    let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    if domain.is_empty() || !domain.contains('.') {
+        return Err(format!("'{}' is not a valid domain", domain));
+    }
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let token = get_config(app_handle.clone())?
+        .cloudflare_api_token
+        .ok_or("Add a Cloudflare API token in settings first")?;
+    let cloudflare = Cloudflare::new(secrets::load_credential(&app_handle, &token)?)?;
+
+    let Endpoint { host, port, .. } = address::public_endpoint(&app_handle, &id).await?;
+    let zone = cloudflare.find_zone(&domain).await?;
+
+    let (target, address_record) = match host.parse::<IpAddr>() {
+        Ok(ip) => {
+            let record_type = if ip.is_ipv4() { "A" } else { "AAAA" };
+            cloudflare
+                .upsert(
+                    &zone,
+                    json!({
+                        "type": record_type,
+                        "name": domain,
+                        "content": ip.to_string(),
+                        "ttl": 1,
+                        // Cloudflare's proxy only carries HTTP, not Minecraft
+                        "proxied": false,
+                    }),
+                )
+                .await?;
+            (domain.clone(), Some(format!("{} {}", record_type, ip)))
+        }
+        Err(_) => (host, None),
+    };
+
+    let srv_name = format!("_minecraft._tcp.{}", domain);
+    cloudflare
+        .upsert(
+            &zone,
+            json!({
+                "type": "SRV",
+                "name": srv_name,
+                "ttl": 1,
+                "data": {
+                    "priority": 0,
+                    "weight": 5,
+                    "port": port,
+                    "target": target,
+                },
+            }),
+        )
+        .await?;
+
+    config.domain = Some(domain.clone());
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(SrvRecordResult {
+        domain,
+        srv_name,
+        target,
+        port,
+        address_record,
+    })
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashSet,
+    path::Path,
+    process::{Command, Stdio},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{
+    instance::{get_instance_by_id, update_instance_config},
+    java,
+    models::{InstanceConfig, RuntimeConfig, RuntimeKind},
+    ports,
+    properties::ServerProperties,
+};
+
+/// Where the instance directory is mounted inside the container
+const DATA_DIR: &str = "/data";
+
+/// Instances whose server nuko started in a container, so stats and kills go to Docker
+/// rather than the attached `docker run` client
+fn get_containers() -> &'static Mutex<HashSet<String>> {
+    static CONTAINERS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    CONTAINERS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn container_name(id: &str) -> String {
+    format!("nuko-{}", id)
+}
+
+fn docker(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("docker")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run docker: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The Docker daemon's version, or an error when the CLI or daemon isn't reachable
+fn daemon_version() -> Result<String, String> {
+    docker(&["version", "--format", "{{.Server.Version}}"])
+        .map_err(|e| format!("Docker isn't available: {}", e))
+}
+
+fn image(config: &InstanceConfig) -> String {
+    config.runtime.image.clone().unwrap_or_else(|| {
+        let major = config
+            .runtime
+            .java_major
+            .unwrap_or_else(|| java::required_java_major(config.effective_version()));
+        format!("eclipse-temurin:{}-jre", major)
+    })
+}
+
+/// Whether the instance's container exists and is running
+pub fn is_running(id: &str) -> bool {
+    docker(&[
+        "inspect",
+        "--format",
+        "{{.State.Running}}",
+        &container_name(id),
+    ])
+    .is_ok_and(|running| running == "true")
+}
+
+/// Whether the instance's current server was started in a container by nuko
+pub fn is_managed(id: &str) -> bool {
+    get_containers().lock().unwrap().contains(id)
+}
+
+/// The `docker run` command for the instance, attached so stdin, stdout and exit status
+/// work like a local process. Arguments added afterwards go to `java` in the container.
+/// Call `register` once it's spawned
+pub fn run_command(config: &InstanceConfig, instance_dir: &Path) -> Result<Command, String> {
+    daemon_version()?;
+    let name = container_name(&config.id);
+    if is_running(&config.id) {
+        return Err(format!(
+            "Container {} is still running, stop it before starting '{}'",
+            name, config.name
+        ));
+    }
+    // A container left behind by a crash would block the name
+    let _ = docker(&["rm", "--force", &name]);
+
+    let mut cmd = Command::new("docker");
+    cmd.args(["run", "--rm", "--interactive", "--name", &name])
+        .arg("--label")
+        .arg(format!("nuko.instance={}", config.id))
+        .arg("--volume")
+        .arg(format!("{}:{}", instance_dir.display(), DATA_DIR))
+        .args(["--workdir", DATA_DIR]);
+
+    let properties = ServerProperties::load(instance_dir)?;
+    for (key, port) in ports::configured_ports(&properties) {
+        let protocol = if key == "query.port" { "udp" } else { "tcp" };
+        cmd.arg("--publish")
+            .arg(format!("{}:{}/{}", port, port, protocol));
+    }
+
+    if let Some(limit) = &config.java.memory_limit {
+        cmd.arg("--memory").arg(limit.trim());
+    }
+    if let Some(cores) = config.java.cpu_limit {
+        cmd.arg("--cpus").arg(cores.to_string());
+    }
+    // Run as the owner of the instance directory so files it writes stay editable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Ok(metadata) = std::fs::metadata(instance_dir) {
+            cmd.arg("--user")
+                .arg(format!("{}:{}", metadata.uid(), metadata.gid()));
+        }
+    }
+
+    cmd.arg(image(config)).arg("java");
+    Ok(cmd)
+}
+
+/// Track the instance's container once `run_command` has been spawned successfully
+pub fn register(id: &str) {
+    get_containers().lock().unwrap().insert(id.to_string());
+}
+
+/// Kill the instance's container, for when the server ignores `stop`
+pub fn kill(id: &str) {
+    let _ = docker(&["kill", &container_name(id)]);
+}
+
+/// Forget the instance's container once its server has exited
+pub fn release(id: &str) {
+    get_containers().lock().unwrap().remove(id);
+}
+
+/// Parse sizes as `docker stats` prints them, e.g. `512MiB` or `1.2GB`
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().ok()?;
+    let multiplier = match unit {
+        "B" | "" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+/// CPU usage in percent of one core and memory usage in bytes, from `docker stats`
+pub fn stats(id: &str) -> Option<(f32, u64)> {
+    let output = docker(&[
+        "stats",
+        "--no-stream",
+        "--format",
+        "{{.CPUPerc}}\t{{.MemUsage}}",
+        &container_name(id),
+    ])
+    .ok()?;
+    let (cpu, memory) = output.lines().next()?.split_once('\t')?;
+    let cpu = cpu.trim().trim_end_matches('%').parse().ok()?;
+    let memory = parse_size(memory.split('/').next()?)?;
+    Some((cpu, memory))
+}
+
+/// Run the instance natively or in a Docker container. Applies from the next start
+#[tauri::command]
+pub async fn set_instance_runtime(
+    app_handle: tauri::AppHandle,
+    id: String,
+    runtime: RuntimeConfig,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let mut runtime = runtime;
+    runtime.image = runtime
+        .image
+        .map(|image| image.trim().to_string())
+        .filter(|image| !image.is_empty());
+    if runtime.kind == RuntimeKind::Docker {
+        tauri::async_runtime::spawn_blocking(daemon_version)
+            .await
+            .map_err(|e| format!("Background task failed: {}", e))??;
+    }
+
+    config.runtime = runtime;
+    update_instance_config(&app_handle, &config)
+}
@@ -1,8 +1,111 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use md5::{Digest, Md5};
+use reqwest::{Client, RequestBuilder};
+use tokio::{
+    sync::Semaphore,
+    time::{sleep, Duration, Instant},
+};
+
+use crate::models::{
+    self, DownloadSettings, Instance, PurpurBuild, PurpurVersion, VersionDetails, VersionManifest,
+};
+use crate::{forge, papermc};
+
+const PURPUR_API: &str = "https://api.purpurmc.org/v2/purpur";
+
+/// Bandwidth cap shared by every download in bytes per second, 0 when uncapped
+static BANDWIDTH_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Limits how many downloads run at once. Replaced rather than resized when the setting
+/// changes, so downloads already running finish under the old limit
+fn get_slots() -> &'static Mutex<Arc<Semaphore>> {
+    static SLOTS: OnceLock<Mutex<Arc<Semaphore>>> = OnceLock::new();
+    SLOTS.get_or_init(|| {
+        Mutex::new(Arc::new(Semaphore::new(
+            DownloadSettings::default().max_parallel,
+        )))
+    })
+}
 
-use reqwest::Client;
+/// When the bandwidth budget allows the next chunk to be read
+fn get_pacer() -> &'static Mutex<Instant> {
+    static PACER: OnceLock<Mutex<Instant>> = OnceLock::new();
+    PACER.get_or_init(|| Mutex::new(Instant::now()))
+}
 
-use crate::models::{self, Instance, PaperBuilds, PaperDownload, VersionDetails, VersionManifest};
+/// Apply the download settings from config.toml to downloads started from now on
+pub fn apply_limits(settings: &DownloadSettings) {
+    *get_slots().lock().unwrap() = Arc::new(Semaphore::new(settings.max_parallel.max(1)));
+    BANDWIDTH_LIMIT.store(
+        settings.bandwidth_limit_kib.unwrap_or(0) * 1024,
+        Ordering::Relaxed,
+    );
+}
+
+/// Wait until `bytes` more fit in the bandwidth cap. Not reading the body while waiting
+/// makes TCP slow the sender down, so the cap holds on the wire too
+async fn pace(bytes: usize) {
+    let limit = BANDWIDTH_LIMIT.load(Ordering::Relaxed);
+    if limit == 0 {
+        return;
+    }
+    let wait = {
+        let mut next = get_pacer().lock().unwrap();
+        let now = Instant::now();
+        let start = (*next).max(now);
+        *next = start + Duration::from_secs_f64(bytes as f64 / limit as f64);
+        *next - now
+    };
+    sleep(wait).await;
+}
+
+/// Send `request` and pass its body to `on_chunk` as it arrives, within the global limits
+/// on parallel downloads and bandwidth
+pub async fn stream(
+    request: RequestBuilder,
+    mut on_chunk: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let slots = get_slots().lock().unwrap().clone();
+    let _slot = slots
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to wait for a download slot: {}", e))?;
+
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Download failed: {}", e))?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Reading body failed: {}", e))?
+    {
+        pace(chunk.len()).await;
+        on_chunk(&chunk)?;
+    }
+    Ok(())
+}
+
+/// Like `stream`, collecting the whole body in memory
+pub async fn fetch_bytes(request: RequestBuilder) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    stream(request, |chunk| {
+        bytes.extend_from_slice(chunk);
+        Ok(())
+    })
+    .await?;
+    Ok(bytes)
+}
 
 /// Download the appropriate server JAR for the given instance
 pub async fn download_server_jar(instance_dir: &Path, instance: &Instance) -> Result<(), String> {
@@ -12,9 +115,19 @@ pub async fn download_server_jar(instance_dir: &Path, instance: &Instance) -> Re
     );
     let url = match instance.software.as_str() {
         "vanilla" => resolve_vanilla_url(&instance.version).await?,
-        "papermc" => resolve_paper_url(&instance.version).await?,
-        "purpur" => resolve_purpur_url(&instance.version).await?,
-        "fabric" => resolve_fabric_url(&instance.version, instance.loader.as_deref()).await?,
+        "papermc" => papermc::latest_download_url("paper", &instance.version).await?,
+        "purpur" => {
+            return install_purpur(instance_dir, &instance.version, instance.loader.as_deref())
+                .await;
+        }
+        "fabric" => {
+            resolve_fabric_url(
+                &instance.version,
+                instance.loader.as_deref(),
+                instance.installer.as_deref(),
+            )
+            .await?
+        }
         "forge" => {
             let loader = instance
                 .loader
@@ -61,17 +174,8 @@ pub async fn download_server_jar(instance_dir: &Path, instance: &Instance) -> Re
     Ok(())
 }
 
-async fn download_to_path(url: &str, path: &Path) -> Result<(), String> {
-    let response = reqwest::get(url)
-        .await
-        .map_err(|e| format!("GET {} failed: {}", url, e))?;
-    if !response.status().is_success() {
-        return Err(format!("{} -> HTTP {}", url, response.status()));
-    }
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Reading body failed: {}", e))?;
+pub async fn download_to_path(url: &str, path: &Path) -> Result<(), String> {
+    let bytes = fetch_bytes(Client::new().get(url)).await?;
 
     fs::write(path, &bytes).map_err(|e| format!("Writing {} failed: {}", path.display(), e))?;
     Ok(())
@@ -103,66 +207,34 @@ async fn resolve_vanilla_url(version: &str) -> Result<String, String> {
     Ok(details.downloads.server.url)
 }
 
-async fn resolve_paper_url(version: &str) -> Result<String, String> {
-    let builds_url = format!(
-        "https://api.papermc.io/v2/projects/paper/versions/{}",
-        version
-    );
-    let builds: PaperBuilds = reqwest::get(&builds_url)
-        .await
-        .map_err(|e| format!("fetch Paper builds failed: {}", e))?
-        .json()
-        .await
-        .map_err(|e| format!("parse Paper builds failed: {}", e))?;
-
-    let latest = builds
-        .builds
-        .last()
-        .ok_or_else(|| format!("No Paper builds for {}", version))?
-        .build;
-
-    let meta_url = format!(
-        "https://api.papermc.io/v2/projects/paper/versions/{}/builds/{}",
-        version, latest
-    );
-    let meta: PaperDownload = reqwest::get(&meta_url)
-        .await
-        .map_err(|e| format!("fetch Paper build meta failed: {}", e))?
-        .json()
-        .await
-        .map_err(|e| format!("parse Paper build meta failed: {}", e))?;
-
-    let download = format!(
-        "https://api.papermc.io/v2/projects/paper/versions/{}/builds/{}/downloads/{}",
-        version, latest, meta.downloads.application.name
-    );
-
-    Ok(download)
-}
-
-async fn resolve_fabric_url(
+pub async fn resolve_fabric_url(
     mc_version: &str,
     loader_version: Option<&str>,
+    installer_version: Option<&str>,
 ) -> Result<String, String> {
     let loader = loader_version.ok_or_else(|| "Fabric loader version missing".to_string())?;
 
-    #[derive(serde::Deserialize)]
-    struct Installer {
-        version: String,
-    }
-
-    let installers: Vec<Installer> =
-        reqwest::get("https://meta.fabricmc.net/v2/versions/installer")
-            .await
-            .map_err(|e| format!("fetch Fabric installer versions failed: {}", e))?
-            .json()
-            .await
-            .map_err(|e| format!("parse Fabric installer versions failed: {}", e))?;
-
-    let installer_version = &installers
-        .first()
-        .ok_or_else(|| "No Fabric installer versions found".to_string())?
-        .version;
+    let installer_version = match installer_version {
+        Some(pinned) => pinned.to_string(),
+        None => {
+            let installers: Vec<models::FabricInstallerVersion> =
+                reqwest::get("https://meta.fabricmc.net/v2/versions/installer")
+                    .await
+                    .map_err(|e| format!("fetch Fabric installer versions failed: {}", e))?
+                    .json()
+                    .await
+                    .map_err(|e| format!("parse Fabric installer versions failed: {}", e))?;
+
+            // Newest first; fall back to a beta when nothing stable is published
+            installers
+                .iter()
+                .find(|installer| installer.stable)
+                .or(installers.first())
+                .ok_or_else(|| "No Fabric installer versions found".to_string())?
+                .version
+                .clone()
+        }
+    };
 
     Ok(format!(
         "https://meta.fabricmc.net/v2/versions/loader/{}/{}/{}/server/jar",
@@ -198,6 +270,10 @@ async fn install_forge(
     let _ = fs::remove_file(&installer_path);
     let _ = fs::remove_file(instance_dir.join("forge-installer.jar.log"));
 
+    // Forge 1.17+ launches through an args file instead, see `forge::args_file`
+    if forge::args_file(instance_dir, Some(forge_version), !cfg!(windows)).is_some() {
+        return Ok(());
+    }
     if let Ok(entries) = fs::read_dir(instance_dir) {
         for entry in entries.flatten() {
             let file_name = entry.file_name();
@@ -213,11 +289,112 @@ async fn install_forge(
     Ok(())
 }
 
-async fn resolve_purpur_url(version: &str) -> Result<String, String> {
-    Ok(format!(
-        "https://api.purpurmc.org/v2/purpur/{}/latest/download",
-        version
-    ))
+async fn fetch_purpur_version(version: &str) -> Result<PurpurVersion, String> {
+    reqwest::get(format!("{}/{}", PURPUR_API, version))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch Purpur {} builds: {}", version, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Purpur {} builds: {}", version, e))
+}
+
+async fn fetch_purpur_build(version: &str, build: &str) -> Result<PurpurBuild, String> {
+    reqwest::get(format!("{}/{}/{}", PURPUR_API, version, build))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch Purpur build {}: {}", build, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Purpur build {}: {}", build, e))
+}
+
+/// The newest Purpur build for `version`
+pub async fn latest_purpur_build(version: &str) -> Result<String, String> {
+    Ok(fetch_purpur_version(version).await?.builds.latest)
+}
+
+/// Commit summaries of the Purpur builds after `build` up to `latest`, newest first. Each
+/// build is a request of its own, so only the most recent ones are looked at
+pub async fn purpur_changes_since(
+    version: &str,
+    build: &str,
+    latest: &str,
+) -> Result<Vec<String>, String> {
+    const MAX_BUILDS: usize = 20;
+    let builds = fetch_purpur_version(version).await?.builds.all;
+    let start = builds
+        .iter()
+        .position(|b| b == build)
+        .map(|index| index + 1)
+        .unwrap_or(builds.len());
+    let end = builds
+        .iter()
+        .position(|b| b == latest)
+        .map(|index| index + 1)
+        .unwrap_or(builds.len());
+
+    let mut changes = Vec::new();
+    for newer in builds[start.min(end)..end].iter().rev().take(MAX_BUILDS) {
+        let meta = fetch_purpur_build(version, newer).await?;
+        changes.extend(meta.commits.into_iter().map(|commit| {
+            commit
+                .description
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        }));
+    }
+    Ok(changes)
+}
+
+/// Download a Purpur build (the pinned one, else the newest) and check it against the MD5
+/// the API publishes before it's moved into place
+pub async fn install_purpur(
+    instance_dir: &Path,
+    version: &str,
+    build: Option<&str>,
+) -> Result<(), String> {
+    let build = match build.map(str::trim).filter(|build| !build.is_empty()) {
+        Some(build) => build.to_string(),
+        None => fetch_purpur_version(version).await?.builds.latest,
+    };
+
+    let build_url = format!("{}/{}/{}", PURPUR_API, version, build);
+    let meta = fetch_purpur_build(version, &build).await?;
+    if meta.result != "SUCCESS" {
+        return Err(format!(
+            "Purpur build {} for {} failed to build upstream, pick another build",
+            meta.build, version
+        ));
+    }
+    let expected = meta
+        .md5
+        .ok_or_else(|| format!("Purpur didn't publish an MD5 for build {}", meta.build))?;
+
+    let url = format!("{}/download", build_url);
+    println!(
+        "Downloading Purpur {} build {} from {}...",
+        version, build, url
+    );
+    let bytes = fetch_bytes(Client::new().get(&url)).await?;
+    let actual = format!("{:x}", Md5::digest(&bytes));
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "Hash mismatch for Purpur build {}: expected {}, got {}",
+            build, expected, actual
+        ));
+    }
+
+    let partial = instance_dir.join("server.jar.part");
+    fs::write(&partial, &bytes)
+        .map_err(|e| format!("Writing {} failed: {}", partial.display(), e))?;
+    let jar_path = instance_dir.join("server.jar");
+    fs::rename(&partial, &jar_path)
+        .map_err(|e| format!("Writing {} failed: {}", jar_path.display(), e))?;
+    println!("Download complete!");
+    Ok(())
 }
 
 async fn install_neoforge(
@@ -247,6 +424,9 @@ async fn install_neoforge(
     let _ = fs::remove_file(&installer_path);
     let _ = fs::remove_file(instance_dir.join("neoforge-installer.jar.log"));
 
+    if forge::args_file(instance_dir, Some(neoforge_version), !cfg!(windows)).is_some() {
+        return Ok(());
+    }
     if let Ok(entries) = fs::read_dir(instance_dir) {
         for entry in entries.flatten() {
             let file_name = entry.file_name();
@@ -294,23 +474,7 @@ pub async fn get_vanilla_versions() -> Result<Vec<String>, String> {
 /// Returns versions sorted newest first
 #[tauri::command]
 pub async fn get_paper_versions() -> Result<Vec<String>, String> {
-    let client = Client::new();
-    let response = client
-        .get("https://api.papermc.io/v2/projects/paper")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch Paper versions: {}", e))?;
-
-    let project: models::PaperProjectResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Paper response: {}", e))?;
-
-    // Paper API returns versions oldest-first, so reverse them
-    let mut versions = project.versions;
-    versions.reverse();
-
-    Ok(versions)
+    papermc::versions("paper").await
 }
 
 /// Fetch Fabric-supported Minecraft versions
@@ -340,9 +504,12 @@ pub async fn get_fabric_game_versions() -> Result<Vec<String>, String> {
 }
 
 /// Fetch Fabric loader versions compatible with a specific Minecraft version
-/// Returns loader versions sorted newest first
+/// Returns loader versions sorted newest first, only stable ones with `stable_only`
 #[tauri::command]
-pub async fn get_fabric_loader_versions(mc_version: String) -> Result<Vec<String>, String> {
+pub async fn get_fabric_loader_versions(
+    mc_version: String,
+    stable_only: Option<bool>,
+) -> Result<Vec<String>, String> {
     let client = Client::new();
     let url = format!(
         "https://meta.fabricmc.net/v2/versions/loader/{}",
@@ -360,8 +527,13 @@ pub async fn get_fabric_loader_versions(mc_version: String) -> Result<Vec<String
         .await
         .map_err(|e| format!("Failed to parse Fabric loader versions: {}", e))?;
 
-    // Return all loader versions (already sorted newest first by the API)
-    let versions: Vec<String> = loaders.into_iter().map(|l| l.loader.version).collect();
+    // Already sorted newest first by the API
+    let stable_only = stable_only.unwrap_or(false);
+    let versions: Vec<String> = loaders
+        .into_iter()
+        .filter(|l| !stable_only || l.loader.stable)
+        .map(|l| l.loader.version)
+        .collect();
 
     Ok(versions)
 }
@@ -470,7 +642,7 @@ pub async fn get_forge_versions(mc_version: String) -> Result<Vec<String>, Strin
 pub async fn get_purpur_versions() -> Result<Vec<String>, String> {
     let client = Client::new();
     let response = client
-        .get("https://api.purpurmc.org/v2/purpur")
+        .get(PURPUR_API)
         .send()
         .await
         .map_err(|e| format!("Failed to fetch Purpur versions: {}", e))?;
@@ -491,6 +663,15 @@ pub async fn get_purpur_versions() -> Result<Vec<String>, String> {
     Ok(versions)
 }
 
+/// Fetch the builds published for a Purpur version, newest first. Any of them can be pinned
+/// as the instance's loader
+#[tauri::command]
+pub async fn get_purpur_builds(version: String) -> Result<Vec<String>, String> {
+    let mut builds = fetch_purpur_version(&version).await?.builds.all;
+    builds.reverse();
+    Ok(builds)
+}
+
 /// Fetch Minecraft versions that have NeoForge support
 /// Returns versions sorted newest first
 #[tauri::command]
@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+/// An error with a machine-readable code, for failures the frontend reacts to specifically
+/// (e.g. prompting for EULA acceptance). Commands still return `String` errors, so this is
+/// passed across as JSON the frontend can parse
+#[derive(Debug, Serialize)]
+pub struct CommandError {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl CommandError {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        serde_json::to_string(&error).unwrap_or(error.message)
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use tauri::Emitter;
+
+use crate::{
+    archive::{untar_gz_with_progress, unzip_with_progress, zip_paths},
+    audit, filesystem,
+    instance::get_instance_by_id,
+    models::{ArchiveProgress, FileContent, FileEntry},
+};
+
+/// Larger files have to be downloaded or replaced through upload instead of the editor
+const MAX_TEXT_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_UPLOAD_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+/// How much of a file is inspected to decide whether it's binary
+const BINARY_SNIFF_LEN: usize = 8192;
+/// Removing or renaming these would make nuko lose track of the instance
+const PROTECTED: &[&str] = &["nuko.toml"];
+
+/// Resolve `relative` inside the instance directory. `..`, absolute paths and symlinks that
+/// lead outside the instance are rejected, so the frontend can only ever touch files of the
+/// instance it asked about
+pub fn jail(instance_dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative.trim_start_matches(['/', '\\']));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("Invalid path '{}'", relative.display()));
+    }
+
+    let root = instance_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve instance directory: {}", e))?;
+    let path = root.join(relative);
+
+    // The target may not exist yet, so check the closest ancestor that does
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(&root);
+    let resolved = existing
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", relative.display(), e))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "{} is outside the instance directory",
+            relative.display()
+        ));
+    }
+    // A symlink as the last component can point anywhere
+    if path.is_symlink() {
+        let target = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", relative.display(), e))?;
+        if !target.starts_with(&root) {
+            return Err(format!(
+                "{} is outside the instance directory",
+                relative.display()
+            ));
+        }
+    }
+
+    Ok(path)
+}
+
+fn relative_path(instance_dir: &Path, path: &Path) -> String {
+    let root = instance_dir
+        .canonicalize()
+        .unwrap_or(instance_dir.to_path_buf());
+    path.strip_prefix(&root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn ensure_not_protected(instance_dir: &Path, path: &Path) -> Result<(), String> {
+    let relative = relative_path(instance_dir, path);
+    if relative.is_empty() {
+        return Err("The instance directory itself can't be changed".into());
+    }
+    // Case-insensitive filesystems would otherwise let e.g. `NUKO.TOML` through
+    if PROTECTED
+        .iter()
+        .any(|protected| protected.eq_ignore_ascii_case(&relative))
+    {
+        return Err(format!("{} is managed by nuko", relative));
+    }
+    Ok(())
+}
+
+/// Whether the start of a file looks like binary data rather than text
+fn is_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    if sample.contains(&0) {
+        return true;
+    }
+    // A multi-byte character may be cut off at the end of the sample
+    match std::str::from_utf8(sample) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+fn instance_root(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    get_instance_by_id(app_handle, id)?;
+    filesystem::get_instance_dir(app_handle, id)
+}
+
+/// Entries of a directory in the instance, folders first
+#[tauri::command]
+pub async fn list_instance_files(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<Vec<FileEntry>, String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let dir = jail(&instance_dir, &path)?;
+
+    let mut entries: Vec<FileEntry> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(FileEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: relative_path(&instance_dir, &entry.path()),
+                is_dir: metadata.is_dir(),
+                size_bytes: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339()),
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    Ok(entries)
+}
+
+/// Read a text file from the instance. Binary files come back flagged with no content
+#[tauri::command]
+pub async fn read_instance_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<FileContent, String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let file = jail(&instance_dir, &path)?;
+
+    let size = fs::metadata(&file)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .len();
+    if file.is_dir() {
+        return Err(format!("{} is a folder", path));
+    }
+
+    let mut sample = Vec::new();
+    fs::File::open(&file)
+        .and_then(|f| f.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut sample))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if is_binary(&sample) {
+        return Ok(FileContent {
+            path,
+            content: String::new(),
+            binary: true,
+            size_bytes: size,
+        });
+    }
+    if size > MAX_TEXT_SIZE {
+        return Err(format!("{} is too large to open in the editor", path));
+    }
+
+    let bytes = fs::read(&file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    match String::from_utf8(bytes) {
+        Ok(content) => Ok(FileContent {
+            path,
+            content,
+            binary: false,
+            size_bytes: size,
+        }),
+        Err(_) => Ok(FileContent {
+            path,
+            content: String::new(),
+            binary: true,
+            size_bytes: size,
+        }),
+    }
+}
+
+/// Create or overwrite a text file in the instance
+#[tauri::command]
+pub async fn write_instance_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let file = jail(&instance_dir, &path)?;
+    ensure_not_protected(&instance_dir, &file)?;
+    if content.len() as u64 > MAX_TEXT_SIZE {
+        return Err(format!("{} is too large to save from the editor", path));
+    }
+    if file.is_dir() {
+        return Err(format!("{} is a folder", path));
+    }
+
+    let file_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let partial = file.with_file_name(format!("{}.part", file_name));
+    fs::write(&partial, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    fs::rename(&partial, &file).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    audit::record(
+        &app_handle,
+        &id,
+        "write_file",
+        serde_json::json!({ "path": path }),
+    );
+    Ok(())
+}
+
+/// Rename or move a file or folder within the instance
+#[tauri::command]
+pub async fn rename_instance_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    from: String,
+    to: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let source = jail(&instance_dir, &from)?;
+    let dest = jail(&instance_dir, &to)?;
+    ensure_not_protected(&instance_dir, &source)?;
+    ensure_not_protected(&instance_dir, &dest)?;
+
+    if !source.exists() {
+        return Err(format!("{} doesn't exist", from));
+    }
+    if dest.exists() {
+        return Err(format!("{} already exists", to));
+    }
+    if dest.starts_with(&source) {
+        return Err(format!("Can't move {} into itself", from));
+    }
+
+    fs::rename(&source, &dest).map_err(|e| format!("Failed to rename {}: {}", from, e))?;
+    audit::record(
+        &app_handle,
+        &id,
+        "rename_file",
+        serde_json::json!({ "from": from, "to": to }),
+    );
+    Ok(())
+}
+
+/// Delete a file or folder (with everything in it) from the instance
+#[tauri::command]
+pub async fn delete_instance_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let target = jail(&instance_dir, &path)?;
+    ensure_not_protected(&instance_dir, &target)?;
+
+    // Remove symlinks themselves rather than what they point to
+    let metadata =
+        fs::symlink_metadata(&target).map_err(|e| format!("Failed to delete {}: {}", path, e))?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(&target)
+    } else {
+        fs::remove_file(&target)
+    }
+    .map_err(|e| format!("Failed to delete {}: {}", path, e))?;
+    audit::record(
+        &app_handle,
+        &id,
+        "delete_file",
+        serde_json::json!({ "path": path }),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_instance_folder(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let dir = jail(&instance_dir, &path)?;
+    if dir.exists() {
+        return Err(format!("{} already exists", path));
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", path, e))
+}
+
+/// Copy a file picked on the user's machine into a folder of the instance. Returns the
+/// new file's path relative to the instance
+#[tauri::command]
+pub async fn upload_instance_file(
+    app_handle: tauri::AppHandle,
+    id: String,
+    source: String,
+    dest_dir: String,
+) -> Result<String, String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let dir = jail(&instance_dir, &dest_dir)?;
+    if !dir.is_dir() {
+        return Err(format!("{} isn't a folder", dest_dir));
+    }
+
+    let source = PathBuf::from(source);
+    let metadata =
+        fs::metadata(&source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} isn't a file", source.display()));
+    }
+    if metadata.len() > MAX_UPLOAD_SIZE {
+        return Err(format!("{} is too large to upload", source.display()));
+    }
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid file {}", source.display()))?;
+
+    let dest = jail(&instance_dir, &format!("{}/{}", dest_dir, file_name))?;
+    ensure_not_protected(&instance_dir, &dest)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        fs::copy(&source, &dest)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to upload {}: {}", source.display(), e))
+    })
+    .await
+    .map_err(|e| format!("Background task failed: {}", e))??;
+
+    Ok(relative_path(&instance_dir, &dir.join(file_name)))
+}
+
+fn emit_archive_progress(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    operation: &str,
+    archive: &str,
+    bytes_done: u64,
+    bytes_total: u64,
+) {
+    let _ = app_handle.emit(
+        "archive-progress",
+        ArchiveProgress {
+            id: id.to_string(),
+            operation: operation.to_string(),
+            archive: archive.to_string(),
+            bytes_done,
+            bytes_total,
+        },
+    );
+}
+
+/// Zip files and folders of the instance into `dest` (also inside the instance). Progress
+/// is emitted as `archive-progress`
+#[tauri::command]
+pub async fn compress_path(
+    app_handle: tauri::AppHandle,
+    id: String,
+    paths: Vec<String>,
+    dest: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    if paths.is_empty() {
+        return Err("Nothing to compress".into());
+    }
+    if !dest.to_lowercase().ends_with(".zip") {
+        return Err("Archives can only be created as .zip".into());
+    }
+    let sources = paths
+        .iter()
+        .map(|path| {
+            let source = jail(&instance_dir, path)?;
+            if !source.exists() {
+                return Err(format!("{} doesn't exist", path));
+            }
+            if relative_path(&instance_dir, &source).is_empty() {
+                return Err("The whole instance can't be compressed into itself".into());
+            }
+            Ok(source)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let dest_path = jail(&instance_dir, &dest)?;
+    if dest_path.exists() {
+        return Err(format!("{} already exists", dest));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = zip_paths(&sources, &dest_path, |done, total| {
+            emit_archive_progress(&app_handle, &id, "compress", &dest, done, total)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&dest_path);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("Background task failed: {}", e))?
+}
+
+/// Extract a `.zip` or `.tar.gz` from the instance into the `dest` folder. Entries that
+/// would land outside `dest` are rejected. Progress is emitted as `archive-progress`
+#[tauri::command]
+pub async fn extract_archive(
+    app_handle: tauri::AppHandle,
+    id: String,
+    archive: String,
+    dest: String,
+) -> Result<(), String> {
+    let instance_dir = instance_root(&app_handle, &id)?;
+    let archive_path = jail(&instance_dir, &archive)?;
+    if !archive_path.is_file() {
+        return Err(format!("{} isn't a file", archive));
+    }
+    let dest_path = jail(&instance_dir, &dest)?;
+    fs::create_dir_all(&dest_path).map_err(|e| format!("Failed to create {}: {}", dest, e))?;
+
+    let lower = archive.to_lowercase();
+    let is_zip = lower.ends_with(".zip") || lower.ends_with(".jar");
+    let is_tar_gz = lower.ends_with(".tar.gz") || lower.ends_with(".tgz");
+    if !is_zip && !is_tar_gz {
+        return Err(format!("{} isn't a .zip or .tar.gz archive", archive));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let check_path = |path: &Path| ensure_not_protected(&instance_dir, path);
+        let progress =
+            |done, total| emit_archive_progress(&app_handle, &id, "extract", &archive, done, total);
+        if is_zip {
+            unzip_with_progress(&archive_path, &dest_path, check_path, progress)
+        } else {
+            untar_gz_with_progress(&archive_path, &dest_path, check_path, progress)
+        }
+    })
+    .await
+    .map_err(|e| format!("Background task failed: {}", e))?
+}
@@ -1,12 +1,22 @@
+use serde::Serialize;
 use std::{
     fs,
+    io::Write,
     path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
 };
 use tauri::Manager;
 
 use chrono::Utc;
 
-use crate::models::{Instance, InstanceConfig, JavaConfig, MetadataConfig, PlayitMetadata};
+use crate::models::{
+    AutoRestartConfig, HookConfig, Instance, InstanceConfig, InstanceDefaults, JavaConfig,
+    MetadataConfig, PlayitMetadata, ProcessConfig, RuntimeConfig, StartupConfig, TailscaleConfig,
+    WatchdogConfig,
+};
+use crate::{config, migrate};
+
+const MAX_INSTANCE_NAME_LEN: usize = 64;
 
 /// Get the application's data directory, creating it if it doesn't exist
 pub fn get_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
@@ -20,10 +30,60 @@ pub fn get_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(data_dir)
 }
 
-/// Create a new instance directory with the given name, software, version, and optional loader
-/// along with downloading logic
-pub async fn create_directory(data_dir: PathBuf, name: &String) -> Result<PathBuf, String> {
-    let instance_dir = data_dir.join("instances").join(name);
+/// Get the directory of the instance with the given id. Instances are stored under their
+/// UUID so that display names never touch the filesystem
+pub fn get_instance_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let id = uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid instance id '{}'", id))?;
+    Ok(get_instances_dir(app_handle)?.join(id.hyphenated().to_string()))
+}
+
+/// Cached `GlobalConfig.instances_dir`, resolved on first use and replaced on relocation
+fn get_instances_dir_cache() -> &'static Mutex<Option<PathBuf>> {
+    static INSTANCES_DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    INSTANCES_DIR.get_or_init(|| Mutex::new(None))
+}
+
+/// The directory instances live in: `instances` in the data dir unless the user moved it
+/// with `relocate_data_dir`
+pub fn get_instances_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let mut cached = get_instances_dir_cache().lock().unwrap();
+    if let Some(dir) = cached.as_ref() {
+        return Ok(dir.clone());
+    }
+    let dir = match config::get_config(app_handle.clone())?.instances_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => get_data_dir(app_handle)?.join("instances"),
+    };
+    *cached = Some(dir.clone());
+    Ok(dir)
+}
+
+/// Point `get_instances_dir` at a new location after the config has been switched over
+pub fn set_instances_dir(dir: PathBuf) {
+    *get_instances_dir_cache().lock().unwrap() = Some(dir);
+}
+
+/// Validate a user supplied instance name, returning the trimmed name
+pub fn validate_instance_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err("Instance name cannot be empty".into());
+    }
+    if trimmed.chars().count() > MAX_INSTANCE_NAME_LEN {
+        return Err(format!(
+            "Instance name cannot be longer than {} characters",
+            MAX_INSTANCE_NAME_LEN
+        ));
+    }
+    if trimmed.chars().any(|c| c.is_control()) {
+        return Err("Instance name cannot contain control characters".into());
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Create a new instance directory named after the instance id
+pub async fn create_directory(instances_dir: PathBuf, id: &str) -> Result<PathBuf, String> {
+    let instance_dir = instances_dir.join(id);
 
     fs::create_dir_all(&instance_dir)
         .map_err(|e| format!("Failed to create instance directory: {}", e))?;
@@ -31,32 +91,65 @@ pub async fn create_directory(data_dir: PathBuf, name: &String) -> Result<PathBu
     Ok(instance_dir)
 }
 
-pub async fn create_eula_txt(instance_dir: &PathBuf) -> Result<(), String> {
+/// Write eula.txt with the user's explicit answer to the Minecraft EULA
+pub async fn create_eula_txt(instance_dir: &Path, accepted: bool) -> Result<(), String> {
     let eula_path = instance_dir.join("eula.txt");
-    fs::write(&eula_path, "eula=true").map_err(|e| format!("Failed to create eula.txt: {}", e))?;
+    let content = format!(
+        "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n#{}\neula={}\n",
+        Utc::now().to_rfc2822(),
+        accepted
+    );
+    fs::write(&eula_path, content).map_err(|e| format!("Failed to create eula.txt: {}", e))?;
     Ok(())
 }
 
+/// Whether eula.txt in the instance directory records acceptance of the Minecraft EULA
+pub fn is_eula_accepted(instance_dir: &Path) -> bool {
+    fs::read_to_string(instance_dir.join("eula.txt"))
+        .map(|content| {
+            content.lines().any(|line| {
+                line.trim()
+                    .strip_prefix("eula=")
+                    .map(|value| value.trim().eq_ignore_ascii_case("true"))
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
 pub async fn create_nuko_properties(
     instance_dir: &PathBuf,
     instance: &Instance,
+    defaults: &InstanceDefaults,
 ) -> Result<(), String> {
     let properties_path = instance_dir.join("nuko.toml");
 
     let config = InstanceConfig {
-        id: uuid::Uuid::new_v4().to_string(),
+        config_version: migrate::INSTANCE_CONFIG_VERSION,
+        id: instance.id.clone(),
         custom_jar_path: instance.custom_jar_path.clone(),
         name: instance.name.clone(),
         software: instance.software.clone(),
         version: instance.version.clone(),
         loader: instance.loader.clone(),
+        installer: instance.installer.clone(),
+        build: None,
+        detected: None,
         playit: instance.playit,
         playit_secret: None,
+        ngrok: false,
+        tailscale: TailscaleConfig::default(),
+        custom_tunnel: None,
+        upnp: false,
+        lan_broadcast: false,
+        domain: None,
         java: JavaConfig {
-            min_memory: "2G".to_string(),
-            max_memory: "4G".to_string(),
-            java_path: None,
-            additional_args: vec![],
+            min_memory: defaults.min_memory.clone(),
+            max_memory: defaults.max_memory.clone(),
+            java_path: defaults.java_path.clone(),
+            additional_args: defaults.jvm_args.clone(),
+            memory_limit: None,
+            cpu_limit: None,
         },
         metadata: MetadataConfig {
             created_at: Utc::now().to_rfc3339(),
@@ -64,6 +157,25 @@ pub async fn create_nuko_properties(
             play_time_minutes: 0,
             playit: PlayitMetadata::default(),
         },
+        startup: StartupConfig::default(),
+        tags: vec![],
+        favorite: false,
+        notes: String::new(),
+        auto_assign_ports: false,
+        resource_pack: None,
+        backup_retention: defaults.backup_retention.clone(),
+        incremental_backups: defaults.incremental_backups,
+        rcon: None,
+        tasks: vec![],
+        automations: vec![],
+        announcements: vec![],
+        maintenance: None,
+        hooks: HookConfig::default(),
+        process: ProcessConfig::default(),
+        watchdog: WatchdogConfig::default(),
+        auto_restart: AutoRestartConfig::default(),
+        crash_loop: None,
+        runtime: RuntimeConfig::default(),
     };
 
     let toml_string = toml::to_string_pretty(&config)
@@ -75,6 +187,14 @@ pub async fn create_nuko_properties(
     Ok(())
 }
 
+/// Read an instance's nuko.toml, upgrading it in place if it was written in an older format
+pub fn load_instance_config(instance_dir: &Path) -> Result<InstanceConfig, String> {
+    migrate::load(
+        &instance_dir.join("nuko.toml"),
+        migrate::ConfigFile::Instance,
+    )
+}
+
 pub fn save_instance_config(instance_dir: &Path, config: &InstanceConfig) -> Result<(), String> {
     let properties_path = instance_dir.join("nuko.toml");
     let toml_string = toml::to_string_pretty(config)
@@ -83,3 +203,87 @@ pub fn save_instance_config(instance_dir: &Path, config: &InstanceConfig) -> Res
     fs::write(&properties_path, toml_string)
         .map_err(|e| format!("Failed to write nuko.toml: {}", e))
 }
+
+/// Rename instance directories created before instances were stored under their UUID.
+/// Directories that can't be moved are logged and left where they are
+pub fn migrate_legacy_instance_dirs(instances_dir: &Path) -> Result<(), String> {
+    if !instances_dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(instances_dir)
+        .map_err(|e| format!("Failed to read instances directory: {}", e))?
+        .flatten()
+    {
+        let path = entry.path();
+        let Ok(config) = load_instance_config(&path) else {
+            continue;
+        };
+
+        // The id becomes a path component, so anything but a UUID could move the directory
+        // out of the instances directory
+        let Ok(id) = uuid::Uuid::parse_str(&config.id) else {
+            println!(
+                "Not migrating instance '{}': invalid id '{}'",
+                config.name, config.id
+            );
+            continue;
+        };
+
+        let dir_name = id.hyphenated().to_string();
+        if entry.file_name().to_string_lossy() == dir_name {
+            continue;
+        }
+
+        let target = instances_dir.join(&dir_name);
+        if target.exists() {
+            println!(
+                "Not migrating instance '{}': {} already exists",
+                config.name,
+                target.display()
+            );
+            continue;
+        }
+
+        if let Err(e) = fs::rename(&path, &target) {
+            println!(
+                "Failed to migrate instance '{}' to its id directory: {}",
+                config.name, e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Total size in bytes of every file under `path`
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Append `value` as one line of JSON, creating the file if needed. Appends from different
+/// threads are serialized so lines never interleave
+pub fn append_json_line<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    static WRITE_LOCK: Mutex<()> = Mutex::new(());
+    let line =
+        serde_json::to_string(value).map_err(|e| format!("Failed to serialize entry: {}", e))?;
+
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,59 @@
+use std::{fs, path::Path};
+
+/// Where modern Forge and NeoForge installers put each build's launch arguments, relative to
+/// the instance directory. NeoForge for 1.20.1 still used the Forge layout under its own group
+const LIBRARY_DIRS: &[&str] = &[
+    "libraries/net/minecraftforge/forge",
+    "libraries/net/neoforged/neoforge",
+    "libraries/net/neoforged/forge",
+];
+
+/// The args file the installer generated in place of a runnable server.jar, as a path
+/// relative to the instance directory for `java @<file>`. Forge names build folders
+/// `<minecraft>-<forge>` and NeoForge just `<neoforge>`, so the build matching `loader` is
+/// preferred, then the newest one. `unix` picks unix_args.txt over win_args.txt
+pub fn args_file(instance_dir: &Path, loader: Option<&str>, unix: bool) -> Option<String> {
+    let name = if unix {
+        "unix_args.txt"
+    } else {
+        "win_args.txt"
+    };
+    let mut candidates = Vec::new();
+    for library_dir in LIBRARY_DIRS {
+        let Ok(entries) = fs::read_dir(instance_dir.join(library_dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path().join(name);
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let build = entry.file_name().to_string_lossy().to_string();
+            candidates.push((
+                format!("{}/{}/{}", library_dir, build, name),
+                build,
+                metadata.modified().ok(),
+            ));
+        }
+    }
+
+    if let Some(loader) = loader.map(str::trim).filter(|loader| !loader.is_empty()) {
+        let suffix = format!("-{}", loader);
+        if let Some((file, _, _)) = candidates
+            .iter()
+            .find(|(_, build, _)| build == loader || build.ends_with(&suffix))
+        {
+            return Some(file.clone());
+        }
+    }
+    candidates
+        .into_iter()
+        .max_by_key(|(_, _, modified)| *modified)
+        .map(|(file, _, _)| file)
+}
+
+/// Whether a command line argument is a Forge/NeoForge args file, which takes the place of
+/// `server.jar` when telling server processes apart
+pub fn is_args_file_arg(arg: &str) -> bool {
+    arg.starts_with('@') && (arg.ends_with("unix_args.txt") || arg.ends_with("win_args.txt"))
+}
@@ -0,0 +1,178 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    instance::{get_instance_by_id, record_log_line, update_instance_config},
+    models::{HookConfig, InstanceConfig},
+    properties::ServerProperties,
+};
+
+/// Hooks still running after this long are killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookStage {
+    PreStart,
+    PostStart,
+    PreStop,
+    PostStop,
+}
+
+impl HookStage {
+    fn name(self) -> &'static str {
+        match self {
+            HookStage::PreStart => "pre_start",
+            HookStage::PostStart => "post_start",
+            HookStage::PreStop => "pre_stop",
+            HookStage::PostStop => "post_stop",
+        }
+    }
+
+    fn command(self, hooks: &HookConfig) -> Option<&str> {
+        match self {
+            HookStage::PreStart => hooks.pre_start.as_deref(),
+            HookStage::PostStart => hooks.post_start.as_deref(),
+            HookStage::PreStop => hooks.pre_stop.as_deref(),
+            HookStage::PostStop => hooks.post_stop.as_deref(),
+        }
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+fn forward_output(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    stage: HookStage,
+    output: impl Read + Send + 'static,
+) -> thread::JoinHandle<()> {
+    let app_handle = app_handle.clone();
+    let id = id.to_string();
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            record_log_line(&app_handle, &id, format!("[{}] {}", stage.name(), line));
+        }
+    })
+}
+
+/// Run the instance's hook for `stage`, if it has one, and wait for it to finish. The hook
+/// gets INSTANCE_ID, INSTANCE_NAME, INSTANCE_DIR, PORT, SOFTWARE, MC_VERSION and HOOK in its
+/// environment, and its output is streamed into the console
+pub fn run(
+    app_handle: &tauri::AppHandle,
+    config: &InstanceConfig,
+    instance_dir: &Path,
+    stage: HookStage,
+) -> Result<(), String> {
+    let Some(command) = stage.command(&config.hooks) else {
+        return Ok(());
+    };
+
+    let port = ServerProperties::load(instance_dir)
+        .ok()
+        .and_then(|properties| properties.get("server-port").map(str::to_string))
+        .unwrap_or_else(|| "25565".into());
+
+    record_log_line(
+        app_handle,
+        &config.id,
+        format!("[nuko] Running {} hook: {}", stage.name(), command),
+    );
+    let mut child = shell(command)
+        .current_dir(instance_dir)
+        .env("INSTANCE_ID", &config.id)
+        .env("INSTANCE_NAME", &config.name)
+        .env("INSTANCE_DIR", instance_dir)
+        .env("PORT", port.trim())
+        .env("SOFTWARE", &config.software)
+        .env("MC_VERSION", &config.version)
+        .env("HOOK", stage.name())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {} hook: {}", stage.name(), e))?;
+
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(forward_output(app_handle, &config.id, stage, stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(forward_output(app_handle, &config.id, stage, stderr));
+    }
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= HOOK_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} hook timed out after {} seconds",
+                    stage.name(),
+                    HOOK_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(format!("Failed to wait for {} hook: {}", stage.name(), e)),
+        }
+    };
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} hook exited with {}",
+            stage.name(),
+            status
+                .code()
+                .map(|code| format!("code {}", code))
+                .unwrap_or_else(|| "a signal".into())
+        ))
+    }
+}
+
+/// Run a hook where its outcome doesn't change what happens next, logging failures to the
+/// console
+pub fn run_logged(
+    app_handle: &tauri::AppHandle,
+    config: &InstanceConfig,
+    instance_dir: &Path,
+    stage: HookStage,
+) {
+    if let Err(e) = run(app_handle, config, instance_dir, stage) {
+        record_log_line(app_handle, &config.id, format!("[nuko] {}", e));
+    }
+}
+
+#[tauri::command]
+pub async fn set_instance_hooks(
+    app_handle: tauri::AppHandle,
+    id: String,
+    hooks: HookConfig,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.hooks = hooks;
+    update_instance_config(&app_handle, &config)
+}
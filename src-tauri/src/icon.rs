@@ -0,0 +1,69 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{imageops::FilterType, ImageFormat};
+use tauri::Emitter;
+
+use crate::{filesystem, instance::get_instance_by_id};
+
+/// Minecraft only shows server-icon.png if it is exactly this size
+const ICON_SIZE: u32 = 64;
+
+/// Decode any supported image, crop it to a centered square, and save it as the instance's
+/// 64×64 server-icon.png
+pub fn write_server_icon(source: &Path, instance_dir: &Path) -> Result<(), String> {
+    let image = image::open(source)
+        .map_err(|e| format!("Failed to read image {}: {}", source.display(), e))?;
+    let icon = image.resize_to_fill(ICON_SIZE, ICON_SIZE, FilterType::Lanczos3);
+    icon.save_with_format(instance_dir.join("server-icon.png"), ImageFormat::Png)
+        .map_err(|e| format!("Failed to write server icon: {}", e))
+}
+
+/// Encoded icons keyed by path, invalidated when the file's modification time changes
+fn get_icon_cache() -> &'static Mutex<HashMap<PathBuf, (SystemTime, String)>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (SystemTime, String)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The instance's server-icon.png as a data URL the frontend can render directly
+pub fn icon_data_url(instance_dir: &Path) -> Option<String> {
+    let path = instance_dir.join("server-icon.png");
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+    let mut cache = get_icon_cache().lock().unwrap();
+    if let Some((cached_at, url)) = cache.get(&path) {
+        if *cached_at == modified {
+            return Some(url.clone());
+        }
+    }
+
+    let bytes = fs::read(&path).ok()?;
+    let url = format!("data:image/png;base64,{}", STANDARD.encode(bytes));
+    cache.insert(path, (modified, url.clone()));
+    Some(url)
+}
+
+#[tauri::command]
+pub async fn set_instance_icon(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<(), String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        write_server_icon(Path::new(&path), &instance_dir)
+    })
+    .await
+    .map_err(|e| format!("Failed to set server icon: {}", e))??;
+
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(())
+}
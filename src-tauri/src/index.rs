@@ -0,0 +1,111 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{filesystem, models::InstanceConfig};
+
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub dir: PathBuf,
+    pub config: InstanceConfig,
+}
+
+/// In-memory id -> instance lookup so frequent calls (like metric polls) don't re-read every
+/// nuko.toml. The index is built lazily and dropped whenever `instances-updated` is emitted
+#[derive(Default)]
+pub struct InstanceIndex {
+    entries: Mutex<Option<HashMap<String, IndexEntry>>>,
+}
+
+impl InstanceIndex {
+    /// Drop the cached entries so the next lookup rescans the instances directory
+    pub fn invalidate(&self) {
+        *self.entries.lock().unwrap() = None;
+    }
+
+    /// Look up a single instance by id
+    pub fn get(&self, instances_dir: &Path, id: &str) -> Result<IndexEntry, String> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.is_none() {
+            *entries = Some(scan_instances(instances_dir)?);
+        }
+
+        entries
+            .as_ref()
+            .and_then(|map| map.get(id))
+            .cloned()
+            .ok_or_else(|| format!("Instance with id {} not found", id))
+    }
+
+    /// Return every indexed instance
+    pub fn all(&self, instances_dir: &Path) -> Result<Vec<IndexEntry>, String> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.is_none() {
+            *entries = Some(scan_instances(instances_dir)?);
+        }
+
+        Ok(entries
+            .as_ref()
+            .map(|map| map.values().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+fn scan_instances(instances_dir: &Path) -> Result<HashMap<String, IndexEntry>, String> {
+    let mut map = HashMap::new();
+    if !instances_dir.exists() {
+        return Ok(map);
+    }
+
+    for item in fs::read_dir(instances_dir)
+        .map_err(|e| format!("Failed to read instances directory: {}", e))?
+    {
+        let entry = item.map_err(|e| format!("Failed to read instance entry: {}", e))?;
+        if !entry
+            .file_type()
+            .map_err(|e| format!("Failed to get file type: {}", e))?
+            .is_dir()
+        {
+            continue;
+        }
+
+        let config_path = entry.path().join("nuko.toml");
+        if !config_path.exists() {
+            continue;
+        }
+
+        // A single broken nuko.toml shouldn't hide every other instance
+        let config = match filesystem::load_instance_config(&entry.path()) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Skipping {}: {}", config_path.display(), e);
+                continue;
+            }
+        };
+
+        // Everything else resolves instances through `filesystem::get_instance_dir`, so a
+        // directory the legacy migration couldn't move to its id would be unusable
+        let expected = uuid::Uuid::parse_str(&config.id).map(|id| id.hyphenated().to_string());
+        if expected.as_deref() != Ok(entry.file_name().to_string_lossy().as_ref()) {
+            println!(
+                "Skipping {}: the directory doesn't match instance id '{}'",
+                entry.path().display(),
+                config.id
+            );
+            continue;
+        }
+
+        map.insert(
+            config.id.clone(),
+            IndexEntry {
+                dir: entry.path(),
+                config,
+            },
+        );
+    }
+
+    Ok(map)
+}
@@ -1,24 +1,44 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fs,
     io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
     process::{Child, ChildStdin, Command, Stdio},
     sync::{Mutex, OnceLock},
     thread,
+    time::{Duration, Instant},
 };
 
 use crate::{
+    ansi, audit, backup, cgroup, chunky, commands, config, consolelog, crash, docker,
     download::{download_playit, download_server_jar},
+    errors::CommandError,
     filesystem::{self, create_eula_txt, create_nuko_properties, save_instance_config},
-    models::{Instance, InstanceConfig, InstanceInfo, InstanceMetrics, PlayitTunnelMetadata},
-    playit::{claim_playit_secret, fetch_playit_tunnels},
+    forge,
+    hooks::{self, HookStage},
+    icon,
+    index::InstanceIndex,
+    jarinfo, java, lan, logevents, metrics,
+    models::{
+        default_max_log_lines, BackupInfo, InitialServerProperties, Instance, InstanceConfig,
+        InstanceInfo, InstanceMetrics, InstanceStatus, LogEvent, LogPage, PlayitTunnelMetadata,
+        RuntimeKind, TimelineKind,
+    },
+    ngrok, oom, performance,
+    playit::{claim_playit_secret, fetch_playit_tunnels, PlayitClient},
+    portmap, ports, priority, proctree, properties, rcon, relay, secrets, tailscale, timeline,
+    uptime, watchdog,
 };
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 
 const PLAYIT_SECRET_FILE: &str = "playit-secret.txt";
+const MAX_NOTES_LEN: usize = 64 * 1024;
+const DEFAULT_LOG_PAGE: usize = 1000;
+const MAX_LOG_PAGE: usize = 10_000;
+/// How long a server gets to shut down after `stop` before its process tree is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
 
-fn is_instance_server_process(process: &sysinfo::Process, instance_dir: &Path) -> bool {
+pub fn is_instance_server_process(process: &sysinfo::Process, instance_dir: &Path) -> bool {
     let Some(cwd) = process.cwd() else {
         return false;
     };
@@ -26,11 +46,10 @@ fn is_instance_server_process(process: &sysinfo::Process, instance_dir: &Path) -
         return false;
     }
 
-    if process
-        .cmd()
-        .iter()
-        .any(|arg| arg.to_string_lossy().contains("server.jar"))
-    {
+    if process.cmd().iter().any(|arg| {
+        let arg = arg.to_string_lossy();
+        arg.contains("server.jar") || forge::is_args_file_arg(&arg)
+    }) {
         return true;
     }
 
@@ -48,8 +67,121 @@ fn is_instance_server_process(process: &sysinfo::Process, instance_dir: &Path) -
         .unwrap_or(false)
 }
 
-fn get_logs_map() -> &'static Mutex<HashMap<String, Vec<String>>> {
-    static LOGS: OnceLock<Mutex<HashMap<String, Vec<String>>>> = OnceLock::new();
+/// Whether any server process is running from the given instance directory
+pub fn is_instance_running(instance_dir: &Path) -> bool {
+    let mut sys = sysinfo::System::new_all();
+    sys.refresh_all();
+    sys.processes()
+        .values()
+        .any(|process| is_instance_server_process(process, instance_dir))
+}
+
+/// BungeeCord never prints the vanilla `Done (...)!` line, only that its listener is up
+fn ready_marker(software: &str) -> &'static str {
+    match software {
+        "bungeecord" => "Listening on /",
+        _ => "Done (",
+    }
+}
+
+/// Console output of a running instance. Only the newest `capacity` lines are kept; line
+/// numbers keep counting from the first line ever logged so cursors stay valid after old
+/// lines are dropped
+struct LogBuffer {
+    lines: VecDeque<String>,
+    capacity: usize,
+    /// Number of lines dropped from the front, i.e. the line number of `lines[0]`
+    dropped: usize,
+    online: bool,
+    /// Log line fragment that means the server is accepting players
+    ready_marker: &'static str,
+}
+
+impl LogBuffer {
+    fn new(capacity: usize, software: &str) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+            online: false,
+            ready_marker: ready_marker(software),
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        if !self.online && line.contains(self.ready_marker) {
+            self.online = true;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    fn total(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+
+    /// Lines from line number `since` on, or from the oldest kept line if it was dropped
+    fn since(&self, since: usize) -> impl Iterator<Item = &String> {
+        self.lines.iter().skip(since.saturating_sub(self.dropped))
+    }
+}
+
+/// Buffer and persist a console line, then forward it to the console view tagged with its
+/// log level
+pub fn record_log_line(app_handle: &tauri::AppHandle, id: &str, line: String) {
+    let segments = ansi::parse(&line);
+    let line = ansi::plain(&segments);
+    consolelog::append(id, &line);
+    logevents::handle_log_line(app_handle, id, &line);
+    let event = LogEvent {
+        level: consolelog::log_level(&line).map(str::to_string),
+        line: line.clone(),
+        segments,
+    };
+    {
+        let mut logs_map = get_logs_map().lock().unwrap();
+        if let Some(logs) = logs_map.get_mut(id) {
+            logs.push(line);
+        }
+    }
+    let _ = app_handle.emit(&format!("instance-log-{}", id), event);
+}
+
+/// Whether the server has finished starting, based on the "Done (...)!" line it prints
+pub fn is_instance_online(id: &str) -> bool {
+    let logs_map = get_logs_map().lock().unwrap();
+    logs_map.get(id).map(|logs| logs.online).unwrap_or(false)
+}
+
+/// Number of console lines captured so far, so callers can watch for output after a command
+pub fn log_line_count(id: &str) -> usize {
+    let logs_map = get_logs_map().lock().unwrap();
+    logs_map.get(id).map(LogBuffer::total).unwrap_or(0)
+}
+
+/// Whether a line containing `needle` was logged after the first `since` lines
+pub fn logged_since(id: &str, since: usize, needle: &str) -> bool {
+    let logs_map = get_logs_map().lock().unwrap();
+    logs_map
+        .get(id)
+        .map(|logs| logs.since(since).any(|line| line.contains(needle)))
+        .unwrap_or(false)
+}
+
+/// Console lines logged after the first `since` lines
+pub fn logs_since(id: &str, since: usize) -> Vec<String> {
+    let logs_map = get_logs_map().lock().unwrap();
+    logs_map
+        .get(id)
+        .map(|logs| logs.since(since).cloned().collect())
+        .unwrap_or_default()
+}
+
+fn get_logs_map() -> &'static Mutex<HashMap<String, LogBuffer>> {
+    static LOGS: OnceLock<Mutex<HashMap<String, LogBuffer>>> = OnceLock::new();
     LOGS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
@@ -58,9 +190,15 @@ fn get_stdin_map() -> &'static Mutex<HashMap<String, ChildStdin>> {
     STDIN.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-fn get_system() -> &'static Mutex<sysinfo::System> {
-    static SYS: OnceLock<Mutex<sysinfo::System>> = OnceLock::new();
-    SYS.get_or_init(|| Mutex::new(sysinfo::System::new()))
+/// PIDs of the server processes nuko started, removed once they exit
+fn get_server_pids() -> &'static Mutex<HashMap<String, u32>> {
+    static PIDS: OnceLock<Mutex<HashMap<String, u32>>> = OnceLock::new();
+    PIDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// PID of the server process nuko started for the instance, while it runs
+pub fn tracked_pid(id: &str) -> Option<u32> {
+    get_server_pids().lock().unwrap().get(id).copied()
 }
 
 fn get_playit_processes() -> &'static Mutex<HashMap<String, Child>> {
@@ -91,6 +229,11 @@ fn persist_playit_secret(instance_dir: &Path, secret: &str) -> Result<PathBuf, S
     Ok(secret_path)
 }
 
+/// The agent only needs the secret file while it runs, so it doesn't sit on disk otherwise
+fn remove_playit_secret_file(instance_dir: &Path) {
+    let _ = fs::remove_file(instance_dir.join(PLAYIT_SECRET_FILE));
+}
+
 fn playit_binary_name() -> &'static str {
     if std::env::consts::OS == "windows" {
         "playit.exe"
@@ -99,17 +242,28 @@ fn playit_binary_name() -> &'static str {
     }
 }
 
-async fn ensure_playit_secret(
+pub async fn ensure_playit_secret(
+    app_handle: &tauri::AppHandle,
     instance: &mut InstanceConfig,
     instance_dir: &Path,
 ) -> Result<String, String> {
-    if let Some(secret) = instance
+    let credential_name = format!("playit:{}", instance.id);
+    if let Some(stored) = instance
         .playit_secret
-        .as_ref()
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
+        .clone()
+        .filter(|s| !s.trim().is_empty())
     {
-        return Ok(secret.to_string());
+        let secret = secrets::load_credential(app_handle, stored.trim())?;
+        // Secrets saved in plaintext by older versions move into the credential store
+        if !secrets::is_stored_credential(stored.trim()) {
+            instance.playit_secret = Some(secrets::store_credential(
+                app_handle,
+                &credential_name,
+                secret.trim(),
+            )?);
+            update_instance_config(app_handle, instance)?;
+        }
+        return Ok(secret.trim().to_string());
     }
 
     download_playit(instance_dir)
@@ -123,10 +277,15 @@ async fn ensure_playit_secret(
 
     let secret_path = instance_dir.join(PLAYIT_SECRET_FILE);
 
-    let secret = claim_playit_secret(&playit_path, instance_dir, &secret_path).await?;
-    let normalized = secret.trim().to_string();
-    instance.playit_secret = Some(normalized.clone());
-    save_instance_config(instance_dir, instance)?;
+    let secret = claim_playit_secret(&playit_path, instance_dir, &secret_path).await;
+    remove_playit_secret_file(instance_dir);
+    let normalized = secret?.trim().to_string();
+    instance.playit_secret = Some(secrets::store_credential(
+        app_handle,
+        &credential_name,
+        &normalized,
+    )?);
+    update_instance_config(app_handle, instance)?;
     Ok(normalized)
 }
 
@@ -142,13 +301,33 @@ fn kill_playit_agent(id: &str) {
     }
 }
 
+/// A page of console lines. Without an offset the newest `limit` lines are returned
 #[tauri::command]
-pub async fn get_instance_logs(id: String) -> Result<Vec<String>, String> {
+pub async fn get_instance_logs(
+    id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<LogPage, String> {
     let logs_map = get_logs_map().lock().unwrap();
-    Ok(logs_map.get(&id).cloned().unwrap_or_default())
+    let Some(logs) = logs_map.get(&id) else {
+        return Ok(LogPage::default());
+    };
+
+    let total = logs.total();
+    let limit = limit.unwrap_or(DEFAULT_LOG_PAGE).min(MAX_LOG_PAGE);
+    let start = offset
+        .unwrap_or_else(|| total.saturating_sub(limit))
+        .max(logs.dropped);
+
+    Ok(LogPage {
+        lines: logs.since(start).take(limit).cloned().collect(),
+        start,
+        total,
+    })
 }
 
-/// Create a new Minecraft server instance with the given name, software, version, and optional loader
+/// Create a new Minecraft server instance with the given name, software, version, and optional
+/// loader. Fabric instances can also pin the installer version
 #[tauri::command]
 pub async fn create_instance(
     app_handle: tauri::AppHandle,
@@ -157,110 +336,156 @@ pub async fn create_instance(
     version: String,
     playit: bool,
     loader: Option<String>,
+    installer: Option<String>,
     icon_path: Option<String>,
     custom_jar_path: Option<String>,
+    properties: Option<InitialServerProperties>,
+    eula: bool,
 ) -> Result<(), String> {
     let server = Instance {
+        id: uuid::Uuid::new_v4().to_string(),
         name,
         software,
         version,
         playit,
         loader,
+        installer,
         custom_jar_path,
+        properties,
+        eula,
     };
 
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
+    build_instance(&app_handle, server, icon_path).await?;
+
+    let _ = app_handle.emit("instances-updated", ());
 
-    if data_dir.join("instances").join(&server.name).exists() {
+    Ok(())
+}
+
+/// Validate the instance name, lay out the instance directory, and download everything the
+/// instance needs to run. Returns the new instance directory
+pub async fn build_instance(
+    app_handle: &tauri::AppHandle,
+    mut server: Instance,
+    icon_path: Option<String>,
+) -> Result<PathBuf, String> {
+    server.name = filesystem::validate_instance_name(&server.name)?;
+
+    let existing = list_instances(app_handle.clone()).await?;
+    if existing
+        .iter()
+        .any(|instance| instance.name.eq_ignore_ascii_case(&server.name))
+    {
         return Err(format!("Instance '{}' already exists", server.name));
     }
 
-    let instance_dir = filesystem::create_directory(data_dir, &server.name)
+    if let Some(initial) = &server.properties {
+        properties::validate_initial_properties(initial)?;
+    }
+
+    let defaults = config::get_config(app_handle.clone())?.instance_defaults;
+    let mut initial = server.properties.clone().unwrap_or_default();
+    if initial.port.is_none() {
+        // Left unset when the whole range is taken, so the server falls back to 25565
+        let reserved = ports::reserved_ports(app_handle, &server.id)?;
+        initial.port = (defaults.port_range_start..=defaults.port_range_end)
+            .find(|port| !reserved.contains(port) && ports::is_port_free(*port));
+    }
+
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+
+    let instance_dir = filesystem::create_directory(instances_dir, &server.id)
         .await
         .map_err(|e| format!("Error calling create_directory: {}", e))?;
 
     if let Some(icon) = icon_path {
-        fs::copy(&icon, instance_dir.join("server-icon.png"))
-            .map_err(|e| format!("Failed to copy server icon: {}", e))?;
+        icon::write_server_icon(Path::new(&icon), &instance_dir)?;
     }
 
-    create_nuko_properties(&instance_dir, &server)
+    create_nuko_properties(&instance_dir, &server, &defaults)
         .await
         .map_err(|e| format!("Error calling create_nuko_manifest: {}", e))?;
 
     download_server_jar(&instance_dir, &server)
         .await
         .map_err(|e| format!("Error calling download_server_jar: {}", e))?;
+    if let Err(e) = jarinfo::detect_and_store(app_handle, &server.id) {
+        println!("Failed to inspect the custom jar of {}: {}", server.name, e);
+    }
 
-    create_eula_txt(&instance_dir)
+    create_eula_txt(&instance_dir, server.eula || defaults.accept_eula)
         .await
         .map_err(|e| format!("Error calling create_eula_txt: {}", e))?;
 
+    properties::write_initial_properties(&instance_dir, &initial)?;
+    if initial.enable_rcon == Some(true) {
+        let mut config = filesystem::load_instance_config(&instance_dir)?;
+        rcon::provision(app_handle, &mut config, &instance_dir)?;
+    }
+
     if server.playit {
         download_playit(&instance_dir)
             .await
             .map_err(|e| format!("Error calling download_playit: {}", e))?;
     }
 
-    let _ = app_handle.emit("instances-updated", ());
+    app_handle.state::<InstanceIndex>().invalidate();
+    audit::record(
+        app_handle,
+        &server.id,
+        "create",
+        serde_json::json!({
+            "name": server.name,
+            "software": server.software,
+            "version": server.version,
+            "loader": server.loader,
+        }),
+    );
 
-    Ok(())
+    Ok(instance_dir)
+}
+
+/// Lists all existing instances from the instance index, returning the name stored in
+/// nuko.toml of subdirectories in the instances folder, and whether they're running or not
+fn instance_info(config: InstanceConfig, instance_dir: &Path, running: bool) -> InstanceInfo {
+    InstanceInfo {
+        id: config.id,
+        name: config.name,
+        software: config.software,
+        version: config.version,
+        running,
+        playit: config.playit,
+        tags: config.tags,
+        favorite: config.favorite,
+        loader: config.loader,
+        created_at: config.metadata.created_at,
+        last_played: config.metadata.last_played,
+        icon: icon::icon_data_url(instance_dir),
+    }
 }
 
-/// Lists all existing instances by reading the data directory and returning the name
-/// stored in nuko.toml of subdirectories in the instances folder, and whether they're
-/// running or not
 #[tauri::command]
 pub async fn list_instances(app_handle: tauri::AppHandle) -> Result<Vec<InstanceInfo>, String> {
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instances_dir = data_dir.join("instances");
-
-    if !instances_dir.exists() {
-        return Ok(vec![]);
-    }
+    let instances_dir = filesystem::get_instances_dir(&app_handle)?;
+    let entries = app_handle.state::<InstanceIndex>().all(&instances_dir)?;
 
     let mut sys = sysinfo::System::new_all();
     sys.refresh_all();
 
     let mut instances = Vec::new();
 
-    for item in fs::read_dir(instances_dir)
-        .map_err(|e| format!("Failed to read instances directory: {}", e))?
-    {
-        let entry = item.map_err(|e| format!("Failed to read instance entry: {}", e))?;
-        if entry
-            .file_type()
-            .map_err(|e| format!("Failed to get file type: {}", e))?
-            .is_dir()
-        {
-            let config_path = entry.path().join("nuko.toml");
-            if config_path.exists() {
-                let config_content = fs::read_to_string(&config_path)
-                    .map_err(|e| format!("Failed to read nuko.toml: {}", e))?;
-                let config: InstanceConfig = toml::from_str(&config_content)
-                    .map_err(|e| format!("Failed to parse nuko.toml: {}", e))?;
-
-                let instance_path = entry.path();
-                let mut running = false;
-                for process in sys.processes().values() {
-                    if is_instance_server_process(process, &instance_path) {
-                        running = true;
-                        break;
-                    }
-                }
+    for entry in entries {
+        let config = entry.config;
+        let running = sys
+            .processes()
+            .values()
+            .any(|process| is_instance_server_process(process, &entry.dir));
 
-                instances.push(InstanceInfo {
-                    id: config.id,
-                    name: config.name,
-                    software: config.software,
-                    version: config.version,
-                    running,
-                    playit: config.playit,
-                });
-            }
-        }
+        instances.push(instance_info(config, &entry.dir, running));
     }
 
+    instances.sort_by_key(|instance| (!instance.favorite, instance.name.to_lowercase()));
+
     Ok(instances)
 }
 
@@ -269,9 +494,8 @@ pub async fn get_instance_info(
     app_handle: tauri::AppHandle,
     id: String,
 ) -> Result<InstanceInfo, String> {
-    let config = get_instance_by_id(&app_handle, &id).await;
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&config.name);
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
 
     let mut sys = sysinfo::System::new_all();
     sys.refresh_all();
@@ -284,55 +508,92 @@ pub async fn get_instance_info(
         }
     }
 
-    Ok(InstanceInfo {
-        id: config.id,
-        name: config.name,
-        software: config.software,
-        version: config.version,
-        running,
-        playit: config.playit,
-    })
+    Ok(instance_info(config, &instance_dir, running))
 }
 
+/// Record the user's acceptance of the Minecraft EULA (https://aka.ms/MinecraftEULA)
 #[tauri::command]
-pub async fn get_instance_metrics(
+pub async fn accept_eula(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    create_eula_txt(&instance_dir, true).await
+}
+
+/// Replace an instance's tags, dropping blanks and duplicates
+#[tauri::command]
+pub async fn set_instance_tags(
     app_handle: tauri::AppHandle,
     id: String,
-) -> Result<InstanceMetrics, String> {
-    let config = get_instance_by_id(&app_handle, &id).await;
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&config.name);
-
-    let mut sys = get_system().lock().unwrap();
-    sys.refresh_processes_specifics(
-        sysinfo::ProcessesToUpdate::All,
-        true,
-        sysinfo::ProcessRefreshKind::everything(),
-    );
-    thread::sleep(std::time::Duration::from_millis(200));
-    sys.refresh_processes_specifics(
-        sysinfo::ProcessesToUpdate::All,
-        true,
-        sysinfo::ProcessRefreshKind::everything(),
-    );
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
 
-    let mut cpu_usage = 0.0;
-    let mut memory_usage = 0;
+    config.tags = normalized.clone();
+    update_instance_config(&app_handle, &config)?;
+    let _ = app_handle.emit("instances-updated", ());
 
-    for process in sys.processes().values() {
-        if is_instance_server_process(process, &instance_dir) {
-            cpu_usage += process.cpu_usage();
-            memory_usage += process.memory();
-        }
+    Ok(normalized)
+}
+
+/// Flip an instance's favorite flag, returning the new value
+#[tauri::command]
+pub async fn toggle_favorite(app_handle: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.favorite = !config.favorite;
+    update_instance_config(&app_handle, &config)?;
+    let _ = app_handle.emit("instances-updated", ());
+
+    Ok(config.favorite)
+}
+
+#[tauri::command]
+pub async fn get_instance_notes(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<String, String> {
+    Ok(get_instance_by_id(&app_handle, &id)?.notes)
+}
+
+#[tauri::command]
+pub async fn set_instance_notes(
+    app_handle: tauri::AppHandle,
+    id: String,
+    notes: String,
+) -> Result<(), String> {
+    if notes.len() > MAX_NOTES_LEN {
+        return Err(format!(
+            "Notes cannot be longer than {} bytes",
+            MAX_NOTES_LEN
+        ));
     }
 
-    let time = chrono::Local::now().format("%H:%M:%S").to_string();
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.notes = notes;
+    update_instance_config(&app_handle, &config)
+}
 
-    Ok(InstanceMetrics {
-        time,
-        cpu_usage,
-        memory_usage,
-    })
+#[tauri::command]
+pub async fn get_instance_metrics(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<InstanceMetrics, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    Ok(metrics::sample(
+        &app_handle,
+        &id,
+        &config.software,
+        &instance_dir,
+    ))
 }
 
 #[tauri::command]
@@ -340,22 +601,143 @@ pub async fn get_playit_tunnels(
     app_handle: tauri::AppHandle,
     id: String,
 ) -> Result<Vec<PlayitTunnelMetadata>, String> {
-    let mut config = get_instance_by_id(&app_handle, &id).await;
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    if config.ngrok {
+        return Ok(ngrok::tunnels(&id));
+    }
+    if config
+        .custom_tunnel
+        .as_ref()
+        .is_some_and(|tunnel| tunnel.enabled)
+    {
+        return Ok(relay::tunnels(&id));
+    }
+    if config.tailscale.enabled {
+        let port = ports::server_port(&filesystem::get_instance_dir(&app_handle, &id)?);
+        return tauri::async_runtime::spawn_blocking(move || tailscale::tunnels(&config, port))
+            .await
+            .map_err(|e| format!("Background task failed: {}", e));
+    }
     if !config.playit {
         return Ok(vec![]);
     }
 
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&config.name);
-    let secret = ensure_playit_secret(&mut config, &instance_dir).await?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let secret = ensure_playit_secret(&app_handle, &mut config, &instance_dir).await?;
 
     fetch_playit_tunnels(&secret).await
 }
 
+/// Pick how the instance is exposed to the internet: `none`, `playit`, `ngrok`, `tailscale`
+/// or `custom`. Applies from the next start
 #[tauri::command]
-pub async fn send_instance_command(id: String, command: String) -> Result<(), String> {
+pub async fn set_tunnel_provider(
+    app_handle: tauri::AppHandle,
+    id: String,
+    provider: String,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    if !["none", "playit", "ngrok", "tailscale", "custom"].contains(&provider.as_str()) {
+        return Err(format!("Unknown tunnel provider '{}'", provider));
+    }
+    if provider == "custom" && config.custom_tunnel.is_none() {
+        return Err("Set up the custom tunnel's relay first".into());
+    }
+
+    config.playit = provider == "playit";
+    config.ngrok = provider == "ngrok";
+    config.tailscale.enabled = provider == "tailscale";
+    if let Some(custom) = &mut config.custom_tunnel {
+        custom.enabled = provider == "custom";
+    }
+    update_instance_config(&app_handle, &config)
+}
+
+/// The playit client for an instance, claiming an agent secret first if needed
+async fn playit_client(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<(InstanceConfig, PlayitClient), String> {
+    let mut config = get_instance_by_id(app_handle, id)?;
+    if !config.playit {
+        return Err(format!("Playit isn't enabled for '{}'", config.name));
+    }
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let secret = ensure_playit_secret(app_handle, &mut config, &instance_dir).await?;
+    Ok((config, PlayitClient::new(secret)?))
+}
+
+/// Create a playit tunnel for the instance's Java (TCP) or Bedrock (UDP) port. The port
+/// defaults to `server-port` for Java and 19132 for Bedrock. Returns the instance's tunnels
+#[tauri::command]
+pub async fn create_playit_tunnel(
+    app_handle: tauri::AppHandle,
+    id: String,
+    bedrock: bool,
+    port: Option<u16>,
+    name: Option<String>,
+) -> Result<Vec<PlayitTunnelMetadata>, String> {
+    let (config, client) = playit_client(&app_handle, &id).await?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let port = match port {
+        Some(0) => return Err("Port cannot be 0".into()),
+        Some(port) => port,
+        None if bedrock => 19132,
+        None => ports::server_port(&instance_dir),
+    };
+    let (tunnel_type, port_type, suffix) = if bedrock {
+        ("minecraft-bedrock", "udp", "Bedrock")
+    } else {
+        ("minecraft-java", "tcp", "Java")
+    };
+    let name = name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("{} ({})", config.name, suffix));
+
+    client
+        .create_tunnel(&name, tunnel_type, port_type, port)
+        .await?;
+    client.fetch_tunnels().await
+}
+
+#[tauri::command]
+pub async fn rename_playit_tunnel(
+    app_handle: tauri::AppHandle,
+    id: String,
+    tunnel_id: String,
+    name: String,
+) -> Result<Vec<PlayitTunnelMetadata>, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tunnel name cannot be empty".into());
+    }
+    let (_, client) = playit_client(&app_handle, &id).await?;
+    client.rename_tunnel(&tunnel_id, name).await?;
+    client.fetch_tunnels().await
+}
+
+#[tauri::command]
+pub async fn delete_playit_tunnel(
+    app_handle: tauri::AppHandle,
+    id: String,
+    tunnel_id: String,
+) -> Result<Vec<PlayitTunnelMetadata>, String> {
+    let (_, client) = playit_client(&app_handle, &id).await?;
+    client.delete_tunnel(&tunnel_id).await?;
+    client.fetch_tunnels().await
+}
+
+/// Whether nuko holds the stdin of a server it started, i.e. commands can be sent to it
+pub fn has_instance_stdin(id: &str) -> bool {
+    get_stdin_map().lock().unwrap().contains_key(id)
+}
+
+/// Write a console command to a running server's stdin
+pub fn write_instance_stdin(id: &str, command: &str) -> Result<(), String> {
     let mut stdin_map = get_stdin_map().lock().unwrap();
-    if let Some(stdin) = stdin_map.get_mut(&id) {
+    if let Some(stdin) = stdin_map.get_mut(id) {
         writeln!(stdin, "{}", command).map_err(|e| e.to_string())?;
         stdin.flush().map_err(|e| e.to_string())?;
         Ok(())
@@ -364,11 +746,39 @@ pub async fn send_instance_command(id: String, command: String) -> Result<(), St
     }
 }
 
+#[tauri::command]
+pub async fn send_instance_command(
+    app_handle: tauri::AppHandle,
+    id: String,
+    command: String,
+) -> Result<(), String> {
+    write_instance_stdin(&id, &command)?;
+    audit::record(
+        &app_handle,
+        &id,
+        "command",
+        serde_json::json!({ "command": command }),
+    );
+    if let Err(e) = commands::record(&app_handle, &id, &command) {
+        println!("Failed to record command history: {}", e);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn stop_instance(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
-    let instance = get_instance_by_id(&app_handle, &id).await;
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&instance.name);
+    let instance = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    if has_instance_stdin(&id) {
+        let app_hook = app_handle.clone();
+        let config_hook = instance.clone();
+        let dir_hook = instance_dir.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || {
+            hooks::run_logged(&app_hook, &config_hook, &dir_hook, HookStage::PreStop)
+        })
+        .await;
+    }
 
     let mut sent_stop = false;
     {
@@ -380,14 +790,41 @@ pub async fn stop_instance(app_handle: tauri::AppHandle, id: String) -> Result<(
         }
     }
 
-    if !sent_stop {
+    if sent_stop {
+        let pid = get_server_pids().lock().unwrap().get(&id).copied();
+        if let Some(pid) = pid {
+            let app_escalate = app_handle.clone();
+            let id_escalate = id.clone();
+            thread::spawn(move || {
+                let started = Instant::now();
+                while started.elapsed() < STOP_TIMEOUT {
+                    thread::sleep(Duration::from_secs(1));
+                    if get_server_pids().lock().unwrap().get(&id_escalate) != Some(&pid) {
+                        return;
+                    }
+                }
+                record_log_line(
+                    &app_escalate,
+                    &id_escalate,
+                    format!(
+                        "[nuko] Server didn't stop within {} seconds, killing it",
+                        STOP_TIMEOUT.as_secs()
+                    ),
+                );
+                proctree::signal_tree(pid, true);
+                if docker::is_managed(&id_escalate) {
+                    docker::kill(&id_escalate);
+                }
+            });
+        }
+    } else {
         let mut sys = sysinfo::System::new_all();
         sys.refresh_all();
 
         let mut found = false;
         for process in sys.processes().values() {
             if is_instance_server_process(process, &instance_dir) {
-                let _ = process.kill_with(sysinfo::Signal::Term);
+                proctree::signal_tree(process.pid().as_u32(), false);
                 found = true;
             }
         }
@@ -398,6 +835,9 @@ pub async fn stop_instance(app_handle: tauri::AppHandle, id: String) -> Result<(
     }
 
     kill_playit_agent(&id);
+    ngrok::stop(&id);
+    relay::stop(&id);
+    audit::record(&app_handle, &id, "stop", serde_json::json!({}));
     let _ = app_handle.emit("instances-updated", ());
     Ok(())
 }
@@ -409,9 +849,8 @@ pub async fn kill_instance(app_handle: tauri::AppHandle, id: String) -> Result<(
         stdin_map.remove(&id);
     }
 
-    let instance = get_instance_by_id(&app_handle, &id).await;
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&instance.name);
+    let instance = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
 
     let mut sys = sysinfo::System::new_all();
     sys.refresh_all();
@@ -419,16 +858,24 @@ pub async fn kill_instance(app_handle: tauri::AppHandle, id: String) -> Result<(
     let mut found = false;
     for process in sys.processes().values() {
         if is_instance_server_process(process, &instance_dir) {
-            let _ = process.kill_with(sysinfo::Signal::Kill);
+            proctree::signal_tree(process.pid().as_u32(), true);
             found = true;
         }
     }
 
+    // Killing the attached client leaves the container running
+    if docker::is_managed(&id) {
+        docker::kill(&id);
+        found = true;
+    }
     if !found {
         return Err(format!("Instance '{}' is not running", instance.name));
     }
 
     kill_playit_agent(&id);
+    ngrok::stop(&id);
+    relay::stop(&id);
+    audit::record(&app_handle, &id, "kill", serde_json::json!({}));
     let _ = app_handle.emit("instances-updated", ());
     Ok(())
 }
@@ -437,9 +884,7 @@ pub async fn kill_instance(app_handle: tauri::AppHandle, id: String) -> Result<(
 pub async fn restart_instance(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
     let _ = stop_instance(app_handle.clone(), id.clone()).await;
 
-    let instance = get_instance_by_id(&app_handle, &id).await;
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&instance.name);
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
 
     let mut sys = sysinfo::System::new_all();
     for _ in 0..60 {
@@ -484,39 +929,100 @@ pub async fn open_instance_view(
     Ok(())
 }
 
-pub async fn get_instance_by_id(app_handle: &tauri::AppHandle, id: &String) -> InstanceConfig {
-    let data_dir = filesystem::get_data_dir(app_handle).unwrap();
-    let instances_dir = data_dir.join("instances");
+/// Look up an instance's config through the instance index
+pub fn get_instance_by_id(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<InstanceConfig, String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    app_handle
+        .state::<InstanceIndex>()
+        .get(&instances_dir, id)
+        .map(|entry| entry.config)
+}
 
-    for item in fs::read_dir(instances_dir).unwrap() {
-        let entry = item.unwrap();
-        if entry.file_type().unwrap().is_dir() {
-            let config_path = entry.path().join("nuko.toml");
-            if config_path.exists() {
-                let config_content = fs::read_to_string(&config_path).unwrap();
-                let config: InstanceConfig = toml::from_str(&config_content).unwrap();
+/// The software, version, loader and build an instance runs, e.g. `fabric 1.21.1 0.16.5`
+fn version_label(config: &InstanceConfig) -> String {
+    [
+        Some(config.software.as_str()),
+        Some(config.version.as_str()),
+        config.loader.as_deref(),
+        config.build.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
 
-                if config.id == *id {
-                    return config;
-                }
-            }
+/// Persist an instance's nuko.toml and drop the cached index so readers see the change
+pub fn update_instance_config(
+    app_handle: &tauri::AppHandle,
+    config: &InstanceConfig,
+) -> Result<(), String> {
+    let previous = get_instance_by_id(app_handle, &config.id).ok();
+    let instance_dir = filesystem::get_instance_dir(app_handle, &config.id)?;
+    save_instance_config(&instance_dir, config)?;
+    app_handle.state::<InstanceIndex>().invalidate();
+
+    if let Some(previous) = &previous {
+        let (from, to) = (version_label(previous), version_label(config));
+        if from != to {
+            timeline::record(
+                app_handle,
+                &config.id,
+                TimelineKind::VersionChanged,
+                Some(format!("{} -> {}", from, to)),
+                None,
+                None,
+            );
         }
     }
 
-    panic!("Instance with id {} not found", id);
+    if let (Some(previous), Ok(current)) = (previous, serde_json::to_value(config)) {
+        let fields = audit::changed_fields(
+            &serde_json::to_value(previous).unwrap_or_default(),
+            &current,
+        );
+        if !fields.is_empty() {
+            audit::record(
+                app_handle,
+                &config.id,
+                "config",
+                serde_json::json!({ "fields": fields }),
+            );
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
-    let mut instance = get_instance_by_id(&app_handle, &id).await;
+    let mut instance = get_instance_by_id(&app_handle, &id)?;
 
-    let data_dir = filesystem::get_data_dir(&app_handle)?;
-    let instance_dir = data_dir.join("instances").join(&instance.name);
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
 
     if !instance_dir.exists() {
         return Err(format!("Instance '{}' does not exist", instance.name));
     }
 
+    // Starting a crash-looping instance by hand gives auto-restart another chance
+    if instance.crash_loop.take().is_some() {
+        crash::reset(&id);
+        update_instance_config(&app_handle, &instance)?;
+    }
+
+    if !filesystem::is_eula_accepted(&instance_dir) {
+        return Err(CommandError::new(
+            "eula_required",
+            format!(
+                "The Minecraft EULA must be accepted before '{}' can start",
+                instance.name
+            ),
+        )
+        .into());
+    }
+
     let mut sys = sysinfo::System::new_all();
     sys.refresh_all();
     for process in sys.processes().values() {
@@ -525,14 +1031,55 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
         }
     }
 
+    ports::ensure_ports_available(&app_handle, &id, &instance_dir)?;
+
+    let in_docker = instance.runtime.kind == RuntimeKind::Docker;
+    if !in_docker && instance.java.java_path.is_none() {
+        if let Some(java_path) = java::resolve_runtime(&app_handle, instance.effective_version())
+            .await
+            .map_err(|e| format!("No suitable Java found for {}: {}", instance.version, e))?
+        {
+            instance.java.java_path = Some(java_path);
+            update_instance_config(&app_handle, &instance)?;
+        }
+    }
+
     let java_path = instance
         .java
         .java_path
         .clone()
         .unwrap_or_else(|| "java".to_string());
-
-    let mut cmd = Command::new(java_path);
+    let (mut cmd, limits_warning) = if in_docker {
+        let config_docker = instance.clone();
+        let dir_docker = instance_dir.clone();
+        let cmd = tauri::async_runtime::spawn_blocking(move || {
+            docker::run_command(&config_docker, &dir_docker)
+        })
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))??;
+        (cmd, None)
+    } else {
+        java::check_compatible(&java_path, instance.effective_version()).await?;
+        cgroup::java_command(&java_path, &instance.java)
+    };
     cmd.current_dir(&instance_dir);
+    proctree::isolate(&mut cmd);
+
+    // Modern Forge and NeoForge have no runnable server.jar, only the args files their run
+    // scripts pass to java. The container is always Linux, whatever the host is
+    let args_file = if matches!(instance.software.as_str(), "forge" | "neoforge") {
+        forge::args_file(
+            &instance_dir,
+            instance.loader.as_deref(),
+            in_docker || !cfg!(windows),
+        )
+    } else {
+        None
+    };
+    // Passed before nuko's memory settings, so those win over anything set in the file
+    if args_file.is_some() && instance_dir.join("user_jvm_args.txt").exists() {
+        cmd.arg("@user_jvm_args.txt");
+    }
 
     if !instance.java.min_memory.is_empty() {
         cmd.arg(format!("-Xms{}", instance.java.min_memory));
@@ -545,15 +1092,41 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
         cmd.arg(arg);
     }
 
-    cmd.arg("-jar").arg("server.jar").arg("nogui");
+    match &args_file {
+        Some(file) => cmd.arg(format!("@{}", file)),
+        None => cmd.arg("-jar").arg("server.jar"),
+    };
+    cmd.arg("nogui");
 
+    let max_log_lines = config::get_config(app_handle.clone())
+        .map(|config| config.max_log_lines)
+        .unwrap_or_else(|_| default_max_log_lines());
+    if let Err(e) = consolelog::open(&app_handle, &id) {
+        println!("Failed to open console log for {}: {}", instance.name, e);
+    }
     {
         let mut logs_map = get_logs_map().lock().unwrap();
-        logs_map.insert(id.clone(), Vec::new());
+        logs_map.insert(
+            id.clone(),
+            LogBuffer::new(max_log_lines, instance.effective_software()),
+        );
+    }
+    if let Some(warning) = limits_warning {
+        record_log_line(&app_handle, &id, format!("[nuko] {}", warning));
     }
 
+    let app_hook = app_handle.clone();
+    let config_hook = instance.clone();
+    let dir_hook = instance_dir.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        hooks::run(&app_hook, &config_hook, &dir_hook, HookStage::PreStart)
+    })
+    .await
+    .map_err(|e| format!("Background task failed: {}", e))?
+    .inspect_err(|_| consolelog::close(&id))?;
+
     if instance.playit {
-        let secret = ensure_playit_secret(&mut instance, &instance_dir).await?;
+        let secret = ensure_playit_secret(&app_handle, &mut instance, &instance_dir).await?;
 
         let playit_path = instance_dir.join(playit_binary_name());
         if !playit_path.exists() {
@@ -582,13 +1155,7 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
                     for line in reader.lines() {
                         if let Ok(line) = line {
                             let log_line = format!("[playit] {}", line);
-                            {
-                                let mut logs_map = get_logs_map().lock().unwrap();
-                                if let Some(logs) = logs_map.get_mut(&id_clone) {
-                                    logs.push(log_line.clone());
-                                }
-                            }
-                            let _ = app_clone.emit(&format!("instance-log-{}", id_clone), log_line);
+                            record_log_line(&app_clone, &id_clone, log_line);
                         }
                     }
                 });
@@ -601,13 +1168,7 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
                     for line in reader.lines() {
                         if let Ok(line) = line {
                             let log_line = format!("[playit] {}", line);
-                            {
-                                let mut logs_map = get_logs_map().lock().unwrap();
-                                if let Some(logs) = logs_map.get_mut(&id_clone) {
-                                    logs.push(log_line.clone());
-                                }
-                            }
-                            let _ = app_clone.emit(&format!("instance-log-{}", id_clone), log_line);
+                            record_log_line(&app_clone, &id_clone, log_line);
                         }
                     }
                 });
@@ -618,12 +1179,77 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
         }
     }
 
+    if instance.ngrok {
+        ngrok::start(&app_handle, &id, ports::server_port(&instance_dir)).await?;
+    }
+    if let Some(tunnel) = instance
+        .custom_tunnel
+        .as_ref()
+        .filter(|tunnel| tunnel.enabled)
+    {
+        relay::start(&app_handle, &id, tunnel, ports::server_port(&instance_dir))?;
+    }
+    if instance.tailscale.enabled {
+        let config_tailscale = instance.clone();
+        let port = ports::server_port(&instance_dir);
+        tauri::async_runtime::spawn_blocking(move || tailscale::start(&config_tailscale, port))
+            .await
+            .map_err(|e| format!("Background task failed: {}", e))?
+            .map_err(|e| format!("Failed to enable Tailscale Funnel: {}", e))?;
+    }
+
+    if instance.upnp {
+        let app_portmap = app_handle.clone();
+        let id_portmap = id.clone();
+        let port = ports::server_port(&instance_dir);
+        tauri::async_runtime::spawn_blocking(move || {
+            portmap::open(&app_portmap, &id_portmap, port)
+        })
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?;
+    }
+
+    let started_at = std::time::SystemTime::now();
     let mut child = cmd
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to start Java process: {}", e))?;
+        .map_err(|e| {
+            kill_playit_agent(&id);
+            ngrok::stop(&id);
+            relay::stop(&id);
+            let id_portmap = id.clone();
+            thread::spawn(move || portmap::close(&id_portmap));
+            format!("Failed to start Java process: {}", e)
+        })?;
+
+    get_server_pids()
+        .lock()
+        .unwrap()
+        .insert(id.clone(), child.id());
+    if in_docker {
+        docker::register(&id);
+    }
+
+    if instance.lan_broadcast {
+        if let Err(e) = lan::start(&id, &instance_dir) {
+            record_log_line(&app_handle, &id, format!("[nuko] {}", e));
+        }
+    }
+
+    // The container's processes aren't children of the attached client
+    if !in_docker && priority::is_configured(&instance.process) {
+        let app_priority = app_handle.clone();
+        let id_priority = id.clone();
+        let settings = instance.process.clone();
+        let pid = child.id();
+        thread::spawn(move || {
+            if let Err(e) = priority::apply(pid, &settings) {
+                record_log_line(&app_priority, &id_priority, format!("[nuko] {}", e));
+            }
+        });
+    }
 
     if let Some(stdin) = child.stdin.take() {
         let mut stdin_map = get_stdin_map().lock().unwrap();
@@ -639,13 +1265,10 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
         let reader = BufReader::new(stdout);
         for line in reader.lines() {
             if let Ok(line) = line {
-                {
-                    let mut logs_map = get_logs_map().lock().unwrap();
-                    if let Some(logs) = logs_map.get_mut(&id_clone) {
-                        logs.push(line.clone());
-                    }
-                }
-                let _ = app_clone.emit(&format!("instance-log-{}", id_clone), line);
+                watchdog::note_output(&id_clone);
+                oom::handle_log_line(&id_clone, &line);
+                chunky::handle_log_line(&app_clone, &id_clone, &line);
+                record_log_line(&app_clone, &id_clone, line);
             }
         }
     });
@@ -656,30 +1279,134 @@ pub async fn start_instance(app_handle: tauri::AppHandle, id: String) -> Result<
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
             if let Ok(line) = line {
-                {
-                    let mut logs_map = get_logs_map().lock().unwrap();
-                    if let Some(logs) = logs_map.get_mut(&id_clone_err) {
-                        logs.push(line.clone());
-                    }
-                }
-                let _ = app_clone_err.emit(&format!("instance-log-{}", id_clone_err), line);
+                watchdog::note_output(&id_clone_err);
+                oom::handle_log_line(&id_clone_err, &line);
+                record_log_line(&app_clone_err, &id_clone_err, line);
             }
         }
     });
 
+    let app_hook = app_handle.clone();
+    let config_hook = instance.clone();
+    let dir_hook = instance_dir.clone();
+    thread::spawn(move || {
+        hooks::run_logged(&app_hook, &config_hook, &dir_hook, HookStage::PostStart)
+    });
+
     let app_clone_wait = app_handle.clone();
     let id_clone_wait = id.clone();
+    let instance_dir_wait = instance_dir.clone();
+    let config_wait = instance.clone();
     thread::spawn(move || {
-        let _ = child.wait();
-        {
+        let status = child.wait();
+        get_server_pids().lock().unwrap().remove(&id_clone_wait);
+        // stop_instance and kill_instance take the stdin handle before the process exits
+        let requested = {
             let mut stdin_map = get_stdin_map().lock().unwrap();
-            stdin_map.remove(&id_clone_wait);
-        }
+            stdin_map.remove(&id_clone_wait).is_none()
+        };
         kill_playit_agent(&id_clone_wait);
+        ngrok::stop(&id_clone_wait);
+        relay::stop(&id_clone_wait);
+        portmap::close(&id_clone_wait);
+        lan::stop(&id_clone_wait);
+        docker::release(&id_clone_wait);
+        if config_wait.tailscale.enabled {
+            tailscale::stop(&config_wait);
+        }
+        remove_playit_secret_file(&instance_dir_wait);
+        hooks::run_logged(
+            &app_clone_wait,
+            &config_wait,
+            &instance_dir_wait,
+            HookStage::PostStop,
+        );
+        performance::clear(&id_clone_wait);
+        consolelog::close(&id_clone_wait);
+        logevents::clear(&id_clone_wait);
+        let exit_code = status.as_ref().ok().and_then(|status| status.code());
+        oom::handle_exit(
+            &app_clone_wait,
+            &id_clone_wait,
+            &instance_dir_wait,
+            started_at,
+            exit_code,
+            status.as_ref().ok().and_then(oom::signal),
+            requested,
+        );
+        let uptime_secs = started_at.elapsed().map(|uptime| uptime.as_secs()).ok();
+        let crashed = crash::handle_exit(
+            &app_clone_wait,
+            &id_clone_wait,
+            &instance_dir_wait,
+            started_at,
+            exit_code,
+            requested,
+        );
+        uptime::transition(
+            &app_clone_wait,
+            &id_clone_wait,
+            if crashed {
+                InstanceStatus::Crashed
+            } else {
+                InstanceStatus::Stopped
+            },
+        );
+        timeline::record(
+            &app_clone_wait,
+            &id_clone_wait,
+            if crashed {
+                TimelineKind::Crashed
+            } else {
+                TimelineKind::Stopped
+            },
+            None,
+            exit_code,
+            uptime_secs,
+        );
         let _ = app_clone_wait.emit("instances-updated", ());
     });
 
+    audit::record(&app_handle, &id, "start", serde_json::json!({}));
+    uptime::transition(&app_handle, &id, InstanceStatus::Starting);
+    timeline::record(
+        &app_handle,
+        &id,
+        TimelineKind::Started,
+        Some(version_label(&instance)),
+        None,
+        None,
+    );
     let _ = app_handle.emit("instances-updated", ());
 
     Ok(())
 }
+
+/// Swap the jar of a custom-software instance. A backup is taken first so the previous jar
+/// can be restored with `rollback_jar_change`
+#[tauri::command]
+pub async fn replace_custom_jar(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+) -> Result<BackupInfo, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    if config.software != "custom" {
+        return Err(format!("'{}' does not use a custom jar", config.name));
+    }
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before replacing its jar", config.name));
+    }
+
+    let backup =
+        backup::backup_before_jar_change(&app_handle, &config, "custom jar replacement").await?;
+
+    fs::copy(&path, instance_dir.join("server.jar"))
+        .map_err(|e| format!("Failed to copy custom jar: {}", e))?;
+    config.custom_jar_path = Some(path);
+    config.detected = jarinfo::detect(&instance_dir.join("server.jar")).ok();
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(backup)
+}
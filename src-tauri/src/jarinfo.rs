@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, update_instance_config},
+    models::DetectedJar,
+};
+
+/// Software for well-known `Main-Class` prefixes, most specific first
+const MAIN_CLASSES: &[(&str, &str)] = &[
+    ("io.papermc.paperclip.", "papermc"),
+    ("com.destroystokyo.paperclip.", "papermc"),
+    ("org.bukkit.craftbukkit.", "spigot"),
+    ("net.fabricmc.", "fabric"),
+    ("net.neoforged.", "neoforge"),
+    ("net.minecraftforge.", "forge"),
+    ("com.velocitypowered.proxy.", "velocity"),
+    ("net.md_5.bungee.", "bungeecord"),
+    ("net.minecraft.", "vanilla"),
+];
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+/// A `key: value` attribute from the jar manifest, or `key=value` from a properties file
+fn attribute(content: &str, key: &str, separator: char) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(separator)?;
+        (name.trim() == key)
+            .then(|| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// Paperclip lists the patched jar it unpacks as `<hash>\t<version>\t<brand>-<version>.jar`,
+/// which tells Paper, Purpur and Folia apart
+fn paperclip_brand(versions_list: &str) -> Option<(String, String)> {
+    let mut fields = versions_list.lines().next()?.split('\t');
+    let version = fields.nth(1)?.trim().to_string();
+    let file = fields.next()?.trim();
+    let brand = file
+        .rsplit('/')
+        .next()?
+        .strip_suffix(&format!("-{}.jar", version))?;
+    let software = match brand {
+        "paper" => "papermc",
+        other => other,
+    };
+    Some((software.to_string(), version))
+}
+
+/// Work out which server a jar is and the Minecraft version it runs, from what it bundles:
+/// Paperclip's versions.list, Fabric's install.properties, version.json, Spigot's Maven
+/// metadata and finally the manifest's `Main-Class`
+pub fn detect(jar: &Path) -> Result<DetectedJar, String> {
+    let file =
+        fs::File::open(jar).map_err(|e| format!("Failed to open {}: {}", jar.display(), e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read {}: {}", jar.display(), e))?;
+
+    let manifest = read_entry(&mut archive, "META-INF/MANIFEST.MF").unwrap_or_default();
+    let version_json = read_entry(&mut archive, "version.json")
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|json| json.get("id")?.as_str().map(str::to_string));
+
+    if let Some((software, version)) =
+        read_entry(&mut archive, "META-INF/versions.list").and_then(|list| paperclip_brand(&list))
+    {
+        return Ok(DetectedJar {
+            software,
+            version: Some(version),
+        });
+    }
+    if let Some(install) = read_entry(&mut archive, "install.properties") {
+        return Ok(DetectedJar {
+            software: "fabric".into(),
+            version: attribute(&install, "game-version", '='),
+        });
+    }
+    let spigot_version = read_entry(
+        &mut archive,
+        "META-INF/maven/org.spigotmc/spigot/pom.properties",
+    )
+    .and_then(|pom| attribute(&pom, "version", '='))
+    .map(|version| version.split('-').next().unwrap_or_default().to_string());
+
+    let main_class = attribute(&manifest, "Main-Class", ':').unwrap_or_default();
+    let software = MAIN_CLASSES
+        .iter()
+        .find(|(prefix, _)| main_class.starts_with(prefix))
+        .map(|(_, software)| software.to_string())
+        .or_else(|| spigot_version.as_ref().map(|_| "spigot".to_string()))
+        .ok_or_else(|| format!("Couldn't tell which server {} is", jar.display()))?;
+
+    Ok(DetectedJar {
+        software,
+        version: version_json.or(spigot_version),
+    })
+}
+
+/// Detect what a custom instance's server.jar is and store it in nuko.toml, so Java checks
+/// and plugin or mod lookups can treat it as that software
+pub fn detect_and_store(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<Option<DetectedJar>, String> {
+    let mut config = get_instance_by_id(app_handle, id)?;
+    if config.software != "custom" {
+        return Ok(None);
+    }
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    config.detected = detect(&instance_dir.join("server.jar")).ok();
+    update_instance_config(app_handle, &config)?;
+    Ok(config.detected)
+}
+
+/// Inspect a custom instance's server.jar again, e.g. after it was replaced outside nuko
+#[tauri::command]
+pub async fn detect_custom_jar(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Option<DetectedJar>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    if config.software != "custom" {
+        return Err(format!("'{}' does not use a custom jar", config.name));
+    }
+    detect_and_store(&app_handle, &id)
+}
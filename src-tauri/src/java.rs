@@ -0,0 +1,454 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+    thread,
+};
+
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::{
+    archive, download,
+    errors::CommandError,
+    filesystem,
+    instance::{get_instance_by_id, update_instance_config},
+    models::{AdoptiumAsset, JavaRuntime},
+};
+
+const ADOPTIUM_API: &str = "https://api.adoptium.net/v3";
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+
+fn java_exe_name() -> &'static str {
+    if cfg!(windows) {
+        "java.exe"
+    } else {
+        "java"
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var).map(PathBuf::from)
+}
+
+/// Directories that each hold one JDK/JRE per subdirectory
+fn vendor_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    if cfg!(windows) {
+        for var in ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"] {
+            let Some(program_files) = std::env::var_os(var).map(PathBuf::from) else {
+                continue;
+            };
+            for vendor in [
+                "Java",
+                "Eclipse Adoptium",
+                "Eclipse Foundation",
+                "AdoptOpenJDK",
+                "Microsoft",
+                "Zulu",
+                "BellSoft",
+                "Amazon Corretto",
+                "Semeru",
+                "GraalVM",
+            ] {
+                roots.push(program_files.join(vendor));
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        roots.push(PathBuf::from("/Library/Java/JavaVirtualMachines"));
+        roots.push(PathBuf::from("/opt/homebrew/opt"));
+        roots.push(PathBuf::from("/usr/local/opt"));
+    } else {
+        roots.push(PathBuf::from("/usr/lib/jvm"));
+        roots.push(PathBuf::from("/usr/lib64/jvm"));
+        roots.push(PathBuf::from("/usr/java"));
+        roots.push(PathBuf::from("/opt/java"));
+        roots.push(PathBuf::from("/opt"));
+    }
+
+    if let Some(home) = home_dir() {
+        roots.push(home.join(".jdks"));
+        roots.push(home.join(".sdkman/candidates/java"));
+        roots.push(home.join(".asdf/installs/java"));
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library/Java/JavaVirtualMachines"));
+        }
+    }
+
+    roots
+}
+
+/// The java binary inside a JDK/JRE directory, if there is one
+fn java_in(home: &Path) -> Option<PathBuf> {
+    [
+        home.join("bin"),
+        home.join("Contents/Home/bin"),
+        home.join("libexec/openjdk.jdk/Contents/Home/bin"),
+    ]
+    .into_iter()
+    .map(|bin| bin.join(java_exe_name()))
+    .find(|java| java.is_file())
+}
+
+/// Runtimes nuko downloaded itself, one `temurin-<major>-jre` folder each
+fn get_runtimes_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("runtimes");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// The java binary of a managed runtime. The archive's own top-level folder is kept inside
+fn managed_java(runtime_dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(runtime_dir)
+        .ok()?
+        .flatten()
+        .find_map(|entry| java_in(&entry.path()))
+}
+
+fn managed_candidates(runtimes_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(runtimes_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| !entry.file_name().to_string_lossy().ends_with(".tmp"))
+                .filter_map(|entry| managed_java(&entry.path()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Every java binary worth probing: managed runtimes, PATH, JAVA_HOME and the usual install
+/// locations
+fn candidates(runtimes_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut found = runtimes_dir.map(managed_candidates).unwrap_or_default();
+
+    if let Some(path) = std::env::var_os("PATH") {
+        found.extend(
+            std::env::split_paths(&path)
+                .map(|dir| dir.join(java_exe_name()))
+                .filter(|java| java.is_file()),
+        );
+    }
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        found.extend(java_in(Path::new(&java_home)));
+    }
+    for root in vendor_roots() {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+        found.extend(entries.flatten().filter_map(|entry| java_in(&entry.path())));
+    }
+
+    // /usr/bin/java and friends are usually symlinks into one of the vendor directories
+    let mut seen = HashSet::new();
+    found
+        .into_iter()
+        .filter(|java| seen.insert(fs::canonicalize(java).unwrap_or_else(|_| java.clone())))
+        .collect()
+}
+
+/// Major version from a `java.version` string: `1.8.0_392` is 8, `17.0.9` is 17
+fn parse_major(version: &str) -> Option<u32> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// The oldest Java major version a Minecraft version's server runs on. Versions that can't be
+/// parsed (snapshots) are assumed to be recent
+pub fn required_java_major(mc_version: &str) -> u32 {
+    let mut parts = mc_version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u32>().ok());
+    let (Some(Some(major)), Some(Some(minor))) = (parts.next(), parts.next()) else {
+        return 21;
+    };
+    let patch = parts.next().flatten().unwrap_or(0);
+
+    match (major, minor, patch) {
+        // Year-based versions, starting with 26.1
+        (major, _, _) if major >= 26 => 25,
+        (1, minor, _) if minor >= 21 => 21,
+        (1, 20, patch) if patch >= 5 => 21,
+        (1, minor, _) if minor >= 17 => 17,
+        (1, _, _) => 8,
+        _ => 21,
+    }
+}
+
+/// Run `java -XshowSettings:properties -version` and read the version, vendor and
+/// architecture from the properties it prints
+pub fn probe(java: &Path) -> Result<JavaRuntime, String> {
+    let output = Command::new(java)
+        .args(["-XshowSettings:properties", "-version"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", java.display(), e))?;
+    let text = String::from_utf8_lossy(&output.stderr);
+
+    let property = |name: &str| {
+        text.lines().find_map(|line| {
+            let (key, value) = line.trim().split_once(" = ")?;
+            (key == name).then(|| value.trim().to_string())
+        })
+    };
+    // Fall back to the `openjdk version "17.0.9"` banner for JVMs without -XshowSettings
+    let version = property("java.version")
+        .or_else(|| {
+            text.lines()
+                .find(|line| line.contains(" version \""))
+                .and_then(|line| line.split('"').nth(1))
+                .map(str::to_string)
+        })
+        .ok_or_else(|| format!("{} didn't report a Java version", java.display()))?;
+    let major =
+        parse_major(&version).ok_or_else(|| format!("Unrecognised Java version '{}'", version))?;
+
+    Ok(JavaRuntime {
+        path: java.to_string_lossy().into_owned(),
+        version,
+        major,
+        vendor: property("java.vendor"),
+        arch: property("os.arch"),
+        managed: false,
+    })
+}
+
+/// Find the Java installations on this machine, newest major version first
+pub fn discover(runtimes_dir: Option<&Path>) -> Vec<JavaRuntime> {
+    let candidates = candidates(runtimes_dir);
+    let mut runtimes: Vec<JavaRuntime> = thread::scope(|scope| {
+        candidates
+            .iter()
+            .map(|java| scope.spawn(move || probe(java).ok()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect()
+    });
+    if let Some(runtimes_dir) = runtimes_dir {
+        for runtime in &mut runtimes {
+            runtime.managed = Path::new(&runtime.path).starts_with(runtimes_dir);
+        }
+    }
+    runtimes.sort_by(|a, b| b.major.cmp(&a.major).then_with(|| a.path.cmp(&b.path)));
+    runtimes
+}
+
+/// Adoptium's names for this machine's OS and architecture
+fn adoptium_platform() -> Result<(&'static str, &'static str), String> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "mac",
+        "windows" => "windows",
+        other => return Err(format!("Temurin isn't available for {}", other)),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "aarch64",
+        other => return Err(format!("Temurin isn't available for {}", other)),
+    };
+    Ok((os, arch))
+}
+
+/// Only one runtime is downloaded at a time, so instances starting together share it
+fn get_install_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// Download the latest Temurin JRE for a Java major version into the runtimes directory,
+/// verifying the archive's SHA-256 before it's extracted. Returns the java binary, reusing
+/// a runtime that's already there
+pub async fn install_temurin(app_handle: &tauri::AppHandle, major: u32) -> Result<String, String> {
+    let _guard = get_install_lock().lock().await;
+
+    let runtimes_dir = get_runtimes_dir(app_handle)?;
+    let runtime_dir = runtimes_dir.join(format!("temurin-{}-jre", major));
+    if let Some(java) = managed_java(&runtime_dir) {
+        return Ok(java.to_string_lossy().into_owned());
+    }
+
+    let (os, arch) = adoptium_platform()?;
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let url = format!("{}/assets/latest/{}/hotspot", ADOPTIUM_API, major);
+    let assets: Vec<AdoptiumAsset> = client
+        .get(&url)
+        .query(&[
+            ("architecture", arch),
+            ("image_type", "jre"),
+            ("os", os),
+            ("vendor", "eclipse"),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("GET {} failed: {}", url, e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to look up Temurin {}: {}", major, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response from {}: {}", url, e))?;
+    let asset = assets
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No Temurin {} JRE is published for {}/{}", major, os, arch))?;
+    let package = asset.binary.package;
+
+    println!(
+        "Downloading {} ({} bytes) from {}...",
+        asset.release_name, package.size, package.link
+    );
+    let archive_path = runtimes_dir.join(format!("{}.part", package.name));
+    let mut file = fs::File::create(&archive_path)
+        .map_err(|e| format!("Failed to create {}: {}", archive_path.display(), e))?;
+    let mut hasher = Sha256::new();
+    download::stream(client.get(&package.link), |chunk| {
+        hasher.update(chunk);
+        file.write_all(chunk)
+            .map_err(|e| format!("Writing {} failed: {}", archive_path.display(), e))
+    })
+    .await
+    .map_err(|e| format!("Failed to download {}: {}", package.name, e))?;
+    drop(file);
+
+    let actual = format!("{:x}", hasher.finalize());
+    if !actual.eq_ignore_ascii_case(&package.checksum) {
+        let _ = fs::remove_file(&archive_path);
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            package.name, package.checksum, actual
+        ));
+    }
+
+    // Extract next to the final folder and swap it in, so a half-extracted runtime is never
+    // picked up
+    let staging = runtimes_dir.join(format!("temurin-{}-jre.tmp", major));
+    let zipped = package.name.ends_with(".zip");
+    let extracted = {
+        let archive_path = archive_path.clone();
+        let staging = staging.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let _ = fs::remove_dir_all(&staging);
+            if zipped {
+                archive::unzip_to(&archive_path, &staging)
+            } else {
+                archive::untar_gz_with_progress(&archive_path, &staging, |_| Ok(()), |_, _| {})
+            }
+        })
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+    };
+    let _ = fs::remove_file(&archive_path);
+    extracted?;
+
+    let _ = fs::remove_dir_all(&runtime_dir);
+    fs::rename(&staging, &runtime_dir)
+        .map_err(|e| format!("Failed to move {}: {}", runtime_dir.display(), e))?;
+    let java = managed_java(&runtime_dir)
+        .ok_or_else(|| format!("{} doesn't contain a java binary", package.name))?;
+
+    println!(
+        "Installed {} to {}",
+        asset.release_name,
+        runtime_dir.display()
+    );
+    Ok(java.to_string_lossy().into_owned())
+}
+
+/// Pick a Java for a Minecraft version when the instance doesn't name one. Returns `None` if
+/// the `java` on PATH will do, otherwise an installed runtime of the recommended major version
+/// (or newer), downloading Temurin if there isn't one
+pub async fn resolve_runtime(
+    app_handle: &tauri::AppHandle,
+    mc_version: &str,
+) -> Result<Option<String>, String> {
+    let required = required_java_major(mc_version);
+    let runtimes_dir = get_runtimes_dir(app_handle)?;
+    let runtimes = tauri::async_runtime::spawn_blocking(move || {
+        let on_path = probe(Path::new(java_exe_name())).ok();
+        (on_path, discover(Some(&runtimes_dir)))
+    })
+    .await
+    .map_err(|e| format!("Background task failed: {}", e))?;
+
+    match runtimes {
+        (Some(on_path), _) if on_path.major >= required => Ok(None),
+        (_, installed) => {
+            let best = installed
+                .iter()
+                .find(|runtime| runtime.major == required)
+                .or_else(|| {
+                    installed
+                        .iter()
+                        .rev()
+                        .find(|runtime| runtime.major > required)
+                });
+            match best {
+                Some(runtime) => Ok(Some(runtime.path.clone())),
+                None => install_temurin(app_handle, required).await.map(Some),
+            }
+        }
+    }
+}
+
+/// Probe the Java an instance will launch with and fail with an `incompatible_java` error,
+/// carrying the required and found major versions, if it's too old for the Minecraft version.
+/// A Java that can't be probed is left for the launch itself to report
+pub async fn check_compatible(java_path: &str, mc_version: &str) -> Result<(), String> {
+    let required = required_java_major(mc_version);
+    let java = PathBuf::from(java_path);
+    let Ok(Ok(runtime)) = tauri::async_runtime::spawn_blocking(move || probe(&java)).await else {
+        return Ok(());
+    };
+    if runtime.major >= required {
+        return Ok(());
+    }
+
+    Err(CommandError::new(
+        "incompatible_java",
+        format!(
+            "Minecraft {} needs Java {} or newer, but {} is Java {}",
+            mc_version, required, java_path, runtime.major
+        ),
+    )
+    .with_details(serde_json::json!({
+        "required_major": required,
+        "found_major": runtime.major,
+        "java_path": java_path,
+    }))
+    .into())
+}
+
+/// Java installations an instance can be pointed at
+#[tauri::command]
+pub async fn list_java_runtimes(app_handle: tauri::AppHandle) -> Result<Vec<JavaRuntime>, String> {
+    let runtimes_dir = get_runtimes_dir(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || discover(Some(&runtimes_dir)))
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+}
+
+/// Download the Temurin JRE an instance's Minecraft version needs and point the instance at
+/// it. Returns the java path
+#[tauri::command]
+pub async fn install_java_runtime(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<String, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let java =
+        install_temurin(&app_handle, required_java_major(config.effective_version())).await?;
+    config.java.java_path = Some(java.clone());
+    update_instance_config(&app_handle, &config)?;
+    Ok(java)
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    docker, filesystem,
+    instance::{get_instance_by_id, is_instance_server_process, record_log_line, tracked_pid},
+    models::JvmStats,
+};
+
+/// jstat and jcmd each start their own JVM, so they're only run this often
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+struct CachedStats {
+    pid: u32,
+    stats: Option<JvmStats>,
+    checked_at: Option<Instant>,
+    refreshing: bool,
+}
+
+fn get_stats_map() -> &'static Mutex<HashMap<String, CachedStats>> {
+    static STATS: OnceLock<Mutex<HashMap<String, CachedStats>>> = OnceLock::new();
+    STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A JDK tool next to the server's java binary, falling back to whatever is on PATH.
+/// JRE-only installs don't ship these tools, in which case no JVM stats are reported
+fn tool_path(java_exe: Option<&Path>, tool: &str) -> PathBuf {
+    let name = if cfg!(windows) {
+        format!("{}.exe", tool)
+    } else {
+        tool.to_string()
+    };
+    java_exe
+        .and_then(Path::parent)
+        .map(|bin| bin.join(&name))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+fn run_tool(tool: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(tool)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", tool.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            tool.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `jstat -gc` output: a header row of column names and a row of values, with
+/// capacities and usage in KB and GC times in seconds
+fn parse_jstat_gc(output: &str) -> Option<HashMap<String, f64>> {
+    let mut lines = output.lines().filter(|line| !line.trim().is_empty());
+    let header = lines.next()?.split_whitespace();
+    let values = lines.next()?.split_whitespace();
+    Some(
+        header
+            .zip(values)
+            .filter_map(|(name, value)| Some((name.to_string(), value.parse().ok()?)))
+            .collect(),
+    )
+}
+
+/// Live thread count from `jcmd <pid> PerfCounter.print`
+fn parse_thread_count(output: &str) -> Option<u32> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("java.threads.live="))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+fn query(pid: u32, java_exe: Option<&Path>) -> Result<JvmStats, String> {
+    let pid = pid.to_string();
+    let gc_output = run_tool(&tool_path(java_exe, "jstat"), &["-gc", &pid])?;
+    let gc = parse_jstat_gc(&gc_output).ok_or("Unexpected jstat output")?;
+    let column = |name: &str| gc.get(name).copied().unwrap_or(0.0);
+    let kb = |names: &[&str]| (names.iter().map(|name| column(name)).sum::<f64>() * 1024.0) as u64;
+
+    let thread_count = run_tool(&tool_path(java_exe, "jcmd"), &[&pid, "PerfCounter.print"])
+        .ok()
+        .and_then(|output| parse_thread_count(&output));
+
+    Ok(JvmStats {
+        heap_used: kb(&["S0U", "S1U", "EU", "OU"]),
+        heap_committed: kb(&["S0C", "S1C", "EC", "OC"]),
+        young_gc_count: column("YGC") as u64,
+        full_gc_count: (column("FGC") + column("CGC")) as u64,
+        gc_time_ms: (column("GCT") * 1000.0) as u64,
+        thread_count,
+    })
+}
+
+/// The latest JVM stats for an instance's server process. Returns the cached values right
+/// away and refreshes them in the background once they're older than `REFRESH_INTERVAL`
+pub fn latest(id: &str, pid: u32, java_exe: Option<PathBuf>) -> Option<JvmStats> {
+    let mut stats_map = get_stats_map().lock().unwrap();
+    let cached = stats_map.entry(id.to_string()).or_insert(CachedStats {
+        pid,
+        stats: None,
+        checked_at: None,
+        refreshing: false,
+    });
+
+    // A restarted server is a different JVM, so its old numbers no longer apply
+    if cached.pid != pid {
+        cached.pid = pid;
+        cached.stats = None;
+        cached.checked_at = None;
+    }
+
+    let stale = cached
+        .checked_at
+        .map(|at| at.elapsed() >= REFRESH_INTERVAL)
+        .unwrap_or(true);
+    if stale && !cached.refreshing {
+        cached.refreshing = true;
+
+        let id = id.to_string();
+        thread::spawn(move || {
+            let stats = query(pid, java_exe.as_deref()).ok();
+            let mut stats_map = get_stats_map().lock().unwrap();
+            if let Some(cached) = stats_map.get_mut(&id) {
+                if cached.pid == pid {
+                    cached.stats = stats;
+                    cached.checked_at = Some(Instant::now());
+                }
+                cached.refreshing = false;
+            }
+        });
+    }
+
+    cached.stats.clone()
+}
+
+fn is_java(process: &sysinfo::Process) -> bool {
+    process
+        .exe()
+        .and_then(|path| path.file_stem())
+        .is_some_and(|name| name.eq_ignore_ascii_case("java") || name.eq_ignore_ascii_case("javaw"))
+}
+
+/// The server's JVM: the tracked PID when it's java itself, else the java process found in
+/// the instance directory, since a resource-limit wrapper may sit in between
+fn server_jvm(id: &str, instance_dir: &Path) -> Option<(u32, Option<PathBuf>)> {
+    let mut sys = sysinfo::System::new_all();
+    sys.refresh_all();
+    let tracked = tracked_pid(id)
+        .and_then(|pid| sys.process(sysinfo::Pid::from_u32(pid)))
+        .filter(|process| is_java(process));
+    tracked
+        .or_else(|| {
+            sys.processes().values().find(|process| {
+                is_java(process) && is_instance_server_process(process, instance_dir)
+            })
+        })
+        .map(|process| (process.pid().as_u32(), process.exe().map(Path::to_path_buf)))
+}
+
+/// Run `capture` against the instance's JVM, writing to a new file in its `diagnostics/`
+/// folder. Returns the file's path
+async fn capture(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    kind: &str,
+    extension: &str,
+    capture: fn(u32, Option<&Path>, &Path) -> Result<(), String>,
+) -> Result<String, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    if docker::is_managed(id) {
+        return Err(format!(
+            "{} dumps aren't available while '{}' runs in Docker",
+            kind, config.name
+        ));
+    }
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let (pid, java_exe) =
+        server_jvm(id, &instance_dir).ok_or_else(|| format!("'{}' is not running", config.name))?;
+
+    let diagnostics_dir = instance_dir.join("diagnostics");
+    fs::create_dir_all(&diagnostics_dir)
+        .map_err(|e| format!("Failed to create {}: {}", diagnostics_dir.display(), e))?;
+    let path = diagnostics_dir.join(format!(
+        "{}-{}.{}",
+        kind.to_lowercase(),
+        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"),
+        extension
+    ));
+
+    let target = path.clone();
+    tauri::async_runtime::spawn_blocking(move || capture(pid, java_exe.as_deref(), &target))
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))??;
+
+    record_log_line(
+        app_handle,
+        id,
+        format!("[nuko] {} dump saved to {}", kind, path.display()),
+    );
+    Ok(path.to_string_lossy().into_owned())
+}
+
+fn thread_dump(pid: u32, java_exe: Option<&Path>, path: &Path) -> Result<(), String> {
+    let pid = pid.to_string();
+    let dump =
+        run_tool(&tool_path(java_exe, "jcmd"), &[&pid, "Thread.print", "-l"]).or_else(|e| {
+            run_tool(&tool_path(java_exe, "jstack"), &["-l", &pid])
+                .map_err(|jstack_error| format!("{}; {}", e, jstack_error))
+        })?;
+    fs::write(path, dump).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// jcmd resolves relative paths against the server's working directory, so the path passed
+/// is absolute
+fn heap_dump(pid: u32, java_exe: Option<&Path>, path: &Path) -> Result<(), String> {
+    let pid = pid.to_string();
+    let file = path.to_string_lossy();
+    run_tool(
+        &tool_path(java_exe, "jcmd"),
+        &[&pid, "GC.heap_dump", file.as_ref()],
+    )
+    .or_else(|e| {
+        run_tool(
+            &tool_path(java_exe, "jmap"),
+            &[&format!("-dump:live,format=b,file={}", file), &pid],
+        )
+        .map_err(|jmap_error| format!("{}; {}", e, jmap_error))
+    })?;
+    if !path.exists() {
+        return Err("The JVM didn't write a heap dump".into());
+    }
+    Ok(())
+}
+
+/// Save the stack of every thread in the server's JVM to `diagnostics/`, for working out
+/// why it hangs. Needs jcmd or jstack from a JDK
+#[tauri::command]
+pub async fn capture_thread_dump(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<String, String> {
+    capture(&app_handle, &id, "Thread", "txt", thread_dump).await
+}
+
+/// Save a heap dump of the server's JVM to `diagnostics/` for tracking down memory leaks.
+/// The file is about as big as the used heap and the server pauses while it's written
+#[tauri::command]
+pub async fn capture_heap_dump(app_handle: tauri::AppHandle, id: String) -> Result<String, String> {
+    capture(&app_handle, &id, "Heap", "hprof", heap_dump).await
+}
@@ -0,0 +1,175 @@
+use serde::Deserialize;
+
+use crate::instance::{get_instance_by_id, update_instance_config};
+
+/// Heaps above this get Aikar's large-heap G1 tuning
+const LARGE_HEAP_MB: u64 = 12 * 1024;
+
+/// GC flag sets that can be applied to an instance's additional JVM args
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmPreset {
+    /// Aikar's G1 tuning from mcflags.emc.gs
+    Aikar,
+    Zgc,
+    Shenandoah,
+    /// Remove preset flags and go back to the JVM's defaults
+    None,
+}
+
+/// Flags any preset may set (or that pick a different GC), without their `-XX:+`/`-XX:`
+/// prefix or value. These are removed before a preset is applied so presets don't stack
+const MANAGED_FLAGS: &[&str] = &[
+    "UseG1GC",
+    "UseZGC",
+    "ZGenerational",
+    "UseShenandoahGC",
+    "ShenandoahGCMode",
+    "UseParallelGC",
+    "UseSerialGC",
+    "ParallelRefProcEnabled",
+    "MaxGCPauseMillis",
+    "UnlockExperimentalVMOptions",
+    "DisableExplicitGC",
+    "AlwaysPreTouch",
+    "G1NewSizePercent",
+    "G1MaxNewSizePercent",
+    "G1HeapRegionSize",
+    "G1ReservePercent",
+    "G1HeapWastePercent",
+    "G1MixedGCCountTarget",
+    "InitiatingHeapOccupancyPercent",
+    "G1MixedGCLiveThresholdPercent",
+    "G1RSetUpdatingPauseTimePercent",
+    "SurvivorRatio",
+    "PerfDisableSharedMem",
+    "MaxTenuringThreshold",
+    "UseTransparentHugePages",
+    "-Dusing.aikars.flags",
+    "-Daikars.new.flags",
+];
+
+/// Parse a JVM memory size (`4G`, `4096M`, `512m`, `1048576`) into megabytes
+pub fn parse_memory_mb(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, ""),
+    };
+    let number: u64 = number.parse().ok()?;
+    match unit.to_ascii_lowercase().as_str() {
+        "" => Some(number / (1024 * 1024)),
+        "k" => Some(number / 1024),
+        "m" => Some(number),
+        "g" => Some(number * 1024),
+        "t" => Some(number * 1024 * 1024),
+        _ => None,
+    }
+}
+
+/// The flag name an argument sets, e.g. `G1HeapRegionSize` for `-XX:G1HeapRegionSize=8M`
+fn flag_name(arg: &str) -> &str {
+    let name = match arg.strip_prefix("-XX:") {
+        Some(rest) => rest.trim_start_matches(['+', '-']),
+        None => arg,
+    };
+    name.split('=').next().unwrap_or(name)
+}
+
+fn preset_flags(preset: JvmPreset, heap_mb: Option<u64>) -> Vec<String> {
+    let large_heap = heap_mb.is_some_and(|mb| mb > LARGE_HEAP_MB);
+    let flags: Vec<&str> = match preset {
+        JvmPreset::Aikar => {
+            let mut flags = vec![
+                "-XX:+UseG1GC",
+                "-XX:+ParallelRefProcEnabled",
+                "-XX:MaxGCPauseMillis=200",
+                "-XX:+UnlockExperimentalVMOptions",
+                "-XX:+DisableExplicitGC",
+                "-XX:+AlwaysPreTouch",
+            ];
+            if large_heap {
+                flags.extend([
+                    "-XX:G1NewSizePercent=40",
+                    "-XX:G1MaxNewSizePercent=50",
+                    "-XX:G1HeapRegionSize=16M",
+                    "-XX:G1ReservePercent=15",
+                ]);
+            } else {
+                flags.extend([
+                    "-XX:G1NewSizePercent=30",
+                    "-XX:G1MaxNewSizePercent=40",
+                    "-XX:G1HeapRegionSize=8M",
+                    "-XX:G1ReservePercent=20",
+                ]);
+            }
+            flags.extend([
+                "-XX:G1HeapWastePercent=5",
+                "-XX:G1MixedGCCountTarget=4",
+                if large_heap {
+                    "-XX:InitiatingHeapOccupancyPercent=20"
+                } else {
+                    "-XX:InitiatingHeapOccupancyPercent=15"
+                },
+                "-XX:G1MixedGCLiveThresholdPercent=90",
+                "-XX:G1RSetUpdatingPauseTimePercent=5",
+                "-XX:SurvivorRatio=32",
+                "-XX:+PerfDisableSharedMem",
+                "-XX:MaxTenuringThreshold=1",
+                "-Dusing.aikars.flags=https://mcflags.emc.gs",
+                "-Daikars.new.flags=true",
+            ]);
+            flags
+        }
+        JvmPreset::Zgc => {
+            let mut flags = vec![
+                "-XX:+UseZGC",
+                "-XX:+DisableExplicitGC",
+                "-XX:+PerfDisableSharedMem",
+            ];
+            // Pre-touching a small heap costs startup time for little gain
+            if heap_mb.is_some_and(|mb| mb >= 8 * 1024) {
+                flags.push("-XX:+AlwaysPreTouch");
+            }
+            flags
+        }
+        JvmPreset::Shenandoah => {
+            let mut flags = vec![
+                "-XX:+UseShenandoahGC",
+                "-XX:+ParallelRefProcEnabled",
+                "-XX:+DisableExplicitGC",
+                "-XX:+PerfDisableSharedMem",
+            ];
+            if heap_mb.is_some_and(|mb| mb >= 8 * 1024) {
+                flags.push("-XX:+AlwaysPreTouch");
+            }
+            flags
+        }
+        JvmPreset::None => vec![],
+    };
+    flags.into_iter().map(str::to_string).collect()
+}
+
+/// Replace the instance's GC flags with a preset sized for its max heap, keeping any other
+/// additional args. Returns the new additional args
+#[tauri::command]
+pub async fn apply_jvm_preset(
+    app_handle: tauri::AppHandle,
+    id: String,
+    preset: JvmPreset,
+) -> Result<Vec<String>, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let heap_mb = parse_memory_mb(&config.java.max_memory);
+
+    let mut args: Vec<String> = config
+        .java
+        .additional_args
+        .into_iter()
+        .filter(|arg| !MANAGED_FLAGS.contains(&flag_name(arg)))
+        .collect();
+    args.extend(preset_flags(preset, heap_mb));
+
+    config.java.additional_args = args.clone();
+    update_instance_config(&app_handle, &config)?;
+    Ok(args)
+}
@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, UdpSocket},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    instance::{get_instance_by_id, update_instance_config},
+    ports,
+    properties::ServerProperties,
+};
+
+/// Where the client's "LAN Worlds" list listens, the same as an opened singleplayer world
+const LAN_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 2, 60);
+const LAN_PORT: u16 = 4445;
+const INTERVAL: Duration = Duration::from_millis(1500);
+
+fn get_broadcasts() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    static BROADCASTS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+    BROADCASTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn announcement(instance_dir: &Path) -> String {
+    let motd = ServerProperties::load(instance_dir)
+        .ok()
+        .and_then(|properties| properties.get("motd").map(str::to_string))
+        .filter(|motd| !motd.trim().is_empty())
+        .unwrap_or_else(|| "A Minecraft Server".into());
+    // The client splits on these tags, so they can't appear in the MOTD itself
+    let motd = motd.replace("[/MOTD]", "").replace('\n', " ");
+    format!(
+        "[MOTD]{}[/MOTD][AD]{}[/AD]",
+        motd,
+        ports::server_port(instance_dir)
+    )
+}
+
+/// Announce the instance to Minecraft clients on the local network until `stop` is called.
+/// The MOTD and port are re-read each time so edits show up without a restart
+pub fn start(id: &str, instance_dir: &Path) -> Result<(), String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Failed to open LAN broadcast socket: {}", e))?;
+    let _ = socket.set_multicast_ttl_v4(1);
+
+    let running = Arc::new(AtomicBool::new(true));
+    if let Some(previous) = get_broadcasts()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), running.clone())
+    {
+        previous.store(false, Ordering::Relaxed);
+    }
+
+    let instance_dir = instance_dir.to_path_buf();
+    thread::spawn(move || {
+        while running.load(Ordering::Relaxed) {
+            let _ = socket.send_to(
+                announcement(&instance_dir).as_bytes(),
+                (LAN_GROUP, LAN_PORT),
+            );
+            thread::sleep(INTERVAL);
+        }
+    });
+    Ok(())
+}
+
+/// Stop announcing the instance on the local network
+pub fn stop(id: &str) {
+    if let Some(running) = get_broadcasts().lock().unwrap().remove(id) {
+        running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Turn the LAN Worlds announcement on or off for the instance. Applies from the next start
+#[tauri::command]
+pub async fn set_lan_broadcast(
+    app_handle: tauri::AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.lan_broadcast = enabled;
+    update_instance_config(&app_handle, &config)
+}
@@ -1,11 +1,87 @@
-use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri::{
+    AppHandle, Listener, Manager, RunEvent, WebviewUrl, WebviewWindowBuilder, WindowEvent,
+};
 
+mod access;
+mod addons;
+mod address;
+mod announcer;
+mod ansi;
+mod archive;
+mod audit;
+mod automations;
+mod backup;
+mod bulk;
+mod cgroup;
+mod chunky;
+mod commands;
 mod config;
+mod configfiles;
+mod consolelog;
+mod crash;
+mod crossplay;
+mod curseforge;
+mod disk;
+mod dns;
+mod docker;
 mod download;
+mod errors;
+mod files;
 mod filesystem;
+mod forge;
+mod hooks;
+mod icon;
+mod index;
 mod instance;
+mod jarinfo;
+mod java;
+mod jvm;
+mod jvmflags;
+mod lan;
+mod logevents;
+mod maintenance;
+mod memory;
+mod metrics;
+mod migrate;
 mod models;
+mod modrinth;
+mod motd;
+mod network;
+mod ngrok;
+mod notifications;
+mod oom;
+mod papermc;
+mod performance;
+mod playerdata;
+mod players;
+mod playerstats;
 mod playit;
+mod portmap;
+mod ports;
+mod priority;
+mod proctree;
+mod properties;
+mod protocol;
+mod rcon;
+mod relay;
+mod relocate;
+mod remote;
+mod resourcepack;
+mod scheduler;
+mod secrets;
+mod serverjar;
+mod service;
+mod snapshot;
+mod spiget;
+mod tailscale;
+mod templates;
+mod timeline;
+mod tray;
+mod upgrade;
+mod uptime;
+mod via;
+mod watchdog;
+mod world;
 
 #[tauri::command]
 fn close_current_window(window: tauri::Window) -> Result<(), String> {
@@ -38,15 +114,51 @@ fn open_new_instance_window(app_handle: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Bring the main window to the front, e.g. when the GUI is opened while nuko is already
+/// running headless in the background
+fn show_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if service::is_headless() {
+        if let Err(e) = service::ensure_graphical_session() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            show_main_window(app);
+        }))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(index::InstanceIndex::default())
         .setup(|app| {
-            let data_dir = filesystem::get_data_dir(&app.app_handle())?;
-            if !data_dir.join("instances").exists() {
+            let app_handle = app.app_handle().clone();
+            app.listen("instances-updated", move |_| {
+                app_handle.state::<index::InstanceIndex>().invalidate();
+                tray::refresh(&app_handle);
+            });
+
+            download::apply_limits(&config::get_config(app.app_handle().clone())?.downloads);
+            let instances_dir = filesystem::get_instances_dir(app.app_handle())?;
+            if let Err(e) = filesystem::migrate_legacy_instance_dirs(&instances_dir) {
+                println!("Failed to migrate legacy instance directories: {}", e);
+            }
+            if service::is_headless() {
+                if let Some(main_window) = app.get_webview_window("main") {
+                    main_window.hide().map_err(|e| e.to_string())?;
+                }
+            } else if !instances_dir.exists() {
                 let main_window = app
                     .app_handle()
                     .get_webview_window("main")
@@ -60,11 +172,45 @@ pub fn run() {
                     .map_err(|e| e.to_string())?;
             }
 
+            if let Err(e) = resourcepack::restore_hosted_packs(app.app_handle()) {
+                println!("Failed to restore hosted resource packs: {}", e);
+            }
+
+            tray::create(app.app_handle())?;
+            metrics::start_recorder(app.app_handle().clone());
+            scheduler::start(app.app_handle().clone());
+            announcer::start(app.app_handle().clone());
+            watchdog::start(app.app_handle().clone());
+
+            let app_handle = app.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = bulk::autostart_instances(app_handle).await {
+                    println!("Failed to autostart instances: {}", e);
+                }
+            });
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" && tray::close_to_tray(window.app_handle()) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             config::get_config,
             config::set_theme,
+            config::get_instance_defaults,
+            config::set_instance_defaults,
+            config::set_download_settings,
+            config::set_max_log_lines,
+            config::set_notification_settings,
+            config::set_close_to_tray,
+            config::set_curseforge_api_key,
+            config::set_ngrok_authtoken,
+            config::set_cloudflare_api_token,
             open_new_instance_window,
             close_current_window,
             download::get_vanilla_versions,
@@ -74,6 +220,7 @@ pub fn run() {
             download::get_forge_mc_versions,
             download::get_forge_versions,
             download::get_purpur_versions,
+            download::get_purpur_builds,
             download::get_neoforge_mc_versions,
             download::get_neoforge_versions,
             instance::create_instance,
@@ -84,11 +231,164 @@ pub fn run() {
             instance::kill_instance,
             instance::restart_instance,
             instance::get_instance_logs,
+            consolelog::get_console_history,
+            consolelog::list_server_logs,
+            consolelog::read_server_log,
+            consolelog::search_instance_logs,
+            crash::list_crash_reports,
+            logevents::get_online_players,
+            commands::get_command_history,
+            commands::get_known_commands,
+            addons::list_addons,
+            modrinth::search_modrinth,
+            modrinth::get_modrinth_versions,
+            modrinth::install_modrinth_project,
+            spiget::search_spiget,
+            spiget::install_spiget_resource,
+            curseforge::search_curseforge,
+            curseforge::get_curseforge_files,
+            curseforge::install_curseforge_mod,
+            crossplay::setup_crossplay,
+            via::install_via_version,
+            configfiles::list_config_files,
+            configfiles::read_config_file,
+            configfiles::write_config_file,
+            files::list_instance_files,
+            files::read_instance_file,
+            files::write_instance_file,
+            files::rename_instance_file,
+            files::delete_instance_file,
+            files::create_instance_folder,
+            files::upload_instance_file,
+            files::compress_path,
+            files::extract_archive,
+            scheduler::list_tasks,
+            scheduler::add_task,
+            scheduler::remove_task,
+            scheduler::run_task_now,
+            automations::list_automations,
+            automations::add_automation,
+            automations::remove_automation,
+            automations::set_automation_enabled,
+            hooks::set_instance_hooks,
+            java::list_java_runtimes,
+            java::install_java_runtime,
+            jvmflags::apply_jvm_preset,
+            memory::recommend_memory,
+            priority::set_process_settings,
+            cgroup::set_resource_limits,
+            crash::read_crash_report,
             instance::get_instance_info,
             instance::get_instance_metrics,
+            metrics::subscribe_instance_metrics,
+            metrics::unsubscribe_instance_metrics,
+            metrics::get_metrics_history,
+            disk::get_instance_disk_usage,
+            metrics::export_metrics_history,
             instance::get_playit_tunnels,
+            instance::create_playit_tunnel,
+            instance::rename_playit_tunnel,
+            instance::delete_playit_tunnel,
+            instance::set_tunnel_provider,
+            tailscale::get_tailscale_status,
+            tailscale::set_tailscale_funnel,
+            relay::set_custom_tunnel,
+            portmap::set_port_forwarding,
+            portmap::get_port_forward_status,
+            address::get_connection_address,
+            lan::set_lan_broadcast,
+            dns::publish_srv_record,
+            network::setup_proxy_network,
+            docker::set_instance_runtime,
+            relocate::relocate_data_dir,
             instance::send_instance_command,
+            instance::accept_eula,
+            world::get_world_info,
+            world::list_worlds,
+            world::set_active_world,
+            world::delete_world,
+            world::export_world,
+            world::trim_world,
+            resourcepack::host_resource_pack,
+            resourcepack::remove_resource_pack,
+            icon::set_instance_icon,
+            backup::create_backup,
+            backup::list_backups,
+            backup::delete_backup,
+            backup::restore_backup,
+            backup::set_backup_retention,
+            backup::set_incremental_backups,
+            backup::upload_backup,
+            backup::rollback_jar_change,
+            instance::replace_custom_jar,
+            jarinfo::detect_custom_jar,
+            jvm::capture_thread_dump,
+            jvm::capture_heap_dump,
+            watchdog::set_watchdog,
+            crash::set_auto_restart,
+            oom::apply_oom_suggestion,
+            playerstats::get_player_stats,
+            playerdata::get_player_data,
+            audit::get_audit_log,
+            timeline::get_instance_timeline,
+            uptime::get_uptime_stats,
+            announcer::set_announcements,
+            maintenance::set_maintenance_mode,
+            serverjar::update_server_jar,
+            upgrade::upgrade_instance_version,
+            upgrade::migrate_instance_software,
+            access::get_whitelist,
+            access::add_to_whitelist,
+            access::remove_from_whitelist,
+            access::get_ops,
+            access::add_op,
+            access::remove_op,
+            players::lookup_player,
+            players::get_player_head,
+            protocol::ping_instance,
+            rcon::rcon_execute,
+            config::set_backup_remote,
+            backup::get_backup_usage,
+            chunky::install_chunky,
+            chunky::start_pregeneration,
+            chunky::pause_pregeneration,
+            chunky::continue_pregeneration,
+            chunky::cancel_pregeneration,
+            chunky::get_pregeneration_progress,
+            instance::set_instance_tags,
+            instance::toggle_favorite,
+            instance::get_instance_notes,
+            instance::set_instance_notes,
+            bulk::start_instances,
+            bulk::stop_instances,
+            bulk::restart_instances,
+            bulk::set_instance_startup,
+            bulk::start_network,
+            bulk::set_instance_autostart,
+            ports::set_auto_assign_ports,
+            motd::parse_motd,
+            motd::build_motd,
+            motd::get_instance_motd,
+            motd::set_instance_motd,
+            templates::save_template,
+            templates::list_templates,
+            templates::delete_template,
+            templates::create_instance_from_template,
+            service::get_service_definition,
+            service::install_service,
+            service::uninstall_service,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app_handle, event| {
+            // In service mode closing the GUI shouldn't take the supervised servers down with it
+            if let RunEvent::ExitRequested {
+                code: None, api, ..
+            } = event
+            {
+                if service::is_headless() {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use tauri::Emitter;
+
+use crate::{
+    automations::{self, TriggerEvent, TriggerKind},
+    consolelog,
+    instance::get_instance_by_id,
+    models::{ChatMessage, OnlinePlayer},
+    notifications::{self, NotificationKind},
+    players,
+};
+
+#[derive(Default)]
+struct ServerState {
+    /// Online players by name, in join order
+    online: Vec<OnlinePlayer>,
+    /// UUIDs announced during login, waiting for the matching join line
+    pending: HashMap<String, String>,
+}
+
+fn get_states() -> &'static Mutex<HashMap<String, ServerState>> {
+    static STATES: OnceLock<Mutex<HashMap<String, ServerState>>> = OnceLock::new();
+    STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// What a console line says happened
+enum ConsoleEvent<'a> {
+    Uuid(&'a str, &'a str),
+    Joined(&'a str),
+    Left(&'a str),
+    Chat(&'a str, &'a str),
+    Ready,
+    Stopping,
+}
+
+fn valid_name(name: &str) -> Option<&str> {
+    players::is_valid_username(name).then_some(name)
+}
+
+fn parse(message: &str) -> Option<ConsoleEvent<'_>> {
+    if let Some(rest) = message.strip_prefix("UUID of player ") {
+        let (name, uuid) = rest.split_once(" is ")?;
+        return Some(ConsoleEvent::Uuid(valid_name(name)?, uuid.trim()));
+    }
+    if let Some(name) = message.strip_suffix(" joined the game") {
+        return Some(ConsoleEvent::Joined(valid_name(name)?));
+    }
+    if let Some(name) = message.strip_suffix(" left the game") {
+        return Some(ConsoleEvent::Left(valid_name(name)?));
+    }
+    if message.starts_with("Done (") {
+        return Some(ConsoleEvent::Ready);
+    }
+    if message.starts_with("Stopping server") || message.starts_with("Stopping the server") {
+        return Some(ConsoleEvent::Stopping);
+    }
+
+    // Paper prefixes unsigned chat with "[Not Secure] "
+    let chat = message.strip_prefix("[Not Secure] ").unwrap_or(message);
+    let (name, text) = chat.strip_prefix('<')?.split_once("> ")?;
+    Some(ConsoleEvent::Chat(valid_name(name)?, text))
+}
+
+/// Look for joins, leaves, chat and lifecycle lines in the console stream and emit them as
+/// `player-joined-{id}`, `player-left-{id}`, `player-chat-{id}`, `instance-ready-{id}` and
+/// `instance-stopping-{id}`. Every line is also handed to the instance's automations
+pub fn handle_log_line(app_handle: &tauri::AppHandle, id: &str, line: &str) {
+    let message = consolelog::log_message(line);
+    let trigger = |kind, player: Option<&str>, message: Option<&str>| TriggerEvent {
+        kind,
+        player: player.map(str::to_string),
+        message: message.map(str::to_string),
+        line: line.to_string(),
+    };
+    automations::dispatch(
+        app_handle,
+        id,
+        trigger(TriggerKind::LogLine, None, Some(message)),
+    );
+
+    let Some(event) = parse(message) else {
+        return;
+    };
+
+    let mut states = get_states().lock().unwrap();
+    let state = states.entry(id.to_string()).or_default();
+
+    match event {
+        ConsoleEvent::Uuid(name, uuid) => {
+            state.pending.insert(name.to_string(), uuid.to_string());
+        }
+        ConsoleEvent::Joined(name) => {
+            let player = OnlinePlayer {
+                name: name.to_string(),
+                uuid: state.pending.remove(name),
+                joined_at: chrono::Utc::now().to_rfc3339(),
+            };
+            state.online.retain(|online| online.name != name);
+            state.online.push(player.clone());
+            drop(states);
+
+            let instance = get_instance_by_id(app_handle, id)
+                .map(|config| config.name)
+                .unwrap_or_else(|_| id.to_string());
+            notifications::notify(
+                app_handle,
+                NotificationKind::PlayerJoined,
+                &format!("{} joined {}", name, instance),
+                &format!("{} is now playing on {}", name, instance),
+            );
+            let _ = app_handle.emit(&format!("player-joined-{}", id), player);
+            automations::dispatch(
+                app_handle,
+                id,
+                trigger(TriggerKind::PlayerJoined, Some(name), None),
+            );
+        }
+        ConsoleEvent::Left(name) => {
+            if let Some(index) = state.online.iter().position(|online| online.name == name) {
+                let player = state.online.remove(index);
+                let empty = state.online.is_empty();
+                drop(states);
+
+                let _ = app_handle.emit(&format!("player-left-{}", id), player);
+                automations::dispatch(
+                    app_handle,
+                    id,
+                    trigger(TriggerKind::PlayerLeft, Some(name), None),
+                );
+                if empty {
+                    automations::dispatch(
+                        app_handle,
+                        id,
+                        trigger(TriggerKind::ServerEmpty, Some(name), None),
+                    );
+                }
+            }
+        }
+        ConsoleEvent::Chat(name, message) => {
+            drop(states);
+            let _ = app_handle.emit(
+                &format!("player-chat-{}", id),
+                ChatMessage {
+                    name: name.to_string(),
+                    message: message.to_string(),
+                    time: chrono::Utc::now().to_rfc3339(),
+                },
+            );
+            automations::dispatch(
+                app_handle,
+                id,
+                trigger(TriggerKind::Chat, Some(name), Some(message)),
+            );
+        }
+        ConsoleEvent::Ready => {
+            drop(states);
+            let _ = app_handle.emit(&format!("instance-ready-{}", id), ());
+            automations::dispatch(
+                app_handle,
+                id,
+                trigger(TriggerKind::ServerReady, None, None),
+            );
+        }
+        ConsoleEvent::Stopping => {
+            drop(states);
+            let _ = app_handle.emit(&format!("instance-stopping-{}", id), ());
+            automations::dispatch(
+                app_handle,
+                id,
+                trigger(TriggerKind::ServerStopping, None, None),
+            );
+        }
+    }
+}
+
+/// Forget who was online once the server has exited, and call off pending automations
+pub fn clear(id: &str) {
+    get_states().lock().unwrap().remove(id);
+    automations::clear(id);
+}
+
+/// Players currently online according to the console, in join order
+pub fn online_players(id: &str) -> Vec<OnlinePlayer> {
+    get_states()
+        .lock()
+        .unwrap()
+        .get(id)
+        .map(|state| state.online.clone())
+        .unwrap_or_default()
+}
+
+/// Players currently online according to the console, without needing RCON or a ping
+#[tauri::command]
+pub async fn get_online_players(id: String) -> Result<Vec<OnlinePlayer>, String> {
+    Ok(online_players(&id))
+}
@@ -0,0 +1,149 @@
+use chrono::Utc;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::{
+    access::read_json_list,
+    filesystem,
+    instance::{
+        get_instance_by_id, has_instance_stdin, is_instance_running, log_line_count, logged_since,
+        update_instance_config, write_instance_stdin,
+    },
+    logevents,
+    models::{MaintenanceReport, MaintenanceState, OpEntry, WhitelistEntry},
+    motd,
+    properties::ServerProperties,
+};
+
+const DEFAULT_MESSAGE: &str = "Down for maintenance, back soon";
+/// How long to wait for the server to confirm a `whitelist` command
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Turn the whitelist on or off through the console and wait for the server to confirm.
+/// The server rewrites server.properties when it does, so nuko's own edits have to wait
+async fn toggle_whitelist(id: &str, on: bool) -> Result<(), String> {
+    let since = log_line_count(id);
+    write_instance_stdin(id, if on { "whitelist on" } else { "whitelist off" })?;
+
+    let deadline = Instant::now() + CONFIRM_TIMEOUT;
+    while Instant::now() < deadline {
+        if logged_since(id, since, "Whitelist is ") {
+            return Ok(());
+        }
+        sleep(Duration::from_millis(250)).await;
+    }
+    Err("The server didn't confirm the whitelist change".into())
+}
+
+/// Kick everyone online who isn't whitelisted or an operator, as the whitelist only keeps
+/// out new joins
+fn kick_outsiders(
+    id: &str,
+    instance_dir: &std::path::Path,
+    message: &str,
+) -> Result<Vec<String>, String> {
+    let whitelist: Vec<WhitelistEntry> = read_json_list(&instance_dir.join("whitelist.json"))?;
+    let ops: Vec<OpEntry> = read_json_list(&instance_dir.join("ops.json"))?;
+    let allowed = |name: &str| {
+        whitelist
+            .iter()
+            .map(|entry| &entry.name)
+            .chain(ops.iter().map(|entry| &entry.name))
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+    };
+
+    let mut kicked = Vec::new();
+    for player in logevents::online_players(id) {
+        if allowed(&player.name) {
+            continue;
+        }
+        write_instance_stdin(id, &format!("kick {} {}", player.name, message))?;
+        kicked.push(player.name);
+    }
+    Ok(kicked)
+}
+
+/// Put the instance into maintenance: the whitelist is enforced, players who aren't on it
+/// are kicked and the MOTD shows `message`. Turning it off restores the whitelist settings
+/// and MOTD from before. A running server picks up the MOTD on its next start
+#[tauri::command]
+pub async fn set_maintenance_mode(
+    app_handle: tauri::AppHandle,
+    id: String,
+    on: bool,
+    message: Option<String>,
+) -> Result<MaintenanceReport, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let attached = has_instance_stdin(&id);
+    // A server nuko isn't attached to would overwrite the properties when it saves
+    if !attached && is_instance_running(&instance_dir) {
+        return Err(format!(
+            "'{}' is running outside nuko; stop it before toggling maintenance mode",
+            config.name
+        ));
+    }
+    if on == config.maintenance.is_some() {
+        return Err(format!(
+            "'{}' is already {} maintenance mode",
+            config.name,
+            if on { "in" } else { "out of" }
+        ));
+    }
+
+    let mut kicked = Vec::new();
+    if on {
+        let message = message
+            .map(|message| message.trim().to_string())
+            .filter(|message| !message.is_empty())
+            .unwrap_or_else(|| DEFAULT_MESSAGE.to_string());
+        let motd = motd::parse(&message);
+        if motd.raw.contains('\n') {
+            return Err("The maintenance message must be a single line".into());
+        }
+
+        let properties = ServerProperties::load(&instance_dir)?;
+        let state = MaintenanceState {
+            since: Utc::now().to_rfc3339(),
+            whitelist: properties.get("white-list").map(str::to_string),
+            enforce_whitelist: properties.get("enforce-whitelist").map(str::to_string),
+            motd: properties.get("motd").map(str::to_string),
+        };
+
+        if attached {
+            toggle_whitelist(&id, true).await?;
+        }
+        let mut properties = ServerProperties::load(&instance_dir)?;
+        properties.set("white-list", "true");
+        properties.set("enforce-whitelist", "true");
+        properties.set("motd", motd.raw);
+        properties.save()?;
+
+        if attached {
+            kicked = kick_outsiders(&id, &instance_dir, &message)?;
+        }
+        config.maintenance = Some(state);
+    } else if let Some(state) = config.maintenance.take() {
+        let whitelist = state.whitelist.unwrap_or_else(|| "false".into());
+        if attached && whitelist != "true" {
+            toggle_whitelist(&id, false).await?;
+        }
+        let mut properties = ServerProperties::load(&instance_dir)?;
+        properties.set("white-list", whitelist);
+        properties.set(
+            "enforce-whitelist",
+            state.enforce_whitelist.unwrap_or_else(|| "false".into()),
+        );
+        properties.set(
+            "motd",
+            state.motd.unwrap_or_else(|| "A Minecraft Server".into()),
+        );
+        properties.save()?;
+    }
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(MaintenanceReport {
+        enabled: on,
+        kicked,
+        restart_required: attached,
+    })
+}
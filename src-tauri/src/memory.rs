@@ -0,0 +1,125 @@
+use tauri::Manager;
+
+use crate::{
+    filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, update_instance_config},
+    jvmflags::parse_memory_mb,
+    models::MemoryRecommendation,
+    properties::ServerProperties,
+};
+
+/// Left for the OS and everything else on the machine, at least
+const MIN_RESERVED_MB: u64 = 2048;
+const MIN_HEAP_MB: u64 = 1024;
+const MAX_HEAP_MB: u64 = 16 * 1024;
+
+/// Starting heap for a server type before player count is considered. Modded servers load
+/// far more classes and data
+fn base_heap_mb(software: &str) -> u64 {
+    match software {
+        "forge" | "neoforge" => 4096,
+        "fabric" => 3072,
+        _ => 2048,
+    }
+}
+
+/// Round down to a multiple of 512 MB
+fn round_heap(mb: u64) -> u64 {
+    mb / 512 * 512
+}
+
+pub fn format_memory(mb: u64) -> String {
+    if mb.is_multiple_of(1024) {
+        format!("{}G", mb / 1024)
+    } else {
+        format!("{}M", mb)
+    }
+}
+
+/// The machine's RAM and the sum of the other instances' max heaps, in MB
+pub fn ram_usage(app_handle: &tauri::AppHandle, id: &str) -> Result<(u64, u64), String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
+    let total_ram_mb = sys.total_memory() / (1024 * 1024);
+
+    let allocated_mb: u64 = app_handle
+        .state::<InstanceIndex>()
+        .all(&instances_dir)?
+        .iter()
+        .filter(|entry| entry.config.id != id)
+        .filter_map(|entry| parse_memory_mb(&entry.config.java.max_memory))
+        .sum();
+    Ok((total_ram_mb, allocated_mb))
+}
+
+/// RAM an instance's heap can use without starving the OS or the other instances
+pub fn available_heap_mb(total_ram_mb: u64, allocated_mb: u64) -> u64 {
+    let reserved = MIN_RESERVED_MB.max(total_ram_mb / 5);
+    total_ram_mb.saturating_sub(reserved + allocated_mb)
+}
+
+/// Recommend -Xms/-Xmx for an instance from its software, max-players and the RAM the
+/// machine has left after the other instances' heaps. -Xms is set to match -Xmx, as a
+/// growing heap only causes extra GC work on a server. With `apply`, the values are saved
+#[tauri::command]
+pub async fn recommend_memory(
+    app_handle: tauri::AppHandle,
+    id: String,
+    apply: Option<bool>,
+) -> Result<MemoryRecommendation, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let (total_ram_mb, allocated_mb) = ram_usage(&app_handle, &id)?;
+
+    let max_players: u64 = ServerProperties::load(&instance_dir)
+        .ok()
+        .and_then(|properties| properties.get("max-players")?.trim().parse().ok())
+        .unwrap_or(20);
+    // Roughly 64 MB more per player slot beyond a small friends server
+    let wanted = base_heap_mb(&config.software) + max_players.saturating_sub(10) * 64;
+
+    let available = available_heap_mb(total_ram_mb, allocated_mb);
+    let mut warnings = Vec::new();
+
+    let heap = if wanted > available {
+        warnings.push(format!(
+            "Only {} of {} are free after other instances and the OS, less than the {} this \
+             server would like",
+            format_memory(available),
+            format_memory(total_ram_mb),
+            format_memory(wanted)
+        ));
+        available
+    } else {
+        wanted
+    };
+    let heap = round_heap(heap.clamp(MIN_HEAP_MB, MAX_HEAP_MB));
+
+    if allocated_mb + heap > total_ram_mb {
+        warnings.push(format!(
+            "All instances together may use {} of heap, more than the {} of RAM this machine \
+             has. Don't run them all at once",
+            format_memory(allocated_mb + heap),
+            format_memory(total_ram_mb)
+        ));
+    }
+
+    let apply = apply.unwrap_or(false);
+    if apply {
+        config.java.min_memory = format_memory(heap);
+        config.java.max_memory = format_memory(heap);
+        update_instance_config(&app_handle, &config)?;
+    }
+
+    Ok(MemoryRecommendation {
+        min_memory: format_memory(heap),
+        max_memory: format_memory(heap),
+        total_ram_mb,
+        allocated_mb,
+        warnings,
+        applied: apply,
+    })
+}
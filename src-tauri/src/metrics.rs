@@ -0,0 +1,376 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use chrono::Utc;
+use tauri::{Emitter, Manager};
+
+use crate::{
+    disk, docker, filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, is_instance_server_process},
+    jvm,
+    models::{InstanceMetrics, MetricsSample},
+    performance, uptime,
+};
+
+/// How often the recorder samples every running instance
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+/// Samples older than this are dropped when the history file is compacted
+const HISTORY_RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+/// History files are compacted once they grow past this size
+const COMPACT_THRESHOLD: u64 = 4 * 1024 * 1024;
+/// Long ranges are averaged down to at most this many points
+const MAX_HISTORY_POINTS: usize = 720;
+/// Fastest rate a subscriber can ask for
+const MIN_PUSH_INTERVAL_MS: u64 = 500;
+
+fn get_system() -> &'static Mutex<sysinfo::System> {
+    static SYS: OnceLock<Mutex<sysinfo::System>> = OnceLock::new();
+    SYS.get_or_init(|| Mutex::new(sysinfo::System::new()))
+}
+
+/// CPU usage needs two refreshes a moment apart to have something to compare against
+fn refresh_processes(sys: &mut sysinfo::System) {
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::All,
+        true,
+        sysinfo::ProcessRefreshKind::everything(),
+    );
+    thread::sleep(Duration::from_millis(200));
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::All,
+        true,
+        sysinfo::ProcessRefreshKind::everything(),
+    );
+}
+
+#[derive(Default)]
+struct ProcessUsage {
+    cpu_usage: f32,
+    memory_usage: u64,
+    /// The server's JVM, for jstat/jcmd
+    java: Option<(u32, Option<PathBuf>)>,
+}
+
+/// Summed CPU and memory usage of the instance's processes, or None when it isn't running
+fn process_usage(sys: &sysinfo::System, instance_dir: &Path) -> Option<ProcessUsage> {
+    let mut usage = None;
+    for process in sys.processes().values() {
+        if is_instance_server_process(process, instance_dir) {
+            let usage = usage.get_or_insert_with(ProcessUsage::default);
+            usage.cpu_usage += process.cpu_usage();
+            usage.memory_usage += process.memory();
+            if usage.java.is_none() {
+                usage.java = Some((process.pid().as_u32(), process.exe().map(Path::to_path_buf)));
+            }
+        }
+    }
+    usage
+}
+
+fn build_metrics(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    software: &str,
+    mut usage: Option<ProcessUsage>,
+) -> InstanceMetrics {
+    // The local process is only the attached `docker run` client; the JVM is in the container
+    if let Some(usage) = usage.as_mut().filter(|_| docker::is_managed(id)) {
+        let (cpu_usage, memory_usage) = docker::stats(id).unwrap_or_default();
+        usage.cpu_usage = cpu_usage;
+        usage.memory_usage = memory_usage;
+        usage.java = None;
+    }
+    let ticks = if usage.is_some() {
+        performance::latest(app_handle, id, software)
+    } else {
+        performance::TickStats::default()
+    };
+    let usage = usage.unwrap_or_default();
+    let jvm = usage
+        .java
+        .and_then(|(pid, java_exe)| jvm::latest(id, pid, java_exe));
+
+    InstanceMetrics {
+        time: chrono::Local::now().format("%H:%M:%S").to_string(),
+        cpu_usage: usage.cpu_usage,
+        memory_usage: usage.memory_usage,
+        tps: ticks.tps,
+        mspt: ticks.mspt,
+        jvm,
+    }
+}
+
+/// Take a point-in-time sample of an instance's resource usage
+pub fn sample(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    software: &str,
+    instance_dir: &Path,
+) -> InstanceMetrics {
+    let usage = {
+        let mut sys = get_system().lock().unwrap();
+        refresh_processes(&mut sys);
+        process_usage(&sys, instance_dir)
+    };
+    build_metrics(app_handle, id, software, usage)
+}
+
+/// Active metrics subscriptions, keyed by instance id. Each subscription gets a new token so
+/// a replaced sampling thread notices and exits
+fn get_subscriptions() -> &'static Mutex<HashMap<String, u64>> {
+    static SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn is_subscribed(id: &str, token: u64) -> bool {
+    get_subscriptions().lock().unwrap().get(id) == Some(&token)
+}
+
+/// Start emitting `instance-metrics-{id}` every `interval` milliseconds. Subscribing again
+/// replaces the previous interval
+#[tauri::command]
+pub async fn subscribe_instance_metrics(
+    app_handle: tauri::AppHandle,
+    id: String,
+    interval: u64,
+) -> Result<(), String> {
+    static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let interval = Duration::from_millis(interval.max(MIN_PUSH_INTERVAL_MS));
+
+    let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+    get_subscriptions()
+        .lock()
+        .unwrap()
+        .insert(id.clone(), token);
+
+    thread::spawn(move || {
+        while is_subscribed(&id, token) {
+            let metrics = sample(&app_handle, &id, &config.software, &instance_dir);
+            let _ = app_handle.emit(&format!("instance-metrics-{}", id), metrics);
+            thread::sleep(interval);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unsubscribe_instance_metrics(id: String) -> Result<(), String> {
+    get_subscriptions().lock().unwrap().remove(&id);
+    Ok(())
+}
+
+fn history_path(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("metrics");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create metrics dir: {}", e))?;
+    Ok(dir.join(format!("{}.jsonl", id)))
+}
+
+fn read_history(path: &Path) -> Vec<MetricsSample> {
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// When the instance was last sampled running, from its metrics history
+pub fn last_sample_time(app_handle: &tauri::AppHandle, id: &str) -> Option<i64> {
+    let content = fs::read_to_string(history_path(app_handle, id).ok()?).ok()?;
+    let last = content.lines().last()?;
+    serde_json::from_str::<MetricsSample>(last)
+        .ok()
+        .map(|sample| sample.timestamp)
+}
+
+fn write_history(path: &Path, samples: &[MetricsSample]) -> Result<(), String> {
+    let mut content = String::new();
+    for sample in samples {
+        let line = serde_json::to_string(sample)
+            .map_err(|e| format!("Failed to serialize metrics sample: {}", e))?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write metrics history: {}", e))
+}
+
+fn append_sample(path: &Path, sample: &MetricsSample) -> Result<(), String> {
+    let line = serde_json::to_string(sample)
+        .map_err(|e| format!("Failed to serialize metrics sample: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open metrics history: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write metrics history: {}", e))?;
+
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if size > COMPACT_THRESHOLD {
+        let cutoff = Utc::now().timestamp() - HISTORY_RETENTION_SECS;
+        let kept: Vec<MetricsSample> = read_history(path)
+            .into_iter()
+            .filter(|sample| sample.timestamp >= cutoff)
+            .collect();
+        write_history(path, &kept)?;
+    }
+    Ok(())
+}
+
+fn record_running_instances(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    let entries = app_handle.state::<InstanceIndex>().all(&instances_dir)?;
+
+    let usages: Vec<_> = {
+        let mut sys = get_system().lock().unwrap();
+        refresh_processes(&mut sys);
+        entries
+            .into_iter()
+            .filter_map(|entry| process_usage(&sys, &entry.dir).map(|usage| (entry.config, usage)))
+            .collect()
+    };
+
+    for (config, usage) in usages {
+        let metrics = build_metrics(app_handle, &config.id, &config.software, Some(usage));
+        let sample = MetricsSample {
+            timestamp: Utc::now().timestamp(),
+            cpu_usage: metrics.cpu_usage,
+            memory_usage: metrics.memory_usage,
+            tps: metrics.tps,
+            mspt: metrics.mspt,
+        };
+        append_sample(&history_path(app_handle, &config.id)?, &sample)?;
+    }
+    Ok(())
+}
+
+/// Sample every running instance in the background so history survives closed windows, and
+/// keep an eye on instance statuses and free disk space while at it
+pub fn start_recorder(app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        if let Err(e) = record_running_instances(&app_handle) {
+            println!("Failed to record metrics: {}", e);
+        }
+        if let Err(e) = uptime::observe(&app_handle) {
+            println!("Failed to record instance statuses: {}", e);
+        }
+        if let Err(e) = disk::check_free_space(&app_handle) {
+            println!("Failed to check free disk space: {}", e);
+        }
+        thread::sleep(SAMPLE_INTERVAL);
+    });
+}
+
+/// Parse a range like "30m", "6h" or "7d" into seconds
+pub fn parse_range(range: &str) -> Result<i64, String> {
+    let range = range.trim();
+    let invalid = || format!("Invalid metrics range '{}'", range);
+    let (amount, unit_secs) = [("m", 60), ("h", 60 * 60), ("d", 24 * 60 * 60)]
+        .into_iter()
+        .find_map(|(unit, secs)| Some((range.strip_suffix(unit)?, secs)))
+        .ok_or_else(invalid)?;
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    amount.max(1).checked_mul(unit_secs).ok_or_else(invalid)
+}
+
+fn history_in_range(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    range: &str,
+) -> Result<Vec<MetricsSample>, String> {
+    get_instance_by_id(app_handle, id)?;
+    let cutoff = Utc::now().timestamp() - parse_range(range)?;
+    Ok(read_history(&history_path(app_handle, id)?)
+        .into_iter()
+        .filter(|sample| sample.timestamp >= cutoff)
+        .collect())
+}
+
+fn average(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    let values: Vec<f64> = values.flatten().collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Average consecutive samples so a multi-day range still charts smoothly
+fn downsample(samples: Vec<MetricsSample>) -> Vec<MetricsSample> {
+    if samples.len() <= MAX_HISTORY_POINTS {
+        return samples;
+    }
+
+    let bucket = samples.len().div_ceil(MAX_HISTORY_POINTS);
+    samples
+        .chunks(bucket)
+        .map(|chunk| {
+            let count = chunk.len();
+            MetricsSample {
+                timestamp: chunk[count / 2].timestamp,
+                cpu_usage: chunk.iter().map(|s| s.cpu_usage).sum::<f32>() / count as f32,
+                memory_usage: chunk.iter().map(|s| s.memory_usage).sum::<u64>() / count as u64,
+                tps: average(chunk.iter().map(|s| s.tps)),
+                mspt: average(chunk.iter().map(|s| s.mspt)),
+            }
+        })
+        .collect()
+}
+
+/// Recorded metrics for the given range, e.g. "1h", "24h" or "7d"
+#[tauri::command]
+pub async fn get_metrics_history(
+    app_handle: tauri::AppHandle,
+    id: String,
+    range: String,
+) -> Result<Vec<MetricsSample>, String> {
+    Ok(downsample(history_in_range(&app_handle, &id, &range)?))
+}
+
+/// Write the raw recorded metrics for the range to `dest` as "csv" or "json"
+#[tauri::command]
+pub async fn export_metrics_history(
+    app_handle: tauri::AppHandle,
+    id: String,
+    range: String,
+    format: String,
+    dest: String,
+) -> Result<(), String> {
+    let samples = history_in_range(&app_handle, &id, &range)?;
+
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&samples)
+            .map_err(|e| format!("Failed to serialize metrics: {}", e))?,
+        "csv" => {
+            let mut csv = String::from("timestamp,cpu_usage,memory_usage,tps,mspt\n");
+            for sample in &samples {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    chrono::DateTime::from_timestamp(sample.timestamp, 0)
+                        .map(|time| time.to_rfc3339())
+                        .unwrap_or_default(),
+                    sample.cpu_usage,
+                    sample.memory_usage,
+                    sample.tps.map(|tps| tps.to_string()).unwrap_or_default(),
+                    sample.mspt.map(|mspt| mspt.to_string()).unwrap_or_default(),
+                ));
+            }
+            csv
+        }
+        _ => return Err(format!("Unknown export format '{}'", format)),
+    };
+
+    fs::write(&dest, content).map_err(|e| format!("Failed to write {}: {}", dest, e))
+}
@@ -0,0 +1,110 @@
+use std::{fs, path::Path};
+
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+
+/// Current format of nuko.toml. Bump it and append to `INSTANCE_MIGRATIONS` when the format
+/// changes in a way `#[serde(default)]` can't cover
+pub const INSTANCE_CONFIG_VERSION: u32 = 1;
+/// Current format of config.toml, with migrations in `GLOBAL_MIGRATIONS`
+pub const GLOBAL_CONFIG_VERSION: u32 = 1;
+
+const VERSION_KEY: &str = "config_version";
+
+/// Rewrites a raw config from one version to the next
+type Migration = fn(&mut Table) -> Result<(), String>;
+
+/// Entry `n` upgrades an instance config from version `n` to `n + 1`
+const INSTANCE_MIGRATIONS: &[Migration] = &[unversioned];
+/// Entry `n` upgrades config.toml from version `n` to `n + 1`
+const GLOBAL_MIGRATIONS: &[Migration] = &[unversioned];
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConfigFile {
+    Instance,
+    Global,
+}
+
+impl ConfigFile {
+    fn current_version(self) -> u32 {
+        match self {
+            ConfigFile::Instance => INSTANCE_CONFIG_VERSION,
+            ConfigFile::Global => GLOBAL_CONFIG_VERSION,
+        }
+    }
+
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            ConfigFile::Instance => INSTANCE_MIGRATIONS,
+            ConfigFile::Global => GLOBAL_MIGRATIONS,
+        }
+    }
+}
+
+/// Version 0 is every file written before configs were versioned. Its layout is the same as
+/// version 1, so there is nothing to rewrite
+fn unversioned(_: &mut Table) -> Result<(), String> {
+    Ok(())
+}
+
+/// Run every migration from the file's version up to the current one. Returns whether
+/// anything was upgraded, and refuses files from a newer nuko rather than dropping their
+/// unknown settings
+fn upgrade(table: &mut Table, file: ConfigFile) -> Result<Option<u32>, String> {
+    let version = match table.get(VERSION_KEY) {
+        None => 0,
+        Some(value) => value
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("Invalid {} {}", VERSION_KEY, value))?,
+    };
+    let current = file.current_version();
+    if version > current {
+        return Err(format!(
+            "It was written by a newer version of nuko (format {}, this version reads up to {})",
+            version, current
+        ));
+    }
+    if version == current {
+        return Ok(None);
+    }
+
+    for (from, migration) in file.migrations().iter().enumerate().skip(version as usize) {
+        migration(table).map_err(|e| format!("Upgrading from format {} failed: {}", from, e))?;
+    }
+    table.insert(VERSION_KEY.into(), Value::Integer(current.into()));
+    Ok(Some(version))
+}
+
+/// Parse a config file, upgrading it to the current format first. When it was upgraded the
+/// original is kept next to it as `<name>.v<old version>.bak` and the new format written back
+pub fn load<T: DeserializeOwned>(path: &Path, file: ConfigFile) -> Result<T, String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let mut table: Table =
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", name, e))?;
+
+    let upgraded_from =
+        upgrade(&mut table, file).map_err(|e| format!("Failed to load {}: {}", name, e))?;
+    let config: T = table
+        .clone()
+        .try_into()
+        .map_err(|e| format!("Failed to parse {}: {}", name, e))?;
+
+    if let Some(old_version) = upgraded_from {
+        let backup = path.with_file_name(format!("{}.v{}.bak", name, old_version));
+        if !backup.exists() {
+            fs::write(&backup, &content)
+                .map_err(|e| format!("Failed to back up {}: {}", name, e))?;
+        }
+        let upgraded = toml::to_string_pretty(&table)
+            .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+        fs::write(path, upgraded).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+
+    Ok(config)
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 // ============ Versions ============
@@ -33,36 +35,432 @@ pub struct FabricLoaderVersion {
 #[derive(Deserialize)]
 pub struct FabricLoader {
     pub version: String,
+    #[serde(default)]
+    pub stable: bool,
+}
+
+#[derive(Deserialize)]
+pub struct FabricInstallerVersion {
+    pub version: String,
+    #[serde(default)]
+    pub stable: bool,
 }
 
 // ============ Instances ============
 
 #[derive(Debug)]
 pub struct Instance {
+    pub id: String,
     pub name: String,
     pub software: String,
     pub version: String,
     pub playit: bool,
     pub loader: Option<String>,
+    /// Fabric installer the server jar is built with, the newest stable one when unset
+    pub installer: Option<String>,
     pub custom_jar_path: Option<String>,
+    pub properties: Option<InitialServerProperties>,
+    pub eula: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// server.properties values that can be chosen when creating an instance
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InitialServerProperties {
+    pub port: Option<u16>,
+    pub max_players: Option<u32>,
+    pub motd: Option<String>,
+    pub seed: Option<String>,
+    pub gamemode: Option<String>,
+    pub difficulty: Option<String>,
+    pub online_mode: Option<bool>,
+    /// Turn on RCON with a generated password and a free port
+    pub enable_rcon: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstanceConfig {
+    /// Format version of this nuko.toml, see `migrate`
+    #[serde(default)]
+    pub config_version: u32,
     pub id: String,
     pub name: String,
     pub software: String,
     pub version: String,
     pub loader: Option<String>,
+    /// Pinned Fabric installer version, the newest stable one when unset
+    #[serde(default)]
+    pub installer: Option<String>,
+    /// Paper or Purpur build of server.jar, recorded by `update_server_jar`
+    #[serde(default)]
+    pub build: Option<String>,
+    /// Set for custom jars once they have been inspected
+    #[serde(default)]
+    pub detected: Option<DetectedJar>,
     #[serde(default)]
     pub playit: bool,
+    /// Reference into the credential store (see `secrets::store_credential`)
     #[serde(default)]
     pub playit_secret: Option<String>,
+    /// Expose the server through an ngrok TCP tunnel instead of playit
+    #[serde(default)]
+    pub ngrok: bool,
+    #[serde(default)]
+    pub tailscale: TailscaleConfig,
+    /// A bore or frp client connecting to the user's own relay
+    #[serde(default)]
+    pub custom_tunnel: Option<CustomTunnelConfig>,
+    /// Ask the router to forward the server port over UPnP or NAT-PMP while running
+    #[serde(default)]
+    pub upnp: bool,
+    /// Announce the server in the LAN Worlds list of clients on the local network
+    #[serde(default)]
+    pub lan_broadcast: bool,
+    /// Domain whose SRV record was last pointed at this instance
+    #[serde(default)]
+    pub domain: Option<String>,
     pub custom_jar_path: Option<String>,
     #[serde(default)]
     pub java: JavaConfig,
     #[serde(default)]
     pub metadata: MetadataConfig,
+    #[serde(default)]
+    pub startup: StartupConfig,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub notes: String,
+    /// Move conflicting ports to the next free one on start instead of failing
+    #[serde(default)]
+    pub auto_assign_ports: bool,
+    #[serde(default)]
+    pub resource_pack: Option<ResourcePackConfig>,
+    #[serde(default)]
+    pub backup_retention: RetentionPolicy,
+    /// Store only changed files in a deduplicated object store instead of full zips
+    #[serde(default)]
+    pub incremental_backups: bool,
+    #[serde(default)]
+    pub rcon: Option<RconCredentials>,
+    #[serde(default)]
+    pub tasks: Vec<ScheduledTask>,
+    #[serde(default)]
+    pub automations: Vec<AutomationRule>,
+    #[serde(default)]
+    pub announcements: Vec<Announcement>,
+    /// Set while maintenance mode is on
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceState>,
+    #[serde(default)]
+    pub hooks: HookConfig,
+    #[serde(default)]
+    pub process: ProcessConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub auto_restart: AutoRestartConfig,
+    /// Auto-restart gave up on the instance; cleared when it's started again
+    #[serde(default)]
+    pub crash_loop: Option<CrashLoop>,
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+}
+
+impl InstanceConfig {
+    /// The software to treat the instance as, which for a custom jar is what it was
+    /// detected to be
+    pub fn effective_software(&self) -> &str {
+        match &self.detected {
+            Some(detected) if self.software == "custom" => &detected.software,
+            _ => &self.software,
+        }
+    }
+
+    /// The Minecraft version the instance runs, preferring the one detected in a custom jar
+    pub fn effective_version(&self) -> &str {
+        match self.detected.as_ref().and_then(|d| d.version.as_deref()) {
+            Some(version) if self.software == "custom" => version,
+            _ => &self.version,
+        }
+    }
+}
+
+/// What a custom server.jar turned out to be, see `jarinfo::detect`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedJar {
+    /// Named like `InstanceConfig.software`, plus `spigot`, `velocity` and `bungeecord`
+    pub software: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeKind {
+    /// A Java process on this machine
+    #[default]
+    Native,
+    /// A container with the instance directory mounted at /data
+    Docker,
+}
+
+/// Where the server process runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    #[serde(default)]
+    pub kind: RuntimeKind,
+    /// Image to run the server in. Defaults to eclipse-temurin's JRE for `java_major`
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Java version for the default image, picked from the Minecraft version when unset
+    #[serde(default)]
+    pub java_major: Option<u32>,
+}
+
+/// OS scheduling for the server process, applied after it's spawned
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessConfig {
+    /// Unix niceness from -20 (highest) to 19 (lowest). Mapped to a priority class on
+    /// Windows. Raising priority above normal usually needs admin rights
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// CPU cores the server may run on; empty means all
+    #[serde(default)]
+    pub cpu_affinity: Vec<usize>,
+}
+
+/// Restart the server after it crashes. More than `max_crashes` crashes within
+/// `window_minutes` is a crash loop, which stops the restarts until the next start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoRestartConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_restart_delay")]
+    pub delay_secs: u64,
+    #[serde(default = "default_max_crashes")]
+    pub max_crashes: u32,
+    #[serde(default = "default_crash_window")]
+    pub window_minutes: u64,
+}
+
+impl Default for AutoRestartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_secs: default_restart_delay(),
+            max_crashes: default_max_crashes(),
+            window_minutes: default_crash_window(),
+        }
+    }
+}
+
+fn default_restart_delay() -> u64 {
+    10
+}
+
+fn default_max_crashes() -> u32 {
+    3
+}
+
+fn default_crash_window() -> u64 {
+    10
+}
+
+/// Set on an instance that kept crashing after being restarted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashLoop {
+    pub since: String,
+    pub crashes: u32,
+}
+
+/// Hang detection: a server that prints nothing for `silent_minutes` and then fails
+/// `failed_pings` Server List Pings in a row while its process is alive is considered hung.
+/// The steps run in order; restart only happens once the server is down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_silent_minutes")]
+    pub silent_minutes: u64,
+    #[serde(default = "default_failed_pings")]
+    pub failed_pings: u32,
+    #[serde(default = "default_true")]
+    pub thread_dump: bool,
+    #[serde(default = "default_true")]
+    pub stop: bool,
+    #[serde(default = "default_true")]
+    pub kill: bool,
+    #[serde(default)]
+    pub restart: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            silent_minutes: default_silent_minutes(),
+            failed_pings: default_failed_pings(),
+            thread_dump: true,
+            stop: true,
+            kill: true,
+            restart: false,
+        }
+    }
+}
+
+fn default_silent_minutes() -> u64 {
+    5
+}
+
+fn default_failed_pings() -> u32 {
+    3
+}
+
+/// Payload of the `instance-hung` event
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceHung {
+    pub id: String,
+    pub silent_secs: u64,
+    pub failed_pings: u32,
+}
+
+/// Shell commands run around the server's lifecycle, in the instance directory. Their
+/// output goes to the console
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// A failing pre_start hook keeps the server from starting
+    #[serde(default)]
+    pub pre_start: Option<String>,
+    #[serde(default)]
+    pub post_start: Option<String>,
+    #[serde(default)]
+    pub pre_stop: Option<String>,
+    #[serde(default)]
+    pub post_stop: Option<String>,
+}
+
+/// A task run on a cron schedule, in the machine's local time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    pub id: String,
+    pub name: String,
+    /// Standard five-field cron expression, e.g. `0 4 * * *` for 04:00 every day
+    pub cron: String,
+    pub action: TaskAction,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_run: Option<String>,
+    /// `None` when the last run succeeded
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskAction {
+    Command { command: String },
+    Restart,
+    Backup,
+    Start,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementFormat {
+    /// Plain text sent with `say`, shown with the `[Server]` prefix
+    #[default]
+    Say,
+    /// A JSON text component sent with `tellraw @a`, for colors and links
+    Tellraw,
+}
+
+/// A message broadcast to every player each `interval_minutes` while the server is online
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    #[serde(default)]
+    pub id: String,
+    pub message: String,
+    pub interval_minutes: u64,
+    #[serde(default)]
+    pub format: AnnouncementFormat,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// Runs `action` whenever `trigger` happens in the console. With a delay the action becomes
+/// pending and can be called off by a `cancel_pending` action before it runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationRule {
+    pub id: String,
+    pub name: String,
+    pub trigger: AutomationTrigger,
+    pub action: AutomationAction,
+    #[serde(default)]
+    pub delay_secs: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationTrigger {
+    PlayerJoined,
+    PlayerLeft,
+    /// The last online player left
+    ServerEmpty,
+    ServerReady,
+    ServerStopping,
+    /// A chat message, optionally only ones matching a regex
+    Chat {
+        pattern: Option<String>,
+    },
+    /// Any console line matching a regex
+    LogMatch {
+        pattern: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationAction {
+    /// `{player}`, `{message}` and `{line}` are replaced with details of the trigger
+    Command {
+        command: String,
+    },
+    /// POSTs the trigger details as JSON
+    Webhook {
+        url: String,
+    },
+    Stop,
+    Restart,
+    /// Call off every delayed action still waiting to run on this instance
+    CancelPending,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskReport {
+    #[serde(flatten)]
+    pub task: ScheduledTask,
+    pub next_run: Option<String>,
+}
+
+/// RCON credentials nuko generated for the instance. The password lives in the credential
+/// store (older instances have it sealed with the machine-local secret key)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RconCredentials {
+    pub port: u16,
+    pub password: String,
+}
+
+/// A resource pack served to players by nuko's built-in file server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcePackConfig {
+    pub public_host: String,
+    pub port: u16,
+    pub sha1: String,
+    pub url: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -73,6 +471,28 @@ pub struct InstanceInfo {
     pub version: String,
     pub running: bool,
     pub playit: bool,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub loader: Option<String>,
+    pub created_at: String,
+    pub last_played: Option<String>,
+    /// server-icon.png as a `data:image/png;base64,...` URL
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkActionResult {
+    pub id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupProgress {
+    pub id: String,
+    pub name: String,
+    pub stage: String,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -80,18 +500,214 @@ pub struct InstanceMetrics {
     pub time: String,
     pub cpu_usage: f32,
     pub memory_usage: u64,
+    /// Ticks per second, on Paper-family servers or with spark installed
+    pub tps: Option<f64>,
+    /// Average milliseconds per tick
+    pub mspt: Option<f64>,
+    /// Heap and GC figures from the JVM itself; RSS overstates usage since -Xmx is reserved
+    pub jvm: Option<JvmStats>,
+}
+
+/// JVM internals collected with jstat/jcmd, which need a JDK
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmStats {
+    pub heap_used: u64,
+    pub heap_committed: u64,
+    pub young_gc_count: u64,
+    /// Full and concurrent collections
+    pub full_gc_count: u64,
+    /// Total time spent in GC since the JVM started
+    pub gc_time_ms: u64,
+    pub thread_count: Option<u32>,
+}
+
+/// Disk used by an instance, including its backups which live outside the instance folder
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    pub worlds_bytes: u64,
+    pub plugins_bytes: u64,
+    pub mods_bytes: u64,
+    pub logs_bytes: u64,
+    pub backups_bytes: u64,
+    pub other_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskSpaceWarning {
+    pub path: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// A slice of an instance's console. `start` is the line number of the first line and
+/// `total` the number of lines logged since the server started
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct LogPage {
+    pub lines: Vec<String>,
+    pub start: usize,
+    pub total: usize,
+}
+
+/// A console line as emitted on `instance-log-{id}`. `level` is None for lines without a
+/// log prefix, such as stack trace continuations
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEvent {
+    /// The line with ANSI and `§` codes stripped
+    pub line: String,
+    pub level: Option<String>,
+    /// The line split into styled spans for rendering colors
+    pub segments: Vec<MotdSegment>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogSearchOptions {
+    /// Treat the query as a regular expression instead of a plain substring
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only match lines at this level, e.g. "ERROR"
+    pub level: Option<String>,
+    /// Also search persisted console logs and the server's logs/ directory
+    #[serde(default)]
+    pub include_files: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogMatch {
+    /// "console" for the in-memory buffer, otherwise the file the line came from
+    pub source: String,
+    pub line_number: usize,
+    pub line: String,
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReportInfo {
+    pub name: String,
+    /// "crash-report" for Minecraft's own reports, "hs_err" for fatal JVM errors
+    pub kind: String,
+    pub size_bytes: u64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    #[serde(flatten)]
+    pub info: CrashReportInfo,
+    pub content: String,
+    pub truncated: bool,
+}
+
+/// Emitted as `instance-crashed` when a server exits unexpectedly
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashEvent {
+    pub instance_id: String,
+    pub exit_code: Option<i32>,
+    pub report: Option<CrashReport>,
+}
+
+/// Emitted as `instance-crash-loop` when auto-restart gives up on a server
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashLoopEvent {
+    pub instance_id: String,
+    pub crashes: u32,
+    pub window_minutes: u64,
+    pub report: Option<CrashReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OomKind {
+    /// `OutOfMemoryError: Java heap space` or `GC overhead limit exceeded`
+    Heap,
+    Metaspace,
+    /// The JVM couldn't allocate memory outside the heap, e.g. for threads
+    NativeMemory,
+    /// SIGKILLed without being asked to, usually by the OOM killer or the memory limit
+    Killed,
+}
+
+/// Emitted as `instance-oom` when a server exits after running out of memory
+#[derive(Debug, Clone, Serialize)]
+pub struct OomEvent {
+    pub instance_id: String,
+    pub kind: OomKind,
+    pub current_max_memory: String,
+    pub current_memory_limit: Option<String>,
+    pub suggested_max_memory: Option<String>,
+    pub suggested_memory_limit: Option<String>,
+    pub total_ram_mb: u64,
+    pub message: String,
+}
+
+/// A player seen joining in the console. `uuid` is known when the login line was logged
+#[derive(Debug, Clone, Serialize)]
+pub struct OnlinePlayer {
+    pub name: String,
+    pub uuid: Option<String>,
+    pub joined_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub name: String,
+    pub message: String,
+    pub time: String,
+}
+
+/// A file in the server's own logs/ directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerLogFile {
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+}
+
+/// A recorded metrics sample, stored one per line in data_dir/metrics/<id>.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub timestamp: i64,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+    pub tps: Option<f64>,
+    pub mspt: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct JavaConfig {
     pub min_memory: String,
     pub max_memory: String,
     pub java_path: Option<String>,
     #[serde(default)]
     pub additional_args: Vec<String>,
+    /// Hard memory cap for the whole process (heap plus native memory), e.g. `6G`. Linux only
+    #[serde(default)]
+    pub memory_limit: Option<String>,
+    /// CPU time cap in cores, e.g. 2.5. Linux only
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StartupConfig {
+    /// Instances that must be online before this one is started by `start_network`
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Instances that wait for this one to be online, e.g. backends started after their proxy
+    #[serde(default)]
+    pub start_before: Vec<String>,
+    /// Extra delay after dependencies are online before starting this instance
+    #[serde(default)]
+    pub delay_seconds: u64,
+    /// Start this instance automatically when nuko launches
+    #[serde(default)]
+    pub autostart: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MetadataConfig {
     pub created_at: String,
     pub last_played: Option<String>,
@@ -101,7 +717,7 @@ pub struct MetadataConfig {
     pub playit: PlayitMetadata,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PlayitMetadata {
     #[serde(default)]
     pub tunnels: Vec<PlayitTunnelMetadata>,
@@ -120,64 +736,1071 @@ pub struct PlayitTunnelMetadata {
     pub last_heartbeat: Option<String>,
 }
 
-// ============ Download (Vanilla) ============
+/// Reach the server over the machine's tailnet, optionally made public with Funnel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TailscaleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Public TCP port Funnel listens on (443, 8443 or 10000). Funnel is off when unset
+    #[serde(default)]
+    pub funnel_port: Option<u16>,
+}
 
-#[derive(Deserialize)]
-pub struct VersionManifest {
-    pub versions: Vec<VersionEntry>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomTunnelKind {
+    Bore,
+    Frp,
 }
 
-#[derive(Deserialize)]
-pub struct VersionEntry {
-    pub id: String,
-    pub url: String,
+/// A self-hosted relay the server is exposed through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTunnelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub kind: CustomTunnelKind,
+    pub relay_host: String,
+    /// frp's control port, 7000 when unset. bore always connects on 7835
+    #[serde(default)]
+    pub relay_port: Option<u16>,
+    /// Public port to ask the relay for. bore picks a random one when unset; frp requires it
+    #[serde(default)]
+    pub remote_port: Option<u16>,
+    /// Shared secret or token, as a reference into the credential store
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Client binary to run instead of `bore`/`frpc` from PATH
+    #[serde(default)]
+    pub client_path: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct VersionDetails {
-    pub downloads: VersionDownloads,
+/// The local tailscaled, as reported by `tailscale status`
+#[derive(Debug, Clone, Serialize)]
+pub struct TailscaleStatus {
+    pub installed: bool,
+    pub running: bool,
+    /// MagicDNS name of this machine, without the trailing dot
+    pub dns_name: Option<String>,
+    pub ips: Vec<String>,
 }
 
-#[derive(Deserialize)]
-pub struct VersionDownloads {
-    pub server: DownloadItem,
+/// Outcome of the router port mapping requested when the instance started
+#[derive(Debug, Clone, Serialize)]
+pub struct PortForwardStatus {
+    pub active: bool,
+    /// `upnp` or `natpmp`, whichever the router answered
+    pub method: Option<String>,
+    pub external_ip: Option<String>,
+    pub external_port: Option<u16>,
+    pub internal_port: u16,
+    pub error: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct DownloadItem {
-    pub url: String,
-    pub sha1: String,
+/// Where players should connect to reach the instance, and whether that worked
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionAddress {
+    /// Ready to paste into the multiplayer screen; the port is left off when it's 25565
+    pub address: String,
+    pub host: String,
+    pub port: u16,
+    /// `tunnel`, `port_forward` or `direct`
+    pub source: String,
+    pub public_ip: Option<String>,
+    pub reachable: bool,
+    /// `external` when a public status checker tried the address, `local` when only this
+    /// machine could test it
+    pub checked_from: Option<String>,
+    /// Why the server couldn't be reached, when it couldn't
+    pub detail: Option<String>,
 }
 
-// ============ Download (Paper) ============
+/// DNS records `publish_srv_record` created or updated
+#[derive(Debug, Clone, Serialize)]
+pub struct SrvRecordResult {
+    /// What players type to join
+    pub domain: String,
+    pub srv_name: String,
+    pub target: String,
+    pub port: u16,
+    /// The A/AAAA record added when the target was a bare IP, e.g. `A 203.0.113.7`
+    pub address_record: Option<String>,
+}
 
-#[derive(Deserialize)]
-pub struct PaperBuilds {
-    pub builds: Vec<PaperBuild>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    Velocity,
+    Bungeecord,
 }
 
-#[derive(Deserialize)]
-#[serde(transparent)]
-pub struct PaperBuild {
-    pub build: u32,
+/// A backend as `setup_proxy_network` registered it with the proxy
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyBackend {
+    pub id: String,
+    pub name: String,
+    /// Name in the proxy's server list, as used by `/server`
+    pub server_name: String,
+    pub port: u16,
 }
 
-#[derive(Deserialize)]
-pub struct PaperDownload {
-    pub downloads: PaperArtifacts,
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyNetworkSetup {
+    pub kind: ProxyKind,
+    /// Port players connect to, on the proxy
+    pub proxy_port: u16,
+    /// In join order; players land on the first one
+    pub backends: Vec<ProxyBackend>,
 }
 
-#[derive(Deserialize)]
-pub struct PaperArtifacts {
-    pub application: PaperFile,
+/// What `setup_crossplay` installed and configured
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossplaySetup {
+    pub geyser_file: String,
+    pub floodgate_file: String,
+    /// UDP port Bedrock players connect to
+    pub bedrock_port: u16,
+    /// Id of the playit Bedrock tunnel, when one was requested
+    pub tunnel_id: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct PaperFile {
+// ============ MOTD ============
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MotdSegment {
+    pub text: String,
+    /// Legacy color name, e.g. "gold" or "dark_aqua"
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Hex value of `color` for rendering previews
+    #[serde(default)]
+    pub hex: Option<String>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub underlined: bool,
+    #[serde(default)]
+    pub strikethrough: bool,
+    #[serde(default)]
+    pub obfuscated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MotdPreview {
+    pub raw: String,
+    pub lines: Vec<Vec<MotdSegment>>,
+}
+
+/// The server.properties values maintenance mode replaced, so turning it off can put them
+/// back. `None` means the key wasn't set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    pub since: String,
+    pub whitelist: Option<String>,
+    pub enforce_whitelist: Option<String>,
+    pub motd: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    pub enabled: bool,
+    /// Players kicked for not being whitelisted
+    pub kicked: Vec<String>,
+    /// The server is running, so the MOTD changes on its next start
+    pub restart_required: bool,
+}
+
+/// A state-changing action recorded in an instance's audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: String,
+    /// "user", or what acted on its own: "scheduler", "automation", "watchdog", ...
+    pub actor: String,
+    /// e.g. "start", "command", "config", "backup"
+    pub action: String,
+    pub params: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceStatus {
+    Stopped,
+    /// The process is running but the server hasn't finished starting
+    Starting,
+    Online,
+    /// Considered hung by the watchdog
+    Unresponsive,
+    /// Exited on its own, until it's started again
+    Crashed,
+}
+
+/// An instance entering a status, persisted for uptime tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub timestamp: i64,
+    pub status: InstanceStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UptimeStats {
+    pub status: InstanceStatus,
+    /// Time online since tracking began
+    pub total_uptime_secs: u64,
+    pub longest_streak_secs: u64,
+    /// How long the server has been online without interruption, 0 when it isn't
+    pub current_streak_secs: u64,
+    pub range_uptime_secs: u64,
+    /// Time in the range the server was meant to be up but wasn't online
+    pub range_downtime_secs: u64,
+    /// None when the server wasn't meant to be up at all in the range
+    pub availability_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    Started,
+    Stopped,
+    Crashed,
+    Backup,
+    Restored,
+    VersionChanged,
+}
+
+/// Something that happened to an instance, kept in its timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub time: String,
+    pub kind: TimelineKind,
+    /// e.g. the new version, or the backup's note
+    #[serde(default)]
+    pub detail: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// How long the server ran, on stops and crashes
+    #[serde(default)]
+    pub uptime_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceTimeline {
+    /// Newest first
+    pub events: Vec<TimelineEvent>,
+    pub total_uptime_secs: u64,
+    pub longest_run_secs: u64,
+    /// Set while the server is running
+    pub current_run_secs: Option<u64>,
+    pub crashes: u32,
+    pub last_crash: Option<String>,
+    /// Runs that ended in a clean stop since the last crash
+    pub clean_runs_since_crash: u32,
+}
+
+// ============ Worlds ============
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldInfo {
+    pub level_name: String,
+    pub seed: Option<i64>,
+    pub spawn: Option<[i32; 3]>,
+    pub day_time: Option<i64>,
+    pub game_time: Option<i64>,
+    pub data_version: Option<i32>,
+    pub version_name: Option<String>,
+    pub gamerules: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldSummary {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Whether the server loads this world (the `level-name` world or its Bukkit dimensions)
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldExportProgress {
+    pub id: String,
+    pub world_name: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrimOptions {
+    pub world_name: String,
+    /// Keep regions that overlap this many blocks around the center, in overworld
+    /// coordinates. The nether keeps the matching area at 1/8 scale
+    pub radius: Option<u32>,
+    #[serde(default)]
+    pub center_x: i64,
+    #[serde(default)]
+    pub center_z: i64,
+    /// Drop regions with no chunk saved since this date (RFC 3339 or YYYY-MM-DD)
+    pub untouched_since: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrimReport {
+    pub dry_run: bool,
+    pub files: Vec<String>,
+    pub bytes: u64,
+}
+
+// ============ Backups ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub instance_id: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub note: String,
+    /// What triggered the backup: "manual", "pre-restore", ...
+    pub kind: String,
+    /// Archive size for full backups; bytes newly added to the object store for incremental ones
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub incremental: bool,
+    /// Set on backups taken before server.jar was replaced, so the change can be rolled back
+    #[serde(default)]
+    pub jar_change: Option<JarChange>,
+}
+
+/// The software the instance ran before server.jar was replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JarChange {
+    pub reason: String,
+    pub software: String,
+    pub version: String,
+    pub loader: Option<String>,
+    #[serde(default)]
+    pub build: Option<String>,
+    pub custom_jar_path: Option<String>,
+}
+
+/// Whether an installed plugin or mod has a release for the version being upgraded to
+#[derive(Debug, Clone, Serialize)]
+pub struct AddonCompatibility {
+    pub file_name: String,
+    /// The platform it was installed from, unset for jars added by hand
+    pub source: Option<String>,
+    /// `compatible`, `update_available`, `incompatible` or `unknown`
+    pub status: String,
+    /// Newest release for the new version, when it isn't the installed one
+    pub latest_version: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// Outcome of `upgrade_instance_version`
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionUpgrade {
+    pub previous_version: String,
+    pub version: String,
+    pub loader: Option<String>,
+    /// Taken before anything changed, for `rollback_jar_change`
+    pub backup: BackupInfo,
+    pub addons: Vec<AddonCompatibility>,
+}
+
+/// Outcome of `migrate_instance_software`
+#[derive(Debug, Clone, Serialize)]
+pub struct SoftwareMigration {
+    pub previous_software: String,
+    pub software: String,
+    /// Taken before the jar was swapped, for `rollback_jar_change`
+    pub backup: BackupInfo,
+}
+
+/// Outcome of `update_server_jar`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerJarUpdate {
+    /// False when the instance already ran the newest build
+    pub updated: bool,
+    pub previous_build: Option<String>,
+    pub build: String,
+    /// Commit summaries since the previous build, newest first, when the API publishes them
+    pub changes: Vec<String>,
+    /// Taken before the jar was swapped, for `rollback_jar_change`
+    pub backup: Option<BackupInfo>,
+}
+
+/// Files captured by an incremental backup, each pointing at a content-addressed object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub files: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    /// Modification time in seconds, used to skip re-hashing unchanged files
+    pub modified: u64,
+}
+
+/// Which automatic backups to keep. Unset rules keep nothing extra; with every rule unset,
+/// no backups are pruned. Manual backups are never pruned
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Always keep this many of the newest backups
+    pub keep_last: Option<u32>,
+    /// Keep the newest backup of each of the last N days
+    pub keep_daily: Option<u32>,
+    /// Keep the newest backup of each of the last N weeks
+    pub keep_weekly: Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn is_unset(&self) -> bool {
+        self.keep_last.is_none() && self.keep_daily.is_none() && self.keep_weekly.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupUsage {
+    pub instance_id: String,
+    pub name: String,
+    pub count: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub id: String,
+    pub backup_id: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupUploadProgress {
+    pub id: String,
+    pub backup_id: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+// ============ Players ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    /// Hyphenated UUID
+    pub uuid: String,
+    pub name: String,
+}
+
+/// An item stack in a player's inventory or ender chest
+#[derive(Debug, Clone, Serialize)]
+pub struct InventoryItem {
+    /// The slot number, or `head`, `chest`, `legs`, `feet` or `offhand` for worn items
+    pub slot: String,
+    pub id: String,
+    pub count: i64,
+    /// The item's name component as stored, usually a JSON text component
+    pub custom_name: Option<String>,
+}
+
+/// A player's saved state from `<world>/playerdata/<uuid>.dat`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerData {
+    pub uuid: String,
+    /// When the server last saved the player
+    pub last_modified: Option<String>,
+    /// e.g. `minecraft:the_nether`
+    pub dimension: Option<String>,
+    pub position: Option<[f64; 3]>,
+    pub health: Option<f64>,
+    pub food_level: Option<i64>,
+    pub xp_level: i64,
+    pub xp_total: i64,
+    /// Progress towards the next level, from 0 to 1
+    pub xp_progress: f64,
+    pub game_mode: Option<String>,
+    pub inventory: Vec<InventoryItem>,
+    pub ender_chest: Vec<InventoryItem>,
+}
+
+/// An entry of the server's `usercache.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserCacheEntry {
+    pub name: String,
+    pub uuid: String,
+}
+
+/// A player the server has seen, with stats from `<world>/stats/<uuid>.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerStats {
+    /// Hyphenated UUID
+    pub uuid: String,
+    /// Unknown for players that dropped out of `usercache.json`
+    pub name: Option<String>,
+    pub last_seen: Option<String>,
+    pub play_time_ticks: u64,
+    pub deaths: u64,
+    pub mob_kills: u64,
+    pub player_kills: u64,
+    /// Walked and sprinted
+    pub distance_walked_cm: u64,
+    /// Times the player left the server
+    pub sessions: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedProfile {
+    pub profile: PlayerProfile,
+    pub fetched_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct MojangProfile {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpEntry {
+    pub uuid: String,
+    pub name: String,
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit", default)]
+    pub bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PingResult {
+    pub motd: MotdPreview,
+    pub online_players: i64,
+    pub max_players: i64,
+    /// Names from the server's player sample, which may be a subset of who is online
+    pub sample: Vec<String>,
+    pub version_name: String,
+    pub protocol: i64,
+    pub latency_ms: u64,
+    /// Server icon as a data URL
+    pub favicon: Option<String>,
+}
+
+// ============ Addons ============
+
+/// A plugin or mod jar found in an instance's `plugins/` or `mods/` folder
+#[derive(Debug, Clone, Serialize)]
+pub struct AddonInfo {
+    pub file_name: String,
+    /// "plugin" or "mod"
+    pub kind: String,
+    /// The plugin name or mod id declared in the jar, if it has one
+    pub id: Option<String>,
+    pub name: String,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    /// Disabled addons are renamed to `<name>.jar.disabled` so the server skips them
+    pub enabled: bool,
+    pub size: u64,
+}
+
+// ============ Config files ============
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFileInfo {
+    /// Relative to the instance directory, always with `/` separators
+    pub path: String,
+    /// "yaml", "toml", "json" or "properties"
+    pub format: String,
+    pub size_bytes: u64,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFileContent {
+    pub path: String,
+    pub format: String,
+    pub content: String,
+}
+
+// ============ Files ============
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    pub name: String,
+    /// Relative to the instance directory, always with `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    pub size_bytes: u64,
+    pub modified_at: Option<String>,
+}
+
+/// Emitted as `relocate-progress` while `relocate_data_dir` copies instances
+#[derive(Debug, Clone, Serialize)]
+pub struct RelocateProgress {
+    /// Name of the instance being copied
+    pub instance: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// Emitted as `archive-progress` while `compress_path` or `extract_archive` runs
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveProgress {
+    pub id: String,
+    /// "compress" or "extract"
+    pub operation: String,
+    pub archive: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileContent {
+    pub path: String,
+    /// Empty for binary files, which can't be edited as text
+    pub content: String,
+    pub binary: bool,
+    pub size_bytes: u64,
+}
+
+// ============ Modrinth ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    pub date_published: String,
+    pub files: Vec<ModrinthFile>,
+    #[serde(default)]
+    pub dependencies: Vec<ModrinthDependency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthFile {
+    pub url: String,
+    pub filename: String,
+    pub primary: bool,
+    /// Keyed by algorithm, `sha1` and `sha512`
+    #[serde(default)]
+    pub hashes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthDependency {
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub dependency_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthSearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    pub downloads: u64,
+    pub icon_url: Option<String>,
+    pub project_type: String,
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthSearchResults {
+    pub hits: Vec<ModrinthSearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+// ============ Spiget ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SpigetResource {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default)]
+    pub premium: bool,
+    #[serde(default)]
+    pub external: bool,
+    #[serde(default)]
+    pub downloads: u64,
+    pub file: SpigetFile,
+    pub icon: Option<SpigetIcon>,
+    #[serde(default)]
+    pub tested_versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct SpigetFile {
+    /// ".jar", ".zip", or "external" for resources hosted elsewhere
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub external_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpigetIcon {
+    /// Relative to https://www.spigotmc.org/, empty when the resource has no icon
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpigetVersion {
+    pub id: u64,
+    pub name: String,
+}
+
+/// Result of installing from a platform that doesn't always allow direct downloads
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AddonInstallOutcome {
+    Installed {
+        file_name: String,
+    },
+    /// The file has to be fetched in a browser, like premium or externally hosted resources
+    ManualDownload {
+        url: String,
+        reason: String,
+    },
+}
+
+// ============ CurseForge ============
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeResponse<T> {
+    pub data: T,
+    pub pagination: Option<CurseForgePagination>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgePagination {
+    pub index: u32,
+    pub page_size: u32,
+    pub total_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgeMod {
+    pub id: u64,
+    pub name: String,
+    pub slug: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub download_count: f64,
+    pub logo: Option<CurseForgeLogo>,
+    pub links: CurseForgeLinks,
+    #[serde(default)]
+    pub authors: Vec<CurseForgeAuthor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgeLogo {
+    pub thumbnail_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgeLinks {
+    pub website_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurseForgeAuthor {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgeFile {
+    pub id: u64,
+    pub mod_id: u64,
+    pub display_name: String,
+    pub file_name: String,
+    pub file_date: String,
+    /// Missing when the author has opted out of third-party distribution
+    pub download_url: Option<String>,
+    #[serde(default)]
+    pub hashes: Vec<CurseForgeHash>,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Vec<CurseForgeDependency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurseForgeHash {
+    pub value: String,
+    /// 1 is SHA-1, 2 is MD5
+    pub algo: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CurseForgeDependency {
+    pub mod_id: u64,
+    /// 3 is a required dependency
+    pub relation_type: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CurseForgeSearchResults {
+    pub mods: Vec<CurseForgeMod>,
+    pub pagination: Option<CurseForgePagination>,
+}
+
+// ============ Addon lockfile ============
+
+/// A plugin or mod installed from a platform, recorded in `addons.lock` so it can be
+/// updated later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedAddon {
+    /// "modrinth", "spigot" or "curseforge"
+    pub source: String,
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    pub file_name: String,
+    /// `plugins` or `mods`
+    pub folder: String,
+    pub sha1: Option<String>,
+    pub installed_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddonLockfile {
+    #[serde(default)]
+    pub addons: Vec<LockedAddon>,
+}
+
+// ============ Java ============
+
+/// A Java installation found on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaRuntime {
+    pub path: String,
+    /// Full version string, e.g. `17.0.9` or `1.8.0_392`
+    pub version: String,
+    /// Feature release, e.g. 8 or 17
+    pub major: u32,
+    pub vendor: Option<String>,
+    pub arch: Option<String>,
+    /// Downloaded and managed by nuko
+    pub managed: bool,
+}
+
+/// Suggested heap settings for an instance
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryRecommendation {
+    pub min_memory: String,
+    pub max_memory: String,
+    pub total_ram_mb: u64,
+    /// Sum of the other instances' max heaps
+    pub allocated_mb: u64,
+    pub warnings: Vec<String>,
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdoptiumAsset {
+    pub binary: AdoptiumBinary,
+    pub release_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdoptiumBinary {
+    pub package: AdoptiumPackage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdoptiumPackage {
+    pub name: String,
+    pub link: String,
+    /// SHA-256 of the archive
+    pub checksum: String,
+    pub size: u64,
+}
+
+// ============ Chunky ============
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkyProgress {
+    pub world: String,
+    pub chunks: u64,
+    pub percent: f64,
+    pub eta: Option<String>,
+    pub rate: Option<f64>,
+    pub finished: bool,
+}
+
+// ============ Templates ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceTemplate {
+    pub id: String,
+    pub name: String,
+    pub software: String,
+    pub version: String,
+    pub loader: Option<String>,
+    #[serde(default)]
+    pub installer: Option<String>,
+    #[serde(default)]
+    pub java: JavaConfig,
+    pub created_at: String,
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    #[serde(default)]
+    pub mods: Vec<String>,
+}
+
+// ============ Download (Vanilla) ============
+
+#[derive(Deserialize)]
+pub struct VersionManifest {
+    pub versions: Vec<VersionEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct VersionEntry {
+    pub id: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+pub struct VersionDetails {
+    pub downloads: VersionDownloads,
+}
+
+#[derive(Deserialize)]
+pub struct VersionDownloads {
+    pub server: DownloadItem,
+}
+
+#[derive(Deserialize)]
+pub struct DownloadItem {
+    pub url: String,
+    pub sha1: String,
+}
+
+// ============ Download (Paper) ============
+
+#[derive(Deserialize)]
+pub struct PaperBuilds {
+    pub builds: Vec<PaperBuild>,
+}
+
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct PaperBuild {
+    pub build: u32,
+}
+
+#[derive(Deserialize)]
+pub struct PaperDownload {
+    pub downloads: PaperArtifacts,
+}
+
+#[derive(Deserialize)]
+pub struct PaperArtifacts {
+    pub application: PaperFile,
+}
+
+#[derive(Deserialize)]
+pub struct PaperFile {
     pub name: String,
     pub sha256: String,
 }
 
+/// `GET /v3/projects/{project}/versions` on the Fill API, newest first
+#[derive(Deserialize)]
+pub struct FillVersions {
+    pub versions: Vec<FillVersionEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct FillVersionEntry {
+    pub version: FillVersion,
+}
+
+#[derive(Deserialize)]
+pub struct FillVersion {
+    pub id: String,
+}
+
+/// A build from the Fill API, with downloads keyed like `server:default`
+#[derive(Deserialize)]
+pub struct FillBuild {
+    pub id: u32,
+    pub downloads: BTreeMap<String, FillDownload>,
+    #[serde(default)]
+    pub commits: Vec<FillCommit>,
+}
+
+#[derive(Deserialize)]
+pub struct FillCommit {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct FillDownload {
+    pub url: String,
+}
+
+// ============ Download (Purpur) ============
+
+#[derive(Deserialize)]
+pub struct PurpurVersion {
+    pub builds: PurpurBuilds,
+}
+
+#[derive(Deserialize)]
+pub struct PurpurBuilds {
+    /// Oldest first
+    pub all: Vec<String>,
+    pub latest: String,
+}
+
+#[derive(Deserialize)]
+pub struct PurpurBuild {
+    pub build: String,
+    /// `SUCCESS` or `FAILURE`
+    pub result: String,
+    pub md5: Option<String>,
+    #[serde(default)]
+    pub commits: Vec<PurpurCommit>,
+}
+
+#[derive(Deserialize)]
+pub struct PurpurCommit {
+    pub description: String,
+}
+
 // ============ Download (Fabric) ============
 
 #[derive(Deserialize)]
@@ -199,5 +1822,185 @@ pub struct FabricServer {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GlobalConfig {
+    /// Format version of config.toml, see `migrate`
+    #[serde(default)]
+    pub config_version: u32,
     pub theme: String,
+    /// Where backups are mirrored after they are created
+    #[serde(default)]
+    pub backup_remote: Option<RemoteTarget>,
+    /// Console lines kept in memory per running instance
+    #[serde(default = "default_max_log_lines")]
+    pub max_log_lines: usize,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Hide the main window to the tray on close so servers stay supervised
+    #[serde(default)]
+    pub close_to_tray: bool,
+    /// Needed for CurseForge search and downloads, from https://console.curseforge.com. Kept
+    /// in the credential store; this holds the reference
+    #[serde(default)]
+    pub curseforge_api_key: Option<String>,
+    /// Reference into the credential store for the ngrok authtoken
+    #[serde(default)]
+    pub ngrok_authtoken: Option<String>,
+    /// Reference into the credential store for a Cloudflare API token with DNS edit access
+    #[serde(default)]
+    pub cloudflare_api_token: Option<String>,
+    /// Where instances are stored when moved off the data directory with `relocate_data_dir`
+    #[serde(default)]
+    pub instances_dir: Option<String>,
+    #[serde(default)]
+    pub instance_defaults: InstanceDefaults,
+    #[serde(default)]
+    pub downloads: DownloadSettings,
+}
+
+/// Limits that keep downloads from crowding out running servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadSettings {
+    #[serde(default = "default_max_parallel_downloads")]
+    pub max_parallel: usize,
+    /// Combined cap for every download in KiB per second, unset for no cap
+    #[serde(default)]
+    pub bandwidth_limit_kib: Option<u64>,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            max_parallel: default_max_parallel_downloads(),
+            bandwidth_limit_kib: None,
+        }
+    }
+}
+
+fn default_max_parallel_downloads() -> usize {
+    4
+}
+
+/// Settings new instances are created with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceDefaults {
+    #[serde(default = "default_min_memory")]
+    pub min_memory: String,
+    #[serde(default = "default_max_memory")]
+    pub max_memory: String,
+    #[serde(default)]
+    pub java_path: Option<String>,
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    /// New instances get the first free port in this range unless one is chosen
+    #[serde(default = "default_port_range_start")]
+    pub port_range_start: u16,
+    #[serde(default = "default_port_range_end")]
+    pub port_range_end: u16,
+    /// Standing acceptance of the Minecraft EULA for every new instance
+    #[serde(default)]
+    pub accept_eula: bool,
+    #[serde(default)]
+    pub backup_retention: RetentionPolicy,
+    #[serde(default)]
+    pub incremental_backups: bool,
+}
+
+impl Default for InstanceDefaults {
+    fn default() -> Self {
+        Self {
+            min_memory: default_min_memory(),
+            max_memory: default_max_memory(),
+            java_path: None,
+            jvm_args: vec![],
+            port_range_start: default_port_range_start(),
+            port_range_end: default_port_range_end(),
+            accept_eula: false,
+            backup_retention: RetentionPolicy::default(),
+            incremental_backups: false,
+        }
+    }
+}
+
+fn default_min_memory() -> String {
+    "2G".to_string()
+}
+
+fn default_max_memory() -> String {
+    "4G".to_string()
+}
+
+fn default_port_range_start() -> u16 {
+    25565
+}
+
+fn default_port_range_end() -> u16 {
+    25664
+}
+
+/// Which events raise an OS notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    #[serde(default = "default_true")]
+    pub crashes: bool,
+    #[serde(default = "default_true")]
+    pub backup_failures: bool,
+    /// Off by default since busy servers would be noisy
+    #[serde(default)]
+    pub player_joins: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            crashes: true,
+            backup_failures: true,
+            player_joins: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn default_max_log_lines() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RemoteTarget {
+    /// Any S3-compatible store, addressed path-style as `<endpoint>/<bucket>/<key>`
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        /// Reference into the credential store (see `secrets::store_credential`)
+        secret_access_key: String,
+        #[serde(default)]
+        prefix: String,
+    },
+    /// Uses the system `sftp` client with key-based authentication
+    Sftp {
+        host: String,
+        port: Option<u16>,
+        username: String,
+        identity_file: Option<String>,
+        remote_dir: String,
+    },
+    WebDav {
+        url: String,
+        username: Option<String>,
+        /// Reference into the credential store (see `secrets::store_credential`)
+        password: Option<String>,
+    },
+}
+
+/// A per-user autostart entry (systemd user unit, LaunchAgent or Startup folder script)
+#[derive(Debug, Serialize)]
+pub struct ServiceDefinition {
+    pub platform: String,
+    pub path: String,
+    pub contents: String,
+    pub notes: Option<String>,
 }
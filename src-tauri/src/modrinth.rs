@@ -0,0 +1,304 @@
+use std::{fs, path::Path};
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    addons, download, filesystem,
+    instance::get_instance_by_id,
+    models::{LockedAddon, ModrinthFile, ModrinthSearchResults, ModrinthVersion},
+};
+
+const API: &str = "https://api.modrinth.com/v2";
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+const MAX_SEARCH_LIMIT: u32 = 100;
+
+/// The Modrinth loader tags an instance's server software can run, and its install folder.
+/// Paper forks load Bukkit and Spigot plugins too
+pub fn loader_for(software: &str) -> Option<(&'static [&'static str], &'static str)> {
+    match software {
+        "papermc" => Some((&["paper", "spigot", "bukkit"], "plugins")),
+        "purpur" => Some((&["purpur", "paper", "spigot", "bukkit"], "plugins")),
+        "spigot" => Some((&["spigot", "bukkit"], "plugins")),
+        "fabric" => Some((&["fabric"], "mods")),
+        "forge" => Some((&["forge"], "mods")),
+        "neoforge" => Some((&["neoforge"], "mods")),
+        _ => None,
+    }
+}
+
+fn client() -> Result<Client, String> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// Format values the way Modrinth expects list query parameters: a JSON array
+fn json_list(values: &[&str]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".into())
+}
+
+async fn get_json<T: DeserializeOwned>(url: &str, query: &[(&str, String)]) -> Result<T, String> {
+    let response = client()?
+        .get(url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| format!("GET {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} -> HTTP {}", url, response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response from {}: {}", url, e))
+}
+
+/// Versions of a project that support one of the loaders and the game version, newest first
+pub async fn get_project_versions(
+    project: &str,
+    loaders: &[&str],
+    game_version: &str,
+) -> Result<Vec<ModrinthVersion>, String> {
+    get_json(
+        &format!("{}/project/{}/version", API, project),
+        &[
+            ("loaders", json_list(loaders)),
+            ("game_versions", json_list(&[game_version])),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch Modrinth versions for {}: {}", project, e))
+}
+
+async fn get_version(version_id: &str) -> Result<ModrinthVersion, String> {
+    get_json(&format!("{}/version/{}", API, version_id), &[])
+        .await
+        .map_err(|e| format!("Failed to fetch Modrinth version {}: {}", version_id, e))
+}
+
+fn primary_file(version: &ModrinthVersion) -> Option<&ModrinthFile> {
+    version
+        .files
+        .iter()
+        .find(|file| file.primary)
+        .or_else(|| version.files.first())
+}
+
+/// Download a version file and check it against the hash Modrinth published for it before
+/// it's moved into place, so a truncated or tampered jar never reaches the server
+async fn download_verified(file: &ModrinthFile, dest: &Path) -> Result<(), String> {
+    println!("Downloading {} from {}...", file.filename, file.url);
+    let bytes = download::fetch_bytes(client()?.get(&file.url)).await?;
+
+    let (expected, actual) = if let Some(sha512) = file.hashes.get("sha512") {
+        (sha512, format!("{:x}", Sha512::digest(&bytes)))
+    } else if let Some(sha1) = file.hashes.get("sha1") {
+        (sha1, format!("{:x}", Sha1::digest(&bytes)))
+    } else {
+        return Err(format!(
+            "Modrinth didn't publish a hash for {}",
+            file.filename
+        ));
+    };
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "Hash mismatch for {}: expected {}, got {}",
+            file.filename, expected, actual
+        ));
+    }
+
+    let partial = dest.with_file_name(format!("{}.part", file.filename));
+    fs::write(&partial, &bytes)
+        .map_err(|e| format!("Writing {} failed: {}", partial.display(), e))?;
+    fs::rename(&partial, dest).map_err(|e| format!("Writing {} failed: {}", dest.display(), e))
+}
+
+/// Install a project (a specific version, or the newest compatible one) and its required
+/// dependencies into the instance's `folder`, recording each in `addons.lock`. Dependencies
+/// that are already locked are left alone. Returns the file names that were downloaded
+pub async fn install(
+    instance_dir: &Path,
+    folder: &str,
+    project: &str,
+    version_id: Option<&str>,
+    loaders: &[&str],
+    game_version: &str,
+) -> Result<Vec<String>, String> {
+    let install_dir = instance_dir.join(folder);
+    fs::create_dir_all(&install_dir)
+        .map_err(|e| format!("Failed to create {}: {}", install_dir.display(), e))?;
+
+    let mut installed = Vec::new();
+    let mut queue = vec![(project.to_string(), version_id.map(str::to_string), true)];
+    let mut seen = Vec::new();
+
+    while let Some((project, version_id, requested)) = queue.pop() {
+        if seen.contains(&project) {
+            continue;
+        }
+        seen.push(project.clone());
+
+        if !requested
+            && addons::load_lockfile(instance_dir)?
+                .addons
+                .iter()
+                .any(|addon| addon.source == "modrinth" && addon.project_id == project)
+        {
+            continue;
+        }
+
+        let version = match version_id {
+            Some(version_id) => get_version(&version_id).await?,
+            None => get_project_versions(&project, loaders, game_version)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    format!(
+                        "{} has no release for {} {}",
+                        project,
+                        loaders.join("/"),
+                        game_version
+                    )
+                })?,
+        };
+        let file = primary_file(&version)
+            .ok_or_else(|| format!("{} {} has no files", project, version.version_number))?;
+
+        let dest = install_dir.join(&file.filename);
+        if !dest.exists() {
+            download_verified(file, &dest).await?;
+            installed.push(file.filename.clone());
+        }
+
+        addons::record_install(
+            instance_dir,
+            LockedAddon {
+                source: "modrinth".into(),
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+                version_number: version.version_number.clone(),
+                file_name: file.filename.clone(),
+                folder: folder.to_string(),
+                sha1: file.hashes.get("sha1").cloned(),
+                installed_at: Utc::now().to_rfc3339(),
+            },
+        )?;
+        // Dependencies may name the project by slug, so remember the canonical id as well
+        seen.push(version.project_id.clone());
+
+        queue.extend(
+            version
+                .dependencies
+                .iter()
+                .filter(|dependency| dependency.dependency_type == "required")
+                .filter_map(|dependency| {
+                    Some((
+                        dependency.project_id.clone()?,
+                        dependency.version_id.clone(),
+                        false,
+                    ))
+                }),
+        );
+    }
+
+    Ok(installed)
+}
+
+/// Search Modrinth for plugins or mods that run on the instance's software and Minecraft
+/// version
+#[tauri::command]
+pub async fn search_modrinth(
+    app_handle: tauri::AppHandle,
+    id: String,
+    query: String,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<ModrinthSearchResults, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let (loaders, folder) = loader_for(config.effective_software()).ok_or_else(|| {
+        format!(
+            "{} doesn't support plugins or mods",
+            config.effective_software()
+        )
+    })?;
+    let project_type = if folder == "plugins" { "plugin" } else { "mod" };
+
+    let loader_facet: Vec<String> = loaders
+        .iter()
+        .map(|loader| format!("categories:{}", loader))
+        .collect();
+    let facets = serde_json::json!([
+        loader_facet,
+        [format!("versions:{}", config.effective_version())],
+        [format!("project_type:{}", project_type)],
+        ["server_side!=unsupported"],
+    ]);
+
+    get_json(
+        &format!("{}/search", API),
+        &[
+            ("query", query),
+            ("facets", facets.to_string()),
+            ("offset", offset.unwrap_or(0).to_string()),
+            (
+                "limit",
+                limit.unwrap_or(20).clamp(1, MAX_SEARCH_LIMIT).to_string(),
+            ),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to search Modrinth: {}", e))
+}
+
+/// Versions of a Modrinth project that are compatible with the instance, newest first
+#[tauri::command]
+pub async fn get_modrinth_versions(
+    app_handle: tauri::AppHandle,
+    id: String,
+    project: String,
+) -> Result<Vec<ModrinthVersion>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let (loaders, _) = loader_for(config.effective_software()).ok_or_else(|| {
+        format!(
+            "{} doesn't support plugins or mods",
+            config.effective_software()
+        )
+    })?;
+    get_project_versions(&project, loaders, config.effective_version()).await
+}
+
+/// Install a Modrinth project into the instance, the given version or the newest compatible
+/// one. Returns the downloaded file names
+#[tauri::command]
+pub async fn install_modrinth_project(
+    app_handle: tauri::AppHandle,
+    id: String,
+    project: String,
+    version_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let (loaders, folder) = loader_for(config.effective_software()).ok_or_else(|| {
+        format!(
+            "{} doesn't support plugins or mods",
+            config.effective_software()
+        )
+    })?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    install(
+        &instance_dir,
+        folder,
+        &project,
+        version_id.as_deref(),
+        loaders,
+        config.effective_version(),
+    )
+    .await
+}
@@ -0,0 +1,259 @@
+use crate::{
+    filesystem,
+    instance::get_instance_by_id,
+    models::{MotdPreview, MotdSegment},
+    properties::{self, ServerProperties},
+};
+
+const SECTION: char = '§';
+const MAX_MOTD_LINES: usize = 2;
+
+/// Legacy formatting colors as (code, name, hex)
+pub const COLORS: [(char, &str, &str); 16] = [
+    ('0', "black", "#000000"),
+    ('1', "dark_blue", "#0000AA"),
+    ('2', "dark_green", "#00AA00"),
+    ('3', "dark_aqua", "#00AAAA"),
+    ('4', "dark_red", "#AA0000"),
+    ('5', "dark_purple", "#AA00AA"),
+    ('6', "gold", "#FFAA00"),
+    ('7', "gray", "#AAAAAA"),
+    ('8', "dark_gray", "#555555"),
+    ('9', "blue", "#5555FF"),
+    ('a', "green", "#55FF55"),
+    ('b', "aqua", "#55FFFF"),
+    ('c', "red", "#FF5555"),
+    ('d', "light_purple", "#FF55FF"),
+    ('e', "yellow", "#FFFF55"),
+    ('f', "white", "#FFFFFF"),
+];
+
+fn same_style(a: &MotdSegment, b: &MotdSegment) -> bool {
+    a.color == b.color
+        && a.bold == b.bold
+        && a.italic == b.italic
+        && a.underlined == b.underlined
+        && a.strikethrough == b.strikethrough
+        && a.obfuscated == b.obfuscated
+}
+
+fn parse_line(line: &str) -> Vec<MotdSegment> {
+    let mut segments = Vec::new();
+    let mut current = MotdSegment::default();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c != SECTION {
+            current.text.push(c);
+            continue;
+        }
+
+        let Some(code) = chars.next().map(|code| code.to_ascii_lowercase()) else {
+            break;
+        };
+
+        let mut next = MotdSegment {
+            text: String::new(),
+            ..current.clone()
+        };
+        if let Some((_, name, hex)) = COLORS.iter().find(|(c, _, _)| *c == code) {
+            // Colors reset any active formatting, just like in game
+            next = MotdSegment {
+                color: Some(name.to_string()),
+                hex: Some(hex.to_string()),
+                ..MotdSegment::default()
+            };
+        } else {
+            match code {
+                'k' => next.obfuscated = true,
+                'l' => next.bold = true,
+                'm' => next.strikethrough = true,
+                'n' => next.underlined = true,
+                'o' => next.italic = true,
+                'r' => next = MotdSegment::default(),
+                _ => continue,
+            }
+        }
+
+        if !current.text.is_empty() {
+            segments.push(current);
+        }
+        current = next;
+    }
+
+    if !current.text.is_empty() {
+        segments.push(current);
+    }
+
+    segments
+}
+
+/// Parse a MOTD (with raw `§` codes or `\u00a7` escapes) into styled segments per line
+pub fn parse(raw: &str) -> MotdPreview {
+    let decoded = properties::unescape(raw);
+    MotdPreview {
+        raw: decoded.clone(),
+        lines: decoded.split('\n').map(parse_line).collect(),
+    }
+}
+
+/// Flatten a JSON text component (as sent in status responses) into a `§`-coded string
+pub fn from_chat_component(component: &serde_json::Value) -> String {
+    let mut out = String::new();
+    append_component(component, &mut out);
+    out
+}
+
+fn append_component(component: &serde_json::Value, out: &mut String) {
+    match component {
+        serde_json::Value::String(text) => out.push_str(text),
+        serde_json::Value::Array(parts) => {
+            parts.iter().for_each(|part| append_component(part, out))
+        }
+        serde_json::Value::Object(map) => {
+            let mut codes = String::new();
+            if let Some(code) = map
+                .get("color")
+                .and_then(|color| color.as_str())
+                .and_then(|color| COLORS.iter().find(|(_, name, _)| *name == color))
+                .map(|(code, _, _)| *code)
+            {
+                codes.push(SECTION);
+                codes.push(code);
+            }
+            for (key, code) in [
+                ("obfuscated", 'k'),
+                ("bold", 'l'),
+                ("strikethrough", 'm'),
+                ("underlined", 'n'),
+                ("italic", 'o'),
+            ] {
+                if map.get(key).and_then(|v| v.as_bool()) == Some(true) {
+                    codes.push(SECTION);
+                    codes.push(code);
+                }
+            }
+            // Unstyled components inherit whatever came before; styled ones start clean
+            if !codes.is_empty() {
+                out.push(SECTION);
+                out.push('r');
+                out.push_str(&codes);
+            }
+            if let Some(text) = map.get("text").and_then(|text| text.as_str()) {
+                out.push_str(text);
+            }
+            if let Some(extra) = map.get("extra") {
+                append_component(extra, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Turn styled segments back into a `§`-coded MOTD string
+pub fn serialize(lines: &[Vec<MotdSegment>]) -> Result<String, String> {
+    if lines.len() > MAX_MOTD_LINES {
+        return Err(format!("A MOTD can have at most {} lines", MAX_MOTD_LINES));
+    }
+
+    let mut output = Vec::with_capacity(lines.len());
+    for line in lines {
+        let mut encoded = String::new();
+        let mut previous = MotdSegment::default();
+
+        for segment in line {
+            if segment.text.is_empty() {
+                continue;
+            }
+
+            if !same_style(segment, &previous) {
+                match &segment.color {
+                    Some(color) => {
+                        let (code, _, _) = COLORS
+                            .iter()
+                            .find(|(_, name, _)| name == color)
+                            .ok_or_else(|| format!("Unknown MOTD color '{}'", color))?;
+                        encoded.push(SECTION);
+                        encoded.push(*code);
+                    }
+                    None => {
+                        if previous != MotdSegment::default() {
+                            encoded.push(SECTION);
+                            encoded.push('r');
+                        }
+                    }
+                }
+                for (enabled, code) in [
+                    (segment.obfuscated, 'k'),
+                    (segment.bold, 'l'),
+                    (segment.strikethrough, 'm'),
+                    (segment.underlined, 'n'),
+                    (segment.italic, 'o'),
+                ] {
+                    if enabled {
+                        encoded.push(SECTION);
+                        encoded.push(code);
+                    }
+                }
+            }
+
+            encoded.push_str(&segment.text);
+            previous = MotdSegment {
+                text: String::new(),
+                hex: None,
+                ..segment.clone()
+            };
+        }
+
+        output.push(encoded);
+    }
+
+    Ok(output.join("\n"))
+}
+
+#[tauri::command]
+pub fn parse_motd(motd: String) -> MotdPreview {
+    parse(&motd)
+}
+
+#[tauri::command]
+pub fn build_motd(lines: Vec<Vec<MotdSegment>>) -> Result<MotdPreview, String> {
+    let raw = serialize(&lines)?;
+    Ok(parse(&raw))
+}
+
+#[tauri::command]
+pub async fn get_instance_motd(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<MotdPreview, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let properties = ServerProperties::load(&instance_dir)?;
+
+    Ok(parse(
+        properties.get("motd").unwrap_or("A Minecraft Server"),
+    ))
+}
+
+/// Store a MOTD in server.properties. Takes effect on the next server start
+#[tauri::command]
+pub async fn set_instance_motd(
+    app_handle: tauri::AppHandle,
+    id: String,
+    motd: String,
+) -> Result<MotdPreview, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let preview = parse(&motd);
+    if preview.lines.len() > MAX_MOTD_LINES {
+        return Err(format!("A MOTD can have at most {} lines", MAX_MOTD_LINES));
+    }
+
+    let mut properties = ServerProperties::load(&instance_dir)?;
+    properties.set("motd", preview.raw.clone());
+    properties.save()?;
+
+    Ok(preview)
+}
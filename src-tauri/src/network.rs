@@ -0,0 +1,391 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, is_instance_running},
+    models::{ProxyBackend, ProxyKind, ProxyNetworkSetup},
+    ports,
+    properties::ServerProperties,
+};
+
+const FIRST_BACKEND_PORT: u16 = 25566;
+const VELOCITY_SECRET_FILE: &str = "forwarding.secret";
+
+/// Server names go in proxy configs and `/server` commands, so keep them to `[a-z0-9-]`
+fn server_name(name: &str, taken: &HashSet<String>) -> String {
+    let base: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let base = if base.is_empty() {
+        "server".into()
+    } else {
+        base
+    };
+
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}-{}", base, n);
+        n += 1;
+    }
+    candidate
+}
+
+fn load_yaml(path: &Path) -> Result<Value, String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(Value::Mapping(Mapping::new()));
+    };
+    let value: Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(match value {
+        Value::Null => Value::Mapping(Mapping::new()),
+        value => value,
+    })
+}
+
+fn save_yaml(path: &Path, value: &Value) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_yaml::to_string(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Set `keys` (a path of nested mapping keys) to `new`, creating mappings along the way
+fn set_yaml(value: &mut Value, keys: &[&str], new: Value) {
+    let Some((last, parents)) = keys.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        if !current.is_mapping() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(map) = current else {
+            return;
+        };
+        current = map
+            .entry(Value::from(*key))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+    if !current.is_mapping() {
+        *current = Value::Mapping(Mapping::new());
+    }
+    if let Value::Mapping(map) = current {
+        map.insert(Value::from(*last), new);
+    }
+}
+
+/// Point velocity.toml at the backends with modern forwarding. Other settings and comments
+/// are kept; `[servers]` and `[forced-hosts]` are rewritten since the default forced hosts
+/// name servers that won't exist and Velocity refuses to start over them
+fn write_velocity_config(proxy_dir: &Path, backends: &[ProxyBackend]) -> Result<(), String> {
+    let path = proxy_dir.join("velocity.toml");
+    let content = fs::read_to_string(&path).unwrap_or_default();
+
+    let top_level = [
+        ("player-info-forwarding-mode", "\"modern\"".to_string()),
+        (
+            "forwarding-secret-file",
+            format!("\"{}\"", VELOCITY_SECRET_FILE),
+        ),
+    ];
+    let mut servers_block = String::from("[servers]\n");
+    for backend in backends {
+        servers_block.push_str(&format!(
+            "{} = \"127.0.0.1:{}\"\n",
+            backend.server_name, backend.port
+        ));
+    }
+    let try_list: Vec<String> = backends
+        .iter()
+        .take(1)
+        .map(|backend| format!("\"{}\"", backend.server_name))
+        .collect();
+    servers_block.push_str(&format!("try = [{}]\n", try_list.join(", ")));
+    let forced_hosts_block = "[forced-hosts]\n".to_string();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut section: Option<String> = None;
+    let mut set_keys = HashSet::new();
+    let (mut wrote_servers, mut wrote_forced_hosts) = (false, false);
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[[") {
+            let name = trimmed.trim_matches(|c| c == '[' || c == ']').to_string();
+            // Top-level keys must come before the first table
+            if section.is_none() {
+                for (key, value) in &top_level {
+                    if set_keys.insert(*key) {
+                        lines.push(format!("{} = {}", key, value));
+                    }
+                }
+            }
+            match name.as_str() {
+                "servers" => {
+                    lines.push(servers_block.trim_end().to_string());
+                    wrote_servers = true;
+                }
+                "forced-hosts" => {
+                    lines.push(forced_hosts_block.trim_end().to_string());
+                    wrote_forced_hosts = true;
+                }
+                _ => lines.push(line.to_string()),
+            }
+            section = Some(name);
+            continue;
+        }
+
+        match section.as_deref() {
+            Some("servers") | Some("forced-hosts") => {
+                // Keep comments and spacing, drop the old entries
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    lines.push(line.to_string());
+                }
+            }
+            None => {
+                let key = trimmed.split('=').next().unwrap_or_default().trim();
+                match top_level.iter().find(|(name, _)| *name == key) {
+                    Some((name, value)) => {
+                        set_keys.insert(*name);
+                        lines.push(format!("{} = {}", name, value));
+                    }
+                    None => lines.push(line.to_string()),
+                }
+            }
+            Some(_) => lines.push(line.to_string()),
+        }
+    }
+
+    for (key, value) in &top_level {
+        if !set_keys.contains(key) {
+            lines.push(format!("{} = {}", key, value));
+        }
+    }
+    if !wrote_servers {
+        lines.push(String::new());
+        lines.push(servers_block.trim_end().to_string());
+    }
+    if !wrote_forced_hosts {
+        lines.push(String::new());
+        lines.push(forced_hosts_block.trim_end().to_string());
+    }
+
+    fs::write(&path, lines.join("\n") + "\n")
+        .map_err(|e| format!("Failed to write velocity.toml: {}", e))
+}
+
+/// Add the backends to BungeeCord's config.yml with IP forwarding, the first one as the
+/// server players join on
+fn write_bungee_config(proxy_dir: &Path, backends: &[ProxyBackend]) -> Result<(), String> {
+    let path = proxy_dir.join("config.yml");
+    let mut config = load_yaml(&path)?;
+
+    let mut servers = Mapping::new();
+    for backend in backends {
+        let mut server = Mapping::new();
+        server.insert("motd".into(), backend.name.clone().into());
+        server.insert(
+            "address".into(),
+            format!("127.0.0.1:{}", backend.port).into(),
+        );
+        server.insert("restricted".into(), false.into());
+        servers.insert(backend.server_name.clone().into(), server.into());
+    }
+    set_yaml(&mut config, &["servers"], servers.into());
+    set_yaml(&mut config, &["ip_forward"], true.into());
+
+    let priorities: Vec<Value> = backends
+        .iter()
+        .take(1)
+        .map(|backend| backend.server_name.clone().into())
+        .collect();
+    match config.get_mut("listeners").and_then(Value::as_sequence_mut) {
+        Some(listeners) if !listeners.is_empty() => {
+            for listener in listeners {
+                set_yaml(listener, &["priorities"], priorities.clone().into());
+            }
+        }
+        _ => {
+            let mut listener = Mapping::new();
+            listener.insert("host".into(), "0.0.0.0:25577".into());
+            listener.insert("priorities".into(), priorities.into());
+            set_yaml(
+                &mut config,
+                &["listeners"],
+                Value::Sequence(vec![listener.into()]),
+            );
+        }
+    }
+
+    save_yaml(&path, &config)
+}
+
+/// Have a Paper backend trust the proxy's forwarded player info
+fn configure_backend(
+    backend_dir: &Path,
+    kind: ProxyKind,
+    secret: Option<&str>,
+) -> Result<(), String> {
+    let paper_global = backend_dir.join("config").join("paper-global.yml");
+    let mut paper = load_yaml(&paper_global)?;
+    match kind {
+        ProxyKind::Velocity => {
+            set_yaml(&mut paper, &["proxies", "velocity", "enabled"], true.into());
+            set_yaml(
+                &mut paper,
+                &["proxies", "velocity", "online-mode"],
+                true.into(),
+            );
+            set_yaml(
+                &mut paper,
+                &["proxies", "velocity", "secret"],
+                secret.unwrap_or_default().into(),
+            );
+            set_yaml(
+                &mut paper,
+                &["proxies", "bungee-cord", "online-mode"],
+                false.into(),
+            );
+        }
+        ProxyKind::Bungeecord => {
+            set_yaml(
+                &mut paper,
+                &["proxies", "velocity", "enabled"],
+                false.into(),
+            );
+            set_yaml(
+                &mut paper,
+                &["proxies", "bungee-cord", "online-mode"],
+                true.into(),
+            );
+            let spigot_path = backend_dir.join("spigot.yml");
+            let mut spigot = load_yaml(&spigot_path)?;
+            set_yaml(&mut spigot, &["settings", "bungeecord"], true.into());
+            save_yaml(&spigot_path, &spigot)?;
+        }
+    }
+    save_yaml(&paper_global, &paper)
+}
+
+/// Wire a Velocity or BungeeCord proxy instance to a set of Paper/Purpur backends: write the
+/// proxy's server list (the first backend is where players land), set up forwarding with a
+/// fresh secret for Velocity, and give each backend a free port on localhost with online
+/// mode off so it can only be joined through the proxy
+#[tauri::command]
+pub async fn setup_proxy_network(
+    app_handle: tauri::AppHandle,
+    proxy_id: String,
+    backend_ids: Vec<String>,
+    kind: ProxyKind,
+) -> Result<ProxyNetworkSetup, String> {
+    if backend_ids.is_empty() {
+        return Err("Pick at least one backend server".into());
+    }
+    if backend_ids.contains(&proxy_id) {
+        return Err("The proxy can't also be one of its backends".into());
+    }
+    let unique: HashSet<&String> = backend_ids.iter().collect();
+    if unique.len() != backend_ids.len() {
+        return Err("A backend was picked more than once".into());
+    }
+
+    let proxy = get_instance_by_id(&app_handle, &proxy_id)?;
+    let (software, label) = match kind {
+        ProxyKind::Velocity => ("velocity", "Velocity"),
+        ProxyKind::Bungeecord => ("bungeecord", "BungeeCord"),
+    };
+    if proxy.effective_software() != software {
+        return Err(format!(
+            "'{}' runs {}, but a {} network needs a {} proxy instance",
+            proxy.name,
+            proxy.effective_software(),
+            label,
+            label
+        ));
+    }
+    let proxy_dir = filesystem::get_instance_dir(&app_handle, &proxy_id)?;
+    let mut members = vec![(proxy, proxy_dir.clone())];
+    for id in &backend_ids {
+        let config = get_instance_by_id(&app_handle, id)?;
+        if !matches!(config.software.as_str(), "papermc" | "purpur") {
+            return Err(format!(
+                "'{}' runs {}, but proxy forwarding needs Paper or Purpur backends",
+                config.name, config.software
+            ));
+        }
+        members.push((config, filesystem::get_instance_dir(&app_handle, id)?));
+    }
+    if let Some((config, _)) = members.iter().find(|(_, dir)| is_instance_running(dir)) {
+        return Err(format!(
+            "Stop '{}' before setting up the network",
+            config.name
+        ));
+    }
+
+    // The proxy takes the public port; backends move off it and away from each other
+    let proxy_port = ports::server_port(&proxy_dir);
+    let mut assigned = HashSet::from([proxy_port]);
+    let mut names = HashSet::new();
+    let mut backends = Vec::new();
+    for (config, dir) in members.iter().skip(1) {
+        let mut reserved = ports::reserved_ports(&app_handle, &config.id)?;
+        reserved.extend(&assigned);
+        let current = ports::server_port(dir);
+        let port = if reserved.contains(&current) {
+            ports::next_free_port(FIRST_BACKEND_PORT, &reserved)
+                .ok_or_else(|| format!("No free port for '{}'", config.name))?
+        } else {
+            current
+        };
+        assigned.insert(port);
+
+        let name = server_name(&config.name, &names);
+        names.insert(name.clone());
+        backends.push(ProxyBackend {
+            id: config.id.clone(),
+            name: config.name.clone(),
+            server_name: name,
+            port,
+        });
+    }
+
+    let secret = match kind {
+        ProxyKind::Velocity => {
+            let secret = uuid::Uuid::new_v4().simple().to_string();
+            fs::write(proxy_dir.join(VELOCITY_SECRET_FILE), &secret)
+                .map_err(|e| format!("Failed to write {}: {}", VELOCITY_SECRET_FILE, e))?;
+            write_velocity_config(&proxy_dir, &backends)?;
+            Some(secret)
+        }
+        ProxyKind::Bungeecord => {
+            write_bungee_config(&proxy_dir, &backends)?;
+            None
+        }
+    };
+
+    for (backend, (_, dir)) in backends.iter().zip(members.iter().skip(1)) {
+        let mut properties = ServerProperties::load(dir)?;
+        properties.set("server-port", backend.port.to_string());
+        properties.set("server-ip", "127.0.0.1");
+        properties.set("online-mode", "false");
+        properties.save()?;
+        configure_backend(dir, kind, secret.as_deref())?;
+    }
+
+    Ok(ProxyNetworkSetup {
+        kind,
+        proxy_port,
+        backends,
+    })
+}
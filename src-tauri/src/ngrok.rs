@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Mutex, OnceLock},
+    thread,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    archive, config, download, filesystem, instance::record_log_line, models::PlayitTunnelMetadata,
+    secrets,
+};
+
+const DOWNLOAD_BASE: &str = "https://bin.equinox.io/c/bNyj1mQVY4c";
+
+fn get_agents() -> &'static Mutex<HashMap<String, Child>> {
+    static AGENTS: OnceLock<Mutex<HashMap<String, Child>>> = OnceLock::new();
+    AGENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Tunnels the running agents reported, reusing the playit metadata shape for the frontend
+fn get_tunnels() -> &'static Mutex<HashMap<String, PlayitTunnelMetadata>> {
+    static TUNNELS: OnceLock<Mutex<HashMap<String, PlayitTunnelMetadata>>> = OnceLock::new();
+    TUNNELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A line of the agent's `--log-format json` output
+#[derive(Debug, Deserialize)]
+struct LogLine {
+    #[serde(default)]
+    lvl: String,
+    #[serde(default)]
+    msg: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    err: Option<String>,
+}
+
+fn archive_name() -> Result<&'static str, String> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("ngrok-v3-stable-linux-amd64.tgz"),
+        ("linux", "aarch64") => Ok("ngrok-v3-stable-linux-arm64.tgz"),
+        ("macos", "x86_64") => Ok("ngrok-v3-stable-darwin-amd64.zip"),
+        ("macos", "aarch64") => Ok("ngrok-v3-stable-darwin-arm64.zip"),
+        ("windows", "x86_64") => Ok("ngrok-v3-stable-windows-amd64.zip"),
+        (os, arch) => Err(format!("Unsupported OS/Arch for ngrok: {}/{}", os, arch)),
+    }
+}
+
+/// The ngrok agent, shared by all instances and downloaded on first use
+async fn ensure_agent(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let bin_dir = filesystem::get_data_dir(app_handle)?.join("bin");
+    let agent = bin_dir.join(if cfg!(windows) { "ngrok.exe" } else { "ngrok" });
+    if agent.exists() {
+        return Ok(agent);
+    }
+    fs::create_dir_all(&bin_dir)
+        .map_err(|e| format!("Failed to create {}: {}", bin_dir.display(), e))?;
+
+    let name = archive_name()?;
+    let url = format!("{}/{}", DOWNLOAD_BASE, name);
+    println!("Downloading ngrok agent from {}...", url);
+    let bytes = download::fetch_bytes(reqwest::Client::new().get(&url))
+        .await
+        .map_err(|e| format!("Failed to download ngrok: {}", e))?;
+
+    let archive_path = bin_dir.join(name);
+    fs::write(&archive_path, &bytes)
+        .map_err(|e| format!("Writing {} failed: {}", archive_path.display(), e))?;
+    let extracted = if name.ends_with(".zip") {
+        archive::unzip_to(&archive_path, &bin_dir)
+    } else {
+        archive::untar_gz_with_progress(&archive_path, &bin_dir, |_| Ok(()), |_, _| {})
+    };
+    let _ = fs::remove_file(&archive_path);
+    extracted?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&agent, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make ngrok executable: {}", e))?;
+    }
+
+    Ok(agent)
+}
+
+fn handle_log_line(app_handle: &tauri::AppHandle, id: &str, port: u16, line: &str) {
+    let Ok(log) = serde_json::from_str::<LogLine>(line) else {
+        return;
+    };
+
+    if log.msg == "started tunnel" {
+        let Some(url) = log.url else {
+            return;
+        };
+        let address = url.trim_start_matches("tcp://");
+        let (host, public_port) = match address.rsplit_once(':') {
+            Some((host, port)) => (Some(host.to_string()), port.parse().ok()),
+            None => (Some(address.to_string()), None),
+        };
+        get_tunnels().lock().unwrap().insert(
+            id.to_string(),
+            PlayitTunnelMetadata {
+                id: log.name,
+                name: Some("ngrok".into()),
+                protocol: Some("TCP".into()),
+                public_hostname: host,
+                public_port,
+                destination_port: Some(port),
+                agent_version: None,
+                status: Some("Active".into()),
+                last_heartbeat: None,
+            },
+        );
+        record_log_line(
+            app_handle,
+            id,
+            format!("[ngrok] Public address: {}", address),
+        );
+    } else if matches!(log.lvl.as_str(), "eror" | "crit" | "error") {
+        let detail = log.err.map(|err| format!(": {}", err)).unwrap_or_default();
+        record_log_line(app_handle, id, format!("[ngrok] {}{}", log.msg, detail));
+    }
+}
+
+/// Start an ngrok TCP tunnel to the instance's `port`. The public address is logged to the
+/// console once ngrok reports it, and is available from `tunnels`
+pub async fn start(app_handle: &tauri::AppHandle, id: &str, port: u16) -> Result<(), String> {
+    let stored = config::get_config(app_handle.clone())?
+        .ngrok_authtoken
+        .ok_or("Add an ngrok authtoken in settings first")?;
+    let authtoken = secrets::load_credential(app_handle, &stored)?;
+    let agent = ensure_agent(app_handle).await?;
+
+    stop(id);
+    let mut child = Command::new(&agent)
+        .args([
+            "tcp",
+            &port.to_string(),
+            "--log",
+            "stdout",
+            "--log-format",
+            "json",
+        ])
+        .env("NGROK_AUTHTOKEN", authtoken)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start ngrok: {}", e))?;
+
+    if let Some(stdout) = child.stdout.take() {
+        let app_handle = app_handle.clone();
+        let id = id.to_string();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                handle_log_line(&app_handle, &id, port, &line);
+            }
+            get_tunnels().lock().unwrap().remove(&id);
+        });
+    }
+
+    get_agents().lock().unwrap().insert(id.to_string(), child);
+    Ok(())
+}
+
+/// Stop the instance's ngrok agent, if one is running
+pub fn stop(id: &str) {
+    let child = get_agents().lock().unwrap().remove(id);
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    get_tunnels().lock().unwrap().remove(id);
+}
+
+/// The instance's ngrok tunnel, once the agent has reported it
+pub fn tunnels(id: &str) -> Vec<PlayitTunnelMetadata> {
+    get_tunnels()
+        .lock()
+        .unwrap()
+        .get(id)
+        .cloned()
+        .into_iter()
+        .collect()
+}
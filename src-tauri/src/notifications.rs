@@ -0,0 +1,38 @@
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{config, models::NotificationSettings};
+
+/// Things nuko can notify about, each with its own opt-out in the global config
+pub enum NotificationKind {
+    Crash,
+    BackupFailed,
+    PlayerJoined,
+}
+
+fn enabled(settings: &NotificationSettings, kind: &NotificationKind) -> bool {
+    match kind {
+        NotificationKind::Crash => settings.crashes,
+        NotificationKind::BackupFailed => settings.backup_failures,
+        NotificationKind::PlayerJoined => settings.player_joins,
+    }
+}
+
+/// Show an OS notification, so it's seen even with the nuko window minimized
+pub fn notify(app_handle: &tauri::AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    let settings = config::get_config(app_handle.clone())
+        .map(|config| config.notifications)
+        .unwrap_or_default();
+    if !enabled(&settings, &kind) {
+        return;
+    }
+
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        println!("Failed to show notification: {}", e);
+    }
+}
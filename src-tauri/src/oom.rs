@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    process::ExitStatus,
+    sync::{Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use tauri::Emitter;
+
+use crate::{
+    instance::{get_instance_by_id, record_log_line, update_instance_config},
+    jvmflags::parse_memory_mb,
+    memory::{available_heap_mb, format_memory, ram_usage},
+    models::{OomEvent, OomKind},
+    notifications::{self, NotificationKind},
+};
+
+/// `OutOfMemoryError` messages seen in each running server's console
+fn get_errors() -> &'static Mutex<HashMap<String, OomKind>> {
+    static ERRORS: OnceLock<Mutex<HashMap<String, OomKind>>> = OnceLock::new();
+    ERRORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The last suggestion made for each instance, for `apply_oom_suggestion`
+fn get_suggestions() -> &'static Mutex<HashMap<String, OomEvent>> {
+    static SUGGESTIONS: OnceLock<Mutex<HashMap<String, OomEvent>>> = OnceLock::new();
+    SUGGESTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn classify(line: &str) -> Option<OomKind> {
+    let (_, message) = line.split_once("java.lang.OutOfMemoryError")?;
+    Some(if message.contains("Metaspace") {
+        OomKind::Metaspace
+    } else if message.contains("native thread") || message.contains("Direct buffer memory") {
+        OomKind::NativeMemory
+    } else {
+        OomKind::Heap
+    })
+}
+
+/// Watch the console for `OutOfMemoryError`. Only the first one counts, as the errors that
+/// follow are usually fallout from it
+pub fn handle_log_line(id: &str, line: &str) {
+    if let Some(kind) = classify(line) {
+        get_errors()
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert(kind);
+    }
+}
+
+/// Whether the JVM wrote a fatal error log for failing to allocate native memory since start
+fn native_allocation_failed(instance_dir: &Path, started_at: SystemTime) -> bool {
+    let Ok(entries) = fs::read_dir(instance_dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let recent = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|modified| modified >= started_at)
+            .unwrap_or(false);
+        name.starts_with("hs_err_pid")
+            && recent
+            && fs::read_to_string(entry.path())
+                .map(|content| content.contains("There is insufficient memory"))
+                .unwrap_or(false)
+    })
+}
+
+/// The signal that ended the process, if one did
+#[cfg(unix)]
+pub fn signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+pub fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Round up to a multiple of 512 MB
+fn round_up(mb: u64) -> u64 {
+    mb.div_ceil(512) * 512
+}
+
+/// Work out what to change for the kind of memory that ran out. A full heap or metaspace
+/// needs a bigger -Xmx, as far as the machine allows. When the OS or the process memory
+/// limit killed the server, the process as a whole needs more room than it had
+fn suggest(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    kind: OomKind,
+    max_memory: &str,
+    memory_limit: Option<&str>,
+) -> Result<OomEvent, String> {
+    let (total_ram_mb, allocated_mb) = ram_usage(app_handle, id)?;
+    let available = available_heap_mb(total_ram_mb, allocated_mb);
+    let current = parse_memory_mb(max_memory).unwrap_or(0);
+
+    let mut event = OomEvent {
+        instance_id: id.to_string(),
+        kind,
+        current_max_memory: max_memory.to_string(),
+        current_memory_limit: memory_limit.map(str::to_string),
+        suggested_max_memory: None,
+        suggested_memory_limit: None,
+        total_ram_mb,
+        message: String::new(),
+    };
+
+    match (kind, memory_limit.and_then(parse_memory_mb)) {
+        (OomKind::Heap | OomKind::Metaspace, _) => {
+            let wanted = round_up((current + 1024).max(current * 3 / 2));
+            let heap = wanted.min(available / 512 * 512);
+            if heap > current {
+                event.suggested_max_memory = Some(format_memory(heap));
+                event.message = format!(
+                    "The server ran out of heap with -Xmx{}. Raise it to {}",
+                    max_memory,
+                    format_memory(heap)
+                );
+            } else {
+                event.message = format!(
+                    "The server ran out of heap with -Xmx{}, and this machine has no RAM to \
+                     spare for more. Remove plugins or mods, or lower the view distance",
+                    max_memory
+                );
+            }
+            // The process limit has to grow with the heap, or the kernel kills it instead
+            if let (Some(limit), Some(heap)) = (
+                memory_limit.and_then(parse_memory_mb),
+                event
+                    .suggested_max_memory
+                    .as_deref()
+                    .and_then(parse_memory_mb),
+            ) {
+                let needed = round_up(heap + (heap / 4).max(1024));
+                if needed > limit {
+                    event.suggested_memory_limit = Some(format_memory(needed));
+                }
+            }
+        }
+        (_, Some(limit)) => {
+            let needed = round_up((current + (current / 4).max(1024)).max(limit * 5 / 4));
+            event.suggested_memory_limit = Some(format_memory(needed));
+            event.message = format!(
+                "The server hit its {} memory limit. Raise the limit to {} to leave room for \
+                 the JVM next to its {} heap",
+                format_memory(limit),
+                format_memory(needed),
+                max_memory
+            );
+        }
+        (_, None) if current > available && available >= 1024 => {
+            let heap = available / 512 * 512;
+            event.suggested_max_memory = Some(format_memory(heap));
+            event.message = format!(
+                "The system ran out of memory. -Xmx{} is more than this machine can spare, \
+                 lower it to {}",
+                max_memory,
+                format_memory(heap)
+            );
+        }
+        (_, None) => {
+            event.message = format!(
+                "The system ran out of memory while the server was running. Close other \
+                 programs or stop other instances, {} of RAM is installed",
+                format_memory(total_ram_mb)
+            );
+        }
+    }
+    Ok(event)
+}
+
+/// Called once a server process has exited. An `OutOfMemoryError` in the console, a fatal
+/// native allocation failure, or an unrequested SIGKILL (exit code 137, usually the kernel
+/// OOM killer) emits `instance-oom` with a memory suggestion
+pub fn handle_exit(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    instance_dir: &Path,
+    started_at: SystemTime,
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    requested: bool,
+) {
+    let logged = get_errors().lock().unwrap().remove(id);
+    let kind = match logged {
+        Some(kind) => kind,
+        None if native_allocation_failed(instance_dir, started_at) => OomKind::NativeMemory,
+        None if !requested && (exit_code == Some(137) || signal == Some(9)) => OomKind::Killed,
+        None => return,
+    };
+
+    let Ok(config) = get_instance_by_id(app_handle, id) else {
+        return;
+    };
+    let event = match suggest(
+        app_handle,
+        id,
+        kind,
+        &config.java.max_memory,
+        config.java.memory_limit.as_deref(),
+    ) {
+        Ok(event) => event,
+        Err(e) => {
+            println!("Failed to suggest memory for {}: {}", id, e);
+            return;
+        }
+    };
+
+    record_log_line(app_handle, id, format!("[nuko] {}", event.message));
+    notifications::notify(
+        app_handle,
+        NotificationKind::Crash,
+        &format!("{} ran out of memory", config.name),
+        &event.message,
+    );
+    get_suggestions()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), event.clone());
+    let _ = app_handle.emit("instance-oom", event);
+}
+
+/// Save the memory settings suggested after the instance last ran out of memory
+#[tauri::command]
+pub async fn apply_oom_suggestion(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<OomEvent, String> {
+    let event = get_suggestions()
+        .lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| "There is no memory suggestion for this instance".to_string())?;
+    if event.suggested_max_memory.is_none() && event.suggested_memory_limit.is_none() {
+        return Err("The suggestion has no settings to change".into());
+    }
+
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    if let Some(max_memory) = &event.suggested_max_memory {
+        // Keep -Xms from exceeding the new -Xmx when it was lowered
+        if parse_memory_mb(&config.java.min_memory) >= parse_memory_mb(&config.java.max_memory)
+            || parse_memory_mb(&config.java.min_memory) > parse_memory_mb(max_memory)
+        {
+            config.java.min_memory = max_memory.clone();
+        }
+        config.java.max_memory = max_memory.clone();
+    }
+    if let Some(memory_limit) = &event.suggested_memory_limit {
+        config.java.memory_limit = Some(memory_limit.clone());
+    }
+    update_instance_config(&app_handle, &config)?;
+    get_suggestions().lock().unwrap().remove(&id);
+    Ok(event)
+}
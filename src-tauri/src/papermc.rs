@@ -0,0 +1,164 @@
+use reqwest::Client;
+
+use crate::models::{FillBuild, FillVersions, PaperBuilds, PaperDownload, PaperProjectResponse};
+
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+/// The Fill API replacing v2, which PaperMC is sunsetting
+const FILL_API: &str = "https://fill.papermc.io/v3";
+const LEGACY_API: &str = "https://api.papermc.io/v2";
+
+/// Fill rejects requests that don't identify the client
+fn client() -> Result<Client, String> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+async fn fill_versions(project: &str) -> Result<Vec<String>, String> {
+    let versions: FillVersions = client()?
+        .get(format!("{}/projects/{}/versions", FILL_API, project))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} versions: {}", project, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} versions: {}", project, e))?;
+    Ok(versions
+        .versions
+        .into_iter()
+        .map(|entry| entry.version.id)
+        .collect())
+}
+
+async fn legacy_versions(project: &str) -> Result<Vec<String>, String> {
+    let response: PaperProjectResponse = client()?
+        .get(format!("{}/projects/{}", LEGACY_API, project))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} versions: {}", project, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} versions: {}", project, e))?;
+
+    // v2 lists versions oldest-first
+    let mut versions = response.versions;
+    versions.reverse();
+    Ok(versions)
+}
+
+/// Versions of a PaperMC project (paper, folia, velocity, ...), newest first
+pub async fn versions(project: &str) -> Result<Vec<String>, String> {
+    match fill_versions(project).await {
+        Ok(versions) => Ok(versions),
+        Err(e) => {
+            println!("{}; falling back to the v2 API", e);
+            legacy_versions(project).await
+        }
+    }
+}
+
+async fn fill_latest_build(project: &str, version: &str) -> Result<(u32, String), String> {
+    let build: FillBuild = client()?
+        .get(format!(
+            "{}/projects/{}/versions/{}/builds/latest",
+            FILL_API, project, version
+        ))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} {} builds: {}", project, version, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} {} builds: {}", project, version, e))?;
+
+    build
+        .downloads
+        .get("server:default")
+        .map(|download| (build.id, download.url.clone()))
+        .ok_or_else(|| format!("{} build {} has no server download", project, build.id))
+}
+
+async fn legacy_latest_build(project: &str, version: &str) -> Result<(u32, String), String> {
+    let version_url = format!("{}/projects/{}/versions/{}", LEGACY_API, project, version);
+    let builds: PaperBuilds = client()?
+        .get(&version_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} {} builds: {}", project, version, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} {} builds: {}", project, version, e))?;
+
+    let latest = builds
+        .builds
+        .last()
+        .ok_or_else(|| format!("No {} builds for {}", project, version))?
+        .build;
+
+    let build_url = format!("{}/builds/{}", version_url, latest);
+    let meta: PaperDownload = client()?
+        .get(&build_url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} build {}: {}", project, latest, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} build {}: {}", project, latest, e))?;
+
+    Ok((
+        latest,
+        format!(
+            "{}/downloads/{}",
+            build_url, meta.downloads.application.name
+        ),
+    ))
+}
+
+/// Number and download URL of the newest build of `version`, from Fill with v2 as a fallback
+pub async fn latest_build(project: &str, version: &str) -> Result<(u32, String), String> {
+    match fill_latest_build(project, version).await {
+        Ok(build) => Ok(build),
+        Err(e) => {
+            println!("{}; falling back to the v2 API", e);
+            legacy_latest_build(project, version).await
+        }
+    }
+}
+
+/// Download URL of the newest build of `version`
+pub async fn latest_download_url(project: &str, version: &str) -> Result<String, String> {
+    latest_build(project, version).await.map(|(_, url)| url)
+}
+
+/// First lines of the commit messages of every build of `version` after `build`, newest
+/// first. Only the Fill API lists builds with their commits
+pub async fn changes_since(
+    project: &str,
+    version: &str,
+    build: u32,
+) -> Result<Vec<String>, String> {
+    let builds: Vec<FillBuild> = client()?
+        .get(format!(
+            "{}/projects/{}/versions/{}/builds",
+            FILL_API, project, version
+        ))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {} {} builds: {}", project, version, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} {} builds: {}", project, version, e))?;
+
+    Ok(builds
+        .into_iter()
+        .filter(|newer| newer.id > build)
+        .flat_map(|newer| newer.commits)
+        .filter_map(|commit| commit.message.lines().next().map(str::to_string))
+        .collect())
+}
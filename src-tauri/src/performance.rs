@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{consolelog, filesystem, instance, properties::ServerProperties, rcon};
+
+/// TPS commands are only issued this often, however frequently metrics are polled
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// How long to wait for a reply when the command goes through stdin
+const STDIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickStats {
+    pub tps: Option<f64>,
+    pub mspt: Option<f64>,
+}
+
+struct CachedStats {
+    stats: TickStats,
+    checked_at: Option<Instant>,
+    refreshing: bool,
+}
+
+fn get_stats_map() -> &'static Mutex<HashMap<String, CachedStats>> {
+    static STATS: OnceLock<Mutex<HashMap<String, CachedStats>>> = OnceLock::new();
+    STATS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Commands that report tick stats, as (tps command, mspt command). Paper-family servers
+/// have both built in; anything else needs spark
+fn stat_commands(software: &str, instance_dir: &Path) -> Option<(&'static str, &'static str)> {
+    if has_spark(instance_dir) {
+        return Some(("spark tps", "spark tps"));
+    }
+    match software {
+        "papermc" | "purpur" => Some(("tps", "mspt")),
+        _ => None,
+    }
+}
+
+fn has_spark(instance_dir: &Path) -> bool {
+    ["plugins", "mods"].iter().any(|folder| {
+        fs::read_dir(instance_dir.join(folder))
+            .map(|entries| {
+                entries.flatten().any(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_lowercase();
+                    name.starts_with("spark") && name.ends_with(".jar")
+                })
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Drop `§` formatting codes and ANSI escapes so the numbers can be parsed
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '§' => {
+                chars.next();
+            }
+            '\u{1b}' => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn message(line: &str) -> String {
+    consolelog::log_message(&strip_formatting(line)).to_string()
+}
+
+fn numbers(text: &str) -> Vec<f64> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// The line after `header` (or the rest of the header line itself, when the values follow
+/// the colon directly)
+fn values_after<'a>(lines: &'a [String], header: &str) -> Option<&'a str> {
+    let index = lines.iter().position(|line| line.contains(header))?;
+    let rest = lines[index].rsplit_once(':').map(|(_, rest)| rest.trim());
+    match rest {
+        Some(rest) if !numbers(rest).is_empty() => Some(rest),
+        _ => lines.get(index + 1).map(String::as_str),
+    }
+}
+
+/// Most recent TPS figure from `tps` (Paper) or `spark tps` output
+fn parse_tps(output: &str) -> Option<f64> {
+    let lines: Vec<String> = output.lines().map(message).collect();
+    values_after(&lines, "TPS from last").and_then(|values| numbers(values).first().copied())
+}
+
+/// Most recent average tick time from `mspt` (Paper, avg/min/max) or `spark tps`
+/// (min/median/95th/max, where the median is used)
+fn parse_mspt(output: &str) -> Option<f64> {
+    let lines: Vec<String> = output.lines().map(message).collect();
+    if let Some(values) = values_after(&lines, "Tick durations") {
+        let first = values.split([';', ',']).next()?;
+        return numbers(first).get(1).copied();
+    }
+    let values = values_after(&lines, "tick times")?;
+    let first = values.split(',').next()?;
+    numbers(first).first().copied()
+}
+
+/// Run a command and collect its output, over RCON when it's set up and otherwise through
+/// the stdin of a server nuko started
+async fn run_command(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    instance_dir: &Path,
+    command: &str,
+    header: &str,
+) -> Result<String, String> {
+    let rcon_enabled = ServerProperties::load(instance_dir)
+        .map(|properties| properties.get("enable-rcon").map(str::trim) == Some("true"))
+        .unwrap_or(false);
+    if rcon_enabled {
+        if let Ok(output) = rcon::execute(app_handle, id, command.to_string()).await {
+            return Ok(output);
+        }
+    }
+
+    if !instance::has_instance_stdin(id) {
+        return Err("Instance can't be reached over RCON or stdin".into());
+    }
+
+    let since = instance::log_line_count(id);
+    instance::write_instance_stdin(id, command)?;
+
+    let started = Instant::now();
+    while started.elapsed() < STDIN_TIMEOUT {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let lines = instance::logs_since(id, since);
+        // Multi-line replies put their values on the line after the header
+        if let Some(index) = lines.iter().position(|line| line.contains(header)) {
+            if index + 1 < lines.len() || !numbers(&lines[index]).is_empty() {
+                return Ok(lines.join("\n"));
+            }
+        }
+    }
+
+    Err(format!("No reply to '{}'", command))
+}
+
+async fn query(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    software: &str,
+) -> Result<TickStats, String> {
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let Some((tps_command, mspt_command)) = stat_commands(software, &instance_dir) else {
+        return Ok(TickStats::default());
+    };
+
+    let output = run_command(app_handle, id, &instance_dir, tps_command, "TPS from last").await?;
+    let tps = parse_tps(&output);
+    let mspt = if mspt_command == tps_command {
+        parse_mspt(&output)
+    } else {
+        let output = run_command(app_handle, id, &instance_dir, mspt_command, "tick times").await?;
+        parse_mspt(&output)
+    };
+
+    Ok(TickStats { tps, mspt })
+}
+
+/// The latest tick stats for an instance. Returns immediately with the cached values and
+/// refreshes them in the background once they're older than `REFRESH_INTERVAL`
+pub fn latest(app_handle: &tauri::AppHandle, id: &str, software: &str) -> TickStats {
+    let mut stats_map = get_stats_map().lock().unwrap();
+    let cached = stats_map.entry(id.to_string()).or_insert(CachedStats {
+        stats: TickStats::default(),
+        checked_at: None,
+        refreshing: false,
+    });
+
+    let stale = cached
+        .checked_at
+        .map(|at| at.elapsed() >= REFRESH_INTERVAL)
+        .unwrap_or(true);
+    if stale && !cached.refreshing {
+        cached.refreshing = true;
+
+        let app_handle = app_handle.clone();
+        let id = id.to_string();
+        let software = software.to_string();
+        tauri::async_runtime::spawn(async move {
+            let stats = query(&app_handle, &id, &software).await.unwrap_or_default();
+            let mut stats_map = get_stats_map().lock().unwrap();
+            if let Some(cached) = stats_map.get_mut(&id) {
+                cached.stats = stats;
+                cached.checked_at = Some(Instant::now());
+                cached.refreshing = false;
+            }
+        });
+    }
+
+    cached.stats
+}
+
+/// Forget cached stats so a stopped server doesn't keep reporting its last TPS
+pub fn clear(id: &str) {
+    get_stats_map().lock().unwrap().remove(id);
+}
@@ -0,0 +1,153 @@
+use std::fs;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    filesystem,
+    instance::get_instance_by_id,
+    models::{InventoryItem, PlayerData},
+    world::{active_world_name, read_nbt_file, world_dir, Tag},
+};
+
+/// Slots of armor and the offhand before 1.21.5 kept them in a separate `equipment` tag
+fn legacy_slot_name(slot: i64) -> String {
+    match slot {
+        100 => "feet".into(),
+        101 => "legs".into(),
+        102 => "chest".into(),
+        103 => "head".into(),
+        -106 => "offhand".into(),
+        other => other.to_string(),
+    }
+}
+
+/// Item stacks are `{id, count, components}` since 1.20.5 and `{id, Count, tag}` before
+fn item(slot: String, tag: &Tag) -> Option<InventoryItem> {
+    let id = tag.get("id")?.as_str()?.to_string();
+    let count = tag
+        .get("count")
+        .or_else(|| tag.get("Count"))
+        .and_then(Tag::as_i64)
+        .unwrap_or(1);
+    let custom_name = tag
+        .get("components")
+        .and_then(|components| components.get("minecraft:custom_name"))
+        .or_else(|| tag.get("tag")?.get("display")?.get("Name"))
+        .and_then(Tag::as_str)
+        .map(str::to_string);
+    Some(InventoryItem {
+        slot,
+        id,
+        count,
+        custom_name,
+    })
+}
+
+fn items(list: Option<&Tag>) -> Vec<InventoryItem> {
+    list.map(Tag::as_list)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|stack| {
+            let slot = stack.get("Slot").and_then(Tag::as_i64).unwrap_or(0);
+            item(legacy_slot_name(slot), stack)
+        })
+        .collect()
+}
+
+/// Before 1.16 the dimension was stored as a number
+fn dimension(tag: Option<&Tag>) -> Option<String> {
+    let tag = tag?;
+    if let Some(name) = tag.as_str() {
+        return Some(name.to_string());
+    }
+    Some(
+        match tag.as_i64()? {
+            -1 => "minecraft:the_nether",
+            1 => "minecraft:the_end",
+            _ => "minecraft:overworld",
+        }
+        .to_string(),
+    )
+}
+
+fn game_mode(tag: Option<&Tag>) -> Option<String> {
+    let mode = match tag?.as_i64()? {
+        0 => "survival",
+        1 => "creative",
+        2 => "adventure",
+        3 => "spectator",
+        _ => return None,
+    };
+    Some(mode.to_string())
+}
+
+fn player_data(uuid: String, root: &Tag) -> PlayerData {
+    let position = match root.get("Pos").map(Tag::as_list) {
+        Some([x, y, z]) => match (x.as_f64(), y.as_f64(), z.as_f64()) {
+            (Some(x), Some(y), Some(z)) => Some([x, y, z]),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut inventory = items(root.get("Inventory"));
+    if let Some(Tag::Compound(equipment)) = root.get("equipment") {
+        let mut worn: Vec<InventoryItem> = equipment
+            .iter()
+            .filter_map(|(slot, stack)| item(slot.clone(), stack))
+            .collect();
+        worn.sort_by(|a, b| a.slot.cmp(&b.slot));
+        inventory.extend(worn);
+    }
+
+    PlayerData {
+        uuid,
+        last_modified: None,
+        dimension: dimension(root.get("Dimension")),
+        position,
+        health: root.get("Health").and_then(Tag::as_f64),
+        food_level: root.get("foodLevel").and_then(Tag::as_i64),
+        xp_level: root.get("XpLevel").and_then(Tag::as_i64).unwrap_or(0),
+        xp_total: root.get("XpTotal").and_then(Tag::as_i64).unwrap_or(0),
+        xp_progress: root.get("XpP").and_then(Tag::as_f64).unwrap_or(0.0),
+        game_mode: game_mode(root.get("playerGameType")),
+        inventory,
+        ender_chest: items(root.get("EnderItems")),
+    }
+}
+
+/// Read a player's saved state from `<world>/playerdata/<uuid>.dat`: where they are, their
+/// health and XP, and what's in their inventory and ender chest. Online players are only
+/// saved on autosave and when they leave, so the file can be a few minutes behind
+#[tauri::command]
+pub async fn get_player_data(
+    app_handle: tauri::AppHandle,
+    id: String,
+    uuid: String,
+) -> Result<PlayerData, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let uuid = uuid::Uuid::parse_str(uuid.trim())
+        .map_err(|_| format!("'{}' is not a valid UUID", uuid))?
+        .hyphenated()
+        .to_string();
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let path = world_dir(&instance_dir, &active_world_name(&instance_dir)?)?
+        .join("playerdata")
+        .join(format!("{}.dat", uuid));
+    if !path.exists() {
+        return Err(format!("No saved data for player {}", uuid));
+    }
+
+    let last_modified = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339());
+    let root = tauri::async_runtime::spawn_blocking(move || read_nbt_file(&path))
+        .await
+        .map_err(|e| format!("Failed to read player data: {}", e))??;
+
+    Ok(PlayerData {
+        last_modified,
+        ..player_data(uuid, &root)
+    })
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+
+use crate::{
+    filesystem,
+    models::{CachedProfile, MojangProfile, PlayerProfile},
+};
+
+const MOJANG_PROFILE_API: &str = "https://api.mojang.com/users/profiles/minecraft";
+/// Usernames can change, so cached lookups are refreshed after a week
+const PROFILE_TTL_DAYS: i64 = 7;
+/// Skins change more often than names
+const HEAD_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_HEAD_SIZE: u32 = 64;
+
+/// Insert hyphens into a 32 character UUID
+pub fn hyphenate_uuid(raw: &str) -> String {
+    if raw.len() != 32 {
+        return raw.to_string();
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &raw[..8],
+        &raw[8..12],
+        &raw[12..16],
+        &raw[16..20],
+        &raw[20..]
+    )
+}
+
+/// The UUID an offline-mode server assigns: a v3 UUID of `OfflinePlayer:<name>`
+pub fn offline_uuid(name: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", name)).into();
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+    uuid::Uuid::from_bytes(hash).hyphenated().to_string()
+}
+
+/// Whether `name` could be a Minecraft username
+pub fn is_valid_username(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Look up a player's UUID and correctly-cased name with the Mojang API
+async fn fetch_player(name: &str) -> Result<PlayerProfile, String> {
+    if !is_valid_username(name) {
+        return Err(format!("'{}' is not a valid Minecraft username", name));
+    }
+
+    let response = reqwest::get(format!("{}/{}", MOJANG_PROFILE_API, name))
+        .await
+        .map_err(|e| format!("Failed to look up player {}: {}", name, e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::NO_CONTENT
+    {
+        return Err(format!("No Minecraft account named '{}'", name));
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to look up player {}: HTTP {}",
+            name,
+            response.status()
+        ));
+    }
+
+    let profile: MojangProfile = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse profile for {}: {}", name, e))?;
+
+    Ok(PlayerProfile {
+        uuid: hyphenate_uuid(&profile.id),
+        name: profile.name,
+    })
+}
+
+fn get_cache_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let cache_dir = filesystem::get_data_dir(app_handle)?.join("cache");
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    Ok(cache_dir)
+}
+
+/// Lowercased username -> profile, mirrored to cache/players.json
+fn get_profile_cache(
+    app_handle: &tauri::AppHandle,
+) -> Result<&'static Mutex<HashMap<String, CachedProfile>>, String> {
+    static PROFILES: OnceLock<Mutex<HashMap<String, CachedProfile>>> = OnceLock::new();
+    if let Some(profiles) = PROFILES.get() {
+        return Ok(profiles);
+    }
+
+    let path = get_cache_dir(app_handle)?.join("players.json");
+    let profiles = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    Ok(PROFILES.get_or_init(|| Mutex::new(profiles)))
+}
+
+/// Resolve a username to a profile, using the on-disk cache when it is fresh enough
+pub async fn resolve_player(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+) -> Result<PlayerProfile, String> {
+    let key = name.to_lowercase();
+    let cache = get_profile_cache(app_handle)?;
+
+    if let Some(cached) = cache.lock().unwrap().get(&key) {
+        let fresh = DateTime::parse_from_rfc3339(&cached.fetched_at)
+            .map(|fetched| Utc::now() - fetched.with_timezone(&Utc))
+            .is_ok_and(|age| age < chrono::Duration::days(PROFILE_TTL_DAYS));
+        if fresh {
+            return Ok(cached.profile.clone());
+        }
+    }
+
+    let profile = fetch_player(name).await?;
+
+    let mut profiles = cache.lock().unwrap();
+    profiles.insert(
+        key,
+        CachedProfile {
+            profile: profile.clone(),
+            fetched_at: Utc::now().to_rfc3339(),
+        },
+    );
+    let json = serde_json::to_string(&*profiles)
+        .map_err(|e| format!("Failed to serialize player cache: {}", e))?;
+    fs::write(get_cache_dir(app_handle)?.join("players.json"), json)
+        .map_err(|e| format!("Failed to write player cache: {}", e))?;
+
+    Ok(profile)
+}
+
+async fn download_head(uuid: &str, size: u32) -> Result<Vec<u8>, String> {
+    let sources = [
+        format!("https://mc-heads.net/avatar/{}/{}", uuid, size),
+        format!(
+            "https://crafatar.com/avatars/{}?size={}&overlay",
+            uuid, size
+        ),
+    ];
+
+    let mut last_error = String::new();
+    for url in sources {
+        match reqwest::get(&url).await {
+            Ok(response) if response.status().is_success() => {
+                return response
+                    .bytes()
+                    .await
+                    .map(|bytes| bytes.to_vec())
+                    .map_err(|e| format!("Failed to download head: {}", e));
+            }
+            Ok(response) => last_error = format!("{} -> HTTP {}", url, response.status()),
+            Err(e) => last_error = format!("GET {} failed: {}", url, e),
+        }
+    }
+    Err(last_error)
+}
+
+#[tauri::command]
+pub async fn lookup_player(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<PlayerProfile, String> {
+    resolve_player(&app_handle, name.trim()).await
+}
+
+/// A player's head as a PNG data URL, cached under cache/heads for a day. A stale cached
+/// head is still returned if the avatar services can't be reached
+#[tauri::command]
+pub async fn get_player_head(
+    app_handle: tauri::AppHandle,
+    uuid: String,
+    size: Option<u32>,
+) -> Result<String, String> {
+    let uuid = uuid::Uuid::parse_str(&uuid)
+        .map_err(|_| format!("Invalid player UUID '{}'", uuid))?
+        .simple()
+        .to_string();
+    let size = size.unwrap_or(DEFAULT_HEAD_SIZE).clamp(8, 512);
+
+    let heads_dir = get_cache_dir(&app_handle)?.join("heads");
+    fs::create_dir_all(&heads_dir)
+        .map_err(|e| format!("Failed to create head cache directory: {}", e))?;
+    let path = heads_dir.join(format!("{}-{}.png", uuid, size));
+
+    let age = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+    let bytes = match age {
+        Some(age) if age < HEAD_TTL => fs::read(&path).ok(),
+        _ => None,
+    };
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => match download_head(&uuid, size).await {
+            Ok(bytes) => {
+                fs::write(&path, &bytes)
+                    .map_err(|e| format!("Failed to cache player head: {}", e))?;
+                bytes
+            }
+            Err(e) => fs::read(&path).map_err(|_| e)?,
+        },
+    };
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(bytes)))
+}
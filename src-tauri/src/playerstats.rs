@@ -0,0 +1,116 @@
+use std::{collections::HashMap, fs, path::Path, time::SystemTime};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::{
+    filesystem,
+    instance::get_instance_by_id,
+    models::{PlayerStats, UserCacheEntry},
+    world::{active_world_name, world_dir},
+};
+
+/// Ticks played. Named `play_one_minute` before 1.17 even though it counted ticks
+const PLAY_TIME: &[&str] = &["minecraft:play_time", "minecraft:play_one_minute"];
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// A stat from a stats file. 1.13+ nests them by category, older versions use flat
+/// `stat.*` keys
+fn custom_stat(stats: &Value, names: &[&str], legacy: &str) -> u64 {
+    let custom = stats.pointer("/stats/minecraft:custom");
+    names
+        .iter()
+        .find_map(|name| custom.and_then(|custom| custom.get(*name)?.as_u64()))
+        .or_else(|| stats.get(legacy)?.as_u64())
+        .unwrap_or(0)
+}
+
+fn read_stats(uuid: String, name: Option<String>, path: &Path) -> PlayerStats {
+    let stats: Value = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or(Value::Null);
+
+    PlayerStats {
+        uuid,
+        name,
+        last_seen: None,
+        play_time_ticks: custom_stat(&stats, PLAY_TIME, "stat.playOneMinute"),
+        deaths: custom_stat(&stats, &["minecraft:deaths"], "stat.deaths"),
+        mob_kills: custom_stat(&stats, &["minecraft:mob_kills"], "stat.mobKills"),
+        player_kills: custom_stat(&stats, &["minecraft:player_kills"], "stat.playerKills"),
+        distance_walked_cm: custom_stat(&stats, &["minecraft:walk_one_cm"], "stat.walkOneCm")
+            + custom_stat(&stats, &["minecraft:sprint_one_cm"], "stat.sprintOneCm"),
+        sessions: custom_stat(&stats, &["minecraft:leave_game"], "stat.leaveGame"),
+    }
+}
+
+/// Every player the server has seen, from `usercache.json` and the active world's stats
+/// files, most recently seen first. Works from the files alone, so the server doesn't
+/// need to be running. The server saves stats when players leave and on autosave, so
+/// they can lag behind a running server by a few minutes
+#[tauri::command]
+pub async fn get_player_stats(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<PlayerStats>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let world = world_dir(&instance_dir, &active_world_name(&instance_dir)?)?;
+
+    let usercache: Vec<UserCacheEntry> =
+        match fs::read_to_string(instance_dir.join("usercache.json")) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse usercache.json: {}", e))?,
+            Err(_) => Vec::new(),
+        };
+    let mut names: HashMap<String, String> = usercache
+        .into_iter()
+        .map(|entry| (entry.uuid.to_ascii_lowercase(), entry.name))
+        .collect();
+
+    let stats_dir = world.join("stats");
+    let playerdata_dir = world.join("playerdata");
+    let mut players = Vec::new();
+    if let Ok(entries) = fs::read_dir(&stats_dir) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(uuid) = file_name.strip_suffix(".json") else {
+                continue;
+            };
+            let uuid = uuid.to_ascii_lowercase();
+            let name = names.remove(&uuid);
+            players.push(read_stats(uuid, name, &entry.path()));
+        }
+    }
+    // Cached players without stats joined before stats were kept, or never got saved
+    players.extend(names.into_iter().map(|(uuid, name)| PlayerStats {
+        uuid,
+        name: Some(name),
+        last_seen: None,
+        play_time_ticks: 0,
+        deaths: 0,
+        mob_kills: 0,
+        player_kills: 0,
+        distance_walked_cm: 0,
+        sessions: 0,
+    }));
+
+    // Stats and player data are both written when a player leaves
+    let mut last_seen: Vec<(PlayerStats, Option<SystemTime>)> = players
+        .into_iter()
+        .map(|mut player| {
+            let seen = modified(&stats_dir.join(format!("{}.json", player.uuid))).max(modified(
+                &playerdata_dir.join(format!("{}.dat", player.uuid)),
+            ));
+            player.last_seen = seen.map(|seen| DateTime::<Utc>::from(seen).to_rfc3339());
+            (player, seen)
+        })
+        .collect();
+    last_seen.sort_by(|(a, a_seen), (b, b_seen)| b_seen.cmp(a_seen).then(a.name.cmp(&b.name)));
+
+    Ok(last_seen.into_iter().map(|(player, _)| player).collect())
+}
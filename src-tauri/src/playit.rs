@@ -8,7 +8,7 @@ use std::{
 };
 
 use reqwest::{header, Client, StatusCode};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::json;
 use tauri::async_runtime;
 use tokio::time::sleep;
@@ -18,6 +18,9 @@ use crate::models::PlayitTunnelMetadata;
 const API_BASE: &str = "https://api.playit.gg";
 const RUN_DATA_PATH: &str = "/v1/agents/rundata";
 const LEGACY_RUN_DATA_PATH: &str = "/agents/rundata";
+const CREATE_TUNNEL_PATH: &str = "/tunnels/create";
+const RENAME_TUNNEL_PATH: &str = "/tunnels/rename";
+const DELETE_TUNNEL_PATH: &str = "/tunnels/delete";
 const USER_AGENT: &str = "nuko-playit/0.1";
 const AGENT_TYPE: &str = "self-managed";
 const AGENT_VERSION: &str = "0.15.13";
@@ -80,6 +83,135 @@ impl PlayitClient {
         }
     }
 
+    /// Create a tunnel that forwards to `local_port` on this agent, returning the tunnel id
+    pub async fn create_tunnel(
+        &self,
+        name: &str,
+        tunnel_type: &str,
+        port_type: &str,
+        local_port: u16,
+    ) -> Result<String, String> {
+        let agent_id = self.fetch_agent_id().await?;
+        let created: CreatedTunnel = self
+            .post_action(
+                CREATE_TUNNEL_PATH,
+                json!({
+                    "name": name,
+                    "tunnel_type": tunnel_type,
+                    "port_type": port_type,
+                    "port_count": 1,
+                    "origin": {
+                        "type": "agent",
+                        "data": {
+                            "agent_id": agent_id,
+                            "local_ip": "127.0.0.1",
+                            "local_port": local_port,
+                        },
+                    },
+                    "enabled": true,
+                    "alloc": null,
+                    "firewall_id": null,
+                    "proxy_protocol": null,
+                }),
+            )
+            .await
+            .map_err(|e| format!("Playit couldn't create the tunnel: {e}"))?;
+        Ok(created.id)
+    }
+
+    /// Rename one of this account's tunnels
+    pub async fn rename_tunnel(&self, tunnel_id: &str, name: &str) -> Result<(), String> {
+        self.post_action::<serde_json::Value>(
+            RENAME_TUNNEL_PATH,
+            json!({ "tunnel_id": tunnel_id, "name": name }),
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Playit couldn't rename the tunnel: {e}"))
+    }
+
+    /// Delete one of this account's tunnels
+    pub async fn delete_tunnel(&self, tunnel_id: &str) -> Result<(), String> {
+        self.post_action::<serde_json::Value>(DELETE_TUNNEL_PATH, json!({ "tunnel_id": tunnel_id }))
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Playit couldn't delete the tunnel: {e}"))
+    }
+
+    /// POST to an API endpoint and unwrap its response envelope
+    async fn post_action<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<T, String> {
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .header(
+                header::AUTHORIZATION,
+                format!("Agent-Key {}", self.secret.trim()),
+            )
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Playit request failed: {e}"))?;
+
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read Playit response body: {e}"))?;
+        if status != StatusCode::OK {
+            return Err(format!(
+                "Playit responded with {}: {}",
+                status,
+                body_snippet(&body)
+            ));
+        }
+
+        let envelope: ApiEnvelope<T> = serde_json::from_slice(&body).map_err(|e| {
+            format!(
+                "Failed to parse Playit response: {e}. Body: {}",
+                body_snippet(&body)
+            )
+        })?;
+        match envelope {
+            ApiEnvelope::Success { data } => Ok(data),
+            ApiEnvelope::Fail { data } => Err(data.to_string()),
+            ApiEnvelope::Error { error } => {
+                Err(format!("Playit API internal error: {}", error.message()))
+            }
+        }
+    }
+
+    async fn fetch_agent_id(&self) -> Result<String, String> {
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, RUN_DATA_PATH))
+            .header(
+                header::AUTHORIZATION,
+                format!("Agent-Key {}", self.secret.trim()),
+            )
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .map_err(|e| format!("Playit request failed: {e}"))?;
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read Playit response body: {e}"))?;
+
+        match serde_json::from_slice::<ApiEnvelope<AgentRunDataV1>>(&body) {
+            Ok(ApiEnvelope::Success { data }) => data
+                .agent_id
+                .ok_or_else(|| "Playit didn't report an agent id".to_string()),
+            _ => Err(format!(
+                "Failed to look up the Playit agent: {}",
+                body_snippet(&body)
+            )),
+        }
+    }
+
     async fn fetch_tunnels_v1(&self) -> Result<Vec<PlayitTunnelMetadata>, (bool, String)> {
         let response = self
             .http
@@ -240,10 +372,17 @@ impl ApiErrorPayload {
 
 #[derive(Debug, Deserialize)]
 struct AgentRunDataV1 {
+    #[serde(default)]
+    agent_id: Option<String>,
     #[serde(default)]
     tunnels: Vec<AgentTunnelV1>,
 }
 
+#[derive(Debug, Deserialize)]
+struct CreatedTunnel {
+    id: String,
+}
+
 impl AgentRunDataV1 {
     fn into_metadata(self) -> Vec<PlayitTunnelMetadata> {
         self.tunnels.into_iter().map(|t| t.into()).collect()
@@ -0,0 +1,310 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use igd_next::{Gateway, PortMappingProtocol, SearchOptions};
+
+use crate::{
+    instance::{get_instance_by_id, record_log_line, update_instance_config},
+    models::PortForwardStatus,
+};
+
+const NATPMP_PORT: u16 = 5351;
+/// Mappings are leased rather than permanent so a crash can't leave the port open forever
+const LEASE_SECS: u32 = 7200;
+const RENEW_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+enum Mapping {
+    Upnp(Box<Gateway>),
+    NatPmp(Ipv4Addr),
+}
+
+struct ActiveMapping {
+    mapping: Mapping,
+    internal_port: u16,
+    external_port: u16,
+}
+
+fn get_mappings() -> &'static Mutex<HashMap<String, ActiveMapping>> {
+    static MAPPINGS: OnceLock<Mutex<HashMap<String, ActiveMapping>>> = OnceLock::new();
+    MAPPINGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_statuses() -> &'static Mutex<HashMap<String, PortForwardStatus>> {
+    static STATUSES: OnceLock<Mutex<HashMap<String, PortForwardStatus>>> = OnceLock::new();
+    STATUSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The address this machine uses to reach `gateway`, which is where the router should forward to
+fn local_ip_towards(gateway: SocketAddr) -> Result<IpAddr, String> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))
+        .map_err(|e| format!("Failed to open a UDP socket: {}", e))?;
+    socket
+        .connect(gateway)
+        .map_err(|e| format!("Failed to route to {}: {}", gateway, e))?;
+    socket
+        .local_addr()
+        .map(|addr| addr.ip())
+        .map_err(|e| format!("Failed to read the local address: {}", e))
+}
+
+fn upnp_map(port: u16) -> Result<(Gateway, Option<IpAddr>), String> {
+    let gateway = igd_next::search_gateway(SearchOptions {
+        timeout: Some(Duration::from_secs(3)),
+        ..Default::default()
+    })
+    .map_err(|e| format!("No UPnP router found: {}", e))?;
+    let local = SocketAddr::new(local_ip_towards(gateway.addr)?, port);
+
+    gateway
+        .add_port(PortMappingProtocol::TCP, port, local, LEASE_SECS, "nuko")
+        .map_err(|e| format!("Router refused the UPnP mapping: {}", e))?;
+    let external_ip = gateway.get_external_ip().ok();
+    Ok((gateway, external_ip))
+}
+
+/// The IPv4 default gateway, which is where NAT-PMP requests go
+fn default_gateway() -> Option<Ipv4Addr> {
+    #[cfg(target_os = "linux")]
+    {
+        // Columns are Iface, Destination, Gateway, ... with addresses in little-endian hex
+        let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+        routes.lines().skip(1).find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) != Some(&"00000000") {
+                return None;
+            }
+            let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+            Some(Ipv4Addr::from(gateway.to_le_bytes())).filter(|ip| !ip.is_unspecified())
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("route")
+            .args(["-n", "get", "default"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("gateway:")?.trim().parse().ok())
+    }
+    #[cfg(windows)]
+    {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "(Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | Select-Object -First 1).NextHop",
+            ])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        None
+    }
+}
+
+/// Send a NAT-PMP request, retrying with a doubling timeout as RFC 6886 asks
+fn natpmp_request(gateway: Ipv4Addr, request: &[u8]) -> Result<Vec<u8>, String> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))
+        .map_err(|e| format!("Failed to open a UDP socket: {}", e))?;
+    socket
+        .connect((gateway, NATPMP_PORT))
+        .map_err(|e| format!("Failed to reach {}: {}", gateway, e))?;
+
+    let mut timeout = Duration::from_millis(250);
+    let mut buf = [0u8; 16];
+    for _ in 0..4 {
+        socket
+            .send(request)
+            .map_err(|e| format!("Failed to send NAT-PMP request: {}", e))?;
+        let _ = socket.set_read_timeout(Some(timeout));
+        if let Ok(len) = socket.recv(&mut buf) {
+            if len < 4 || buf[1] != request[1] | 0x80 {
+                continue;
+            }
+            let result = u16::from_be_bytes([buf[2], buf[3]]);
+            if result != 0 {
+                return Err(format!(
+                    "Router refused the NAT-PMP request (code {})",
+                    result
+                ));
+            }
+            return Ok(buf[..len].to_vec());
+        }
+        timeout *= 2;
+    }
+    Err(format!("No NAT-PMP response from {}", gateway))
+}
+
+/// Ask for a TCP mapping of `port`, returning the external port the router picked
+fn natpmp_map(gateway: Ipv4Addr, port: u16, lifetime: u32) -> Result<u16, String> {
+    let mut request = vec![0, 2, 0, 0];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&lifetime.to_be_bytes());
+    let response = natpmp_request(gateway, &request)?;
+    if response.len() < 16 {
+        return Err("Malformed NAT-PMP response".into());
+    }
+    Ok(u16::from_be_bytes([response[10], response[11]]))
+}
+
+fn natpmp_external_ip(gateway: Ipv4Addr) -> Option<IpAddr> {
+    let response = natpmp_request(gateway, &[0, 0]).ok()?;
+    let octets: [u8; 4] = response.get(8..12)?.try_into().ok()?;
+    Some(IpAddr::V4(Ipv4Addr::from(octets)))
+}
+
+/// Keep the lease alive for as long as the mapping is registered for the instance
+fn spawn_renewal(id: String) {
+    thread::spawn(move || loop {
+        thread::sleep(RENEW_INTERVAL);
+        let renewal = get_mappings().lock().unwrap().get(&id).map(|active| {
+            let mapping = match &active.mapping {
+                Mapping::Upnp(gateway) => Mapping::Upnp(gateway.clone()),
+                Mapping::NatPmp(gateway) => Mapping::NatPmp(*gateway),
+            };
+            (mapping, active.internal_port, active.external_port)
+        });
+        let Some((mapping, internal_port, external_port)) = renewal else {
+            return;
+        };
+        match mapping {
+            Mapping::Upnp(gateway) => {
+                if let Ok(local_ip) = local_ip_towards(gateway.addr) {
+                    let _ = gateway.add_port(
+                        PortMappingProtocol::TCP,
+                        external_port,
+                        SocketAddr::new(local_ip, internal_port),
+                        LEASE_SECS,
+                        "nuko",
+                    );
+                }
+            }
+            Mapping::NatPmp(gateway) => {
+                let _ = natpmp_map(gateway, internal_port, LEASE_SECS);
+            }
+        }
+    });
+}
+
+/// Forward `port` on the local router, trying UPnP first and then NAT-PMP. The outcome is
+/// written to the console and kept for `get_port_forward_status`; failure never stops the
+/// server from starting
+pub fn open(app_handle: &tauri::AppHandle, id: &str, port: u16) {
+    let result = upnp_map(port)
+        .map(|(gateway, external_ip)| (Mapping::Upnp(Box::new(gateway)), "upnp", external_ip, port))
+        .or_else(|upnp_error| {
+            let gateway = default_gateway().ok_or(format!(
+                "{}; NAT-PMP unavailable: no default gateway",
+                upnp_error
+            ))?;
+            let external_port = natpmp_map(gateway, port, LEASE_SECS)
+                .map_err(|e| format!("{}; {}", upnp_error, e))?;
+            Ok::<_, String>((
+                Mapping::NatPmp(gateway),
+                "natpmp",
+                natpmp_external_ip(gateway),
+                external_port,
+            ))
+        });
+
+    let status = match result {
+        Ok((mapping, method, external_ip, external_port)) => {
+            get_mappings().lock().unwrap().insert(
+                id.to_string(),
+                ActiveMapping {
+                    mapping,
+                    internal_port: port,
+                    external_port,
+                },
+            );
+            spawn_renewal(id.to_string());
+            let external_ip = external_ip.map(|ip| ip.to_string());
+            record_log_line(
+                app_handle,
+                id,
+                format!(
+                    "[nuko] Router forwards {}:{} to port {} ({})",
+                    external_ip.as_deref().unwrap_or("?"),
+                    external_port,
+                    port,
+                    method
+                ),
+            );
+            PortForwardStatus {
+                active: true,
+                method: Some(method.to_string()),
+                external_ip,
+                external_port: Some(external_port),
+                internal_port: port,
+                error: None,
+            }
+        }
+        Err(error) => {
+            record_log_line(
+                app_handle,
+                id,
+                format!("[nuko] Port forwarding failed: {}", error),
+            );
+            PortForwardStatus {
+                active: false,
+                method: None,
+                external_ip: None,
+                external_port: None,
+                internal_port: port,
+                error: Some(error),
+            }
+        }
+    };
+    get_statuses()
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), status);
+}
+
+/// Remove the instance's router mapping, if one was made
+pub fn close(id: &str) {
+    get_statuses().lock().unwrap().remove(id);
+    let Some(active) = get_mappings().lock().unwrap().remove(id) else {
+        return;
+    };
+    match active.mapping {
+        Mapping::Upnp(gateway) => {
+            let _ = gateway.remove_port(PortMappingProtocol::TCP, active.external_port);
+        }
+        Mapping::NatPmp(gateway) => {
+            let _ = natpmp_map(gateway, active.internal_port, 0);
+        }
+    }
+}
+
+/// The last port mapping outcome for a running instance
+pub fn status(id: &str) -> Option<PortForwardStatus> {
+    get_statuses().lock().unwrap().get(id).cloned()
+}
+
+/// Turn router port forwarding on or off for the instance. Applies from the next start
+#[tauri::command]
+pub async fn set_port_forwarding(
+    app_handle: tauri::AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.upnp = enabled;
+    update_instance_config(&app_handle, &config)
+}
+
+/// Whether the router mapping for a running instance succeeded, and on which external port
+#[tauri::command]
+pub async fn get_port_forward_status(id: String) -> Result<Option<PortForwardStatus>, String> {
+    Ok(status(&id))
+}
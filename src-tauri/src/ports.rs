@@ -0,0 +1,191 @@
+use std::{
+    collections::HashSet,
+    net::{TcpListener, UdpSocket},
+    path::{Path, PathBuf},
+};
+
+use tauri::Manager;
+
+use crate::{
+    filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, is_instance_server_process, update_instance_config},
+    properties::ServerProperties,
+};
+
+const DEFAULT_SERVER_PORT: u16 = 25565;
+pub const DEFAULT_RCON_PORT: u16 = 25575;
+
+/// Ports a server will bind according to its server.properties, as (property, port)
+pub fn configured_ports(properties: &ServerProperties) -> Vec<(&'static str, u16)> {
+    let port_of = |key: &str, default: u16| {
+        properties
+            .get(key)
+            .and_then(|value| value.trim().parse::<u16>().ok())
+            .unwrap_or(default)
+    };
+    let enabled = |key: &str| {
+        properties
+            .get(key)
+            .map(|v| v.trim() == "true")
+            .unwrap_or(false)
+    };
+
+    let server_port = port_of("server-port", DEFAULT_SERVER_PORT);
+    let mut ports = vec![("server-port", server_port)];
+    if enabled("enable-rcon") {
+        ports.push(("rcon.port", port_of("rcon.port", DEFAULT_RCON_PORT)));
+    }
+    if enabled("enable-query") {
+        ports.push(("query.port", port_of("query.port", server_port)));
+    }
+    ports
+}
+
+/// The port the server listens on, from its server.properties
+pub fn server_port(instance_dir: &Path) -> u16 {
+    ServerProperties::load(instance_dir)
+        .ok()
+        .and_then(|properties| properties.get("server-port")?.trim().parse().ok())
+        .unwrap_or(DEFAULT_SERVER_PORT)
+}
+
+/// Whether nothing on this machine is currently listening on the TCP port
+pub fn is_port_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// Query runs over UDP while the game and RCON ports are TCP
+fn is_property_port_free(key: &str, port: u16) -> bool {
+    if key == "query.port" {
+        UdpSocket::bind(("0.0.0.0", port)).is_ok()
+    } else {
+        is_port_free(port)
+    }
+}
+
+/// Find the first port at or after `start` that is free and not reserved
+pub fn next_free_port(start: u16, reserved: &HashSet<u16>) -> Option<u16> {
+    (start..=u16::MAX).find(|port| !reserved.contains(port) && is_port_free(*port))
+}
+
+/// Like `next_free_port`, for servers that listen on UDP such as Bedrock
+pub fn next_free_udp_port(start: u16, reserved: &HashSet<u16>) -> Option<u16> {
+    (start..=u16::MAX)
+        .find(|port| !reserved.contains(port) && UdpSocket::bind(("0.0.0.0", *port)).is_ok())
+}
+
+/// `next_free_port` for the protocol the property's port is used with
+fn next_free_property_port(key: &str, start: u16, reserved: &HashSet<u16>) -> Option<u16> {
+    if key == "query.port" {
+        next_free_udp_port(start, reserved)
+    } else {
+        next_free_port(start, reserved)
+    }
+}
+
+/// Ports configured by every other instance, paired with that instance's name and directory
+fn other_instance_ports(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<Vec<(u16, String, PathBuf)>, String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    let mut ports = Vec::new();
+
+    for entry in app_handle.state::<InstanceIndex>().all(&instances_dir)? {
+        if entry.config.id == id {
+            continue;
+        }
+        let Ok(properties) = ServerProperties::load(&entry.dir) else {
+            continue;
+        };
+        for (_, port) in configured_ports(&properties) {
+            ports.push((port, entry.config.name.clone(), entry.dir.clone()));
+        }
+    }
+
+    Ok(ports)
+}
+
+/// Ports claimed by every instance other than `id`, so new ports don't collide with them
+pub fn reserved_ports(app_handle: &tauri::AppHandle, id: &str) -> Result<HashSet<u16>, String> {
+    Ok(other_instance_ports(app_handle, id)?
+        .into_iter()
+        .map(|(port, _, _)| port)
+        .collect())
+}
+
+/// Make sure every port the instance is about to bind is available. With `auto_assign_ports`
+/// enabled, conflicting ports are moved to the next free port in server.properties instead
+pub fn ensure_ports_available(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    instance_dir: &Path,
+) -> Result<(), String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let mut properties = ServerProperties::load(instance_dir)?;
+    let others = other_instance_ports(app_handle, id)?;
+
+    let mut reserved: HashSet<u16> = others.iter().map(|(port, _, _)| *port).collect();
+    let mut changed = false;
+
+    let mut sys = sysinfo::System::new_all();
+    sys.refresh_all();
+    let is_running = |dir: &Path| {
+        sys.processes()
+            .values()
+            .any(|process| is_instance_server_process(process, dir))
+    };
+
+    for (key, port) in configured_ports(&properties) {
+        let owner = others
+            .iter()
+            .find(|(other_port, _, dir)| *other_port == port && is_running(dir))
+            .map(|(_, name, _)| name.clone());
+
+        if owner.is_none() && is_property_port_free(key, port) {
+            reserved.insert(port);
+            continue;
+        }
+
+        if !config.auto_assign_ports {
+            return Err(match owner {
+                Some(name) => format!(
+                    "Port {} ({}) is already in use by instance '{}'",
+                    port, key, name
+                ),
+                None => format!(
+                    "Port {} ({}) is already in use by another program",
+                    port, key
+                ),
+            });
+        }
+
+        let new_port = next_free_property_port(key, port.saturating_add(1), &reserved)
+            .ok_or_else(|| format!("No free port available for {}", key))?;
+        println!(
+            "Port {} ({}) is in use, moving {} to {}",
+            port, key, config.name, new_port
+        );
+        properties.set(key, new_port.to_string());
+        reserved.insert(new_port);
+        changed = true;
+    }
+
+    if changed {
+        properties.save()?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_auto_assign_ports(
+    app_handle: tauri::AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.auto_assign_ports = enabled;
+    update_instance_config(&app_handle, &config)
+}
@@ -0,0 +1,180 @@
+use std::{path::Path, process::Command, thread};
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, is_instance_server_process, update_instance_config},
+    models::ProcessConfig,
+};
+
+fn run(program: &str, args: &[String]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Niceness is per thread on Linux, so every thread the JVM has started gets reniced.
+/// Threads started later inherit it from the thread that creates them
+#[cfg(target_os = "linux")]
+fn set_priority(pid: u32, nice: i32) -> Result<(), String> {
+    let mut args = vec!["-n".to_string(), nice.to_string(), "-p".to_string()];
+    match std::fs::read_dir(format!("/proc/{}/task", pid)) {
+        Ok(tasks) => args.extend(
+            tasks
+                .flatten()
+                .map(|task| task.file_name().to_string_lossy().into_owned()),
+        ),
+        Err(_) => args.push(pid.to_string()),
+    }
+    run("renice", &args)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_priority(pid: u32, nice: i32) -> Result<(), String> {
+    run(
+        "renice",
+        &["-n".into(), nice.to_string(), "-p".into(), pid.to_string()],
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(pid: u32, cores: &[usize]) -> Result<(), String> {
+    let list = cores
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    run(
+        "taskset",
+        &["-a".into(), "-p".into(), "-c".into(), list, pid.to_string()],
+    )
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_affinity(_pid: u32, _cores: &[usize]) -> Result<(), String> {
+    Err("CPU affinity isn't supported on this OS".into())
+}
+
+/// Windows priority class for a Unix niceness
+#[cfg(windows)]
+fn priority_class(nice: i32) -> &'static str {
+    match nice {
+        i32::MIN..=-10 => "High",
+        -9..=-1 => "AboveNormal",
+        0 => "Normal",
+        1..=9 => "BelowNormal",
+        _ => "Idle",
+    }
+}
+
+#[cfg(windows)]
+fn set_priority(pid: u32, nice: i32) -> Result<(), String> {
+    run(
+        "powershell",
+        &[
+            "-NoProfile".into(),
+            "-Command".into(),
+            format!(
+                "(Get-Process -Id {}).PriorityClass = '{}'",
+                pid,
+                priority_class(nice)
+            ),
+        ],
+    )
+}
+
+#[cfg(windows)]
+fn set_affinity(pid: u32, cores: &[usize]) -> Result<(), String> {
+    let mask = cores.iter().fold(0u64, |mask, core| mask | (1 << core));
+    run(
+        "powershell",
+        &[
+            "-NoProfile".into(),
+            "-Command".into(),
+            format!("(Get-Process -Id {}).ProcessorAffinity = {}", pid, mask),
+        ],
+    )
+}
+
+/// Apply priority and affinity settings to a running process. Unset values are left alone
+pub fn apply(pid: u32, settings: &ProcessConfig) -> Result<(), String> {
+    if let Some(nice) = settings.priority {
+        set_priority(pid, nice).map_err(|e| format!("Failed to set priority: {}", e))?;
+    }
+    if !settings.cpu_affinity.is_empty() {
+        set_affinity(pid, &settings.cpu_affinity)
+            .map_err(|e| format!("Failed to set CPU affinity: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Whether there's anything to apply at all
+pub fn is_configured(settings: &ProcessConfig) -> bool {
+    settings.priority.is_some() || !settings.cpu_affinity.is_empty()
+}
+
+fn validate(settings: &ProcessConfig) -> Result<(), String> {
+    if let Some(nice) = settings.priority {
+        if !(-20..=19).contains(&nice) {
+            return Err("Priority must be between -20 and 19".into());
+        }
+    }
+    let cores = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    // Windows affinity is a 64-bit mask
+    if let Some(core) = settings
+        .cpu_affinity
+        .iter()
+        .find(|&&core| core >= cores || core >= 64)
+    {
+        return Err(format!(
+            "CPU core {} doesn't exist, this machine has {} cores",
+            core, cores
+        ));
+    }
+    Ok(())
+}
+
+fn running_pids(instance_dir: &Path) -> Vec<u32> {
+    let mut sys = sysinfo::System::new();
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+    sys.processes()
+        .values()
+        .filter(|process| is_instance_server_process(process, instance_dir))
+        .map(|process| process.pid().as_u32())
+        .collect()
+}
+
+/// Save an instance's priority and CPU affinity, applying them right away if it's running
+#[tauri::command]
+pub async fn set_process_settings(
+    app_handle: tauri::AppHandle,
+    id: String,
+    settings: ProcessConfig,
+) -> Result<(), String> {
+    validate(&settings)?;
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    config.process = settings.clone();
+    update_instance_config(&app_handle, &config)?;
+
+    if is_configured(&settings) {
+        tauri::async_runtime::spawn_blocking(move || {
+            running_pids(&instance_dir)
+                .into_iter()
+                .try_for_each(|pid| apply(pid, &settings))
+        })
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))??;
+    }
+    Ok(())
+}
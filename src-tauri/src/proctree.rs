@@ -0,0 +1,69 @@
+use std::process::Command;
+
+use sysinfo::{Pid, ProcessesToUpdate, Signal, System};
+
+/// Start a child in its own process group (a new console process group on Windows), so the
+/// whole tree it spawns can be signalled together and Ctrl+C in nuko's terminal doesn't
+/// reach it
+pub fn isolate(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// `root` and every process below it, parents before children. Collected up front because
+/// killing a parent re-parents its children
+fn tree(sys: &System, root: Pid) -> Vec<Pid> {
+    let mut pids = vec![root];
+    let mut next = 0;
+    while next < pids.len() {
+        let parent = pids[next];
+        pids.extend(
+            sys.processes()
+                .values()
+                .filter(|process| process.parent() == Some(parent))
+                .map(|process| process.pid()),
+        );
+        next += 1;
+    }
+    pids
+}
+
+/// Signal a process and all of its descendants: SIGKILL with `force`, SIGTERM otherwise.
+/// Wrapper scripts (e.g. Forge's run.sh) would otherwise leave the JVM they started behind
+pub fn signal_tree(root: u32, force: bool) {
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    let pids = tree(&sys, Pid::from_u32(root));
+
+    #[cfg(windows)]
+    if force {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &root.to_string()])
+            .output();
+    }
+    // Processes started with `isolate` lead their own group, which also catches anything
+    // that already slipped out of the tree by double-forking
+    #[cfg(unix)]
+    {
+        let signal = if force { "-KILL" } else { "-TERM" };
+        let _ = Command::new("kill")
+            .args([signal, "--", &format!("-{}", root)])
+            .output();
+    }
+
+    let signal = if force { Signal::Kill } else { Signal::Term };
+    for pid in pids.into_iter().rev() {
+        if let Some(process) = sys.process(pid) {
+            let _ = process.kill_with(signal);
+        }
+    }
+}
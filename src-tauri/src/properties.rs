@@ -0,0 +1,246 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::models::InitialServerProperties;
+
+const GAMEMODES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+const DIFFICULTIES: [&str; 4] = ["peaceful", "easy", "normal", "hard"];
+
+#[derive(Debug, Clone)]
+enum PropertyLine {
+    Comment(String),
+    Entry(String, String),
+}
+
+/// A `server.properties` file that keeps comments and key order intact when edited
+#[derive(Debug, Clone)]
+pub struct ServerProperties {
+    path: PathBuf,
+    lines: Vec<PropertyLine>,
+}
+
+impl ServerProperties {
+    /// Load the instance's server.properties, or start an empty one if it doesn't exist yet
+    pub fn load(instance_dir: &Path) -> Result<Self, String> {
+        let path = instance_dir.join("server.properties");
+        let content = if path.exists() {
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read server.properties: {}", e))?
+        } else {
+            String::new()
+        };
+
+        let lines = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    return PropertyLine::Comment(line.to_string());
+                }
+                let (key, value) = split_entry(trimmed);
+                PropertyLine::Entry(unescape(&key), unescape(&value))
+            })
+            .collect();
+
+        Ok(Self { path, lines })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            PropertyLine::Entry(k, v) if k == key => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        for line in &mut self.lines {
+            if let PropertyLine::Entry(k, v) = line {
+                if k == key {
+                    *v = value;
+                    return;
+                }
+            }
+        }
+        self.lines.push(PropertyLine::Entry(key.to_string(), value));
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.lines
+            .retain(|line| !matches!(line, PropertyLine::Entry(k, _) if k == key));
+    }
+
+    /// Key/value pairs in file order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            PropertyLine::Entry(k, v) => Some((k.as_str(), v.as_str())),
+            PropertyLine::Comment(_) => None,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut content = String::new();
+        for line in &self.lines {
+            match line {
+                PropertyLine::Comment(comment) => content.push_str(comment),
+                PropertyLine::Entry(k, v) => {
+                    content.push_str(&escape(k, true));
+                    content.push('=');
+                    content.push_str(&escape(v, false));
+                }
+            }
+            content.push('\n');
+        }
+
+        fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write server.properties: {}", e))
+    }
+}
+
+/// Split a properties line on the first unescaped `=`, `:` or whitespace separator
+fn split_entry(line: &str) -> (String, String) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' => {
+                return (
+                    line[..i].trim_end().to_string(),
+                    line[i + 1..].trim_start().to_string(),
+                )
+            }
+            c if c.is_whitespace() => {
+                let rest = line[i..].trim_start();
+                let rest = rest
+                    .strip_prefix('=')
+                    .or_else(|| rest.strip_prefix(':'))
+                    .unwrap_or(rest);
+                return (line[..i].to_string(), rest.trim_start().to_string());
+            }
+            _ => {}
+        }
+    }
+    (line.to_string(), String::new())
+}
+
+/// Decode Java properties escapes, including `\uXXXX` sequences
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) => result.push(decoded),
+                    None => {
+                        result.push_str("\\u");
+                        result.push_str(&hex);
+                    }
+                }
+            }
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Encode a key or value the way Minecraft writes server.properties: non-ASCII characters
+/// (like the `§` formatting prefix) become `\uXXXX`, and separators are backslash-escaped
+pub fn escape(value: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            ' ' if is_key || i == 0 => result.push_str("\\ "),
+            c if (c as u32) < 0x20 || (c as u32) > 0x7e => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    result.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Check the properties chosen at creation time before anything is written to disk
+pub fn validate_initial_properties(initial: &InitialServerProperties) -> Result<(), String> {
+    if initial.port == Some(0) {
+        return Err("Server port cannot be 0".into());
+    }
+    if let Some(gamemode) = &initial.gamemode {
+        if !GAMEMODES.contains(&gamemode.to_lowercase().as_str()) {
+            return Err(format!("Unknown gamemode '{}'", gamemode));
+        }
+    }
+    if let Some(difficulty) = &initial.difficulty {
+        if !DIFFICULTIES.contains(&difficulty.to_lowercase().as_str()) {
+            return Err(format!("Unknown difficulty '{}'", difficulty));
+        }
+    }
+    Ok(())
+}
+
+/// Write the properties chosen at creation time so the server doesn't need a first launch
+/// just to generate server.properties
+pub fn write_initial_properties(
+    instance_dir: &Path,
+    initial: &InitialServerProperties,
+) -> Result<(), String> {
+    validate_initial_properties(initial)?;
+
+    let mut properties = ServerProperties::load(instance_dir)?;
+
+    if let Some(port) = initial.port {
+        properties.set("server-port", port.to_string());
+    }
+    if let Some(max_players) = initial.max_players {
+        properties.set("max-players", max_players.to_string());
+    }
+    if let Some(motd) = &initial.motd {
+        properties.set("motd", motd.clone());
+    }
+    if let Some(seed) = initial
+        .seed
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        properties.set("level-seed", seed);
+    }
+    if let Some(gamemode) = &initial.gamemode {
+        properties.set("gamemode", gamemode.to_lowercase());
+    }
+    if let Some(difficulty) = &initial.difficulty {
+        properties.set("difficulty", difficulty.to_lowercase());
+    }
+    if let Some(online_mode) = initial.online_mode {
+        properties.set("online-mode", online_mode.to_string());
+    }
+
+    properties.save()
+}
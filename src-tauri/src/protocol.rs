@@ -0,0 +1,169 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    filesystem, instance::get_instance_by_id, models::PingResult, motd,
+    properties::ServerProperties,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest status response we accept; favicons make these a few tens of KB
+const MAX_PACKET_LEN: usize = 2 * 1024 * 1024;
+
+pub fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+}
+
+pub fn read_varint(reader: &mut impl Read) -> Result<i32, String> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        reader
+            .read_exact(&mut byte)
+            .map_err(|e| format!("Failed to read from server: {}", e))?;
+        value |= ((byte[0] & 0x7f) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err("VarInt is too long".into())
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_varint(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// Prefix a packet id and body with its length and send it
+fn send_packet(stream: &mut TcpStream, packet_id: i32, body: &[u8]) -> Result<(), String> {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, packet_id);
+    payload.extend_from_slice(body);
+
+    let mut packet = Vec::new();
+    write_varint(&mut packet, payload.len() as i32);
+    packet.extend_from_slice(&payload);
+    stream
+        .write_all(&packet)
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+/// Read one packet, returning its id and body
+fn read_packet(stream: &mut TcpStream) -> Result<(i32, Vec<u8>), String> {
+    let len = read_varint(stream)?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= MAX_PACKET_LEN)
+        .ok_or_else(|| format!("Invalid packet length {}", len))?;
+
+    let mut data = vec![0u8; len];
+    stream
+        .read_exact(&mut data)
+        .map_err(|e| format!("Failed to read from server: {}", e))?;
+    let mut cursor = data.as_slice();
+    let packet_id = read_varint(&mut cursor)?;
+    Ok((packet_id, cursor.to_vec()))
+}
+
+/// Run a Server List Ping against `host:port`: handshake, status request, then ping/pong
+/// for latency
+pub fn ping(host: &str, port: u16) -> Result<PingResult, String> {
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("Failed to resolve {}", host))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)
+        .map_err(|e| format!("Failed to connect to {}:{}: {}", host, port, e))?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+
+    // Protocol version -1 asks the server to answer with whatever it supports
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, -1);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    send_packet(&mut stream, 0x00, &handshake)?;
+    send_packet(&mut stream, 0x00, &[])?;
+
+    let (packet_id, body) = read_packet(&mut stream)?;
+    if packet_id != 0x00 {
+        return Err(format!("Unexpected status packet {:#04x}", packet_id));
+    }
+    let mut cursor = body.as_slice();
+    let json_len = read_varint(&mut cursor)? as usize;
+    let json = cursor
+        .get(..json_len)
+        .ok_or("Status response is truncated")?;
+    let status: serde_json::Value = serde_json::from_slice(json)
+        .map_err(|e| format!("Failed to parse status response: {}", e))?;
+
+    let started = Instant::now();
+    let payload = chrono::Utc::now().timestamp_millis();
+    send_packet(&mut stream, 0x01, &payload.to_be_bytes())?;
+    // Some servers close the connection instead of answering the ping
+    let latency_ms = match read_packet(&mut stream) {
+        Ok((0x01, _)) => started.elapsed().as_millis() as u64,
+        _ => 0,
+    };
+
+    let players = &status["players"];
+    Ok(PingResult {
+        motd: motd::parse(&motd::from_chat_component(&status["description"])),
+        online_players: players["online"].as_i64().unwrap_or(0),
+        max_players: players["max"].as_i64().unwrap_or(0),
+        sample: players["sample"]
+            .as_array()
+            .map(|sample| {
+                sample
+                    .iter()
+                    .filter_map(|player| player["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        version_name: status["version"]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        protocol: status["version"]["protocol"].as_i64().unwrap_or(0),
+        latency_ms,
+        favicon: status["favicon"].as_str().map(str::to_string),
+    })
+}
+
+/// Ping the instance on this machine using the port from its server.properties
+#[tauri::command]
+pub async fn ping_instance(app_handle: tauri::AppHandle, id: String) -> Result<PingResult, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let properties = ServerProperties::load(&instance_dir)?;
+
+    let port = properties
+        .get("server-port")
+        .and_then(|port| port.trim().parse().ok())
+        .unwrap_or(25565);
+    let host = properties
+        .get("server-ip")
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or("127.0.0.1")
+        .to_string();
+
+    tauri::async_runtime::spawn_blocking(move || ping(&host, port))
+        .await
+        .map_err(|e| format!("Failed to ping server: {}", e))?
+}
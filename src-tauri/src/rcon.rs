@@ -0,0 +1,248 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::{
+    filesystem,
+    instance::get_instance_by_id,
+    models::{InstanceConfig, RconCredentials},
+    ports::{self, DEFAULT_RCON_PORT},
+    properties::ServerProperties,
+    secrets,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const PASSWORD_BYTES: usize = 24;
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+/// Minecraft rejects packets with bodies longer than this
+const MAX_COMMAND_LEN: usize = 1446;
+/// Longest packet a server sends (4096 byte body plus header)
+const MAX_PACKET_LEN: i32 = 4096 + 10;
+
+/// Where and how to reach an instance's RCON listener
+pub struct RconSettings {
+    pub host: String,
+    pub port: u16,
+    pub password: String,
+}
+
+/// A logged-in RCON connection
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    pub fn connect(settings: &RconSettings) -> Result<Self, String> {
+        let address = (settings.host.as_str(), settings.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", settings.host, e))?
+            .next()
+            .ok_or_else(|| format!("Failed to resolve {}", settings.host))?;
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| {
+            format!(
+                "Failed to connect to RCON at {}:{}: {}",
+                settings.host, settings.port, e
+            )
+        })?;
+        stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+            .map_err(|e| format!("Failed to configure RCON connection: {}", e))?;
+
+        let mut client = Self { stream, next_id: 1 };
+        let login_id = client.send(TYPE_LOGIN, &settings.password)?;
+        loop {
+            let (id, packet_type, _) = client.read()?;
+            if id == -1 {
+                return Err("RCON authentication failed: wrong password".into());
+            }
+            if id == login_id && packet_type == TYPE_COMMAND {
+                return Ok(client);
+            }
+        }
+    }
+
+    fn send(&mut self, packet_type: i32, body: &str) -> Result<i32, String> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+
+        let length = 4 + 4 + body.len() as i32 + 2;
+        let mut packet = Vec::with_capacity(length as usize + 4);
+        packet.extend_from_slice(&length.to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&packet_type.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+
+        self.stream
+            .write_all(&packet)
+            .map_err(|e| format!("Failed to send RCON packet: {}", e))?;
+        Ok(id)
+    }
+
+    /// Read one packet as (request id, type, body)
+    fn read(&mut self) -> Result<(i32, i32, String), String> {
+        let mut header = [0u8; 12];
+        self.stream
+            .read_exact(&mut header)
+            .map_err(|e| format!("Failed to read RCON packet: {}", e))?;
+        let length = i32::from_le_bytes(header[0..4].try_into().unwrap());
+        let id = i32::from_le_bytes(header[4..8].try_into().unwrap());
+        let packet_type = i32::from_le_bytes(header[8..12].try_into().unwrap());
+        if !(10..=MAX_PACKET_LEN).contains(&length) {
+            return Err(format!("Invalid RCON packet length {}", length));
+        }
+
+        let mut body = vec![0u8; length as usize - 8];
+        self.stream
+            .read_exact(&mut body)
+            .map_err(|e| format!("Failed to read RCON packet: {}", e))?;
+        let end = body.iter().position(|b| *b == 0).unwrap_or(body.len());
+        Ok((
+            id,
+            packet_type,
+            String::from_utf8_lossy(&body[..end]).into_owned(),
+        ))
+    }
+
+    /// Run a command and return its full output. Long responses are split across packets,
+    /// so an extra empty packet is sent after the command: once its reply arrives, every
+    /// fragment of the command's output has been received
+    pub fn execute(&mut self, command: &str) -> Result<String, String> {
+        if command.len() > MAX_COMMAND_LEN {
+            return Err(format!(
+                "RCON commands can be at most {} bytes",
+                MAX_COMMAND_LEN
+            ));
+        }
+
+        let command_id = self.send(TYPE_COMMAND, command)?;
+        let sentinel_id = self.send(TYPE_RESPONSE, "")?;
+
+        let mut output = String::new();
+        loop {
+            let (id, _, body) = self.read()?;
+            if id == sentinel_id {
+                return Ok(output);
+            }
+            if id == command_id {
+                output.push_str(&body);
+            }
+        }
+    }
+}
+
+/// Turn on RCON for a freshly created instance: generate a random password, pick a port no
+/// other instance uses, and keep the credentials (sealed) in nuko.toml
+pub fn provision(
+    app_handle: &tauri::AppHandle,
+    config: &mut InstanceConfig,
+    instance_dir: &Path,
+) -> Result<(), String> {
+    let mut properties = ServerProperties::load(instance_dir)?;
+    let mut reserved = ports::reserved_ports(app_handle, &config.id)?;
+    reserved.extend(
+        ports::configured_ports(&properties)
+            .into_iter()
+            .map(|(_, port)| port),
+    );
+
+    let port = ports::next_free_port(DEFAULT_RCON_PORT, &reserved)
+        .ok_or("No free port available for RCON")?;
+    let mut bytes = [0u8; PASSWORD_BYTES];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Failed to generate an RCON password: {}", e))?;
+    let password = URL_SAFE_NO_PAD.encode(bytes);
+
+    properties.set("enable-rcon", "true");
+    properties.set("rcon.port", port.to_string());
+    properties.set("rcon.password", password.clone());
+    properties.save()?;
+
+    config.rcon = Some(RconCredentials {
+        port,
+        password: secrets::store_credential(app_handle, &format!("rcon:{}", config.id), &password)?,
+    });
+    filesystem::save_instance_config(instance_dir, config)
+}
+
+/// RCON settings for an instance. Credentials nuko provisioned are preferred, falling back
+/// to whatever server.properties holds for servers set up by hand
+pub fn settings_for_instance(
+    app_handle: &tauri::AppHandle,
+    config: &InstanceConfig,
+    instance_dir: &Path,
+) -> Result<RconSettings, String> {
+    let properties = ServerProperties::load(instance_dir)?;
+    if properties.get("enable-rcon").map(str::trim) != Some("true") {
+        return Err("RCON is not enabled for this instance (enable-rcon=false)".into());
+    }
+
+    let host = properties
+        .get("server-ip")
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or("127.0.0.1")
+        .to_string();
+
+    // The port may have been moved since provisioning (e.g. by auto port assignment), so
+    // server.properties stays authoritative for it
+    let port = properties
+        .get("rcon.port")
+        .and_then(|port| port.trim().parse().ok())
+        .or(config.rcon.as_ref().map(|credentials| credentials.port))
+        .unwrap_or(DEFAULT_RCON_PORT);
+
+    let password = match &config.rcon {
+        Some(credentials) => secrets::load_credential(app_handle, &credentials.password)?,
+        None => properties
+            .get("rcon.password")
+            .unwrap_or_default()
+            .to_string(),
+    };
+    if password.is_empty() {
+        return Err("RCON needs an rcon.password to be set".into());
+    }
+
+    Ok(RconSettings {
+        host,
+        port,
+        password,
+    })
+}
+
+/// Connect to an instance over RCON and run a single command
+pub async fn execute(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    command: String,
+) -> Result<String, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    let settings = settings_for_instance(app_handle, &config, &instance_dir)?;
+
+    tauri::async_runtime::spawn_blocking(move || RconClient::connect(&settings)?.execute(&command))
+        .await
+        .map_err(|e| format!("RCON task failed: {}", e))?
+}
+
+/// Run a console command over RCON and return its output. Works for any server with RCON
+/// enabled, including ones nuko didn't start
+#[tauri::command]
+pub async fn rcon_execute(
+    app_handle: tauri::AppHandle,
+    id: String,
+    command: String,
+) -> Result<String, String> {
+    execute(&app_handle, &id, command).await
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Read},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Mutex, OnceLock},
+    thread,
+};
+
+use crate::{
+    ansi, filesystem,
+    instance::{get_instance_by_id, record_log_line, update_instance_config},
+    models::{CustomTunnelConfig, CustomTunnelKind, PlayitTunnelMetadata},
+    secrets,
+};
+
+const FRP_DEFAULT_PORT: u16 = 7000;
+
+fn get_clients() -> &'static Mutex<HashMap<String, Child>> {
+    static CLIENTS: OnceLock<Mutex<HashMap<String, Child>>> = OnceLock::new();
+    CLIENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_tunnels() -> &'static Mutex<HashMap<String, PlayitTunnelMetadata>> {
+    static TUNNELS: OnceLock<Mutex<HashMap<String, PlayitTunnelMetadata>>> = OnceLock::new();
+    TUNNELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn label(kind: CustomTunnelKind) -> &'static str {
+    match kind {
+        CustomTunnelKind::Bore => "bore",
+        CustomTunnelKind::Frp => "frp",
+    }
+}
+
+/// frpc reads its config from a file. The token isn't written there; frp substitutes it
+/// from the environment
+fn write_frpc_config(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    tunnel: &CustomTunnelConfig,
+    local_port: u16,
+    remote_port: u16,
+) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("tunnels");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let mut config = format!(
+        "serverAddr = {:?}\nserverPort = {}\n",
+        tunnel.relay_host,
+        tunnel.relay_port.unwrap_or(FRP_DEFAULT_PORT)
+    );
+    if tunnel.secret.is_some() {
+        config.push_str("auth.token = \"{{ .Envs.NUKO_FRP_TOKEN }}\"\n");
+    }
+    config.push_str(&format!(
+        "\n[[proxies]]\nname = \"nuko-{}\"\ntype = \"tcp\"\nlocalIP = \"127.0.0.1\"\nlocalPort = {}\nremotePort = {}\n",
+        id, local_port, remote_port
+    ));
+
+    let path = dir.join(format!("{}.frpc.toml", id));
+    fs::write(&path, config).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn forward_output(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    tunnel: &CustomTunnelConfig,
+    output: impl Read + Send + 'static,
+) {
+    let app_handle = app_handle.clone();
+    let id = id.to_string();
+    let kind = tunnel.kind;
+    let remote_port = tunnel.remote_port;
+
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            let line = ansi::plain(&ansi::parse(&line));
+            // bore prints the address it was given; frp only confirms the requested port
+            let public_port = match kind {
+                CustomTunnelKind::Bore => line
+                    .split("listening at ")
+                    .nth(1)
+                    .and_then(|address| address.trim().rsplit_once(':'))
+                    .and_then(|(_, port)| port.parse().ok()),
+                CustomTunnelKind::Frp if line.contains("start proxy success") => remote_port,
+                CustomTunnelKind::Frp => None,
+            };
+            if let Some(public_port) = public_port {
+                if let Some(tunnel) = get_tunnels().lock().unwrap().get_mut(&id) {
+                    tunnel.public_port = Some(public_port);
+                    tunnel.status = Some("Active".into());
+                }
+            }
+            record_log_line(&app_handle, &id, format!("[{}] {}", label(kind), line));
+        }
+    });
+}
+
+/// Start the instance's bore or frp client, forwarding the relay to `local_port`
+pub fn start(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    tunnel: &CustomTunnelConfig,
+    local_port: u16,
+) -> Result<(), String> {
+    let secret = tunnel
+        .secret
+        .as_deref()
+        .map(|stored| secrets::load_credential(app_handle, stored))
+        .transpose()?;
+    let default_client = match tunnel.kind {
+        CustomTunnelKind::Bore => "bore",
+        CustomTunnelKind::Frp => "frpc",
+    };
+    let mut cmd = Command::new(tunnel.client_path.as_deref().unwrap_or(default_client));
+
+    match tunnel.kind {
+        CustomTunnelKind::Bore => {
+            cmd.args(["local", &local_port.to_string(), "--to", &tunnel.relay_host]);
+            if let Some(remote_port) = tunnel.remote_port {
+                cmd.args(["--port", &remote_port.to_string()]);
+            }
+            if let Some(secret) = secret {
+                cmd.env("BORE_SECRET", secret);
+            }
+        }
+        CustomTunnelKind::Frp => {
+            let remote_port = tunnel
+                .remote_port
+                .ok_or("frp needs a remote port to be set")?;
+            let config = write_frpc_config(app_handle, id, tunnel, local_port, remote_port)?;
+            cmd.arg("-c").arg(config);
+            if let Some(secret) = secret {
+                cmd.env("NUKO_FRP_TOKEN", secret);
+            }
+        }
+    }
+
+    stop(id);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {} client: {}", label(tunnel.kind), e))?;
+
+    get_tunnels().lock().unwrap().insert(
+        id.to_string(),
+        PlayitTunnelMetadata {
+            id: Some(format!("{}-{}", label(tunnel.kind), id)),
+            name: Some(format!("{} via {}", label(tunnel.kind), tunnel.relay_host)),
+            protocol: Some("TCP".into()),
+            public_hostname: Some(tunnel.relay_host.clone()),
+            public_port: None,
+            destination_port: Some(local_port),
+            status: Some("Connecting".into()),
+            ..Default::default()
+        },
+    );
+    if let Some(stdout) = child.stdout.take() {
+        forward_output(app_handle, id, tunnel, stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_output(app_handle, id, tunnel, stderr);
+    }
+
+    get_clients().lock().unwrap().insert(id.to_string(), child);
+    Ok(())
+}
+
+/// Stop the instance's relay client, if one is running
+pub fn stop(id: &str) {
+    let child = get_clients().lock().unwrap().remove(id);
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    get_tunnels().lock().unwrap().remove(id);
+}
+
+/// The instance's relay tunnel while its client runs, marked down if the client exited
+pub fn tunnels(id: &str) -> Vec<PlayitTunnelMetadata> {
+    let exited = get_clients()
+        .lock()
+        .unwrap()
+        .get_mut(id)
+        .map(|child| !matches!(child.try_wait(), Ok(None)))
+        .unwrap_or(true);
+
+    let mut tunnels = get_tunnels().lock().unwrap();
+    let Some(tunnel) = tunnels.get_mut(id) else {
+        return vec![];
+    };
+    if exited {
+        tunnel.status = Some("Client exited, see the console".into());
+    }
+    vec![tunnel.clone()]
+}
+
+/// Configure (or remove, with `None`) the instance's self-hosted relay. A plaintext
+/// `secret` is moved into the credential store. Applies from the next start
+#[tauri::command]
+pub async fn set_custom_tunnel(
+    app_handle: tauri::AppHandle,
+    id: String,
+    tunnel: Option<CustomTunnelConfig>,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let credential_name = format!("relay:{}", id);
+    let old_secret = config.custom_tunnel.as_ref().and_then(|t| t.secret.clone());
+    // Re-saving the tunnel unchanged sends the existing reference back, so only a changed
+    // secret replaces the stored one
+    let replace_secret = |new_secret: Option<&str>| {
+        if let Some(old) = old_secret.as_deref().filter(|old| Some(*old) != new_secret) {
+            secrets::delete_credential(old);
+        }
+    };
+
+    config.custom_tunnel = match tunnel {
+        Some(mut tunnel) => {
+            tunnel.relay_host = tunnel.relay_host.trim().to_string();
+            if tunnel.relay_host.is_empty() {
+                return Err("Relay host cannot be empty".into());
+            }
+            if tunnel.kind == CustomTunnelKind::Frp && tunnel.remote_port.is_none() {
+                return Err("frp needs a remote port to be set".into());
+            }
+            tunnel.secret = tunnel
+                .secret
+                .map(|secret| secret.trim().to_string())
+                .filter(|secret| !secret.is_empty());
+            replace_secret(tunnel.secret.as_deref());
+            tunnel.secret = tunnel
+                .secret
+                .map(|secret| {
+                    if secrets::is_stored_credential(&secret) {
+                        Ok(secret)
+                    } else {
+                        secrets::store_credential(&app_handle, &credential_name, &secret)
+                    }
+                })
+                .transpose()?;
+            tunnel.enabled = tunnel.enabled
+                || config
+                    .custom_tunnel
+                    .as_ref()
+                    .is_some_and(|existing| existing.enabled);
+            Some(tunnel)
+        }
+        None => {
+            replace_secret(None);
+            None
+        }
+    };
+    update_instance_config(&app_handle, &config)
+}
@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tauri::{Emitter, Manager};
+
+use crate::{
+    config::get_config, filesystem, index::InstanceIndex, instance::is_instance_server_process,
+    models::RelocateProgress,
+};
+
+/// Every file and folder under `dir`, folders before their contents
+fn walk(dir: &Path, entries: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+    {
+        let path = entry
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .path();
+        entries.push(path.clone());
+        if path.is_dir() {
+            walk(&path, entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy `src` to `dest`, calling `on_progress` with the bytes copied so far after each file
+fn copy_dir(src: &Path, dest: &Path, on_progress: &mut impl FnMut(u64)) -> Result<(), String> {
+    let mut entries = Vec::new();
+    walk(src, &mut entries)?;
+    fs::create_dir_all(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+
+    let mut copied = 0;
+    for path in entries {
+        let target = dest.join(path.strip_prefix(src).unwrap_or(&path));
+        if path.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        } else {
+            copied += fs::copy(&path, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            on_progress(copied);
+        }
+    }
+    Ok(())
+}
+
+/// Check every file under `src` exists under `dest` with the same size
+fn verify_copy(src: &Path, dest: &Path) -> Result<(), String> {
+    let mut entries = Vec::new();
+    walk(src, &mut entries)?;
+    for path in entries.iter().filter(|path| !path.is_dir()) {
+        let target = dest.join(path.strip_prefix(src).unwrap_or(path));
+        let expected = fs::metadata(path).map(|m| m.len()).ok();
+        let actual = fs::metadata(&target).map(|m| m.len()).ok();
+        if actual.is_none() || actual != expected {
+            return Err(format!(
+                "Copy of {} doesn't match the original",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Save the new location to config.toml through a temporary file, so a crash leaves either
+/// the old or the new setting and never a half-written config
+fn switch_config(
+    app_handle: &tauri::AppHandle,
+    instances_dir: Option<&Path>,
+) -> Result<(), String> {
+    let mut config = get_config(app_handle.clone())?;
+    config.instances_dir = instances_dir.map(|dir| dir.to_string_lossy().to_string());
+
+    let config_path = filesystem::get_data_dir(app_handle)?.join("config.toml");
+    let temp_path = config_path.with_extension("toml.tmp");
+    let toml_string = toml::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&temp_path, toml_string)
+        .map_err(|e| format!("Failed to write config.toml: {}", e))?;
+    fs::rename(&temp_path, &config_path).map_err(|e| format!("Failed to write config.toml: {}", e))
+}
+
+fn relocate(app_handle: &tauri::AppHandle, target: &Path) -> Result<(), String> {
+    let current = filesystem::get_instances_dir(app_handle)?;
+    let default_dir = filesystem::get_data_dir(app_handle)?.join("instances");
+    let canonical_current = fs::canonicalize(&current).unwrap_or(current.clone());
+    if target == canonical_current {
+        return Err("Instances are already stored there".into());
+    }
+    if target.starts_with(&canonical_current) {
+        return Err("The new folder can't be inside the current instances folder".into());
+    }
+    if fs::read_dir(target).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err("Choose an empty folder to move instances into".into());
+    }
+
+    let sources: Vec<PathBuf> = match fs::read_dir(&current) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+        Err(_) => vec![],
+    };
+
+    let mut sys = sysinfo::System::new_all();
+    sys.refresh_all();
+    if let Some(running) = sources.iter().find(|dir| {
+        sys.processes()
+            .values()
+            .any(|process| is_instance_server_process(process, dir))
+    }) {
+        let name = filesystem::load_instance_config(running)
+            .map(|config| config.name)
+            .unwrap_or_else(|_| running.display().to_string());
+        return Err(format!("Stop '{}' before moving instances", name));
+    }
+
+    fs::create_dir_all(target)
+        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+
+    // Copy and verify everything before switching, so a failure leaves the old location in use
+    let copy_all = || -> Result<(), String> {
+        for source in &sources {
+            let name = filesystem::load_instance_config(source)
+                .map(|config| config.name)
+                .unwrap_or_else(|_| {
+                    source
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                });
+            let dest = target.join(source.file_name().unwrap_or_default());
+            let bytes_total = filesystem::dir_size(source);
+            copy_dir(source, &dest, &mut |bytes_done| {
+                let _ = app_handle.emit(
+                    "relocate-progress",
+                    RelocateProgress {
+                        instance: name.clone(),
+                        bytes_done,
+                        bytes_total,
+                    },
+                );
+            })?;
+            verify_copy(source, &dest)?;
+        }
+        Ok(())
+    };
+    if let Err(e) = copy_all() {
+        for source in &sources {
+            let _ = fs::remove_dir_all(target.join(source.file_name().unwrap_or_default()));
+        }
+        return Err(e);
+    }
+
+    let setting = (target != default_dir).then_some(target);
+    switch_config(app_handle, setting)?;
+    filesystem::set_instances_dir(target.to_path_buf());
+    app_handle.state::<InstanceIndex>().invalidate();
+
+    for source in &sources {
+        if let Err(e) = fs::remove_dir_all(source) {
+            println!("Failed to remove old copy {}: {}", source.display(), e);
+        }
+    }
+    if current != default_dir {
+        let _ = fs::remove_dir(&current);
+    }
+    Ok(())
+}
+
+/// Move every instance to `new_path`, e.g. on a bigger drive. Instances are copied with
+/// `relocate-progress` events and verified before nuko switches to the new folder; the old
+/// copies are removed only after that. All instances must be stopped
+#[tauri::command]
+pub async fn relocate_data_dir(
+    app_handle: tauri::AppHandle,
+    new_path: String,
+) -> Result<(), String> {
+    let target = PathBuf::from(new_path.trim());
+    if !target.is_absolute() {
+        return Err("Choose an absolute path for the instances folder".into());
+    }
+
+    let app_relocate = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || relocate(&app_relocate, &target))
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))??;
+
+    let _ = app_handle.emit("instances-updated", ());
+    Ok(())
+}
@@ -0,0 +1,348 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use chrono::Utc;
+use reqwest::blocking::{Body, Client};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    models::RemoteTarget,
+    secrets::{self, hmac_sha256},
+};
+
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wraps a reader and reports the running byte count as it is read
+struct ProgressReader<R, F> {
+    inner: R,
+    read: u64,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        (self.on_progress)(self.read);
+        Ok(n)
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn client() -> Result<Client, String> {
+    Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+fn file_body(
+    local: &Path,
+    on_progress: impl FnMut(u64) + Send + 'static,
+) -> Result<(Body, u64), String> {
+    let file =
+        fs::File::open(local).map_err(|e| format!("Failed to open {}: {}", local.display(), e))?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let reader = ProgressReader {
+        inner: file,
+        read: 0,
+        on_progress,
+    };
+    Ok((Body::sized(reader, len), len))
+}
+
+struct S3Bucket<'a> {
+    endpoint: &'a str,
+    bucket: &'a str,
+    region: &'a str,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+}
+
+/// PUT an object to an S3-compatible endpoint, signed with AWS Signature Version 4
+fn upload_s3(
+    s3: S3Bucket,
+    key: &str,
+    local: &Path,
+    on_progress: impl FnMut(u64) + Send + 'static,
+) -> Result<(), String> {
+    let url = reqwest::Url::parse(&format!(
+        "{}/{}/{}",
+        s3.endpoint.trim_end_matches('/'),
+        s3.bucket,
+        key
+    ))
+    .map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        _ => return Err("Invalid S3 endpoint: missing host".into()),
+    };
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, s3.region);
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:UNSIGNED-PAYLOAD\nx-amz-date:{}\n\n{}\nUNSIGNED-PAYLOAD",
+        url.path(),
+        host,
+        amz_date,
+        signed_headers
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let mut signing_key = hmac_sha256(
+        format!("AWS4{}", s3.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [s3.region, "s3", "aws4_request"] {
+        signing_key = hmac_sha256(&signing_key, part.as_bytes());
+    }
+    let signature = hmac_sha256(&signing_key, string_to_sign.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    let (body, _) = file_body(local, on_progress)?;
+    let response = client()?
+        .put(url)
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", "UNSIGNED-PAYLOAD")
+        .header(
+            "Authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                s3.access_key_id, scope, signed_headers, signature
+            ),
+        )
+        .body(body)
+        .send()
+        .map_err(|e| format!("S3 upload failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("S3 upload failed: HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+fn upload_webdav(
+    base_url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+    remote_path: &str,
+    local: &Path,
+    on_progress: impl FnMut(u64) + Send + 'static,
+) -> Result<(), String> {
+    let client = client()?;
+    let base_url = base_url.trim_end_matches('/');
+    let with_auth = |request: reqwest::blocking::RequestBuilder| match username {
+        Some(username) => request.basic_auth(username, password),
+        None => request,
+    };
+
+    // Create each parent collection; servers answer 405 for ones that already exist
+    let segments: Vec<&str> = remote_path.split('/').collect();
+    for depth in 1..segments.len() {
+        let collection = format!("{}/{}/", base_url, segments[..depth].join("/"));
+        let method = reqwest::Method::from_bytes(b"MKCOL").unwrap();
+        let _ = with_auth(client.request(method, &collection)).send();
+    }
+
+    let (body, _) = file_body(local, on_progress)?;
+    let response = with_auth(client.put(format!("{}/{}", base_url, remote_path)))
+        .body(body)
+        .send()
+        .map_err(|e| format!("WebDAV upload failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("WebDAV upload failed: HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+fn upload_sftp(
+    host: &str,
+    port: Option<u16>,
+    username: &str,
+    identity_file: Option<&str>,
+    remote_dir: &str,
+    remote_path: &str,
+    local: &Path,
+) -> Result<(), String> {
+    let target = format!("{}/{}", remote_dir.trim_end_matches('/'), remote_path);
+    let quote = |path: &str| format!("\"{}\"", path.replace('"', "\\\""));
+
+    // A leading '-' tells sftp to ignore the error when the directory already exists
+    let mut batch = String::new();
+    let segments: Vec<&str> = remote_path.split('/').collect();
+    for depth in 1..segments.len() {
+        let dir = format!(
+            "{}/{}",
+            remote_dir.trim_end_matches('/'),
+            segments[..depth].join("/")
+        );
+        batch.push_str(&format!("-mkdir {}\n", quote(&dir)));
+    }
+    batch.push_str(&format!(
+        "put {} {}\n",
+        quote(&local.to_string_lossy()),
+        quote(&target)
+    ));
+
+    let mut command = Command::new("sftp");
+    command
+        .args(["-b", "-", "-o", "BatchMode=yes"])
+        .args(["-P", &port.unwrap_or(22).to_string()]);
+    if let Some(identity_file) = identity_file {
+        command.args(["-i", identity_file]);
+    }
+    let mut child = command
+        .arg(format!("{}@{}", username, host))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run sftp: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(batch.as_bytes())
+            .map_err(|e| format!("Failed to write to sftp: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run sftp: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "SFTP upload failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn upload_once(
+    target: &RemoteTarget,
+    local: &Path,
+    remote_path: &str,
+    on_progress: impl FnMut(u64) + Send + 'static,
+) -> Result<(), String> {
+    match target {
+        RemoteTarget::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+            prefix,
+        } => {
+            let key = match prefix.trim_matches('/') {
+                "" => remote_path.to_string(),
+                prefix => format!("{}/{}", prefix, remote_path),
+            };
+            let s3 = S3Bucket {
+                endpoint,
+                bucket,
+                region,
+                access_key_id,
+                secret_access_key,
+            };
+            upload_s3(s3, &key, local, on_progress)
+        }
+        RemoteTarget::Sftp {
+            host,
+            port,
+            username,
+            identity_file,
+            remote_dir,
+        } => {
+            let mut on_progress = on_progress;
+            upload_sftp(
+                host,
+                *port,
+                username,
+                identity_file.as_deref(),
+                remote_dir,
+                remote_path,
+                local,
+            )?;
+            on_progress(fs::metadata(local).map(|m| m.len()).unwrap_or(0));
+            Ok(())
+        }
+        RemoteTarget::WebDav {
+            url,
+            username,
+            password,
+        } => upload_webdav(
+            url,
+            username.as_deref(),
+            password.as_deref(),
+            remote_path,
+            local,
+            on_progress,
+        ),
+    }
+}
+
+/// A copy of the target with its stored credential resolved to plaintext
+fn resolve_credentials(
+    app_handle: &tauri::AppHandle,
+    target: &RemoteTarget,
+) -> Result<RemoteTarget, String> {
+    let mut target = target.clone();
+    match &mut target {
+        RemoteTarget::S3 {
+            secret_access_key, ..
+        } => *secret_access_key = secrets::load_credential(app_handle, secret_access_key)?,
+        RemoteTarget::WebDav {
+            password: Some(password),
+            ..
+        } => *password = secrets::load_credential(app_handle, password)?,
+        _ => {}
+    }
+    Ok(target)
+}
+
+/// Upload a file to `remote_path` (relative, `/`-separated) on the target, retrying with
+/// backoff. `on_progress` receives bytes sent of the current attempt
+pub fn upload_file(
+    app_handle: &tauri::AppHandle,
+    target: &RemoteTarget,
+    local: &Path,
+    remote_path: &str,
+    on_progress: impl FnMut(u64) + Send + Clone + 'static,
+) -> Result<(), String> {
+    let target = &resolve_credentials(app_handle, target)?;
+    let mut attempt = 1;
+    loop {
+        match upload_once(target, local, remote_path, on_progress.clone()) {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                println!(
+                    "Upload of {} failed (attempt {}/{}): {}",
+                    remote_path, attempt, MAX_ATTEMPTS, e
+                );
+                thread::sleep(Duration::from_secs(2u64.pow(attempt)));
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use sha1::{Digest, Sha1};
+use tauri::Manager;
+
+use crate::{
+    filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, update_instance_config},
+    models::ResourcePackConfig,
+    properties::ServerProperties,
+};
+
+pub const DEFAULT_PACK_PORT: u16 = 8163;
+const PACK_FILE: &str = "nuko-resource-pack.zip";
+/// The server is reachable from the internet, so idle and oversized requests are cut off and
+/// only this many connections are handled at once
+const MAX_CONNECTIONS: usize = 32;
+const MAX_REQUEST_BYTES: u64 = 8192;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Instance id -> pack file served at `/<id>.zip`
+fn get_packs_map() -> &'static Mutex<HashMap<String, PathBuf>> {
+    static PACKS: OnceLock<Mutex<HashMap<String, PathBuf>>> = OnceLock::new();
+    PACKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Port the file server is listening on, once started
+fn get_server_port() -> &'static Mutex<Option<u16>> {
+    static PORT: OnceLock<Mutex<Option<u16>>> = OnceLock::new();
+    PORT.get_or_init(|| Mutex::new(None))
+}
+
+fn sha1_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha1::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn respond(stream: &mut TcpStream, status: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    );
+}
+
+/// Answer a single GET request for a hosted pack
+fn handle_connection(mut stream: TcpStream) {
+    if stream.set_read_timeout(Some(READ_TIMEOUT)).is_err()
+        || stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+    {
+        return;
+    }
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream.take(MAX_REQUEST_BYTES),
+        Err(_) => return,
+    });
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Drain the headers; nothing in them changes the response
+    let mut header = String::new();
+    while reader
+        .read_line(&mut header)
+        .map(|n| n > 2)
+        .unwrap_or(false)
+    {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(&mut stream, "400 Bad Request");
+    };
+    if method != "GET" && method != "HEAD" {
+        return respond(&mut stream, "405 Method Not Allowed");
+    }
+
+    let id = target
+        .split('?')
+        .next()
+        .and_then(|path| path.strip_prefix('/'))
+        .and_then(|path| path.strip_suffix(".zip"))
+        .unwrap_or_default();
+    let Some(path) = get_packs_map().lock().unwrap().get(id).cloned() else {
+        return respond(&mut stream, "404 Not Found");
+    };
+    let Ok(mut file) = fs::File::open(&path) else {
+        return respond(&mut stream, "404 Not Found");
+    };
+    let length = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/zip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        length
+    );
+    if method == "GET" {
+        let _ = io::copy(&mut file, &mut stream);
+    }
+}
+
+/// Start the pack file server on `port` if it isn't already running. Every instance shares
+/// one server, so the first port used wins until nuko restarts
+fn ensure_server(port: u16) -> Result<u16, String> {
+    let mut server_port = get_server_port().lock().unwrap();
+    if let Some(running) = *server_port {
+        return Ok(running);
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| {
+        format!(
+            "Failed to start resource pack server on port {}: {}",
+            port, e
+        )
+    })?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if ACTIVE_CONNECTIONS.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                respond(&mut stream, "503 Service Unavailable");
+                continue;
+            }
+            thread::spawn(move || {
+                handle_connection(stream);
+                ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    *server_port = Some(port);
+    Ok(port)
+}
+
+/// Serve a resource pack zip to players and point server.properties at it. nuko has to be
+/// running for players to download the pack
+#[tauri::command]
+pub async fn host_resource_pack(
+    app_handle: tauri::AppHandle,
+    id: String,
+    path: String,
+    public_host: String,
+    port: Option<u16>,
+) -> Result<ResourcePackConfig, String> {
+    let public_host = public_host.trim().to_string();
+    if public_host.is_empty() {
+        return Err("A public hostname or IP is required so players can reach the pack".into());
+    }
+    let source = PathBuf::from(&path);
+    if source.extension().and_then(|e| e.to_str()) != Some("zip") {
+        return Err("Resource packs must be .zip files".into());
+    }
+
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let pack_path = instance_dir.join(PACK_FILE);
+    fs::copy(&source, &pack_path).map_err(|e| format!("Failed to copy resource pack: {}", e))?;
+    let sha1 = sha1_file(&pack_path)?;
+
+    let port = ensure_server(port.unwrap_or(DEFAULT_PACK_PORT))?;
+    get_packs_map()
+        .lock()
+        .unwrap()
+        .insert(id.clone(), pack_path);
+
+    let url = format!("http://{}:{}/{}.zip", public_host, port, id);
+    let mut properties = ServerProperties::load(&instance_dir)?;
+    properties.set("resource-pack", url.clone());
+    properties.set("resource-pack-sha1", sha1.clone());
+    properties.save()?;
+
+    let pack = ResourcePackConfig {
+        public_host,
+        port,
+        sha1,
+        url,
+    };
+    config.resource_pack = Some(pack.clone());
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(pack)
+}
+
+/// Stop serving the instance's pack and clear it from server.properties
+#[tauri::command]
+pub async fn remove_resource_pack(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    get_packs_map().lock().unwrap().remove(&id);
+
+    let mut properties = ServerProperties::load(&instance_dir)?;
+    properties.set("resource-pack", "");
+    properties.set("resource-pack-sha1", "");
+    properties.save()?;
+
+    let pack_path = instance_dir.join(PACK_FILE);
+    if pack_path.exists() {
+        fs::remove_file(&pack_path)
+            .map_err(|e| format!("Failed to delete resource pack: {}", e))?;
+    }
+
+    config.resource_pack = None;
+    update_instance_config(&app_handle, &config)
+}
+
+/// Resume serving every hosted pack after nuko starts
+pub fn restore_hosted_packs(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    for entry in app_handle.state::<InstanceIndex>().all(&instances_dir)? {
+        let Some(pack) = &entry.config.resource_pack else {
+            continue;
+        };
+        let pack_path = entry.dir.join(PACK_FILE);
+        if !pack_path.exists() {
+            continue;
+        }
+
+        let port = ensure_server(pack.port)?;
+        if port != pack.port {
+            println!(
+                "Resource pack for {} expects port {} but the server is on {}",
+                entry.config.name, pack.port, port
+            );
+        }
+        get_packs_map()
+            .lock()
+            .unwrap()
+            .insert(entry.config.id.clone(), pack_path);
+    }
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use cron::Schedule;
+use regex::Regex;
+use tauri::Manager;
+
+use crate::{
+    audit, backup, filesystem,
+    index::InstanceIndex,
+    instance::{
+        self, get_instance_by_id, has_instance_stdin, is_instance_running, update_instance_config,
+    },
+    models::{ScheduledTask, TaskAction, TaskReport},
+};
+
+/// How often schedules are checked. Cron has minute resolution, so this only needs to be
+/// comfortably below a minute
+const TICK: Duration = Duration::from_secs(15);
+
+const WEEKDAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// Parse a five-field cron expression. The cron crate expects a leading seconds field and
+/// numbers weekdays from 1 (Sunday), so weekday numbers are written out as names first
+fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!(
+            "Cron expression '{}' must have 5 fields (minute hour day month weekday)",
+            expression
+        ));
+    }
+
+    // Step values like `*/2` stay numeric
+    let weekday_number = Regex::new(r"(^|[^/\d])(\d+)").unwrap();
+    let mut invalid = false;
+    let weekdays = weekday_number.replace_all(fields[4], |captures: &regex::Captures| {
+        let name = captures[2]
+            .parse::<usize>()
+            .ok()
+            .and_then(|day| WEEKDAYS.get(day))
+            .copied()
+            .unwrap_or_else(|| {
+                invalid = true;
+                ""
+            });
+        format!("{}{}", &captures[1], name)
+    });
+    if invalid {
+        return Err(format!(
+            "Invalid weekday in '{}', use 0-7 or SUN-SAT",
+            expression
+        ));
+    }
+
+    Schedule::from_str(&format!(
+        "0 {} {} {} {} {}",
+        fields[0], fields[1], fields[2], fields[3], weekdays
+    ))
+    .map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+fn next_run(task: &ScheduledTask) -> Option<DateTime<Local>> {
+    parse_cron(&task.cron).ok()?.after(&Local::now()).next()
+}
+
+fn report(task: ScheduledTask) -> TaskReport {
+    TaskReport {
+        next_run: task
+            .enabled
+            .then(|| next_run(&task))
+            .flatten()
+            .map(|next| next.to_rfc3339()),
+        task,
+    }
+}
+
+async fn execute(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    task: &ScheduledTask,
+) -> Result<(), String> {
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    match &task.action {
+        TaskAction::Command { command } => {
+            if !has_instance_stdin(id) {
+                return Err("Instance isn't running".into());
+            }
+            instance::write_instance_stdin(id, command)?;
+            audit::record(
+                app_handle,
+                id,
+                "command",
+                serde_json::json!({ "command": command }),
+            );
+            Ok(())
+        }
+        TaskAction::Restart => instance::restart_instance(app_handle.clone(), id.to_string()).await,
+        TaskAction::Backup => {
+            backup::create_backup_internal(app_handle, id, task.name.clone(), "scheduled", None)
+                .await
+                .map(|_| ())
+        }
+        TaskAction::Start => {
+            if is_instance_running(&instance_dir) {
+                return Ok(());
+            }
+            instance::start_instance(app_handle.clone(), id.to_string()).await
+        }
+        TaskAction::Stop => {
+            if !is_instance_running(&instance_dir) {
+                return Ok(());
+            }
+            instance::stop_instance(app_handle.clone(), id.to_string()).await
+        }
+    }
+}
+
+/// Run a task and record the outcome in nuko.toml
+async fn run(app_handle: &tauri::AppHandle, id: &str, task: &ScheduledTask) -> Result<(), String> {
+    let result = audit::as_actor("scheduler", execute(app_handle, id, task)).await;
+    if let Err(e) = &result {
+        println!("Scheduled task '{}' failed: {}", task.name, e);
+    }
+
+    // Re-read the config, the task itself may have changed it (e.g. start updates last_played)
+    let mut config = get_instance_by_id(app_handle, id)?;
+    if let Some(stored) = config.tasks.iter_mut().find(|t| t.id == task.id) {
+        stored.last_run = Some(Utc::now().to_rfc3339());
+        stored.last_error = result.as_ref().err().cloned();
+        update_instance_config(app_handle, &config)?;
+    }
+
+    result
+}
+
+/// Check every instance's schedules in the background and run the tasks that came due since
+/// the last check. Runs missed while nuko was closed are not caught up
+pub fn start(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_check = Local::now();
+        loop {
+            tokio::time::sleep(TICK).await;
+            let now = Local::now();
+
+            let instances = match filesystem::get_instances_dir(&app_handle)
+                .and_then(|instances_dir| app_handle.state::<InstanceIndex>().all(&instances_dir))
+            {
+                Ok(instances) => instances,
+                Err(e) => {
+                    println!("Failed to load instances for scheduled tasks: {}", e);
+                    continue;
+                }
+            };
+
+            for entry in instances {
+                for task in entry.config.tasks.into_iter().filter(|task| task.enabled) {
+                    let Ok(schedule) = parse_cron(&task.cron) else {
+                        continue;
+                    };
+                    let due = schedule
+                        .after(&last_check)
+                        .next()
+                        .is_some_and(|next| next <= now);
+                    if !due {
+                        continue;
+                    }
+
+                    let app_handle = app_handle.clone();
+                    let id = entry.config.id.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = run(&app_handle, &id, &task).await;
+                    });
+                }
+            }
+
+            last_check = now;
+        }
+    });
+}
+
+/// An instance's scheduled tasks with their next run time
+#[tauri::command]
+pub async fn list_tasks(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<TaskReport>, String> {
+    Ok(get_instance_by_id(&app_handle, &id)?
+        .tasks
+        .into_iter()
+        .map(report)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn add_task(
+    app_handle: tauri::AppHandle,
+    id: String,
+    name: String,
+    cron: String,
+    action: TaskAction,
+) -> Result<TaskReport, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    parse_cron(&cron)?;
+    if let TaskAction::Command { command } = &action {
+        if command.trim().is_empty() || command.contains('\n') {
+            return Err("Scheduled commands must be a single non-empty line".into());
+        }
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Task name cannot be empty".into());
+    }
+
+    let task = ScheduledTask {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        cron: cron.trim().to_string(),
+        action,
+        enabled: true,
+        last_run: None,
+        last_error: None,
+    };
+    config.tasks.push(task.clone());
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(report(task))
+}
+
+#[tauri::command]
+pub async fn remove_task(
+    app_handle: tauri::AppHandle,
+    id: String,
+    task_id: String,
+) -> Result<(), String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let before = config.tasks.len();
+    config.tasks.retain(|task| task.id != task_id);
+    if config.tasks.len() == before {
+        return Err(format!("Task {} not found", task_id));
+    }
+    update_instance_config(&app_handle, &config)
+}
+
+/// Run a task immediately, regardless of its schedule or whether it's enabled
+#[tauri::command]
+pub async fn run_task_now(
+    app_handle: tauri::AppHandle,
+    id: String,
+    task_id: String,
+) -> Result<(), String> {
+    let task = get_instance_by_id(&app_handle, &id)?
+        .tasks
+        .into_iter()
+        .find(|task| task.id == task_id)
+        .ok_or_else(|| format!("Task {} not found", task_id))?;
+    run(&app_handle, &id, &task).await
+}
@@ -0,0 +1,130 @@
+use std::{fs, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::filesystem;
+
+const KEY_FILE: &str = "secret.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The machine-local key secrets are sealed with, created on first use
+fn load_key(app_handle: &tauri::AppHandle) -> Result<XChaCha20Poly1305, String> {
+    let path = filesystem::get_data_dir(app_handle)?.join(KEY_FILE);
+    if let Ok(key) = fs::read(&path) {
+        if key.len() == KEY_LEN {
+            return XChaCha20Poly1305::new_from_slice(&key)
+                .map_err(|e| format!("Invalid secret key: {}", e));
+        }
+    }
+
+    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+    fs::write(&path, key).map_err(|e| format!("Failed to write secret key: {}", e))?;
+    restrict_permissions(&path);
+    Ok(XChaCha20Poly1305::new(&key))
+}
+
+fn restrict_permissions(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Encrypt a secret with XChaCha20-Poly1305 so it can be stored in config files
+pub fn seal(app_handle: &tauri::AppHandle, plaintext: &str) -> Result<String, String> {
+    let cipher = load_key(app_handle)?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+/// Decrypt a secret produced by `seal`
+pub fn open(app_handle: &tauri::AppHandle, sealed: &str) -> Result<String, String> {
+    let cipher = load_key(app_handle)?;
+    let bytes = STANDARD
+        .decode(sealed.trim())
+        .map_err(|e| format!("Failed to decode secret: {}", e))?;
+    if bytes.len() < NONCE_LEN {
+        return Err("Failed to decrypt secret: too short".into());
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            "Failed to decrypt secret: it was created on another machine or is corrupt".to_string()
+        })?;
+    String::from_utf8(plaintext).map_err(|e| format!("Failed to decrypt secret: {}", e))
+}
+
+const KEYRING_SERVICE: &str = "nuko";
+const KEYRING_PREFIX: &str = "keyring:";
+const SEALED_PREFIX: &str = "sealed:";
+
+/// Store a credential in the OS keychain under `name`, or seal it with the local key when
+/// there is no keychain (e.g. a headless Linux box without a secret service). Returns the
+/// value to save in config files in place of the credential
+pub fn store_credential(
+    app_handle: &tauri::AppHandle,
+    name: &str,
+    plaintext: &str,
+) -> Result<String, String> {
+    match keyring::Entry::new(KEYRING_SERVICE, name).and_then(|entry| entry.set_password(plaintext))
+    {
+        Ok(()) => Ok(format!("{}{}", KEYRING_PREFIX, name)),
+        Err(e) => {
+            println!("OS keychain unavailable ({}), sealing {} instead", e, name);
+            Ok(format!("{}{}", SEALED_PREFIX, seal(app_handle, plaintext)?))
+        }
+    }
+}
+
+/// Whether a config value was written by `store_credential`, rather than being a plaintext
+/// value from before credentials were stored this way
+pub fn is_stored_credential(stored: &str) -> bool {
+    stored.starts_with(KEYRING_PREFIX) || stored.starts_with(SEALED_PREFIX)
+}
+
+/// Resolve a value written by `store_credential`. Anything else is returned unchanged
+pub fn load_credential(app_handle: &tauri::AppHandle, stored: &str) -> Result<String, String> {
+    if let Some(name) = stored.strip_prefix(KEYRING_PREFIX) {
+        keyring::Entry::new(KEYRING_SERVICE, name)
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| format!("Failed to read {} from the OS keychain: {}", name, e))
+    } else if let Some(sealed) = stored.strip_prefix(SEALED_PREFIX) {
+        open(app_handle, sealed)
+    } else {
+        Ok(stored.to_string())
+    }
+}
+
+/// Remove a credential from the OS keychain, if that's where it's kept
+pub fn delete_credential(stored: &str) {
+    if let Some(name) = stored.strip_prefix(KEYRING_PREFIX) {
+        if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, name) {
+            let _ = entry.delete_credential();
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    backup, download, filesystem,
+    instance::{get_instance_by_id, is_instance_running, update_instance_config},
+    models::ServerJarUpdate,
+    papermc,
+};
+
+/// Replace server.jar with the newest Paper, Purpur or Fabric build for the instance's
+/// Minecraft version. The current jar is backed up first so `rollback_jar_change` can undo
+/// it, and the new build is recorded in nuko.toml (`build`, or `loader` for Fabric)
+#[tauri::command]
+pub async fn update_server_jar(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<ServerJarUpdate, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before updating its jar", config.name));
+    }
+
+    let (previous_build, build, paper_url) = match config.software.as_str() {
+        "papermc" => {
+            let (build, url) = papermc::latest_build("paper", &config.version).await?;
+            (config.build.clone(), build.to_string(), Some(url))
+        }
+        "purpur" => (
+            config.build.clone().or(config.loader.clone()),
+            download::latest_purpur_build(&config.version).await?,
+            None,
+        ),
+        "fabric" => {
+            let loader = download::get_fabric_loader_versions(config.version.clone(), Some(true))
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No stable Fabric loader for {}", config.version))?;
+            (config.loader.clone(), loader, None)
+        }
+        other => {
+            return Err(format!(
+                "Updating the jar isn't supported for {} instances",
+                other
+            ))
+        }
+    };
+
+    if previous_build.as_deref() == Some(build.as_str()) {
+        return Ok(ServerJarUpdate {
+            updated: false,
+            previous_build,
+            build,
+            changes: vec![],
+            backup: None,
+        });
+    }
+
+    // The changelog is a nicety, so a failed lookup doesn't stop the update
+    let changes = match (config.software.as_str(), previous_build.as_deref()) {
+        ("papermc", Some(previous)) => match previous.parse() {
+            Ok(previous) => papermc::changes_since("paper", &config.version, previous)
+                .await
+                .unwrap_or_default(),
+            Err(_) => vec![],
+        },
+        ("purpur", Some(previous)) => {
+            download::purpur_changes_since(&config.version, previous, &build)
+                .await
+                .unwrap_or_default()
+        }
+        _ => vec![],
+    };
+
+    let backup =
+        backup::backup_before_jar_change(&app_handle, &config, "server jar update").await?;
+
+    let jar_path = instance_dir.join("server.jar");
+    match paper_url {
+        Some(url) => download::download_to_path(&url, &jar_path).await?,
+        None if config.software == "purpur" => {
+            download::install_purpur(&instance_dir, &config.version, Some(&build)).await?
+        }
+        None => {
+            let url = download::resolve_fabric_url(
+                &config.version,
+                Some(&build),
+                config.installer.as_deref(),
+            )
+            .await?;
+            download::download_to_path(&url, &jar_path).await?
+        }
+    }
+
+    if config.software == "fabric" {
+        config.loader = Some(build.clone());
+    } else {
+        // A pinned Purpur build would otherwise point back at the old jar
+        if config.software == "purpur" && config.loader.is_some() {
+            config.loader = Some(build.clone());
+        }
+        config.build = Some(build.clone());
+    }
+    update_instance_config(&app_handle, &config)?;
+
+    Ok(ServerJarUpdate {
+        updated: true,
+        previous_build,
+        build,
+        changes,
+        backup: Some(backup),
+    })
+}
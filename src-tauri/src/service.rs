@@ -0,0 +1,150 @@
+use std::{fs, path::PathBuf, sync::OnceLock};
+
+use crate::models::ServiceDefinition;
+
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// Whether nuko was launched in the background with `--headless`
+pub fn is_headless() -> bool {
+    static HEADLESS: OnceLock<bool> = OnceLock::new();
+    *HEADLESS.get_or_init(|| std::env::args().any(|arg| arg == HEADLESS_FLAG))
+}
+
+/// Headless mode still runs the GUI toolkit with a hidden window, so it needs a display. On
+/// Linux, fail clearly instead of letting the toolkit abort when started outside a session
+pub fn ensure_graphical_session() -> Result<(), String> {
+    if cfg!(target_os = "linux")
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+    {
+        return Err(format!(
+            "nuko {} needs a graphical session (DISPLAY or WAYLAND_DISPLAY is not set)",
+            HEADLESS_FLAG
+        ));
+    }
+    Ok(())
+}
+
+fn home_dir() -> Result<PathBuf, String> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("{} is not set", var))
+}
+
+/// Build the per-user autostart entry that launches nuko headless when the user logs in.
+/// None of these start at boot: nuko needs a desktop session for its hidden window
+pub fn service_definition() -> Result<ServiceDefinition, String> {
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to resolve nuko executable: {}", e))?;
+    let exe = exe.to_string_lossy();
+
+    match std::env::consts::OS {
+        "linux" => Ok(ServiceDefinition {
+            platform: "systemd-user".into(),
+            path: home_dir()?
+                .join(".config/systemd/user/nuko.service")
+                .to_string_lossy()
+                .to_string(),
+            contents: format!(
+                "[Unit]\n\
+                 Description=nuko Minecraft server manager\n\
+                 PartOf=graphical-session.target\n\
+                 After=graphical-session.target network-online.target\n\
+                 \n\
+                 [Service]\n\
+                 ExecStart=\"{}\" {}\n\
+                 Restart=on-failure\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=graphical-session.target\n",
+                exe, HEADLESS_FLAG
+            ),
+            notes: Some(
+                "Run `systemctl --user enable nuko` to start nuko in the background when you log in to your desktop. It doesn't start at boot, since nuko needs a graphical session".into(),
+            ),
+        }),
+        "macos" => Ok(ServiceDefinition {
+            platform: "launchd-agent".into(),
+            path: home_dir()?
+                .join("Library/LaunchAgents/me.zhai.nuko.plist")
+                .to_string_lossy()
+                .to_string(),
+            contents: format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+                 <plist version=\"1.0\">\n\
+                 <dict>\n\
+                 \t<key>Label</key>\n\
+                 \t<string>me.zhai.nuko</string>\n\
+                 \t<key>ProgramArguments</key>\n\
+                 \t<array>\n\
+                 \t\t<string>{}</string>\n\
+                 \t\t<string>{}</string>\n\
+                 \t</array>\n\
+                 \t<key>RunAtLoad</key>\n\
+                 \t<true/>\n\
+                 \t<key>KeepAlive</key>\n\
+                 \t<dict>\n\
+                 \t\t<key>SuccessfulExit</key>\n\
+                 \t\t<false/>\n\
+                 \t</dict>\n\
+                 </dict>\n\
+                 </plist>\n",
+                exe, HEADLESS_FLAG
+            ),
+            notes: Some(
+                "This LaunchAgent starts nuko in the background when you log in. Run `launchctl load -w` on the plist to start it now".into(),
+            ),
+        }),
+        "windows" => {
+            let appdata = std::env::var_os("APPDATA")
+                .map(PathBuf::from)
+                .ok_or_else(|| "APPDATA is not set".to_string())?;
+            Ok(ServiceDefinition {
+                platform: "windows-startup".into(),
+                path: appdata
+                    .join("Microsoft\\Windows\\Start Menu\\Programs\\Startup\\nuko.cmd")
+                    .to_string_lossy()
+                    .to_string(),
+                contents: format!("@echo off\r\nstart \"\" \"{}\" {}\r\n", exe, HEADLESS_FLAG),
+                notes: Some(
+                    "This Startup folder entry starts nuko in the background when you sign in. It isn't a Windows service and doesn't run before sign-in".into(),
+                ),
+            })
+        }
+        other => Err(format!("Starting in the background is not supported on {}", other)),
+    }
+}
+
+#[tauri::command]
+pub fn get_service_definition() -> Result<ServiceDefinition, String> {
+    service_definition()
+}
+
+/// Write the autostart entry so nuko starts headless in the background at login
+#[tauri::command]
+pub fn install_service() -> Result<ServiceDefinition, String> {
+    let definition = service_definition()?;
+    let path = PathBuf::from(&definition.path);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, &definition.contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(definition)
+}
+
+#[tauri::command]
+pub fn uninstall_service() -> Result<(), String> {
+    let definition = service_definition()?;
+    let path = PathBuf::from(&definition.path);
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
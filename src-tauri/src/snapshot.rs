@@ -0,0 +1,160 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    archive::collect_files,
+    models::{SnapshotEntry, SnapshotManifest},
+};
+
+/// Objects are sharded by the first two hex characters of their hash, like git
+fn object_path(objects_dir: &Path, hash: &str) -> PathBuf {
+    objects_dir.join(&hash[..2]).join(hash)
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Capture every file under `src` into the object store. Files whose size and modification
+/// time match `previous` reuse its hash without being read, and content already in the store
+/// is never copied twice. Returns the manifest and the number of bytes newly stored
+pub fn snapshot(
+    src: &Path,
+    objects_dir: &Path,
+    previous: Option<&SnapshotManifest>,
+    skip: impl Fn(&Path) -> bool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(SnapshotManifest, u64), String> {
+    let previous: HashMap<&str, &SnapshotEntry> = previous
+        .map(|manifest| {
+            manifest
+                .files
+                .iter()
+                .map(|entry| (entry.path.as_str(), entry))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut paths = Vec::new();
+    collect_files(src, src, &skip, &mut paths)?;
+    let total: u64 = paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    let mut manifest = SnapshotManifest::default();
+    let mut done = 0;
+    let mut stored = 0;
+
+    for path in paths {
+        let relative = path
+            .strip_prefix(src)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let metadata =
+            fs::metadata(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let hash = match previous.get(relative.as_str()) {
+            Some(entry) if entry.size == size && entry.modified == modified => entry.hash.clone(),
+            _ => sha256_file(&path)?,
+        };
+
+        let object = object_path(objects_dir, &hash);
+        if !object.exists() {
+            if let Some(parent) = object.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            // Copy to a temporary name first so an interrupted backup never leaves a
+            // truncated object behind under a valid hash
+            let partial = object.with_extension("partial");
+            fs::copy(&path, &partial)
+                .map_err(|e| format!("Failed to store {}: {}", path.display(), e))?;
+            fs::rename(&partial, &object)
+                .map_err(|e| format!("Failed to store {}: {}", path.display(), e))?;
+            stored += size;
+        }
+
+        done += size;
+        on_progress(done, total);
+        manifest.files.push(SnapshotEntry {
+            path: relative,
+            hash,
+            size,
+            modified,
+        });
+    }
+
+    Ok((manifest, stored))
+}
+
+/// Rebuild the files of a snapshot under `dest`
+pub fn restore(manifest: &SnapshotManifest, objects_dir: &Path, dest: &Path) -> Result<(), String> {
+    for entry in &manifest.files {
+        let relative = Path::new(&entry.path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return Err(format!("Unsafe path '{}' in snapshot", entry.path));
+        }
+
+        let object = object_path(objects_dir, &entry.hash);
+        let path = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(&object, &path).map_err(|e| format!("Failed to restore {}: {}", entry.path, e))?;
+    }
+    Ok(())
+}
+
+/// Delete objects no remaining manifest refers to. Returns the bytes freed
+pub fn collect_garbage(objects_dir: &Path, manifests: &[SnapshotManifest]) -> Result<u64, String> {
+    let referenced: HashSet<&str> = manifests
+        .iter()
+        .flat_map(|manifest| manifest.files.iter().map(|entry| entry.hash.as_str()))
+        .collect();
+
+    let mut objects = Vec::new();
+    if objects_dir.exists() {
+        collect_files(objects_dir, objects_dir, &|_| false, &mut objects)?;
+    }
+
+    let mut freed = 0;
+    for object in objects {
+        let name = object
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if referenced.contains(name.as_str()) {
+            continue;
+        }
+        freed += fs::metadata(&object).map(|m| m.len()).unwrap_or(0);
+        fs::remove_file(&object)
+            .map_err(|e| format!("Failed to delete {}: {}", object.display(), e))?;
+    }
+    Ok(freed)
+}
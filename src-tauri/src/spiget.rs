@@ -0,0 +1,203 @@
+use std::fs;
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    addons, download, filesystem,
+    instance::get_instance_by_id,
+    models::{AddonInstallOutcome, LockedAddon, SpigetResource, SpigetVersion},
+    modrinth,
+};
+
+const API: &str = "https://api.spiget.org/v2";
+const USER_AGENT: &str = concat!("hozhai/nuko/", env!("CARGO_PKG_VERSION"));
+const MAX_PAGE_SIZE: u32 = 100;
+
+fn client() -> Result<Client, String> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+async fn get_json<T: DeserializeOwned>(url: &str, query: &[(&str, String)]) -> Result<T, String> {
+    let response = client()?
+        .get(url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| format!("GET {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} -> HTTP {}", url, response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response from {}: {}", url, e))
+}
+
+fn resource_page(resource_id: u64) -> String {
+    format!("https://www.spigotmc.org/resources/{}/", resource_id)
+}
+
+/// Spigot resources are only usable on Bukkit-compatible servers
+fn ensure_plugin_support(app_handle: &tauri::AppHandle, id: &str) -> Result<(), String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    match modrinth::loader_for(config.effective_software()) {
+        Some((_, "plugins")) => Ok(()),
+        _ => Err(format!(
+            "SpigotMC plugins can't run on {} instances",
+            config.effective_software()
+        )),
+    }
+}
+
+/// A file name for the downloaded jar, since Spiget doesn't report the original one
+fn jar_name(resource: &SpigetResource) -> String {
+    let name: String = resource
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('-');
+    if name.is_empty() {
+        format!("spigot-{}.jar", resource.id)
+    } else {
+        format!("{}.jar", name)
+    }
+}
+
+/// Search SpigotMC resources by name, most downloaded first
+#[tauri::command]
+pub async fn search_spiget(
+    app_handle: tauri::AppHandle,
+    id: String,
+    query: String,
+    page: Option<u32>,
+    size: Option<u32>,
+) -> Result<Vec<SpigetResource>, String> {
+    ensure_plugin_support(&app_handle, &id)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query cannot be empty".into());
+    }
+
+    get_json(
+        &format!("{}/search/resources/{}", API, urlencode(query)),
+        &[
+            ("field", "name".into()),
+            ("sort", "-downloads".into()),
+            ("page", page.unwrap_or(1).max(1).to_string()),
+            (
+                "size",
+                size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE).to_string(),
+            ),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to search SpigotMC: {}", e))
+}
+
+/// Percent-encode a path segment
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Install the latest version of a SpigotMC resource into `plugins/`. Premium resources,
+/// ones hosted on another site and ones that aren't a plain jar can't be fetched
+/// automatically, so their page is returned for the user to download it themselves
+#[tauri::command]
+pub async fn install_spiget_resource(
+    app_handle: tauri::AppHandle,
+    id: String,
+    resource_id: u64,
+) -> Result<AddonInstallOutcome, String> {
+    ensure_plugin_support(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let resource: SpigetResource = get_json(&format!("{}/resources/{}", API, resource_id), &[])
+        .await
+        .map_err(|e| format!("Failed to fetch SpigotMC resource {}: {}", resource_id, e))?;
+
+    if resource.premium {
+        return Ok(AddonInstallOutcome::ManualDownload {
+            url: resource_page(resource_id),
+            reason: format!("{} is a premium resource", resource.name),
+        });
+    }
+    if resource.external || resource.file.file_type != ".jar" {
+        return Ok(AddonInstallOutcome::ManualDownload {
+            url: resource
+                .file
+                .external_url
+                .clone()
+                .filter(|url| !url.is_empty())
+                .unwrap_or_else(|| resource_page(resource_id)),
+            reason: format!("{} isn't hosted on SpigotMC as a jar", resource.name),
+        });
+    }
+
+    let version: SpigetVersion = get_json(
+        &format!("{}/resources/{}/versions/latest", API, resource_id),
+        &[],
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch latest version of {}: {}", resource.name, e))?;
+
+    let url = format!("{}/resources/{}/download", API, resource_id);
+    println!("Downloading {} from {}...", resource.name, url);
+    let bytes = download::fetch_bytes(client()?.get(&url)).await?;
+
+    // Spiget mirrors most files, but some downloads end on a Cloudflare challenge page
+    // instead of the jar
+    if !bytes.starts_with(b"PK") {
+        return Ok(AddonInstallOutcome::ManualDownload {
+            url: resource_page(resource_id),
+            reason: format!("SpigotMC didn't serve {} as a jar", resource.name),
+        });
+    }
+
+    let plugins_dir = instance_dir.join("plugins");
+    fs::create_dir_all(&plugins_dir)
+        .map_err(|e| format!("Failed to create {}: {}", plugins_dir.display(), e))?;
+    let file_name = jar_name(&resource);
+    let dest = plugins_dir.join(&file_name);
+    let partial = plugins_dir.join(format!("{}.part", file_name));
+    fs::write(&partial, &bytes)
+        .map_err(|e| format!("Writing {} failed: {}", partial.display(), e))?;
+    fs::rename(&partial, &dest).map_err(|e| format!("Writing {} failed: {}", dest.display(), e))?;
+
+    addons::record_install(
+        &instance_dir,
+        LockedAddon {
+            source: "spigot".into(),
+            project_id: resource_id.to_string(),
+            version_id: version.id.to_string(),
+            version_number: version.name,
+            file_name: file_name.clone(),
+            folder: "plugins".into(),
+            sha1: Some(format!("{:x}", Sha1::digest(&bytes))),
+            installed_at: Utc::now().to_rfc3339(),
+        },
+    )?;
+
+    Ok(AddonInstallOutcome::Installed { file_name })
+}
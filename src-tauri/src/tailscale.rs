@@ -0,0 +1,184 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, update_instance_config},
+    models::{InstanceConfig, PlayitTunnelMetadata, TailscaleStatus},
+    ports,
+};
+
+/// Ports Tailscale Funnel can listen on
+const FUNNEL_PORTS: [u16; 3] = [443, 8443, 10000];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StatusJson {
+    #[serde(default)]
+    backend_state: String,
+    #[serde(rename = "Self")]
+    this: Option<PeerJson>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PeerJson {
+    #[serde(default, rename = "DNSName")]
+    dns_name: String,
+    #[serde(default, rename = "TailscaleIPs")]
+    tailscale_ips: Vec<String>,
+}
+
+/// The tailscale CLI. The macOS app bundles it instead of putting it on PATH
+fn cli() -> &'static str {
+    const MAC_APP_CLI: &str = "/Applications/Tailscale.app/Contents/MacOS/Tailscale";
+    if cfg!(target_os = "macos") && Path::new(MAC_APP_CLI).exists() {
+        MAC_APP_CLI
+    } else {
+        "tailscale"
+    }
+}
+
+fn run(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(cli())
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run tailscale: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tailscale {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether tailscaled is installed and connected, and this machine's tailnet addresses
+pub fn status() -> TailscaleStatus {
+    let not_running = |installed| TailscaleStatus {
+        installed,
+        running: false,
+        dns_name: None,
+        ips: vec![],
+    };
+    let output = match run(&["status", "--json"]) {
+        Ok(output) => output,
+        // A stopped tailscaled still answers, so a failure to run means no CLI at all
+        Err(e) => return not_running(!e.starts_with("Failed to run")),
+    };
+    let Ok(status) = serde_json::from_str::<StatusJson>(&output) else {
+        return not_running(true);
+    };
+    let this = status.this.unwrap_or(PeerJson {
+        dns_name: String::new(),
+        tailscale_ips: vec![],
+    });
+
+    TailscaleStatus {
+        installed: true,
+        running: status.backend_state == "Running",
+        dns_name: Some(this.dns_name.trim_end_matches('.').to_string())
+            .filter(|name| !name.is_empty()),
+        ips: this.tailscale_ips,
+    }
+}
+
+/// Expose the server through Funnel if the instance asks for it
+pub fn start(config: &InstanceConfig, port: u16) -> Result<(), String> {
+    let Some(funnel_port) = config.tailscale.funnel_port else {
+        return Ok(());
+    };
+    run(&[
+        "funnel",
+        "--bg",
+        "--tcp",
+        &funnel_port.to_string(),
+        &format!("tcp://localhost:{}", port),
+    ])
+    .map(|_| ())
+}
+
+/// Turn the instance's Funnel off again
+pub fn stop(config: &InstanceConfig) {
+    if let Some(funnel_port) = config.tailscale.funnel_port {
+        let _ = run(&["funnel", "--tcp", &funnel_port.to_string(), "off"]);
+    }
+}
+
+/// Tailnet and Funnel addresses of the instance, in the playit tunnel shape
+pub fn tunnels(config: &InstanceConfig, port: u16) -> Vec<PlayitTunnelMetadata> {
+    let status = status();
+    let state = if status.running {
+        "Active"
+    } else {
+        "Tailscale is not connected"
+    };
+    let host = status
+        .dns_name
+        .clone()
+        .or_else(|| status.ips.first().cloned());
+
+    let mut tunnels = vec![PlayitTunnelMetadata {
+        id: Some("tailnet".into()),
+        name: Some("Tailnet".into()),
+        protocol: Some("TCP".into()),
+        public_hostname: host,
+        public_port: Some(port),
+        destination_port: Some(port),
+        status: Some(state.into()),
+        ..Default::default()
+    }];
+    if let (Some(funnel_port), Some(dns_name)) = (config.tailscale.funnel_port, status.dns_name) {
+        tunnels.push(PlayitTunnelMetadata {
+            id: Some("funnel".into()),
+            name: Some("Funnel".into()),
+            protocol: Some("TCP".into()),
+            public_hostname: Some(dns_name),
+            public_port: Some(funnel_port),
+            destination_port: Some(port),
+            status: Some(state.into()),
+            ..Default::default()
+        });
+    }
+    tunnels
+}
+
+#[tauri::command]
+pub async fn get_tailscale_status() -> Result<TailscaleStatus, String> {
+    tauri::async_runtime::spawn_blocking(status)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+}
+
+/// Turn Funnel on (on one of its public ports) or off for an instance. Applies from the
+/// next start
+#[tauri::command]
+pub async fn set_tailscale_funnel(
+    app_handle: tauri::AppHandle,
+    id: String,
+    funnel_port: Option<u16>,
+) -> Result<Vec<PlayitTunnelMetadata>, String> {
+    if let Some(port) = funnel_port {
+        if !FUNNEL_PORTS.contains(&port) {
+            return Err("Funnel can only listen on port 443, 8443 or 10000".into());
+        }
+    }
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    if !config.tailscale.enabled {
+        return Err(format!("Tailscale isn't enabled for '{}'", config.name));
+    }
+    config.tailscale.funnel_port = funnel_port;
+    update_instance_config(&app_handle, &config)?;
+
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let port = ports::server_port(&instance_dir);
+    tauri::async_runtime::spawn_blocking(move || tunnels(&config, port))
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use tauri::Emitter;
+
+use crate::{
+    filesystem,
+    instance::{build_instance, get_instance_by_id, update_instance_config},
+    models::{Instance, InstanceTemplate},
+    properties::ServerProperties,
+};
+
+const TEMPLATE_FILE: &str = "template.toml";
+
+/// Whether a server.properties key belongs to one instance and must not be shared through a
+/// template: its ports, RCON settings and world seed
+fn is_instance_property(key: &str) -> bool {
+    matches!(key, "server-port" | "query.port" | "level-seed") || key.starts_with("rcon.")
+}
+
+fn get_templates_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let templates_dir = filesystem::get_data_dir(app_handle)?.join("templates");
+    fs::create_dir_all(&templates_dir)
+        .map_err(|e| format!("Failed to create templates directory: {}", e))?;
+    Ok(templates_dir)
+}
+
+fn get_template_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let id = uuid::Uuid::parse_str(id).map_err(|_| format!("Invalid template id '{}'", id))?;
+    Ok(get_templates_dir(app_handle)?.join(id.hyphenated().to_string()))
+}
+
+fn read_template(template_dir: &Path) -> Result<InstanceTemplate, String> {
+    let content = fs::read_to_string(template_dir.join(TEMPLATE_FILE))
+        .map_err(|e| format!("Failed to read {}: {}", TEMPLATE_FILE, e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", TEMPLATE_FILE, e))
+}
+
+/// Copy every jar in `from` into `to`, returning the copied file names
+fn copy_jars(from: &Path, to: &Path) -> Result<Vec<String>, String> {
+    let mut copied = Vec::new();
+    let Ok(entries) = fs::read_dir(from) else {
+        return Ok(copied);
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("jar") {
+            continue;
+        }
+
+        fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+        fs::copy(&path, to.join(entry.file_name()))
+            .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        copied.push(entry.file_name().to_string_lossy().to_string());
+    }
+
+    copied.sort();
+    Ok(copied)
+}
+
+/// Save an existing instance's software, Java settings, server.properties, and plugin/mod
+/// jars as a reusable template
+#[tauri::command]
+pub async fn save_template(
+    app_handle: tauri::AppHandle,
+    id: String,
+    name: String,
+) -> Result<InstanceTemplate, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".into());
+    }
+
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+
+    let template_id = uuid::Uuid::new_v4().to_string();
+    let template_dir = get_templates_dir(&app_handle)?.join(&template_id);
+    fs::create_dir_all(&template_dir)
+        .map_err(|e| format!("Failed to create template directory: {}", e))?;
+
+    let template = InstanceTemplate {
+        id: template_id,
+        name,
+        software: config.software,
+        version: config.version,
+        loader: config.loader,
+        installer: config.installer,
+        java: config.java,
+        created_at: Utc::now().to_rfc3339(),
+        plugins: Vec::new(),
+        mods: Vec::new(),
+    };
+
+    // Don't leave a half-written template behind
+    write_template(&instance_dir, &template_dir, template).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&template_dir);
+    })
+}
+
+fn write_template(
+    instance_dir: &Path,
+    template_dir: &Path,
+    mut template: InstanceTemplate,
+) -> Result<InstanceTemplate, String> {
+    let properties = instance_dir.join("server.properties");
+    if properties.exists() {
+        fs::copy(&properties, template_dir.join("server.properties"))
+            .map_err(|e| format!("Failed to copy server.properties: {}", e))?;
+        let mut properties = ServerProperties::load(template_dir)?;
+        properties.remove("rcon.password");
+        properties.save()?;
+    }
+
+    // Custom jars can't be re-downloaded, so the template keeps its own copy
+    if template.software == "custom" {
+        fs::copy(
+            instance_dir.join("server.jar"),
+            template_dir.join("server.jar"),
+        )
+        .map_err(|e| format!("Failed to copy server.jar: {}", e))?;
+    }
+
+    template.plugins = copy_jars(&instance_dir.join("plugins"), &template_dir.join("plugins"))?;
+    template.mods = copy_jars(&instance_dir.join("mods"), &template_dir.join("mods"))?;
+
+    let toml_string = toml::to_string_pretty(&template)
+        .map_err(|e| format!("Failed to serialize {}: {}", TEMPLATE_FILE, e))?;
+    fs::write(template_dir.join(TEMPLATE_FILE), toml_string)
+        .map_err(|e| format!("Failed to write {}: {}", TEMPLATE_FILE, e))?;
+
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn list_templates(app_handle: tauri::AppHandle) -> Result<Vec<InstanceTemplate>, String> {
+    let templates_dir = get_templates_dir(&app_handle)?;
+
+    let mut templates: Vec<InstanceTemplate> = fs::read_dir(&templates_dir)
+        .map_err(|e| format!("Failed to read templates directory: {}", e))?
+        .flatten()
+        .filter(|entry| entry.path().join(TEMPLATE_FILE).exists())
+        .filter_map(|entry| read_template(&entry.path()).ok())
+        .collect();
+
+    templates.sort_by_key(|template| template.name.to_lowercase());
+
+    Ok(templates)
+}
+
+#[tauri::command]
+pub async fn delete_template(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    let template_dir = get_template_dir(&app_handle, &id)?;
+    if !template_dir.exists() {
+        return Err(format!("Template with id {} not found", id));
+    }
+
+    fs::remove_dir_all(&template_dir).map_err(|e| format!("Failed to delete template: {}", e))
+}
+
+/// Create a new instance using the software, Java settings, server.properties, and plugin/mod
+/// jars saved in a template
+#[tauri::command]
+pub async fn create_instance_from_template(
+    app_handle: tauri::AppHandle,
+    template_id: String,
+    name: String,
+    playit: bool,
+    eula: bool,
+) -> Result<(), String> {
+    let template_dir = get_template_dir(&app_handle, &template_id)?;
+    let template = read_template(&template_dir)?;
+
+    let custom_jar_path = (template.software == "custom").then(|| {
+        template_dir
+            .join("server.jar")
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let server = Instance {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        software: template.software.clone(),
+        version: template.version.clone(),
+        playit,
+        loader: template.loader.clone(),
+        installer: template.installer.clone(),
+        custom_jar_path,
+        properties: None,
+        eula,
+    };
+    let id = server.id.clone();
+
+    // Don't leave a half-configured instance behind
+    if let Err(e) = apply_template(&app_handle, &template_dir, template, server).await {
+        if let Ok(instance_dir) = filesystem::get_instance_dir(&app_handle, &id) {
+            let _ = fs::remove_dir_all(instance_dir);
+        }
+        let _ = app_handle.emit("instances-updated", ());
+        return Err(e);
+    }
+
+    let _ = app_handle.emit("instances-updated", ());
+
+    Ok(())
+}
+
+async fn apply_template(
+    app_handle: &tauri::AppHandle,
+    template_dir: &Path,
+    template: InstanceTemplate,
+    server: Instance,
+) -> Result<(), String> {
+    let id = server.id.clone();
+    let instance_dir = build_instance(app_handle, server, None).await?;
+
+    if template_dir.join("server.properties").exists() {
+        let template_properties = ServerProperties::load(template_dir)?;
+        let mut properties = ServerProperties::load(&instance_dir)?;
+        for (key, value) in template_properties.entries() {
+            if !is_instance_property(key) {
+                properties.set(key, value);
+            }
+        }
+        properties.save()?;
+    }
+
+    for dir in ["plugins", "mods"] {
+        copy_jars(&template_dir.join(dir), &instance_dir.join(dir))?;
+    }
+
+    let mut config = get_instance_by_id(app_handle, &id)?;
+    config.java = template.java;
+    update_instance_config(app_handle, &config)
+}
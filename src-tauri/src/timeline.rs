@@ -0,0 +1,134 @@
+use std::{fs, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    filesystem,
+    instance::tracked_pid,
+    models::{InstanceTimeline, TimelineEvent, TimelineKind},
+};
+
+const DEFAULT_LIMIT: usize = 500;
+
+/// Kept next to the audit log, outside the instance directory, so restoring a backup
+/// doesn't rewind it
+fn timeline_path(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("timeline");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create timeline dir: {}", e))?;
+    Ok(dir.join(format!("{}.jsonl", id)))
+}
+
+/// Add an event to the instance's timeline. Failing to write it never fails the caller
+pub fn record(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    kind: TimelineKind,
+    detail: Option<String>,
+    exit_code: Option<i32>,
+    uptime_secs: Option<u64>,
+) {
+    let event = TimelineEvent {
+        time: Utc::now().to_rfc3339(),
+        kind,
+        detail,
+        exit_code,
+        uptime_secs,
+    };
+    let result =
+        timeline_path(app_handle, id).and_then(|path| filesystem::append_json_line(&path, &event));
+    if let Err(e) = result {
+        println!(
+            "Failed to record {:?} in the timeline of {}: {}",
+            kind, id, e
+        );
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Uptime and crash figures over a set of events, oldest first
+fn summarize(id: &str, events: Vec<TimelineEvent>) -> InstanceTimeline {
+    let runs: Vec<u64> = events
+        .iter()
+        .filter_map(|event| event.uptime_secs)
+        .collect();
+    let crashes: Vec<&TimelineEvent> = events
+        .iter()
+        .filter(|event| event.kind == TimelineKind::Crashed)
+        .collect();
+    let clean_runs_since_crash = events
+        .iter()
+        .rev()
+        .take_while(|event| event.kind != TimelineKind::Crashed)
+        .filter(|event| event.kind == TimelineKind::Stopped)
+        .count() as u32;
+
+    // Still running when the newest lifecycle event is a start
+    let current_run_secs = events
+        .iter()
+        .rev()
+        .find(|event| {
+            matches!(
+                event.kind,
+                TimelineKind::Started | TimelineKind::Stopped | TimelineKind::Crashed
+            )
+        })
+        .filter(|event| event.kind == TimelineKind::Started && tracked_pid(id).is_some())
+        .and_then(|event| parse_time(&event.time))
+        .map(|started| (Utc::now() - started).num_seconds().max(0) as u64);
+
+    InstanceTimeline {
+        total_uptime_secs: runs.iter().sum::<u64>() + current_run_secs.unwrap_or(0),
+        longest_run_secs: runs
+            .iter()
+            .copied()
+            .chain(current_run_secs)
+            .max()
+            .unwrap_or(0),
+        current_run_secs,
+        crashes: crashes.len() as u32,
+        last_crash: crashes.last().map(|event| event.time.clone()),
+        clean_runs_since_crash,
+        events: events.into_iter().rev().collect(),
+    }
+}
+
+/// The instance's starts, stops, crashes, backups and version changes between `since` and
+/// `until` (RFC 3339, both optional), newest first and at most `limit` of them, with uptime
+/// and crash figures for the whole range
+#[tauri::command]
+pub async fn get_instance_timeline(
+    app_handle: tauri::AppHandle,
+    id: String,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+) -> Result<InstanceTimeline, String> {
+    let parse = |value: &str| parse_time(value).ok_or_else(|| format!("Invalid time '{}'", value));
+    let since = since.as_deref().map(parse).transpose()?;
+    let until = until.as_deref().map(parse).transpose()?;
+
+    let content = match fs::read_to_string(timeline_path(&app_handle, &id)?) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read timeline: {}", e)),
+    };
+    let events: Vec<TimelineEvent> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<TimelineEvent>(line).ok())
+        .filter(|event| {
+            let Some(time) = parse_time(&event.time) else {
+                return false;
+            };
+            since.is_none_or(|since| time >= since) && until.is_none_or(|until| time <= until)
+        })
+        .collect();
+
+    let mut timeline = summarize(&id, events);
+    timeline.events.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+    Ok(timeline)
+}
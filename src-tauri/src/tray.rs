@@ -0,0 +1,145 @@
+use tauri::{
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
+    tray::TrayIconBuilder,
+    Manager,
+};
+
+use crate::{config, filesystem, index::InstanceIndex, instance};
+
+const TRAY_ID: &str = "main";
+
+/// Build the tray menu from the current instance list: one submenu per instance with its
+/// status and quick actions, then the app-level entries
+fn build_menu(app_handle: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app_handle)?;
+
+    let instances_dir = filesystem::get_instances_dir(app_handle).unwrap_or_default();
+    let mut entries = app_handle
+        .state::<InstanceIndex>()
+        .all(&instances_dir)
+        .unwrap_or_default();
+    entries.sort_by_key(|entry| entry.config.name.to_lowercase());
+
+    let mut sys = sysinfo::System::new();
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+
+    for entry in &entries {
+        let id = &entry.config.id;
+        let running = sys
+            .processes()
+            .values()
+            .any(|process| instance::is_instance_server_process(process, &entry.dir));
+        let status = if running { "Running" } else { "Stopped" };
+
+        let submenu = Submenu::new(
+            app_handle,
+            format!("{} ({})", entry.config.name, status),
+            true,
+        )?;
+        submenu.append(&MenuItem::with_id(
+            app_handle,
+            format!("start:{}", id),
+            "Start",
+            !running,
+            None::<&str>,
+        )?)?;
+        submenu.append(&MenuItem::with_id(
+            app_handle,
+            format!("stop:{}", id),
+            "Stop",
+            running,
+            None::<&str>,
+        )?)?;
+        submenu.append(&MenuItem::with_id(
+            app_handle,
+            format!("console:{}", id),
+            "Open console",
+            true,
+            None::<&str>,
+        )?)?;
+        menu.append(&submenu)?;
+    }
+
+    if !entries.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app_handle)?)?;
+    }
+    menu.append(&MenuItem::with_id(
+        app_handle,
+        "show",
+        "Show nuko",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app_handle,
+        "quit",
+        "Quit",
+        true,
+        None::<&str>,
+    )?)?;
+
+    Ok(menu)
+}
+
+fn handle_menu_event(app_handle: &tauri::AppHandle, event_id: &str) {
+    match event_id {
+        "show" => crate::show_main_window(app_handle),
+        "quit" => app_handle.exit(0),
+        _ => {
+            let Some((action, id)) = event_id.split_once(':') else {
+                return;
+            };
+            let app_handle = app_handle.clone();
+            let id = id.to_string();
+            let action = action.to_string();
+            tauri::async_runtime::spawn(async move {
+                let result = match action.as_str() {
+                    "start" => instance::start_instance(app_handle.clone(), id).await,
+                    "stop" => instance::stop_instance(app_handle.clone(), id).await,
+                    "console" => match instance::get_instance_by_id(&app_handle, &id) {
+                        Ok(config) => {
+                            instance::open_instance_view(app_handle.clone(), id, config.name).await
+                        }
+                        Err(e) => Err(e),
+                    },
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    println!("Tray action '{}' failed: {}", action, e);
+                }
+            });
+        }
+    }
+}
+
+/// Create the tray icon. Called once from setup
+pub fn create(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app_handle)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("nuko")
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app_handle, event| handle_menu_event(app_handle, event.id().as_ref()));
+    if let Some(icon) = app_handle.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app_handle)?;
+    Ok(())
+}
+
+/// Rebuild the tray menu so instance statuses stay current
+pub fn refresh(app_handle: &tauri::AppHandle) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_menu(app_handle).and_then(|menu| tray.set_menu(Some(menu))) {
+        println!("Failed to refresh tray menu: {}", e);
+    }
+}
+
+/// Whether closing the main window should hide it to the tray instead of quitting
+pub fn close_to_tray(app_handle: &tauri::AppHandle) -> bool {
+    config::get_config(app_handle.clone())
+        .map(|config| config.close_to_tray)
+        .unwrap_or(false)
+}
@@ -0,0 +1,306 @@
+use std::path::Path;
+
+use tauri::Emitter;
+
+use crate::{
+    addons, backup, curseforge,
+    download::{download_server_jar, get_fabric_loader_versions},
+    filesystem,
+    instance::{get_instance_by_id, is_instance_running, update_instance_config},
+    models::{AddonCompatibility, Instance, LockedAddon, SoftwareMigration, VersionUpgrade},
+    modrinth,
+};
+
+/// Numeric parts of a release version, or None for snapshots and pre-releases
+fn release_parts(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn compatibility(
+    addon: &LockedAddon,
+    status: &str,
+    latest_version: Option<String>,
+    detail: Option<String>,
+) -> AddonCompatibility {
+    AddonCompatibility {
+        file_name: addon.file_name.clone(),
+        source: Some(addon.source.clone()),
+        status: status.to_string(),
+        latest_version,
+        detail,
+    }
+}
+
+/// Look up whether the platform an addon came from has a release for `version`
+async fn check_addon(
+    app_handle: &tauri::AppHandle,
+    addon: &LockedAddon,
+    software: &str,
+    version: &str,
+) -> AddonCompatibility {
+    // Newest first: (id, version number) of each release supporting the new version
+    let releases: Result<Vec<(String, String)>, String> = match addon.source.as_str() {
+        "modrinth" => match modrinth::loader_for(software) {
+            Some((loaders, _)) => {
+                modrinth::get_project_versions(&addon.project_id, loaders, version)
+                    .await
+                    .map(|versions| {
+                        versions
+                            .into_iter()
+                            .map(|release| (release.id, release.version_number))
+                            .collect()
+                    })
+            }
+            None => Err(format!("Modrinth has nothing for {} servers", software)),
+        },
+        "curseforge" => {
+            let lookup = async {
+                let key = curseforge::api_key(app_handle)?;
+                let loader = curseforge::mod_loader_type(software).ok_or_else(|| {
+                    format!("CurseForge mods can't run on {} instances", software)
+                })?;
+                let mod_id = addon
+                    .project_id
+                    .parse()
+                    .map_err(|_| format!("Invalid CurseForge mod id '{}'", addon.project_id))?;
+                curseforge::compatible_files(&key, mod_id, loader, version).await
+            };
+            lookup.await.map(|files| {
+                files
+                    .into_iter()
+                    .map(|file| (file.id.to_string(), file.display_name))
+                    .collect()
+            })
+        }
+        _ => Err("SpigotMC doesn't say which versions a plugin supports".into()),
+    };
+
+    match releases {
+        Ok(releases) if releases.is_empty() => compatibility(
+            addon,
+            "incompatible",
+            None,
+            Some(format!("No release supports {} yet", version)),
+        ),
+        Ok(releases) if releases.iter().any(|(id, _)| *id == addon.version_id) => {
+            compatibility(addon, "compatible", None, None)
+        }
+        Ok(releases) => compatibility(
+            addon,
+            "update_available",
+            releases.into_iter().next().map(|(_, number)| number),
+            Some(format!(
+                "The installed release doesn't list {}, a newer one does",
+                version
+            )),
+        ),
+        Err(e) => compatibility(addon, "unknown", None, Some(e)),
+    }
+}
+
+/// Check every plugin or mod in the instance against `version`: locked addons through the
+/// platform they came from, hand-added jars are reported as unknown
+async fn check_addons(
+    app_handle: &tauri::AppHandle,
+    instance_dir: &Path,
+    software: &str,
+    version: &str,
+) -> Result<Vec<AddonCompatibility>, String> {
+    let lockfile = addons::load_lockfile(instance_dir)?;
+    let mut results = Vec::new();
+    for addon in &lockfile.addons {
+        results.push(check_addon(app_handle, addon, software, version).await);
+    }
+
+    if let Some((_, folder)) = modrinth::loader_for(software) {
+        for path in addons::addon_files(&instance_dir.join(folder)) {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let locked = lockfile
+                .addons
+                .iter()
+                .any(|addon| file_name.trim_end_matches(".disabled") == addon.file_name);
+            if !locked {
+                results.push(AddonCompatibility {
+                    file_name,
+                    source: None,
+                    status: "unknown".into(),
+                    latest_version: None,
+                    detail: Some("Added by hand, check its supported versions manually".into()),
+                });
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Move an instance to another Minecraft version: take a backup, install the server jar (or
+/// re-run the Forge/NeoForge installer) for `new_version`, and report which plugins and mods
+/// are likely to break so they can be dealt with before the first start. `loader` is
+/// required for Forge and NeoForge, Fabric defaults to the newest stable loader. The upgrade
+/// can be undone with `rollback_jar_change` on the returned backup
+#[tauri::command]
+pub async fn upgrade_instance_version(
+    app_handle: tauri::AppHandle,
+    id: String,
+    new_version: String,
+    loader: Option<String>,
+) -> Result<VersionUpgrade, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before upgrading it", config.name));
+    }
+    if config.software == "custom" {
+        return Err("Custom jars can't be upgraded, replace the jar instead".into());
+    }
+
+    let new_version = new_version.trim().to_string();
+    if new_version.is_empty() {
+        return Err("Choose a Minecraft version to upgrade to".into());
+    }
+    if new_version == config.version {
+        return Err(format!("'{}' already runs {}", config.name, new_version));
+    }
+    if let (Some(current), Some(target)) =
+        (release_parts(&config.version), release_parts(&new_version))
+    {
+        if target < current {
+            return Err(
+                "Worlds can't be safely downgraded, restore a backup from that version instead"
+                    .into(),
+            );
+        }
+    }
+
+    let loader = match (config.software.as_str(), loader) {
+        (_, Some(loader)) if !loader.trim().is_empty() => Some(loader.trim().to_string()),
+        ("fabric", _) => Some(
+            get_fabric_loader_versions(new_version.clone(), Some(true))
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No stable Fabric loader for {}", new_version))?,
+        ),
+        ("forge" | "neoforge", _) => {
+            return Err(format!(
+                "Choose the {} version to install for {}",
+                config.software, new_version
+            ))
+        }
+        // A pinned Purpur build belongs to the old version
+        _ => None,
+    };
+
+    let backup = backup::backup_before_jar_change(
+        &app_handle,
+        &config,
+        &format!("upgrade to {}", new_version),
+    )
+    .await?;
+
+    let server = Instance {
+        id: config.id.clone(),
+        name: config.name.clone(),
+        software: config.software.clone(),
+        version: new_version.clone(),
+        playit: config.playit,
+        loader: loader.clone(),
+        installer: config.installer.clone(),
+        custom_jar_path: None,
+        properties: None,
+        eula: false,
+    };
+    download_server_jar(&instance_dir, &server)
+        .await
+        .map_err(|e| format!("Failed to install {}: {}", new_version, e))?;
+
+    let previous_version = std::mem::replace(&mut config.version, new_version.clone());
+    config.loader = loader.clone();
+    config.build = None;
+    update_instance_config(&app_handle, &config)?;
+    let _ = app_handle.emit("instances-updated", ());
+
+    let addons = check_addons(&app_handle, &instance_dir, &config.software, &new_version).await?;
+
+    Ok(VersionUpgrade {
+        previous_version,
+        version: new_version,
+        loader,
+        backup,
+        addons,
+    })
+}
+
+/// Software an instance can move to without losing its worlds. Bukkit-based servers move the
+/// vanilla nether and end into their own world folders on first start, but there is no way
+/// back, and custom jars are usually Spigot
+fn can_migrate(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("vanilla" | "custom", "papermc" | "purpur") | ("papermc", "purpur")
+    )
+}
+
+/// Switch an instance to another server software on the same Minecraft version, e.g.
+/// Vanilla or Spigot to Paper, keeping its worlds and configs. A backup is taken first so
+/// the switch can be undone with `rollback_jar_change`
+#[tauri::command]
+pub async fn migrate_instance_software(
+    app_handle: tauri::AppHandle,
+    id: String,
+    software: String,
+) -> Result<SoftwareMigration, String> {
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before migrating it", config.name));
+    }
+    if !can_migrate(&config.software, &software) {
+        return Err(format!(
+            "'{}' can't be migrated from {} to {}",
+            config.name, config.software, software
+        ));
+    }
+
+    let backup = backup::backup_before_jar_change(
+        &app_handle,
+        &config,
+        &format!("migration to {}", software),
+    )
+    .await?;
+
+    // A custom jar's own version is only known from detection
+    let version = config.effective_version().to_string();
+    let server = Instance {
+        id: config.id.clone(),
+        name: config.name.clone(),
+        software: software.clone(),
+        version: version.clone(),
+        playit: config.playit,
+        loader: None,
+        installer: None,
+        custom_jar_path: None,
+        properties: None,
+        eula: false,
+    };
+    download_server_jar(&instance_dir, &server)
+        .await
+        .map_err(|e| format!("Failed to install {} {}: {}", software, version, e))?;
+
+    let previous_software = std::mem::replace(&mut config.software, software.clone());
+    config.version = version;
+    config.loader = None;
+    config.build = None;
+    config.custom_jar_path = None;
+    update_instance_config(&app_handle, &config)?;
+    let _ = app_handle.emit("instances-updated", ());
+
+    Ok(SoftwareMigration {
+        previous_software,
+        software,
+        backup,
+    })
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::Utc;
+use tauri::Manager;
+
+use crate::{
+    filesystem,
+    index::InstanceIndex,
+    instance::{get_instance_by_id, is_instance_online, tracked_pid},
+    metrics,
+    models::{InstanceStatus, StatusTransition, UptimeStats},
+    watchdog,
+};
+
+const DEFAULT_RANGE: &str = "30d";
+
+/// The status each instance was last recorded in
+fn get_statuses() -> &'static Mutex<HashMap<String, InstanceStatus>> {
+    static STATUSES: OnceLock<Mutex<HashMap<String, InstanceStatus>>> = OnceLock::new();
+    STATUSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn uptime_path(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dir = filesystem::get_data_dir(app_handle)?.join("uptime");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create uptime dir: {}", e))?;
+    Ok(dir.join(format!("{}.jsonl", id)))
+}
+
+fn read_transitions(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+) -> Result<Vec<StatusTransition>, String> {
+    let Ok(file) = fs::File::open(uptime_path(app_handle, id)?) else {
+        return Ok(Vec::new());
+    };
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+fn append(app_handle: &tauri::AppHandle, id: &str, transition: &StatusTransition) {
+    let result = uptime_path(app_handle, id)
+        .and_then(|path| filesystem::append_json_line(&path, transition));
+    if let Err(e) = result {
+        println!("Failed to record the status of {}: {}", id, e);
+    }
+}
+
+/// Whether the server is meant to be up in this status
+fn is_active(status: InstanceStatus) -> bool {
+    matches!(
+        status,
+        InstanceStatus::Starting | InstanceStatus::Online | InstanceStatus::Unresponsive
+    )
+}
+
+/// The status recorded before this run of nuko. A server that was up when nuko last
+/// closed went down with it, at the latest when its last metrics sample was taken
+fn restore_status(app_handle: &tauri::AppHandle, id: &str) -> InstanceStatus {
+    let Some(last) = read_transitions(app_handle, id)
+        .ok()
+        .and_then(|transitions| transitions.last().cloned())
+    else {
+        return InstanceStatus::Stopped;
+    };
+    if !is_active(last.status) || tracked_pid(id).is_some() {
+        return last.status;
+    }
+
+    let last_seen = metrics::last_sample_time(app_handle, id)
+        .unwrap_or(last.timestamp)
+        .max(last.timestamp);
+    append(
+        app_handle,
+        id,
+        &StatusTransition {
+            timestamp: last_seen,
+            status: InstanceStatus::Stopped,
+        },
+    );
+    InstanceStatus::Stopped
+}
+
+/// Move an instance to `status`, recording the transition if it changed
+pub fn transition(app_handle: &tauri::AppHandle, id: &str, status: InstanceStatus) {
+    let mut statuses = get_statuses().lock().unwrap();
+    let current = match statuses.get(id) {
+        Some(current) => *current,
+        None => restore_status(app_handle, id),
+    };
+    statuses.insert(id.to_string(), status);
+    drop(statuses);
+
+    if current != status {
+        append(
+            app_handle,
+            id,
+            &StatusTransition {
+                timestamp: Utc::now().timestamp(),
+                status,
+            },
+        );
+    }
+}
+
+/// Derive each instance's status from its process, the "Done" line and the watchdog. A
+/// crashed server stays crashed until it's started again
+pub fn observe(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    for entry in app_handle.state::<InstanceIndex>().all(&instances_dir)? {
+        let id = entry.config.id;
+        let status = if tracked_pid(&id).is_none() {
+            match get_statuses().lock().unwrap().get(&id) {
+                Some(InstanceStatus::Crashed) => InstanceStatus::Crashed,
+                _ => InstanceStatus::Stopped,
+            }
+        } else if !is_instance_online(&id) {
+            InstanceStatus::Starting
+        } else if watchdog::is_hung(&id) {
+            InstanceStatus::Unresponsive
+        } else {
+            InstanceStatus::Online
+        };
+        transition(app_handle, &id, status);
+    }
+    Ok(())
+}
+
+/// Cumulative uptime, the longest and current stretch online, and availability over
+/// `range` (e.g. "24h" or "30d", 30 days by default). Availability is the share of the time
+/// the server was meant to be up (starting, online, unresponsive or crashed) that it was
+/// online
+#[tauri::command]
+pub async fn get_uptime_stats(
+    app_handle: tauri::AppHandle,
+    id: String,
+    range: Option<String>,
+) -> Result<UptimeStats, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let range_secs = metrics::parse_range(range.as_deref().unwrap_or(DEFAULT_RANGE))?;
+    let transitions = read_transitions(&app_handle, &id)?;
+    let now = Utc::now().timestamp();
+    let range_start = now - range_secs;
+
+    let mut stats = UptimeStats {
+        status: get_statuses()
+            .lock()
+            .unwrap()
+            .get(&id)
+            .copied()
+            .or_else(|| transitions.last().map(|last| last.status))
+            .unwrap_or(InstanceStatus::Stopped),
+        total_uptime_secs: 0,
+        longest_streak_secs: 0,
+        current_streak_secs: 0,
+        range_uptime_secs: 0,
+        range_downtime_secs: 0,
+        availability_percent: None,
+    };
+
+    for (index, transition) in transitions.iter().enumerate() {
+        let end = transitions
+            .get(index + 1)
+            .map(|next| next.timestamp)
+            .unwrap_or(now);
+        let duration = (end - transition.timestamp).max(0) as u64;
+        let in_range = (end - transition.timestamp.max(range_start)).max(0) as u64;
+
+        match transition.status {
+            InstanceStatus::Online => {
+                stats.total_uptime_secs += duration;
+                stats.longest_streak_secs = stats.longest_streak_secs.max(duration);
+                stats.range_uptime_secs += in_range;
+                if index + 1 == transitions.len() {
+                    stats.current_streak_secs = duration;
+                }
+            }
+            status if is_active(status) || status == InstanceStatus::Crashed => {
+                stats.range_downtime_secs += in_range;
+            }
+            _ => {}
+        }
+    }
+
+    let expected = stats.range_uptime_secs + stats.range_downtime_secs;
+    stats.availability_percent =
+        (expected > 0).then(|| stats.range_uptime_secs as f64 * 100.0 / expected as f64);
+    Ok(stats)
+}
@@ -0,0 +1,52 @@
+use crate::{
+    filesystem,
+    instance::{get_instance_by_id, is_instance_running},
+    modrinth,
+};
+
+const VIAVERSION_PROJECT: &str = "viaversion";
+/// Depends on ViaVersion, which Modrinth resolves as a required dependency
+const VIABACKWARDS_PROJECT: &str = "viabackwards";
+
+/// Install ViaVersion so newer clients can join a Paper-family server, and ViaBackwards when
+/// `older_clients` is set so older clients can join too. Builds are picked from Modrinth for
+/// the instance's Minecraft version. Returns the downloaded file names
+#[tauri::command]
+pub async fn install_via_version(
+    app_handle: tauri::AppHandle,
+    id: String,
+    older_clients: bool,
+) -> Result<Vec<String>, String> {
+    let config = get_instance_by_id(&app_handle, &id)?;
+    let (loaders, folder) = match modrinth::loader_for(config.effective_software()) {
+        Some((loaders, "plugins")) => (loaders, "plugins"),
+        _ => {
+            return Err(format!(
+                "ViaVersion needs a Paper-family server, not {}",
+                config.effective_software()
+            ))
+        }
+    };
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!(
+            "Stop '{}' before installing ViaVersion",
+            config.name
+        ));
+    }
+
+    let project = if older_clients {
+        VIABACKWARDS_PROJECT
+    } else {
+        VIAVERSION_PROJECT
+    };
+    modrinth::install(
+        &instance_dir,
+        folder,
+        project,
+        None,
+        loaders,
+        config.effective_version(),
+    )
+    .await
+}
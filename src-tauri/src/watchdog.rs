@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tauri::{Emitter, Manager};
+
+use crate::{
+    audit, filesystem,
+    index::InstanceIndex,
+    instance::{
+        get_instance_by_id, is_instance_online, kill_instance, record_log_line, start_instance,
+        stop_instance, tracked_pid, update_instance_config,
+    },
+    jvm,
+    models::{InstanceHung, WatchdogConfig},
+    ports, protocol,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long each escalation step gets to bring the server down
+const STEP_TIMEOUT: Duration = Duration::from_secs(90);
+
+struct WatchState {
+    last_output: Instant,
+    failed_pings: u32,
+    /// Set while the escalation for a hang is running, so it isn't started twice
+    handling: bool,
+}
+
+fn get_states() -> &'static Mutex<HashMap<String, WatchState>> {
+    static STATES: OnceLock<Mutex<HashMap<String, WatchState>>> = OnceLock::new();
+    STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Record that the server printed something, which means it isn't hung
+pub fn note_output(id: &str) {
+    let mut states = get_states().lock().unwrap();
+    let state = states.entry(id.to_string()).or_insert(WatchState {
+        last_output: Instant::now(),
+        failed_pings: 0,
+        handling: false,
+    });
+    state.last_output = Instant::now();
+    state.failed_pings = 0;
+}
+
+/// Whether the server is considered hung and the escalation for it is under way
+pub fn is_hung(id: &str) -> bool {
+    get_states()
+        .lock()
+        .unwrap()
+        .get(id)
+        .is_some_and(|state| state.handling)
+}
+
+/// Wait up to `STEP_TIMEOUT` for the server process to exit
+async fn wait_for_exit(id: &str) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < STEP_TIMEOUT {
+        if tracked_pid(id).is_none() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    tracked_pid(id).is_none()
+}
+
+/// Work through the configured steps: thread dump, graceful stop, kill, restart
+async fn escalate(app_handle: tauri::AppHandle, id: String, watchdog: WatchdogConfig) {
+    if watchdog.thread_dump {
+        if let Err(e) = jvm::capture_thread_dump(app_handle.clone(), id.clone()).await {
+            record_log_line(
+                &app_handle,
+                &id,
+                format!("[nuko] Watchdog couldn't take a thread dump: {}", e),
+            );
+        }
+    }
+
+    let mut stopped = false;
+    if watchdog.stop {
+        record_log_line(
+            &app_handle,
+            &id,
+            "[nuko] Watchdog is stopping the server".into(),
+        );
+        if stop_instance(app_handle.clone(), id.clone()).await.is_ok() {
+            stopped = wait_for_exit(&id).await;
+        }
+    }
+    if !stopped && watchdog.kill {
+        record_log_line(
+            &app_handle,
+            &id,
+            "[nuko] Watchdog is killing the server".into(),
+        );
+        if kill_instance(app_handle.clone(), id.clone()).await.is_ok() {
+            stopped = wait_for_exit(&id).await;
+        }
+    }
+    if stopped && watchdog.restart {
+        record_log_line(
+            &app_handle,
+            &id,
+            "[nuko] Watchdog is restarting the server".into(),
+        );
+        if let Err(e) = start_instance(app_handle.clone(), id.clone()).await {
+            println!("Watchdog failed to restart {}: {}", id, e);
+        }
+    }
+
+    if let Some(state) = get_states().lock().unwrap().get_mut(&id) {
+        state.handling = false;
+        state.failed_pings = 0;
+        state.last_output = Instant::now();
+    }
+}
+
+fn check(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let instances_dir = filesystem::get_instances_dir(app_handle)?;
+    let instances = app_handle.state::<InstanceIndex>().all(&instances_dir)?;
+
+    for entry in instances {
+        let config = entry.config;
+        let id = config.id.clone();
+        // Servers still starting up are busy generating worlds, not hung
+        if !config.watchdog.enabled || tracked_pid(&id).is_none() || !is_instance_online(&id) {
+            get_states().lock().unwrap().remove(&id);
+            continue;
+        }
+
+        let silent_for = {
+            let mut states = get_states().lock().unwrap();
+            let state = states.entry(id.clone()).or_insert(WatchState {
+                last_output: Instant::now(),
+                failed_pings: 0,
+                handling: false,
+            });
+            if state.handling {
+                continue;
+            }
+            state.last_output.elapsed()
+        };
+        if silent_for < Duration::from_secs(config.watchdog.silent_minutes.max(1) * 60) {
+            continue;
+        }
+
+        // An idle server can be quiet for hours, so silence only counts with failed pings
+        let instance_dir = filesystem::get_instance_dir(app_handle, &id)?;
+        let answered = protocol::ping("127.0.0.1", ports::server_port(&instance_dir)).is_ok();
+        let failed_pings = {
+            let mut states = get_states().lock().unwrap();
+            let Some(state) = states.get_mut(&id) else {
+                continue;
+            };
+            state.failed_pings = if answered { 0 } else { state.failed_pings + 1 };
+            if state.failed_pings < config.watchdog.failed_pings.max(1) {
+                continue;
+            }
+            state.handling = true;
+            state.failed_pings
+        };
+
+        record_log_line(
+            app_handle,
+            &id,
+            format!(
+                "[nuko] Server looks hung: no output for {} minutes and {} failed pings",
+                silent_for.as_secs() / 60,
+                failed_pings
+            ),
+        );
+        let _ = app_handle.emit(
+            "instance-hung",
+            InstanceHung {
+                id: id.clone(),
+                silent_secs: silent_for.as_secs(),
+                failed_pings,
+            },
+        );
+        tauri::async_runtime::spawn(audit::as_actor(
+            "watchdog",
+            escalate(app_handle.clone(), id, config.watchdog),
+        ));
+    }
+    Ok(())
+}
+
+/// Watch running instances with the watchdog enabled for servers that stopped logging and
+/// stopped answering pings while their process is still alive
+pub fn start(app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        if let Err(e) = check(&app_handle) {
+            println!("Failed to check for hung servers: {}", e);
+        }
+    });
+}
+
+/// Configure hang detection and what happens when the instance hangs
+#[tauri::command]
+pub async fn set_watchdog(
+    app_handle: tauri::AppHandle,
+    id: String,
+    watchdog: WatchdogConfig,
+) -> Result<(), String> {
+    if watchdog.silent_minutes == 0 {
+        return Err("The server must be silent for at least a minute".into());
+    }
+    if watchdog.failed_pings == 0 {
+        return Err("At least one ping must fail".into());
+    }
+    let mut config = get_instance_by_id(&app_handle, &id)?;
+    config.watchdog = watchdog;
+    update_instance_config(&app_handle, &config)
+}
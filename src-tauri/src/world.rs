@@ -0,0 +1,641 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use tauri::Emitter;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::{
+    archive::zip_dir,
+    filesystem,
+    instance::{
+        get_instance_by_id, has_instance_stdin, is_instance_running, log_line_count, logged_since,
+        write_instance_stdin,
+    },
+    models::{TrimOptions, TrimReport, WorldExportProgress, WorldInfo, WorldSummary},
+    properties::ServerProperties,
+};
+
+const SAVE_TIMEOUT_SECS: u64 = 60;
+const REGION_BLOCKS: i64 = 512;
+/// One block in the nether spans this many overworld blocks
+const NETHER_SCALE: i64 = 8;
+/// Same nesting limit Minecraft applies, so hostile files can't overflow the stack
+const MAX_NBT_DEPTH: usize = 512;
+/// Region data lives in these folders for the overworld, nether and end
+const REGION_DIRS: [&str; 9] = [
+    "region",
+    "entities",
+    "poi",
+    "DIM-1/region",
+    "DIM-1/entities",
+    "DIM-1/poi",
+    "DIM1/region",
+    "DIM1/entities",
+    "DIM1/poi",
+];
+
+/// A decoded NBT tag. Byte, int and long arrays aren't needed by anything nuko reads, so
+/// their payloads are consumed but not kept
+#[derive(Debug, Clone)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    Skipped,
+}
+
+impl Tag {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Any integral tag widened to i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Any numeric tag widened to f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Tag::Float(v) => Some(*v as f64),
+            Tag::Double(v) => Some(*v),
+            other => other.as_i64().map(|v| v as f64),
+        }
+    }
+
+    pub fn as_list(&self) -> &[Tag] {
+        match self {
+            Tag::List(items) => items,
+            _ => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Render scalar tags the way they'd be typed into a command
+    fn display_value(&self) -> Option<String> {
+        match self {
+            Tag::String(s) => Some(s.clone()),
+            Tag::Float(v) => Some(v.to_string()),
+            Tag::Double(v) => Some(v.to_string()),
+            other => other.as_i64().map(|v| v.to_string()),
+        }
+    }
+}
+
+/// Minimal big-endian NBT reader for Java Edition files
+struct NbtReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> NbtReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("Unexpected end of NBT data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = self.i32()?;
+        usize::try_from(len).map_err(|_| format!("Invalid NBT length {}", len))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        // NBT strings are Java "modified UTF-8", which is plain UTF-8 for everything nuko reads
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, tag_type: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_NBT_DEPTH {
+            return Err(format!(
+                "NBT is nested deeper than {} levels",
+                MAX_NBT_DEPTH
+            ));
+        }
+        Ok(match tag_type {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(self.i16()?),
+            3 => Tag::Int(self.i32()?),
+            4 => Tag::Long(self.i64()?),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let len = self.len()?;
+                self.take(len)?;
+                Tag::Skipped
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_type = self.u8()?;
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item_type, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut map = HashMap::new();
+                loop {
+                    let child_type = self.u8()?;
+                    if child_type == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    map.insert(name, self.payload(child_type, depth + 1)?);
+                }
+                Tag::Compound(map)
+            }
+            11 | 12 => {
+                let len = self.len()?;
+                let width = if tag_type == 11 { 4 } else { 8 };
+                self.take(len.checked_mul(width).ok_or("Unexpected end of NBT data")?)?;
+                Tag::Skipped
+            }
+            other => return Err(format!("Unknown NBT tag type {}", other)),
+        })
+    }
+}
+
+/// Parse an uncompressed NBT document and return its root tag
+pub fn parse_nbt(data: &[u8]) -> Result<Tag, String> {
+    let mut reader = NbtReader { data, pos: 0 };
+    let root_type = reader.u8()?;
+    if root_type != 10 {
+        return Err("NBT root is not a compound".into());
+    }
+    reader.string()?;
+    reader.payload(root_type, 0)
+}
+
+/// Read a gzip-compressed NBT file such as level.dat
+pub fn read_nbt_file(path: &Path) -> Result<Tag, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut data = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to decompress {}: {}", path.display(), e))?;
+    parse_nbt(&data)
+}
+
+/// The world folder the server loads, from `level-name` in server.properties
+pub fn active_world_name(instance_dir: &Path) -> Result<String, String> {
+    let properties = ServerProperties::load(instance_dir)?;
+    Ok(properties
+        .get("level-name")
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("world")
+        .to_string())
+}
+
+/// Extract the interesting fields from a level.dat root tag
+pub fn world_info(level_name: String, root: &Tag) -> Result<WorldInfo, String> {
+    let data = root.get("Data").ok_or("level.dat has no Data tag")?;
+
+    // 1.16+ keeps the seed under WorldGenSettings, older worlds use RandomSeed
+    let seed = data
+        .get("WorldGenSettings")
+        .and_then(|settings| settings.get("seed"))
+        .or_else(|| data.get("RandomSeed"))
+        .and_then(Tag::as_i64);
+
+    let spawn = match (data.get("SpawnX"), data.get("SpawnY"), data.get("SpawnZ")) {
+        (Some(x), Some(y), Some(z)) => match (x.as_i64(), y.as_i64(), z.as_i64()) {
+            (Some(x), Some(y), Some(z)) => Some([x as i32, y as i32, z as i32]),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let gamerules = match data.get("GameRules") {
+        Some(Tag::Compound(rules)) => rules
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.display_value()?)))
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+
+    Ok(WorldInfo {
+        level_name,
+        seed,
+        spawn,
+        day_time: data.get("DayTime").and_then(Tag::as_i64),
+        game_time: data.get("Time").and_then(Tag::as_i64),
+        data_version: data
+            .get("DataVersion")
+            .and_then(Tag::as_i64)
+            .map(|v| v as i32),
+        version_name: data
+            .get("Version")
+            .and_then(|version| version.get("Name"))
+            .and_then(Tag::as_str)
+            .map(str::to_string),
+        gamerules,
+    })
+}
+
+/// Resolve a world folder, rejecting names that could escape the instance directory
+pub fn world_dir(instance_dir: &Path, world_name: &str) -> Result<PathBuf, String> {
+    // Exactly one normal component, so prefixes like `C:` on Windows are rejected too
+    let mut components = Path::new(world_name).components();
+    let valid = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !world_name.contains(['/', '\\']);
+    if !valid {
+        return Err(format!("Invalid world name '{}'", world_name));
+    }
+    Ok(instance_dir.join(world_name))
+}
+
+/// Like `world_dir`, but the world must already be generated
+fn existing_world_dir(instance_dir: &Path, world_name: &str) -> Result<PathBuf, String> {
+    let dir = world_dir(instance_dir, world_name)?;
+    if !dir.join("level.dat").exists() {
+        return Err(format!("World '{}' not found", world_name));
+    }
+    Ok(dir)
+}
+
+/// Instance directory for an existing instance that must not be running
+fn stopped_instance_dir(app_handle: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let config = get_instance_by_id(app_handle, id)?;
+    let instance_dir = filesystem::get_instance_dir(app_handle, id)?;
+    if is_instance_running(&instance_dir) {
+        return Err(format!("Stop '{}' before changing its worlds", config.name));
+    }
+    Ok(instance_dir)
+}
+
+/// Read seed, spawn, time, gamerules and version from the active world's level.dat without
+/// starting the server
+#[tauri::command]
+pub async fn get_world_info(app_handle: tauri::AppHandle, id: String) -> Result<WorldInfo, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let level_name = active_world_name(&instance_dir)?;
+
+    let level_dat = world_dir(&instance_dir, &level_name)?.join("level.dat");
+    if !level_dat.exists() {
+        return Err(format!(
+            "World '{}' has not been generated yet. Start the server once to create it",
+            level_name
+        ));
+    }
+
+    let root = tauri::async_runtime::spawn_blocking(move || read_nbt_file(&level_dat))
+        .await
+        .map_err(|e| format!("Failed to read level.dat: {}", e))??;
+
+    world_info(level_name, &root)
+}
+
+/// Every world folder in the instance: anything with a level.dat, which covers the Bukkit
+/// `_nether`/`_the_end` folders and Multiverse worlds as well as the main world
+#[tauri::command]
+pub async fn list_worlds(
+    app_handle: tauri::AppHandle,
+    id: String,
+) -> Result<Vec<WorldSummary>, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let level_name = active_world_name(&instance_dir)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut worlds: Vec<WorldSummary> = fs::read_dir(&instance_dir)
+            .map_err(|e| format!("Failed to read instance directory: {}", e))?
+            .flatten()
+            .filter(|entry| entry.path().join("level.dat").exists())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                WorldSummary {
+                    active: name == level_name
+                        || name == format!("{}_nether", level_name)
+                        || name == format!("{}_the_end", level_name),
+                    size_bytes: filesystem::dir_size(&entry.path()),
+                    name,
+                }
+            })
+            .collect();
+
+        worlds.sort_by(|a, b| b.active.cmp(&a.active).then(a.name.cmp(&b.name)));
+        Ok(worlds)
+    })
+    .await
+    .map_err(|e| format!("Failed to list worlds: {}", e))?
+}
+
+/// Point `level-name` at another world folder. Takes effect on the next start
+#[tauri::command]
+pub async fn set_active_world(
+    app_handle: tauri::AppHandle,
+    id: String,
+    world_name: String,
+) -> Result<(), String> {
+    let instance_dir = stopped_instance_dir(&app_handle, &id)?;
+    existing_world_dir(&instance_dir, &world_name)?;
+
+    let mut properties = ServerProperties::load(&instance_dir)?;
+    properties.set("level-name", world_name);
+    properties.save()
+}
+
+/// Zip a world into data_dir/world-backups/<id>/ and return the archive path
+fn backup_world(
+    app_handle: &tauri::AppHandle,
+    id: &str,
+    world_dir: &Path,
+    world_name: &str,
+) -> Result<PathBuf, String> {
+    let dest = filesystem::get_data_dir(app_handle)?
+        .join("world-backups")
+        .join(id)
+        .join(format!(
+            "{}-{}.zip",
+            world_name,
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+    zip_dir(world_dir, &dest, |_, _| {})?;
+    Ok(dest)
+}
+
+/// Archive a world to a zip and remove it from the instance. A backup is always taken first
+/// and its path is returned so the world can be restored by hand
+#[tauri::command]
+pub async fn delete_world(
+    app_handle: tauri::AppHandle,
+    id: String,
+    world_name: String,
+) -> Result<String, String> {
+    let instance_dir = stopped_instance_dir(&app_handle, &id)?;
+    let world_dir = existing_world_dir(&instance_dir, &world_name)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let backup = backup_world(&app_handle, &id, &world_dir, &world_name)?;
+        fs::remove_dir_all(&world_dir)
+            .map_err(|e| format!("Failed to delete world '{}': {}", world_name, e))?;
+        Ok(backup.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to delete world: {}", e))?
+}
+
+/// Flush the world to disk with `save-all flush` and wait for the server to confirm
+async fn flush_world(id: &str) -> Result<(), String> {
+    let since = log_line_count(id);
+    write_instance_stdin(id, "save-all flush")?;
+
+    let deadline = Instant::now() + Duration::from_secs(SAVE_TIMEOUT_SECS);
+    while Instant::now() < deadline {
+        if logged_since(id, since, "Saved the game") {
+            return Ok(());
+        }
+        sleep(Duration::from_millis(250)).await;
+    }
+
+    Err(format!(
+        "Server did not finish saving within {} seconds",
+        SAVE_TIMEOUT_SECS
+    ))
+}
+
+/// Run a blocking file operation against a consistent copy of the instance's worlds. If nuko
+/// is attached to the running server, the world is flushed and autosave is paused
+/// (`save-off`) until `task` finishes
+pub async fn with_saving_paused<T: Send + 'static>(
+    id: &str,
+    task: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let running = has_instance_stdin(id);
+    if running {
+        flush_world(id).await?;
+        write_instance_stdin(id, "save-off")?;
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))
+        .and_then(|result| result);
+
+    // Autosave must come back on even if the task failed
+    if running {
+        write_instance_stdin(id, "save-on")?;
+    }
+
+    result
+}
+
+/// Zip a world to `dest` for rendering or single-player use. If the server is running, the
+/// world is flushed and autosave is paused (`save-off`) for the duration of the export.
+/// Progress is emitted as `world-export-progress`
+#[tauri::command]
+pub async fn export_world(
+    app_handle: tauri::AppHandle,
+    id: String,
+    world_name: String,
+    dest: String,
+) -> Result<String, String> {
+    get_instance_by_id(&app_handle, &id)?;
+    let instance_dir = filesystem::get_instance_dir(&app_handle, &id)?;
+    let world_dir = existing_world_dir(&instance_dir, &world_name)?;
+
+    let mut dest = PathBuf::from(dest);
+    if dest.is_dir() {
+        dest = dest.join(format!("{}.zip", world_name));
+    }
+
+    let dest_path = dest.clone();
+    with_saving_paused(&id.clone(), move || {
+        zip_dir(&world_dir, &dest_path, |bytes_done, bytes_total| {
+            let _ = app_handle.emit(
+                "world-export-progress",
+                WorldExportProgress {
+                    id: id.clone(),
+                    world_name: world_name.clone(),
+                    bytes_done,
+                    bytes_total,
+                },
+            );
+        })
+    })
+    .await?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Region coordinates from an `r.<x>.<z>.mca` file name
+fn region_coords(file_name: &str) -> Option<(i64, i64)> {
+    let mut parts = file_name
+        .strip_prefix("r.")?
+        .strip_suffix(".mca")?
+        .split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+/// Newest per-chunk save time (unix seconds) from the region header's timestamp table
+fn region_last_saved(path: &Path) -> Option<i64> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut header = vec![0u8; 8192];
+    file.read_exact(&mut header).ok()?;
+    header[4096..]
+        .chunks_exact(4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64)
+        .max()
+}
+
+fn parse_cutoff(value: &str) -> Result<i64, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.timestamp());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc().timestamp())
+        .ok_or_else(|| format!("Invalid date '{}'", value))
+}
+
+/// Delete region files outside a radius or not saved since a date. With `dry_run` nothing is
+/// deleted and the report shows how much disk would be reclaimed
+#[tauri::command]
+pub async fn trim_world(
+    app_handle: tauri::AppHandle,
+    id: String,
+    options: TrimOptions,
+) -> Result<TrimReport, String> {
+    if options.radius.is_none() && options.untouched_since.is_none() {
+        return Err("Choose a radius, a date, or both to trim by".into());
+    }
+    let cutoff = options
+        .untouched_since
+        .as_deref()
+        .map(parse_cutoff)
+        .transpose()?;
+
+    let instance_dir = if options.dry_run {
+        get_instance_by_id(&app_handle, &id)?;
+        filesystem::get_instance_dir(&app_handle, &id)?
+    } else {
+        stopped_instance_dir(&app_handle, &id)?
+    };
+    let world_dir = existing_world_dir(&instance_dir, &options.world_name)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // `scale` converts the overworld radius and center into the dimension's coordinates
+        let outside_radius = |x: i64, z: i64, scale: i64| {
+            options.radius.is_some_and(|radius| {
+                let radius = (radius as i64 + scale - 1) / scale;
+                let overlaps = |region: i64, center: i64| {
+                    let center = center.div_euclid(scale);
+                    let min = region * REGION_BLOCKS;
+                    let max = min + REGION_BLOCKS - 1;
+                    max >= center - radius && min <= center + radius
+                };
+                !(overlaps(x, options.center_x) && overlaps(z, options.center_z))
+            })
+        };
+
+        let mut report = TrimReport {
+            dry_run: options.dry_run,
+            files: Vec::new(),
+            bytes: 0,
+        };
+
+        for dir in REGION_DIRS {
+            let scale = if dir.starts_with("DIM-1/") {
+                NETHER_SCALE
+            } else {
+                1
+            };
+            let Ok(entries) = fs::read_dir(world_dir.join(dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some((x, z)) = region_coords(&name) else {
+                    continue;
+                };
+
+                let path = entry.path();
+                let stale = cutoff.is_some_and(|cutoff| {
+                    region_last_saved(&path).is_some_and(|saved| saved < cutoff)
+                });
+                if !outside_radius(x, z, scale) && !stale {
+                    continue;
+                }
+
+                report.bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                if !options.dry_run {
+                    fs::remove_file(&path)
+                        .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+                }
+                report.files.push(
+                    path.strip_prefix(&world_dir)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .to_string(),
+                );
+            }
+        }
+
+        report.files.sort();
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Failed to trim world: {}", e))?
+}